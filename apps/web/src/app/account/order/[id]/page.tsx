@@ -21,7 +21,8 @@ import {
   Copy,
   Check,
   XCircle,
-  Timer
+  Timer,
+  MessageSquare
 } from 'lucide-react';
 import { Card, CardContent, CardHeader, CardTitle } from '@/components/ui/card';
 import { Button } from '@/components/ui/button';
@@ -34,6 +35,7 @@ import { formatAddress, getTransactionUrl } from '@/lib/contracts';
 import { getTokenInfo } from '@/lib/tokens';
 import { useWithdraw } from '@/hooks/useWithdraw';
 import { useUpdateExchangeRate } from '@/hooks/useUpdateExchangeRate';
+import { useSetMemoRequired } from '@/hooks/useSetMemoRequired';
 import { useTranslations } from 'next-intl';
 
 // Types matching the backend API
@@ -50,6 +52,7 @@ interface OrderDto {
   created_at: number;
   is_public: boolean;
   private_code?: string;
+  memo_required: boolean;
 }
 
 interface TradeActivity {
@@ -150,6 +153,16 @@ export default function OrderDetailPage() {
     txHash: rateTxHash
   } = useUpdateExchangeRate();
   
+  // Payment memo requirement state
+  const {
+    executeSetMemoRequired,
+    resetState: resetMemoState,
+    currentStep: memoStep,
+    isUpdating: isUpdatingMemo,
+    errorCode: memoError,
+    txHash: memoTxHash
+  } = useSetMemoRequired();
+  
   
   // Fetch order data
   const fetchOrderActivities = useCallback(async () => {
@@ -201,6 +214,17 @@ export default function OrderDetailPage() {
     }
   }, [rateStep, fetchOrderActivities, resetRateState]);
   
+  // Refetch after successful memo requirement update
+  useEffect(() => {
+    if (memoStep === 'success') {
+      const timer = setTimeout(() => {
+        fetchOrderActivities();
+        resetMemoState();
+      }, 2000);
+      return () => clearTimeout(timer);
+    }
+  }, [memoStep, fetchOrderActivities, resetMemoState]);
+  
   
   // Check if user is the seller
   const isSeller = data?.order && address && 
@@ -384,6 +408,14 @@ export default function OrderDetailPage() {
                     </span>
                   )}
                   
+                  {/* Memo Badge */}
+                  {order.memo_required && (
+                    <span className="inline-flex items-center text-sm font-medium px-3 py-1.5 rounded-xl bg-transparent backdrop-blur-sm border border-amber-400/20 text-amber-600 dark:text-amber-400 shadow-sm shadow-amber-500/5">
+                      <MessageSquare className="w-4 h-4 mr-1.5" />
+                      {tOrder('memoRequired')}
+                    </span>
+                  )}
+                  
                   {/* Status Badge - Liquid Glass Effect */}
                   {isCompleted ? (
                     <span className="inline-flex items-center text-sm font-medium px-3 py-1.5 rounded-xl bg-transparent backdrop-blur-sm border border-slate-400/20 text-slate-500 dark:text-slate-400 shadow-sm shadow-slate-500/5">
@@ -556,6 +588,35 @@ export default function OrderDetailPage() {
                   </Alert>
                 )}
                 
+                {memoStep === 'success' && (
+                  <Alert className="bg-emerald-50 border-emerald-200 dark:bg-emerald-900/20 dark:border-emerald-500/30">
+                    <CheckCircle2 className="h-4 w-4 text-emerald-600" />
+                    <AlertDescription className="text-emerald-700 dark:text-emerald-300">
+                      {tOrder('memoUpdateSuccess')}{' '}
+                      {memoTxHash && (
+                        <a
+                          href={getTransactionUrl(memoTxHash)}
+                          target="_blank"
+                          rel="noopener noreferrer"
+                          className="underline inline-flex items-center"
+                        >
+                          {tOrder('viewOnExplorer')} <ExternalLink className="ml-1 h-3 w-3" />
+                        </a>
+                      )}
+                    </AlertDescription>
+                  </Alert>
+                )}
+                
+                {memoError && (
+                  <Alert variant="destructive">
+                    <AlertCircle className="h-4 w-4" />
+                    <AlertDescription className="flex items-center justify-between">
+                      <span>{tOrder(`errors.${memoError}` as any) || tOrder('errors.unknown')}</span>
+                      <Button onClick={resetMemoState} size="sm" variant="outline">{tOrder('dismiss')}</Button>
+                    </AlertDescription>
+                  </Alert>
+                )}
+                
                 
                 {/* Withdraw Section */}
                 <div className="space-y-3">
@@ -689,6 +750,36 @@ export default function OrderDetailPage() {
                   )}
                 </div>
                 
+                {/* Payment Memo Section */}
+                <div className="space-y-3 pt-4 border-t border-slate-200/50 dark:border-slate-700/50">
+                  <div className="flex items-center justify-between gap-4">
+                    <Label className="text-sm font-medium">{tOrder('paymentMemo')}</Label>
+                    <Button
+                      onClick={() => {
+                        executeSetMemoRequired({
+                          orderId: order.order_id,
+                          required: !order.memo_required,
+                        });
+                      }}
+                      disabled={isUpdatingMemo}
+                      className="h-9 px-4 bg-amber-500/10 hover:bg-amber-500/20 text-amber-600 dark:text-amber-400 rounded-xl"
+                    >
+                      {isUpdatingMemo ? (
+                        <>
+                          <Loader2 className="mr-2 h-4 w-4 animate-spin" />
+                          {memoStep === 'confirming' ? tOrder('confirming') : tOrder('signing')}
+                        </>
+                      ) : (
+                        order.memo_required ? tOrder('stopRequiringMemo') : tOrder('requireMemo')
+                      )}
+                    </Button>
+                  </div>
+                  <p className="text-xs text-slate-500 dark:text-slate-400">
+                    {order.memo_required ? tOrder('memoRequiredOn') : tOrder('memoRequiredOff')}{' '}
+                    {tOrder('memoAppliesToNewTrades')}
+                  </p>
+                </div>
+                
               </CardContent>
             </Card>
           </motion.div>
@@ -22,6 +22,7 @@ export interface Trade {
   alipay_name: string;
   cny_amount: string;
   expires_at: number;
+  memo_code?: string; // Trade reference code for the Alipay memo (memo-bound trades)
 }
 
 export interface BuyFlowData {
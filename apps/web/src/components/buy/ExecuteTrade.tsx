@@ -86,7 +86,7 @@ export function ExecuteTrade({ flowData, updateFlowData, goBack, goToNextStep }:
 
       // Wait for trade to sync to database
       setStatus('syncing');
-      await waitForTradeSync(result.trade_id, result.tx_hash);
+      await waitForTradeSync(result.trade_id, result.tx_hash, result.memo_code);
 
     } catch (err: any) {
       console.error('Trade creation error:', err);
@@ -109,7 +109,7 @@ export function ExecuteTrade({ flowData, updateFlowData, goBack, goToNextStep }:
   };
 
   // Wait for trade to be synced to database
-  const waitForTradeSync = async (tradeId: string, txHash: string) => {
+  const waitForTradeSync = async (tradeId: string, txHash: string, memoCode?: string) => {
     const maxAttempts = 30; // 30 attempts
     const delayMs = 2000; // 2 seconds between attempts = 60 seconds max wait
 
@@ -132,6 +132,7 @@ export function ExecuteTrade({ flowData, updateFlowData, goBack, goToNextStep }:
             alipay_name: selectedOrder!.account_name || selectedOrder!.alipay_name || '',
             cny_amount: trade.cny_amount || '0',
             expires_at: trade.expires_at,
+            memo_code: memoCode,
           };
           
           setTrades([tradeData]);
@@ -168,6 +169,7 @@ export function ExecuteTrade({ flowData, updateFlowData, goBack, goToNextStep }:
       alipay_name: selectedOrder!.account_name || selectedOrder!.alipay_name || '',
       cny_amount: cnyAmount,
       expires_at: Math.floor(Date.now() / 1000) + 900, // Approximate 15 min
+      memo_code: memoCode,
     };
     
     setTrades([tradeData]);
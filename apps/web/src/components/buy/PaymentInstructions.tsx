@@ -254,7 +254,17 @@ export function PaymentInstructions({ trades, onAllSettled }: PaymentInstruction
         const errorMessages: Record<string, string> = {
           'REPLAY_ATTACK': t('validationErrors.replay_attack'),
           'PAYMENT_TOO_OLD': t('validationErrors.payment_too_old'),
+          'PAYMENT_AFTER_EXPIRY': t('validationErrors.payment_after_expiry'),
+          'PAYMENT_TIME_IN_FUTURE': t('validationErrors.payment_time_in_future'),
+          'INVALID_PAYMENT_TIME_FORMAT': t('validationErrors.invalid_payment_time_format'),
+          'AMOUNT_MISMATCH': t('validationErrors.amount_mismatch'),
+          'RECIPIENT_MISMATCH': t('validationErrors.recipient_mismatch'),
+          'MEMO_MISMATCH': t('validationErrors.memo_mismatch'),
+          'SIGNATURE_INVALID': t('validationErrors.signature_invalid'),
+          'RECEIPT_IN_USE': t('validationErrors.receipt_in_use'),
           'HASH_MISMATCH': t('validationErrors.hash_mismatch'),
+          'KEY_ROTATION_PENDING': t('validationErrors.key_rotation_pending'),
+          'KEY_UNTRUSTED': t('validationErrors.key_untrusted'),
           'UNKNOWN': t('validationErrors.unknown'),
         };
         
@@ -72,6 +72,19 @@ export function PaymentDetailsSection({
               ¥{cnyAmount}
             </div>
           </div>
+
+          {/* Payment Memo - memo-bound trades only */}
+          {trade.memo_code && (
+            <div className="space-y-1.5">
+              <label className="text-xs font-semibold text-slate-500 dark:text-slate-400 uppercase tracking-wide">
+                {t('paymentNoteCritical')}
+              </label>
+              <div className="font-mono text-2xl font-bold tracking-widest text-amber-700 dark:text-amber-400 bg-amber-500/5 px-4 py-3 rounded-xl border border-amber-300/40">
+                {trade.memo_code}
+              </div>
+              <p className="text-xs text-amber-700 dark:text-amber-400">{t('mustInclude')}</p>
+            </div>
+          )}
         </div>
       </div>
 
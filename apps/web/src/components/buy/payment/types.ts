@@ -10,6 +10,7 @@ export interface Trade {
   alipay_name: string;  // For display (populated from account_name)
   cny_amount: string;
   expires_at: number;
+  memo_code?: string;  // Trade reference code the buyer must put in the Alipay memo
 }
 
 export interface PaymentInstructionsProps {
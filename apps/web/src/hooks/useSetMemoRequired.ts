@@ -0,0 +1,124 @@
+import { useState, useEffect } from 'react';
+import { useWriteContract, useWaitForTransactionReceipt } from 'wagmi';
+import { ESCROW_ABI, ESCROW_ADDRESS, getAddress } from '@/lib/contracts';
+
+export interface SetMemoRequiredParams {
+  orderId: string;
+  required: boolean; // New trades must carry the trade code in the Alipay memo (备注)
+}
+
+export type SetMemoStep = 'idle' | 'updating' | 'confirming' | 'success' | 'error';
+
+export type SetMemoErrorCode =
+  | 'userRejected'
+  | 'notAuthorized'
+  | 'orderNotFound'
+  | 'networkError'
+  | 'unknown';
+
+function parseErrorCode(error: Error | unknown): SetMemoErrorCode {
+  const message = error instanceof Error ? error.message : String(error);
+
+  if (message.includes('User rejected') || message.includes('user rejected') ||
+      message.includes('User denied') || message.includes('denied transaction')) {
+    return 'userRejected';
+  }
+  if (message.includes('NotAuthorized') || message.includes('0xea8e4eb5')) {
+    return 'notAuthorized';
+  }
+  if (message.includes('OrderNotFound')) {
+    return 'orderNotFound';
+  }
+  if (message.includes('network') || message.includes('timeout')) {
+    return 'networkError';
+  }
+
+  return 'unknown';
+}
+
+export function useSetMemoRequired() {
+  const [currentStep, setCurrentStep] = useState<SetMemoStep>('idle');
+  const [errorCode, setErrorCode] = useState<SetMemoErrorCode | null>(null);
+  const [txHash, setTxHash] = useState<string | null>(null);
+
+  const { writeContract, data: updateHash, error: updateError, isPending: isWritePending } = useWriteContract();
+
+  const {
+    isLoading: isConfirming,
+    isSuccess: isUpdateSuccess,
+    isError: isReceiptError,
+    error: receiptError
+  } = useWaitForTransactionReceipt({
+    hash: updateHash,
+  });
+
+  const isUpdating = isWritePending || isConfirming;
+
+  useEffect(() => {
+    if (updateError) {
+      console.error('Set memo requirement error:', updateError);
+      setErrorCode(parseErrorCode(updateError));
+      setCurrentStep('error');
+    }
+  }, [updateError]);
+
+  useEffect(() => {
+    if (isReceiptError && receiptError) {
+      console.error('Transaction receipt error:', receiptError);
+      setErrorCode(parseErrorCode(receiptError));
+      setCurrentStep('error');
+    }
+  }, [isReceiptError, receiptError]);
+
+  useEffect(() => {
+    if (updateHash && currentStep === 'updating') {
+      setCurrentStep('confirming');
+      setTxHash(updateHash);
+    }
+  }, [updateHash, currentStep]);
+
+  useEffect(() => {
+    if (isUpdateSuccess && (currentStep === 'updating' || currentStep === 'confirming')) {
+      console.log('Memo requirement update confirmed!');
+      setCurrentStep('success');
+      setTxHash(updateHash || null);
+    }
+  }, [isUpdateSuccess, currentStep, updateHash]);
+
+  const executeSetMemoRequired = async (params: SetMemoRequiredParams) => {
+    try {
+      setErrorCode(null);
+      setCurrentStep('updating');
+
+      const orderIdBytes = params.orderId.startsWith('0x')
+        ? params.orderId
+        : `0x${params.orderId}`;
+
+      writeContract({
+        address: getAddress(ESCROW_ADDRESS),
+        abi: ESCROW_ABI,
+        functionName: 'setOrderMemoRequired',
+        args: [orderIdBytes as `0x${string}`, params.required],
+      });
+    } catch (err) {
+      console.error('Error setting memo requirement:', err);
+      setErrorCode(parseErrorCode(err));
+      setCurrentStep('error');
+    }
+  };
+
+  const resetState = () => {
+    setCurrentStep('idle');
+    setErrorCode(null);
+    setTxHash(null);
+  };
+
+  return {
+    executeSetMemoRequired,
+    resetState,
+    currentStep,
+    isUpdating,
+    errorCode,
+    txHash,
+  };
+}
@@ -22,6 +22,7 @@ export interface Order {
   // Private listing fields
   is_public: boolean;
   private_code?: string;
+  memo_required?: boolean; // Buyers must put the trade reference code in the Alipay memo (备注)
 }
 
 export interface Trade {
@@ -41,6 +42,7 @@ export interface Trade {
   pdf_uploaded_at?: string;
   proof_generated_at?: string; // When ZK proof was generated
   settlement_error?: string; // Settlement error code if failed (ALREADY_USED, NOT_PENDING, EXPIRED, VERIFICATION_FAILED)
+  memo_code?: string; // Trade reference code required in the Alipay memo (memo-bound trades only)
   token?: string; // Token address (from joined order)
   // Payment account info - support both old (alipay_*) and new (account_*) field names
   account_id?: string; // Seller's account ID (new)
@@ -117,6 +119,7 @@ export const api = {
     order_id: string;
     buyer: string;
     tx_hash: string;
+    memo_code?: string; // Only when the order requires a memo
     message: string;
   }> {
     const response = await axios.post(`${API_BASE}/api/trades/create`, {
@@ -149,13 +152,14 @@ export const api = {
     trade_id: string;
     is_valid: boolean;
     validation_details?: string;
-    validation_code?: string; // REPLAY_ATTACK, PAYMENT_TOO_OLD, HASH_MISMATCH, SUCCESS
+    validation_code?: string; // REPLAY_ATTACK, PAYMENT_TOO_OLD, PAYMENT_AFTER_EXPIRY, PAYMENT_TIME_IN_FUTURE, INVALID_PAYMENT_TIME_FORMAT, AMOUNT_MISMATCH, RECIPIENT_MISMATCH, MEMO_MISMATCH, SIGNATURE_INVALID, RECEIPT_IN_USE, HASH_MISMATCH, KEY_ROTATION_PENDING, KEY_UNTRUSTED, SUCCESS
     filename?: string;
     // Backend sends these fields:
     valid?: boolean;
     message?: string;
     transaction_id?: string;
     payment_time?: string;
+    mismatched_components?: string[]; // HASH_MISMATCH (diagnostic build): pdf, signature, account, tx_id, time_amount, memo, ...
   }> {
     const formData = new FormData();
     formData.append('pdf', pdfFile);
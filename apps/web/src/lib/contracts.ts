@@ -137,6 +137,16 @@ export const ESCROW_ABI = [
     stateMutability: 'nonpayable',
     type: 'function',
   },
+  {
+    inputs: [
+      { name: 'orderId', type: 'bytes32' },
+      { name: 'required', type: 'bool' },
+    ],
+    name: 'setOrderMemoRequired',
+    outputs: [],
+    stateMutability: 'nonpayable',
+    type: 'function',
+  },
   // View function to calculate fee (no gas cost)
   {
     inputs: [
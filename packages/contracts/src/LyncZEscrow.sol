@@ -96,8 +96,23 @@ contract LyncZEscrow is ReentrancyGuard, Ownable, Pausable {
         TradeStatus status;
     }
     
+    /**
+     * @notice One trade settled by a batch proof (see submitBatchProof)
+     * @param tradeId Trade identifier
+     * @param txIdHash SHA256 hash of transaction ID line: SHA256(25_LE || transactionId)
+     * @param paymentTime Payment timestamp string (format: "YYYY-MM-DD HH:MM:SS")
+     */
+    struct BatchSettlement {
+        bytes32 tradeId;
+        bytes32 txIdHash;
+        string paymentTime;
+    }
+    
     // ============ State Variables ============
     
+    /// @notice Maximum number of trades settled by one batch proof (bounds gas per call)
+    uint256 public constant MAX_BATCH_SIZE = 16;
+    
     /// @notice Mapping of orderId to Order
     mapping(bytes32 => Order) public orders;
     
@@ -132,6 +147,14 @@ contract LyncZEscrow is ReentrancyGuard, Ownable, Pausable {
     /// @notice Fee amount per trade (tradeId => fee in tokens)
     mapping(bytes32 => uint256) public tradeFees;
     
+    /// @notice Orders whose trades must be paid with the trade reference code in the memo (备注)
+    /// @dev Set by the seller (setOrderMemoRequired); fillOrder copies it to the trade
+    mapping(bytes32 => bool) public orderMemoRequired;
+    
+    /// @notice Trades whose receipt must carry the trade reference code in the memo (备注)
+    /// @dev Fixed when the trade is created; settlement then uses verifier.verifyPaymentWithMemo
+    mapping(bytes32 => bool) public tradeMemoRequired;
+    
     // ============ Events ============
     
     event OrderCreated(
@@ -156,6 +179,10 @@ contract LyncZEscrow is ReentrancyGuard, Ownable, Pausable {
         uint256 expiresAt
     );
     
+    event TradeMemoRequired(
+        bytes32 indexed tradeId
+    );
+    
     event TradeSettled(
         bytes32 indexed tradeId,
         bytes32 indexed txIdHash
@@ -185,6 +212,12 @@ contract LyncZEscrow is ReentrancyGuard, Ownable, Pausable {
         bytes32 newHash
     );
     
+    event OrderMemoRequirementUpdated(
+        bytes32 indexed orderId,
+        bool oldRequired,
+        bool newRequired
+    );
+    
     event VerifierUpdated(
         PaymentRail indexed rail,
         address indexed oldVerifier,
@@ -231,6 +264,9 @@ contract LyncZEscrow is ReentrancyGuard, Ownable, Pausable {
     error FiatAmountMustBeWholeYuan();  // fiatAmount must be divisible by 100 (whole yuan, no fen)
     error InvalidAccountLinesHash();  // accountLinesHash cannot be zero
     error InvalidAmount();  // generic invalid amount (e.g., zero exchange rate)
+    error EmptyBatch();
+    error BatchTooLarge();
+    error BatchRailMismatch();  // all trades in a batch must use the same payment rail
     
     // ============ Constructor ============
     
@@ -390,6 +426,29 @@ contract LyncZEscrow is ReentrancyGuard, Ownable, Pausable {
         emit AccountLinesHashUpdated(orderId, oldHash, newAccountLinesHash);
     }
     
+    /**
+     * @notice Require (or stop requiring) the trade reference code in the payment memo
+     * @dev Only seller can update. Applies to trades filled afterwards - existing trades
+     *      keep the requirement they were created with. The buyer must put the trade
+     *      reference code (first 8 bytes of tradeId as uppercase hex, see
+     *      AlipayVerifier.memoLine) in the Alipay memo (备注), and the proof must commit
+     *      to that memo line, so a receipt for another payment to the seller cannot
+     *      settle the trade.
+     * @param orderId Order identifier
+     * @param required Whether new trades must carry the memo
+     */
+    function setOrderMemoRequired(bytes32 orderId, bool required) external {
+        Order storage order = orders[orderId];
+        
+        if (order.seller == address(0)) revert OrderNotFound();
+        if (msg.sender != order.seller) revert NotAuthorized();
+        
+        bool oldRequired = orderMemoRequired[orderId];
+        orderMemoRequired[orderId] = required;
+        
+        emit OrderMemoRequirementUpdated(orderId, oldRequired, required);
+    }
+    
     // ============ Buyer Functions ============
     
     /**
@@ -397,6 +456,7 @@ contract LyncZEscrow is ReentrancyGuard, Ownable, Pausable {
      * @dev Fee is calculated via external fee calculator (upgradeable).
      *      Buyer receives tokens calculated from fiat amount, fee is deducted from seller.
      *      Token amount is rounded UP so buyer absorbs any dust.
+     *      If the seller requires a payment memo (orderMemoRequired), the trade does too.
      * @param orderId Order to fill
      * @param buyer Buyer's address
      * @param fiatAmount Amount of fiat in cents (must be whole yuan, divisible by 100)
@@ -472,6 +532,12 @@ contract LyncZEscrow is ReentrancyGuard, Ownable, Pausable {
             fiatAmount,
             expiresAt
         );
+        
+        // Memo binding is the seller's setting - the buyer cannot opt out of it
+        if (orderMemoRequired[orderId]) {
+            tradeMemoRequired[tradeId] = true;
+            emit TradeMemoRequired(tradeId);
+        }
     }
     
     /**
@@ -496,6 +562,66 @@ contract LyncZEscrow is ReentrancyGuard, Ownable, Pausable {
         _validateAndSettle(tradeId, txIdHash, paymentTime, userPublicValues, accumulator, proof);
     }
     
+    /**
+     * @notice Settle several trades with one payment proof
+     * @dev The proof output must be the Merkle root over the trades' expected hashes,
+     *      in array order (see ILyncZVerifier.verifyBatch). All trades must use the same
+     *      payment rail, and any invalid trade reverts the whole batch.
+     * @param settlements Trade, txIdHash and payment time of each receipt in the batch
+     * @param userPublicValues Public output from ZK proof (batch root, 32 bytes)
+     * @param accumulator KZG accumulator (384 bytes)
+     * @param proof Halo2 proof data (1376 bytes)
+     */
+    function submitBatchProof(
+        BatchSettlement[] calldata settlements,
+        bytes32 userPublicValues,
+        bytes calldata accumulator,
+        bytes calldata proof
+    ) external nonReentrant whenNotPaused {
+        if (settlements.length == 0) revert EmptyBatch();
+        if (settlements.length > MAX_BATCH_SIZE) revert BatchTooLarge();
+        
+        // Validate every trade and collect its expected hash (CEI: state updated before transfers)
+        PaymentRail rail = orders[trades[settlements[0].tradeId].orderId].rail;
+        ILyncZVerifier verifier = verifiers[rail];
+        if (address(verifier) == address(0)) revert VerifierNotSet();
+        
+        bytes32[] memory leaves = new bytes32[](settlements.length);
+        for (uint256 i = 0; i < settlements.length; i++) {
+            BatchSettlement calldata settlement = settlements[i];
+            Trade storage trade = _checkAndMarkSettled(settlement.tradeId, settlement.txIdHash, settlement.paymentTime);
+            Order storage order = orders[trade.orderId];
+            if (order.rail != rail) revert BatchRailMismatch();
+            
+            leaves[i] = tradeMemoRequired[settlement.tradeId]
+                ? verifier.expectedHashWithMemo(
+                    order.accountLinesHash,
+                    settlement.txIdHash,
+                    trade.fiatAmount,
+                    settlement.paymentTime,
+                    settlement.tradeId
+                )
+                : verifier.expectedHash(
+                    order.accountLinesHash,
+                    settlement.txIdHash,
+                    trade.fiatAmount,
+                    settlement.paymentTime
+                );
+        }
+        
+        // One proof covers the whole batch
+        if (!verifier.verifyBatch(userPublicValues, accumulator, proof, leaves)) {
+            revert ProofVerificationFailed();
+        }
+        
+        for (uint256 i = 0; i < settlements.length; i++) {
+            bytes32 tradeId = settlements[i].tradeId;
+            Trade storage trade = trades[tradeId];
+            _releaseToBuyer(tradeId, trade, orders[trade.orderId]);
+            emit TradeSettled(tradeId, settlements[i].txIdHash);
+        }
+    }
+    
     /**
      * @dev Internal function to validate proof and settle trade
      */
@@ -507,7 +633,24 @@ contract LyncZEscrow is ReentrancyGuard, Ownable, Pausable {
         bytes calldata accumulator,
         bytes calldata proof
     ) internal {
-        Trade storage trade = trades[tradeId];
+        Trade storage trade = _checkAndMarkSettled(tradeId, txIdHash, paymentTime);
+        
+        // Verify proof, transfer tokens, and collect fee
+        _verifyAndTransfer(tradeId, trade, txIdHash, paymentTime, userPublicValues, accumulator, proof);
+        
+        emit TradeSettled(tradeId, txIdHash);
+    }
+    
+    /**
+     * @dev Validate trade state, payment time and receipt, then mark the receipt used
+     *      and the trade settled (before any external call)
+     */
+    function _checkAndMarkSettled(
+        bytes32 tradeId,
+        bytes32 txIdHash,
+        string calldata paymentTime
+    ) internal returns (Trade storage trade) {
+        trade = trades[tradeId];
         
         // Validate trade state
         if (trade.buyer == address(0)) revert TradeNotFound();
@@ -527,11 +670,6 @@ contract LyncZEscrow is ReentrancyGuard, Ownable, Pausable {
         // Mark as used before external call (CEI pattern)
         usedTransactionIds[txIdHash] = true;
         trade.status = TradeStatus.SETTLED;
-        
-        // Verify proof, transfer tokens, and collect fee
-        _verifyAndTransfer(tradeId, trade, txIdHash, paymentTime, userPublicValues, accumulator, proof);
-        
-        emit TradeSettled(tradeId, txIdHash);
     }
     
     /**
@@ -553,18 +691,41 @@ contract LyncZEscrow is ReentrancyGuard, Ownable, Pausable {
         if (address(verifier) == address(0)) revert VerifierNotSet();
         
         // Verify proof using accountLinesHash from storage and txIdHash from caller
-        bool valid = verifier.verifyPayment(
-            userPublicValues,
-            accumulator,
-            proof,
-            order.accountLinesHash,
-            txIdHash,
-            trade.fiatAmount,
-            paymentTime
-        );
+        // Memo-bound trades also require the proof to commit to the trade's memo line
+        bool valid = tradeMemoRequired[tradeId]
+            ? verifier.verifyPaymentWithMemo(
+                userPublicValues,
+                accumulator,
+                proof,
+                order.accountLinesHash,
+                txIdHash,
+                trade.fiatAmount,
+                paymentTime,
+                tradeId
+            )
+            : verifier.verifyPayment(
+                userPublicValues,
+                accumulator,
+                proof,
+                order.accountLinesHash,
+                txIdHash,
+                trade.fiatAmount,
+                paymentTime
+            );
         
         if (!valid) revert ProofVerificationFailed();
         
+        _releaseToBuyer(tradeId, trade, order);
+    }
+    
+    /**
+     * @dev Internal function to transfer tokens to the buyer and collect the fee
+     */
+    function _releaseToBuyer(
+        bytes32 tradeId,
+        Trade storage trade,
+        Order storage order
+    ) internal {
         // Transfer tokens to buyer (full amount they requested)
         bool success = IERC20(order.token).transfer(trade.buyer, trade.tokenAmount);
         if (!success) revert TransferFailed();
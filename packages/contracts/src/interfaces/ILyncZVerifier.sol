@@ -13,6 +13,10 @@ pragma solidity ^0.8.20;
  *        Passed as hash to prevent transaction ID from appearing on-chain.
  *      - paymentTime: Passed as plain text for createdAt validation.
  *      - amount: From trade storage, used to compute expected hash.
+ *      - memo (optional): Trade reference code the buyer put in the transfer memo,
+ *        derived from the tradeId so it binds the payment to one specific trade.
+ *      - batches: one proof can cover several receipts; its output is then the
+ *        Merkle root over each receipt's expected hash.
  */
 interface ILyncZVerifier {
     /**
@@ -35,4 +39,61 @@ interface ILyncZVerifier {
         uint256 amountCents,
         string calldata paymentTime
     ) external view returns (bool valid);
+
+    /**
+     * @notice Verify a payment proof that also commits to the trade's memo line
+     * @dev Same as verifyPayment, plus the proof output must include the hash of the
+     *      memo line carrying the trade reference code derived from tradeId.
+     * @param tradeId Trade identifier (source of the memo reference code)
+     * @return valid True if proof is valid and matches expected values
+     */
+    function verifyPaymentWithMemo(
+        bytes32 userPublicValues,
+        bytes calldata accumulator,
+        bytes calldata proof,
+        bytes32 accountLinesHash,
+        bytes32 txIdHash,
+        uint256 amountCents,
+        string calldata paymentTime,
+        bytes32 tradeId
+    ) external view returns (bool valid);
+
+    /**
+     * @notice Expected proof output for one receipt (the batch leaf)
+     * @dev Equal to the userPublicValues a single-receipt proof must reveal
+     */
+    function expectedHash(
+        bytes32 accountLinesHash,
+        bytes32 txIdHash,
+        uint256 amountCents,
+        string calldata paymentTime
+    ) external view returns (bytes32);
+
+    /**
+     * @notice Expected proof output for one memo-bound receipt (the batch leaf)
+     */
+    function expectedHashWithMemo(
+        bytes32 accountLinesHash,
+        bytes32 txIdHash,
+        uint256 amountCents,
+        string calldata paymentTime,
+        bytes32 tradeId
+    ) external view returns (bytes32);
+
+    /**
+     * @notice Verify one proof covering a batch of receipts
+     * @dev The proof output is the Merkle root over the receipts' expected hashes,
+     *      in batch order (see expectedHash / expectedHashWithMemo)
+     * @param userPublicValues The 32-byte batch root output from the ZK proof
+     * @param accumulator KZG accumulator from proof (384 bytes)
+     * @param proof Halo2 proof data (1376 bytes)
+     * @param leaves Expected hash of each receipt in the batch
+     * @return valid True if proof is valid and commits to exactly these leaves
+     */
+    function verifyBatch(
+        bytes32 userPublicValues,
+        bytes calldata accumulator,
+        bytes calldata proof,
+        bytes32[] calldata leaves
+    ) external view returns (bool valid);
 }
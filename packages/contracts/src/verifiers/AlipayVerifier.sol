@@ -28,22 +28,44 @@ import "@openzeppelin/contracts/access/Ownable.sol";
  *   timeAmountHash = SHA256(27 || line27 || 29 || line29)
  *   output = SHA256(0x01 || publicKeyHash || accountLinesHash || txIdHash || timeAmountHash)
  * 
+ * With trade memo (optional, see verifyPaymentWithMemo):
+ *   memoHash = SHA256(memoLine), memoLine = "备注：" + first 8 bytes of tradeId as uppercase hex
+ *   output = SHA256(0x01 || publicKeyHash || accountLinesHash || txIdHash || timeAmountHash || memoHash)
+ * 
+ * Batches (see verifyBatch):
+ *   leaf = output above, per receipt
+ *   root = Merkle root over SHA256(0x00 || leaf), parent = SHA256(0x01 || left || right),
+ *          an odd last node is carried up unchanged. A lone receipt's root is its
+ *          own output, so verifyPayment and a one-leaf verifyBatch agree
+ * 
+ * Trusted key set (optional, chosen by the prover per receipt):
+ *   publicKeyHash above may be the root of a set of trusted key hashes instead
+ *   of a single key hash: parent = SHA256(min(a, b) || max(a, b)). The guest folds
+ *   the receipt's key hash up a membership proof and commits the root, so a
+ *   rotation back to any key already in the set needs no update here.
+ * 
  * Note: alipayPublicKeyHash is MUTABLE because Alipay rotates their
  *       PDF signing certificate approximately every 24 hours.
  */
 contract AlipayVerifier is ILyncZVerifier, Ownable {
     // ============ Errors ============
     error ProofVerificationFailed();
+    error EmptyBatch();
     error HashMismatch(bytes32 expected, bytes32 actual);
 
+    // ============ Constants ============
+    /// @dev Batch tree domain separation: leaf = SHA256(0x00 || output), node = SHA256(0x01 || l || r)
+    bytes1 private constant LEAF_PREFIX = 0x00;
+    bytes1 private constant NODE_PREFIX = 0x01;
+
     // ============ Immutables ============
     IOpenVmHalo2Verifier public immutable halo2Verifier;
     bytes32 public immutable appExeCommit;
     bytes32 public immutable appVmCommit;
 
     // ============ Mutable State ============
-    /// @notice Alipay's PDF signing certificate public key hash
-    /// @dev Updated when Alipay rotates their certificate (~24 hours)
+    /// @notice Alipay's PDF signing certificate public key hash, or the trusted key-set root
+    /// @dev Updated when Alipay rotates their certificate (~24 hours) or a key joins the set
     bytes32 public alipayPublicKeyHash;
 
     // ============ Events ============
@@ -67,7 +89,7 @@ contract AlipayVerifier is ILyncZVerifier, Ownable {
     /**
      * @notice Update Alipay's public key hash
      * @dev Called when Alipay rotates their PDF signing certificate
-     * @param _newHash New SHA256 hash of Alipay's DER-encoded public key
+     * @param _newHash New SHA256 hash of Alipay's DER-encoded public key, or new key-set root
      */
     function updatePublicKeyHash(bytes32 _newHash) external onlyOwner {
         bytes32 oldHash = alipayPublicKeyHash;
@@ -95,12 +117,127 @@ contract AlipayVerifier is ILyncZVerifier, Ownable {
             paymentTime
         );
 
-        // Step 2: Compare hashes (fail fast before expensive proof verification)
+        // Step 2: Compare hashes and verify the Halo2 proof
+        return _verifyProof(userPublicValues, expectedHash, accumulator, proof);
+    }
+
+    /// @inheritdoc ILyncZVerifier
+    function verifyPaymentWithMemo(
+        bytes32 userPublicValues,
+        bytes calldata accumulator,
+        bytes calldata proof,
+        bytes32 accountLinesHash,
+        bytes32 txIdHash,
+        uint256 amountCents,
+        string calldata paymentTime,
+        bytes32 tradeId
+    ) external view override returns (bool) {
+        // Step 1: Compute expected hash, including the memo line bound to this trade
+        bytes32 expectedHash = _computeExpectedHashWithMemo(
+            accountLinesHash,
+            txIdHash,
+            _computeTimeAmountHash(amountCents, paymentTime),
+            sha256(bytes(memoLine(tradeId)))
+        );
+
+        // Step 2: Compare hashes and verify the Halo2 proof
+        return _verifyProof(userPublicValues, expectedHash, accumulator, proof);
+    }
+
+    /// @inheritdoc ILyncZVerifier
+    function expectedHash(
+        bytes32 accountLinesHash,
+        bytes32 txIdHash,
+        uint256 amountCents,
+        string calldata paymentTime
+    ) external view override returns (bytes32) {
+        return _computeExpectedHash(accountLinesHash, txIdHash, amountCents, paymentTime);
+    }
+
+    /// @inheritdoc ILyncZVerifier
+    function expectedHashWithMemo(
+        bytes32 accountLinesHash,
+        bytes32 txIdHash,
+        uint256 amountCents,
+        string calldata paymentTime,
+        bytes32 tradeId
+    ) external view override returns (bytes32) {
+        return _computeExpectedHashWithMemo(
+            accountLinesHash,
+            txIdHash,
+            _computeTimeAmountHash(amountCents, paymentTime),
+            sha256(bytes(memoLine(tradeId)))
+        );
+    }
+
+    /// @inheritdoc ILyncZVerifier
+    function verifyBatch(
+        bytes32 userPublicValues,
+        bytes calldata accumulator,
+        bytes calldata proof,
+        bytes32[] calldata leaves
+    ) external view override returns (bool) {
+        if (leaves.length == 0) revert EmptyBatch();
+
+        // Step 1: Recompute the batch root from the expected leaves
+        bytes32 expectedRoot = batchRoot(leaves);
+
+        // Step 2: Compare with the proof output and verify the Halo2 proof
+        return _verifyProof(userPublicValues, expectedRoot, accumulator, proof);
+    }
+
+    /**
+     * @notice Merkle root over receipt hashes, matching the guest program
+     * @dev Leaves are hashed as SHA256(0x00 || leaf) and nodes as SHA256(0x01 || left || right),
+     *      so no interior node can be passed off as a leaf. An odd last node is carried up
+     *      unchanged. A single leaf is its own root (the bare receipt output).
+     * @param leaves Expected hash of each receipt, in batch order
+     */
+    function batchRoot(bytes32[] memory leaves) public pure returns (bytes32) {
+        if (leaves.length == 0) revert EmptyBatch();
+
+        uint256 count = leaves.length;
+        if (count == 1) return leaves[0];
+
+        for (uint256 i = 0; i < count; i++) {
+            leaves[i] = sha256(abi.encodePacked(LEAF_PREFIX, leaves[i]));
+        }
+        while (count > 1) {
+            uint256 next = 0;
+            for (uint256 i = 0; i < count; i += 2) {
+                leaves[next++] = i + 1 < count
+                    ? sha256(abi.encodePacked(NODE_PREFIX, leaves[i], leaves[i + 1]))
+                    : leaves[i];
+            }
+            count = next;
+        }
+        return leaves[0];
+    }
+
+    /**
+     * @notice Memo line the buyer's receipt must contain for a memo-bound trade
+     * @dev "备注：" + trade reference code (first 8 bytes of tradeId, uppercase hex)
+     * @param tradeId Trade identifier
+     */
+    function memoLine(bytes32 tradeId) public pure returns (string memory) {
+        return string(abi.encodePacked(unicode"备注：", _toHexUpper(bytes8(tradeId))));
+    }
+
+    /**
+     * @dev Compare the proof output with the expected hash, then verify the Halo2 proof
+     */
+    function _verifyProof(
+        bytes32 userPublicValues,
+        bytes32 expectedHash,
+        bytes calldata accumulator,
+        bytes calldata proof
+    ) internal view returns (bool) {
+        // Fail fast before expensive proof verification
         if (userPublicValues != expectedHash) {
             revert HashMismatch(expectedHash, userPublicValues);
         }
 
-        // Step 3: Verify Halo2 cryptographic proof
+        // Verify Halo2 cryptographic proof
         bytes memory publicValuesBytes = abi.encodePacked(userPublicValues);
         bytes memory proofData = abi.encodePacked(accumulator, proof);
 
@@ -135,20 +272,8 @@ contract AlipayVerifier is ILyncZVerifier, Ownable {
         uint256 amountCents,
         string calldata paymentTime
     ) internal view returns (bytes32) {
-        // Format CNY amount (e.g., 200 cents -> "2.00")
-        string memory cnyFormatted = _formatCnyAmount(amountCents);
-        
-        // Build time and amount line texts (lines 27, 29)
-        bytes memory line27 = bytes(paymentTime);
-        bytes memory line29 = abi.encodePacked(unicode"小写：", cnyFormatted);
-        
-        // Compute timeAmountHash: SHA256(27 || line27 || 29 || line29)
-        bytes memory timeAmountData = abi.encodePacked(
-            _uint32LE(27), line27,
-            _uint32LE(29), line29
-        );
-        bytes32 timeAmountHash = sha256(timeAmountData);
-        
+        bytes32 timeAmountHash = _computeTimeAmountHash(amountCents, paymentTime);
+
         // Compute final hash: SHA256(0x01 || publicKeyHash || accountLinesHash || txIdHash || timeAmountHash)
         return sha256(abi.encodePacked(
             bytes1(0x01),
@@ -159,6 +284,46 @@ contract AlipayVerifier is ILyncZVerifier, Ownable {
         ));
     }
 
+    /**
+     * @notice Compute expected output hash for memo-bound Alipay receipts
+     * @dev output = SHA256(0x01 || publicKeyHash || accountLinesHash || txIdHash || timeAmountHash || memoHash)
+     */
+    function _computeExpectedHashWithMemo(
+        bytes32 accountLinesHash,
+        bytes32 txIdHash,
+        bytes32 timeAmountHash,
+        bytes32 memoHash
+    ) internal view returns (bytes32) {
+        return sha256(abi.encodePacked(
+            bytes1(0x01),
+            alipayPublicKeyHash,
+            accountLinesHash,
+            txIdHash,
+            timeAmountHash,
+            memoHash
+        ));
+    }
+
+    /**
+     * @notice Compute timeAmountHash: SHA256(27 || line27 || 29 || line29)
+     */
+    function _computeTimeAmountHash(
+        uint256 amountCents,
+        string calldata paymentTime
+    ) internal pure returns (bytes32) {
+        // Format CNY amount (e.g., 200 cents -> "2.00")
+        string memory cnyFormatted = _formatCnyAmount(amountCents);
+
+        // Build time and amount line texts (lines 27, 29)
+        bytes memory line27 = bytes(paymentTime);
+        bytes memory line29 = abi.encodePacked(unicode"小写：", cnyFormatted);
+
+        return sha256(abi.encodePacked(
+            _uint32LE(27), line27,
+            _uint32LE(29), line29
+        ));
+    }
+
     // ============ Helper Functions ============
 
     /**
@@ -186,6 +351,19 @@ contract AlipayVerifier is ILyncZVerifier, Ownable {
         ));
     }
 
+    /**
+     * @notice Convert bytes8 to 16 uppercase hex characters (e.g., 0x1a2b3c4d5e6f7081 -> "1A2B3C4D5E6F7081")
+     */
+    function _toHexUpper(bytes8 value) internal pure returns (string memory) {
+        bytes memory alphabet = "0123456789ABCDEF";
+        bytes memory buffer = new bytes(16);
+        for (uint256 i = 0; i < 8; i++) {
+            buffer[2 * i] = alphabet[uint8(value[i]) >> 4];
+            buffer[2 * i + 1] = alphabet[uint8(value[i]) & 0x0f];
+        }
+        return string(buffer);
+    }
+
     /**
      * @notice Convert uint256 to decimal string
      */
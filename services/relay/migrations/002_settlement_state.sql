@@ -0,0 +1,34 @@
+-- ============================================================================
+-- Settlement state machine + history
+-- ============================================================================
+--
+-- trades.settlement_state is the current sub-status of a PENDING trade's
+-- settlement; trade_settlement_events keeps every transition for the timeline.
+--
+-- States:
+--   receipt_uploaded -> validating -> validated -> proving -> proof_ready
+--   -> submitting -> settled
+--   failed:<CODE> from any step (e.g. failed:HASH_MISMATCH, failed:ALREADY_USED)
+--
+-- ============================================================================
+
+-- Settlement error code (written by the relay, was missing from 001)
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS "settlement_error" TEXT;
+
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS "settlement_state" VARCHAR(64);
+
+CREATE TABLE IF NOT EXISTS trade_settlement_events (
+    "id" BIGSERIAL PRIMARY KEY,
+    "tradeId" VARCHAR(66) NOT NULL,
+    "state" VARCHAR(64) NOT NULL,                         -- SettlementState (see above)
+    "detail" TEXT,                                        -- Free-form context (tx hash, proof id, error)
+    "createdAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    FOREIGN KEY ("tradeId") REFERENCES trades("tradeId") ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS "idx_trade_settlement_events_tradeId" ON trade_settlement_events("tradeId", "id");
+
+COMMENT ON TABLE trade_settlement_events IS 'Settlement sub-status history per trade (timeline)';
+COMMENT ON COLUMN trades."settlement_state" IS 'Current settlement sub-status (latest trade_settlement_events.state)';
+COMMENT ON COLUMN trades."settlement_error" IS 'Stable error code from the last failed settlement attempt';
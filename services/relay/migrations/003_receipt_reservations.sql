@@ -0,0 +1,22 @@
+-- ============================================================================
+-- Receipt transaction ID reservations
+-- ============================================================================
+--
+-- A receipt's transactionId is claimed by the first trade that validates it.
+-- The primary key makes the claim atomic, so two trades can never start paid
+-- proof jobs for the same receipt. Reservations are released when validation
+-- fails or the holding trade expires; settled trades keep theirs.
+--
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS receipt_reservations (
+    "transactionId" TEXT PRIMARY KEY,                     -- Alipay transaction ID (line 25)
+    "tradeId" VARCHAR(66) NOT NULL,                       -- Trade holding the receipt
+    "reservedAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    FOREIGN KEY ("tradeId") REFERENCES trades("tradeId") ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS "idx_receipt_reservations_tradeId" ON receipt_reservations("tradeId");
+
+COMMENT ON TABLE receipt_reservations IS 'Receipt transactionId claimed by the first trade that validated it (anti double-proving)';
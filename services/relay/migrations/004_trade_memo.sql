@@ -0,0 +1,13 @@
+-- ============================================================================
+-- Trade memo binding
+-- ============================================================================
+--
+-- Trades created with fillOrderWithMemo must be paid with the trade reference
+-- code in the Alipay transfer memo (备注). The proof commits to the memo line,
+-- binding the payment to this specific trade. Synced from TradeMemoRequired.
+--
+-- ============================================================================
+
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS "memo_code" VARCHAR(16);
+
+COMMENT ON COLUMN trades."memo_code" IS 'Trade reference code required in the Alipay memo (NULL = memo not required)';
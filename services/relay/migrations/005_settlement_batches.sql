@@ -0,0 +1,26 @@
+-- ============================================================================
+-- Settlement batches - one EVM proof settling several trades
+-- ============================================================================
+--
+-- The guest proves a batch of receipts at once and reveals the Merkle root over
+-- the trades' expected hashes; submitBatchProof settles them in one transaction.
+-- The proof belongs to the batch (not to any single trade), so it is stored here
+-- and resubmitted as a whole. "tradeIds" keeps the proof's leaf order.
+--
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS settlement_batches (
+    "batchId" VARCHAR(100) PRIMARY KEY,                   -- Relay-assigned batch ID
+    "tradeIds" TEXT[] NOT NULL,                           -- Trades in leaf order
+    "proofUserPublicValues" BYTEA,                        -- Batch root (32 bytes)
+    "proofAccumulator" BYTEA,                             -- Halo2 accumulator (384 bytes)
+    "proofData" BYTEA,                                    -- Halo2 proof data (1376 bytes)
+    "axiomProofId" VARCHAR(100),                          -- Axiom API proof ID
+    "settlementTxHash" VARCHAR(66),                       -- submitBatchProof transaction
+    "createdAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    "updatedAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS "idx_settlement_batches_tradeIds" ON settlement_batches USING GIN ("tradeIds");
+
+COMMENT ON TABLE settlement_batches IS 'One batch proof (Merkle root over expected hashes) settling several trades';
@@ -0,0 +1,17 @@
+-- ============================================================================
+-- Trusted Alipay signing keys - leaves of the key-set Merkle tree
+-- ============================================================================
+--
+-- With ALIPAY_TRUSTED_KEYS set, the verifier stores the root over all trusted
+-- key hashes instead of a single key hash. Keys learned from validated receipts
+-- are kept here so the set (and its root) survives restarts.
+--
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS alipay_keys (
+    "pkHash" VARCHAR(66) PRIMARY KEY,                     -- SHA256 of the DER public key (0x hex)
+    "firstTradeId" VARCHAR(66),                           -- Trade whose receipt introduced the key
+    "addedAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE alipay_keys IS 'Trusted Alipay PDF signing key hashes (key-set Merkle leaves)';
@@ -0,0 +1,52 @@
+-- ============================================================================
+-- Governed Alipay key rotation - quarantine, sightings, audit trail
+-- ============================================================================
+--
+-- A receipt signed by a key the verifier doesn't trust no longer updates the
+-- on-chain key hash. The key is quarantined here instead and only submitted
+-- once it was seen on enough distinct valid receipts (and, with a pinned root,
+-- its certificate chain validated), after a delay or an admin approval.
+--
+-- Status: quarantined -> eligible -> (approved) -> submitted, or rejected
+--
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS pending_key_rotations (
+    "pkHash" VARCHAR(66) PRIMARY KEY,                     -- SHA256 of the DER public key (0x hex)
+    "status" VARCHAR(20) NOT NULL DEFAULT 'quarantined',  -- quarantined/eligible/approved/submitted/rejected
+    "sightings" INTEGER NOT NULL DEFAULT 0,               -- Distinct valid receipts signed by the key
+    "chainValidated" BOOLEAN NOT NULL DEFAULT FALSE,      -- Certificate chain checked against the pinned root
+    "firstTradeId" VARCHAR(66),                           -- Trade whose receipt introduced the key
+    "eligibleAt" TIMESTAMP WITH TIME ZONE,                -- When the sighting threshold was reached
+    "approvedBy" VARCHAR(100),                            -- Admin who approved or rejected
+    "approvedAt" TIMESTAMP WITH TIME ZONE,
+    "txHash" VARCHAR(66),                                 -- updatePublicKeyHash transaction
+    "submittedAt" TIMESTAMP WITH TIME ZONE,
+    "firstSeenAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    "updatedAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS "idx_pending_key_rotations_status" ON pending_key_rotations ("status");
+
+CREATE TABLE IF NOT EXISTS key_rotation_sightings (
+    "pkHash" VARCHAR(66) NOT NULL REFERENCES pending_key_rotations("pkHash"),
+    "transactionId" TEXT NOT NULL,                       -- Alipay transaction ID (one sighting per receipt)
+    "tradeId" VARCHAR(66) NOT NULL,
+    "seenAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY ("pkHash", "transactionId")
+);
+
+CREATE TABLE IF NOT EXISTS key_rotation_audit (
+    "id" BIGSERIAL PRIMARY KEY,
+    "pkHash" VARCHAR(66) NOT NULL,
+    "action" VARCHAR(30) NOT NULL,                        -- quarantined/sighting/chain_failed/eligible/approved/rejected/submitted/submit_failed
+    "actor" VARCHAR(100) NOT NULL,                        -- "relay", or the admin's name
+    "detail" TEXT,
+    "createdAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS "idx_key_rotation_audit_pkHash" ON key_rotation_audit ("pkHash", "createdAt");
+
+COMMENT ON TABLE pending_key_rotations IS 'Alipay signing keys awaiting (or past) governed on-chain rotation';
+COMMENT ON TABLE key_rotation_sightings IS 'Distinct valid receipts signed by a pending key';
+COMMENT ON TABLE key_rotation_audit IS 'Append-only audit trail of every key rotation step';
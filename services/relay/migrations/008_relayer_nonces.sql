@@ -0,0 +1,40 @@
+-- ============================================================================
+-- Relayer nonces and pending transactions - shared by every relay process
+-- ============================================================================
+--
+-- The API server and auto-cancel sign with the same relayer key. Nonces are
+-- allocated under a Postgres advisory lock on the relayer address, so processes
+-- never race on a nonce, and every broadcast transaction is tracked until it is
+-- mined. A transaction stuck in the mempool is replaced (same nonce, higher gas
+-- price); "txHashes" keeps every broadcast so any of them can be matched.
+--
+-- Status: pending -> mined / reverted / dropped
+--
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS relayer_nonces (
+    "address" VARCHAR(42) PRIMARY KEY,                    -- Relayer address (0x lowercase hex)
+    "nextNonce" BIGINT NOT NULL,                          -- Next nonce to allocate
+    "updatedAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS relayer_pending_transactions (
+    "id" BIGSERIAL PRIMARY KEY,
+    "address" VARCHAR(42) NOT NULL,                       -- Relayer address (0x lowercase hex)
+    "nonce" BIGINT NOT NULL,
+    "operation" VARCHAR(50) NOT NULL,                     -- submitProof, fillOrder, cancelExpiredTrade, ...
+    "txHash" VARCHAR(66) NOT NULL,                        -- Latest broadcast (replacements change it)
+    "txHashes" TEXT[] NOT NULL,                           -- Every broadcast for this nonce
+    "gasPrice" NUMERIC(78,0) NOT NULL,                    -- Gas price of the latest broadcast (wei)
+    "replacements" INTEGER NOT NULL DEFAULT 0,
+    "txRequest" TEXT NOT NULL,                            -- Unsigned transaction (JSON), re-signed on replacement
+    "status" VARCHAR(20) NOT NULL DEFAULT 'pending',      -- pending/mined/reverted/dropped
+    "minedTxHash" VARCHAR(66),                            -- The broadcast that was mined
+    "createdAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    "updatedAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS "idx_relayer_pending_transactions_status" ON relayer_pending_transactions ("address", "status", "nonce");
+
+COMMENT ON TABLE relayer_nonces IS 'Next nonce per relayer address (allocated under an advisory lock)';
+COMMENT ON TABLE relayer_pending_transactions IS 'Relayer transactions tracked until mined (stuck ones are replaced)';
@@ -0,0 +1,15 @@
+-- ============================================================================
+-- Relayer transaction fees - EIP-1559 pricing and the gas actually paid
+-- ============================================================================
+--
+-- "gasPrice" is the legacy gas price or, for EIP-1559 transactions, the max fee
+-- per gas. The gas paid is recorded from the receipt of the broadcast that was
+-- mined (also for reverted transactions, which still pay for gas).
+--
+-- ============================================================================
+
+ALTER TABLE relayer_pending_transactions ADD COLUMN IF NOT EXISTS "maxPriorityFeePerGas" NUMERIC(78,0);  -- EIP-1559 tip (NULL for legacy)
+ALTER TABLE relayer_pending_transactions ADD COLUMN IF NOT EXISTS "urgency" VARCHAR(10) NOT NULL DEFAULT 'normal';  -- low/normal/high
+ALTER TABLE relayer_pending_transactions ADD COLUMN IF NOT EXISTS "gasUsed" NUMERIC(78,0);
+ALTER TABLE relayer_pending_transactions ADD COLUMN IF NOT EXISTS "effectiveGasPrice" NUMERIC(78,0);  -- wei per gas actually paid
+ALTER TABLE relayer_pending_transactions ADD COLUMN IF NOT EXISTS "gasCost" NUMERIC(78,0);            -- gasUsed x effectiveGasPrice (wei)
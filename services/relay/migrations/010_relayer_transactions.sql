@@ -0,0 +1,33 @@
+-- ============================================================================
+-- Relayer transactions - gas accounting for every mined relayer transaction
+-- ============================================================================
+--
+-- One row per mined (or reverted - it still paid gas) relayer transaction, from
+-- the API server and auto-cancel alike. The last hour's "gasCost" feeds the
+-- spend circuit breaker (RELAYER_HOURLY_SPEND_CAP_WEI).
+--
+-- "tradeId" is NULL for transactions not tied to one trade: batch settlements
+-- (see settlement_batches."settlementTxHash") and key rotations.
+--
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS relayer_transactions (
+    "txHash" VARCHAR(66) PRIMARY KEY,
+    "address" VARCHAR(42) NOT NULL,                       -- Relayer address (0x lowercase hex)
+    "operation" VARCHAR(50) NOT NULL,                     -- submitProof, fillOrder, cancelExpiredTrade, ...
+    "tradeId" VARCHAR(66),
+    "status" VARCHAR(20) NOT NULL,                        -- mined/reverted
+    "gasUsed" NUMERIC(78,0) NOT NULL,
+    "effectiveGasPrice" NUMERIC(78,0) NOT NULL,           -- wei per gas
+    "gasCost" NUMERIC(78,0) NOT NULL,                     -- gasUsed x effectiveGasPrice (wei)
+    "blockNumber" BIGINT,
+    "createdAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS "idx_relayer_transactions_created" ON relayer_transactions ("createdAt");
+CREATE INDEX IF NOT EXISTS "idx_relayer_transactions_trade" ON relayer_transactions ("tradeId");
+
+-- Trade of a pending transaction, so recovered transactions are accounted too
+ALTER TABLE relayer_pending_transactions ADD COLUMN IF NOT EXISTS "tradeId" VARCHAR(66);
+
+COMMENT ON TABLE relayer_transactions IS 'Gas paid by mined relayer transactions (spend cap and accounting)';
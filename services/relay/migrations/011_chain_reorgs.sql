@@ -0,0 +1,53 @@
+-- ============================================================================
+-- Reorg-safe event ingestion - block hashes and the applied-event journal
+-- ============================================================================
+--
+-- The event listener records the hash of every block it checkpoints (the last
+-- block of each synced range) and of every block that carried an escrow event.
+-- Before syncing further it checks the next block's parent hash against the
+-- checkpoint; on a mismatch it walks back to the last stored block that is
+-- still canonical (the common ancestor).
+--
+-- Every applied event is journaled with its raw log, so a reorg is rolled back
+-- by undoing the events above the ancestor newest-first (amounts, statuses,
+-- withdrawals, exchange rates) before the canonical blocks are replayed.
+--
+-- Rolled-back OrderCreated / TradeCreated keep their rows (payment info, PDFs,
+-- proofs) and are marked "orphaned": usually the same transaction is mined again
+-- and replayed. Orders and trades whose creation never comes back are deleted
+-- once the replay is well past the orphaned blocks.
+--
+-- Both tables are pruned past the reorg window (REORG_RETENTION_BLOCKS).
+--
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS event_sync_blocks (
+    "contractAddress" VARCHAR(42) NOT NULL,
+    "blockNumber" BIGINT NOT NULL,
+    "blockHash" VARCHAR(66) NOT NULL,
+    "createdAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY ("contractAddress", "blockNumber")
+);
+
+CREATE TABLE IF NOT EXISTS applied_events (
+    "id" BIGSERIAL PRIMARY KEY,
+    "contractAddress" VARCHAR(42) NOT NULL,
+    "blockNumber" BIGINT NOT NULL,
+    "blockHash" VARCHAR(66) NOT NULL,
+    "txHash" VARCHAR(66) NOT NULL,
+    "logIndex" BIGINT NOT NULL,
+    "event" VARCHAR(50) NOT NULL,                         -- OrderCreated, TradeSettled, ...
+    "entityId" VARCHAR(66),                               -- Order or trade ID the event applies to
+    "topics" TEXT[] NOT NULL,                             -- Raw log topics (0x hex)
+    "data" TEXT NOT NULL,                                 -- Raw log data (0x hex)
+    "orphaned" BOOLEAN NOT NULL DEFAULT FALSE,            -- Rolled back by a reorg
+    "createdAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS "idx_applied_events_block" ON applied_events ("contractAddress", "blockNumber");
+CREATE INDEX IF NOT EXISTS "idx_applied_events_entity" ON applied_events ("entityId") WHERE "entityId" IS NOT NULL;
+CREATE INDEX IF NOT EXISTS "idx_applied_events_orphaned" ON applied_events ("contractAddress") WHERE "orphaned";
+
+COMMENT ON TABLE event_sync_blocks IS 'Hashes of synced blocks (checkpoints and blocks with events) for reorg detection';
+COMMENT ON TABLE applied_events IS 'Journal of applied escrow events, undone newest-first on a reorg';
@@ -0,0 +1,55 @@
+-- ============================================================================
+-- Idempotent event processing - processed logs and the dead-letter queue
+-- ============================================================================
+--
+-- The event listener applies each synced block range in one Postgres
+-- transaction, together with the new sync checkpoint: a crash or DB error
+-- rolls the whole range back and it is synced again.
+--
+-- Every log is claimed in processed_logs by (txHash, logIndex) before its
+-- handler runs, so a log is never applied twice (no double withdrawals or
+-- amount adjustments). A reorg rollback forgets the claims of undone blocks.
+--
+-- A log whose handler fails is rolled back to its savepoint and stored in
+-- failed_logs with the error; the range still commits. Failed logs are
+-- retried oldest-first with exponential backoff (at most 10 attempts), then
+-- kept for inspection ("nextRetryAt" NULL).
+--
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS processed_logs (
+    "txHash" VARCHAR(66) NOT NULL,
+    "logIndex" BIGINT NOT NULL,
+    "contractAddress" VARCHAR(42) NOT NULL,
+    "blockNumber" BIGINT NOT NULL,
+    "blockHash" VARCHAR(66) NOT NULL,
+    "processedAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY ("txHash", "logIndex")
+);
+
+CREATE INDEX IF NOT EXISTS "idx_processed_logs_block" ON processed_logs ("contractAddress", "blockNumber");
+
+CREATE TABLE IF NOT EXISTS failed_logs (
+    "id" BIGSERIAL PRIMARY KEY,
+    "contractAddress" VARCHAR(42) NOT NULL,
+    "blockNumber" BIGINT NOT NULL,
+    "blockHash" VARCHAR(66) NOT NULL,
+    "txHash" VARCHAR(66) NOT NULL,
+    "logIndex" BIGINT NOT NULL,
+    "event" VARCHAR(50) NOT NULL,                         -- OrderCreated, TradeSettled, ...
+    "topics" TEXT[] NOT NULL,                             -- Raw log topics (0x hex)
+    "data" TEXT NOT NULL,                                 -- Raw log data (0x hex)
+    "error" TEXT NOT NULL,                                -- Last handler error
+    "attempts" INTEGER NOT NULL DEFAULT 1,
+    "nextRetryAt" TIMESTAMP WITH TIME ZONE,               -- NULL = retries exhausted
+    "createdAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    "updatedAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    UNIQUE ("txHash", "logIndex")
+);
+
+CREATE INDEX IF NOT EXISTS "idx_failed_logs_retry" ON failed_logs ("contractAddress", "nextRetryAt") WHERE "nextRetryAt" IS NOT NULL;
+
+COMMENT ON TABLE processed_logs IS 'Applied event logs by (txHash, logIndex) - makes re-syncing a range idempotent';
+COMMENT ON TABLE failed_logs IS 'Dead-letter queue: event logs whose handler failed, retried with backoff';
@@ -0,0 +1,33 @@
+-- ============================================================================
+-- Reindex runs - resumable replays of chain history (lyncz-reindex)
+-- ============================================================================
+--
+-- lyncz-reindex replays the escrow's logs over a block range through the event
+-- listener's handlers, one chunk per transaction. Each run records the next
+-- block to replay, so an interrupted run resumes where it stopped.
+--
+-- Replays are idempotent: processed_logs (012) is the ledger of applied logs
+-- and is no longer pruned, so logs the database already has are skipped.
+-- "baseline" runs only record logs as applied (databases synced before 012).
+--
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS reindex_runs (
+    "id" BIGSERIAL PRIMARY KEY,
+    "contractAddress" VARCHAR(42) NOT NULL,
+    "mode" VARCHAR(20) NOT NULL,                          -- apply/baseline
+    "fromBlock" BIGINT NOT NULL,
+    "toBlock" BIGINT NOT NULL,
+    "chunkSize" BIGINT NOT NULL,
+    "nextBlock" BIGINT NOT NULL,                          -- Next block to replay (> toBlock = done)
+    "logsApplied" BIGINT NOT NULL DEFAULT 0,
+    "logsSkipped" BIGINT NOT NULL DEFAULT 0,              -- Already applied
+    "logsFailed" BIGINT NOT NULL DEFAULT 0,               -- Dead-lettered (see failed_logs)
+    "startedAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    "updatedAt" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    "completedAt" TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS "idx_reindex_runs_unfinished" ON reindex_runs ("contractAddress") WHERE "completedAt" IS NULL;
+
+COMMENT ON TABLE reindex_runs IS 'Progress of lyncz-reindex replays (resumable in chunks)';
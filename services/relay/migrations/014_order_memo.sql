@@ -0,0 +1,14 @@
+-- ============================================================================
+-- Order memo binding
+-- ============================================================================
+--
+-- Sellers require the trade reference code in the Alipay transfer memo (备注)
+-- per order (setOrderMemoRequired). fillOrder copies the setting to each new
+-- trade and emits TradeMemoRequired (004), so the buyer cannot opt out.
+-- Synced from OrderMemoRequirementUpdated.
+--
+-- ============================================================================
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS "memoRequired" BOOLEAN NOT NULL DEFAULT FALSE;
+
+COMMENT ON COLUMN orders."memoRequired" IS 'New trades must carry the trade reference code in the Alipay memo';
@@ -0,0 +1,35 @@
+-- ============================================================================
+-- Key rotation sightings per trade
+-- ============================================================================
+--
+-- The transaction ID comes from the uploaded PDF, so counting sightings per
+-- transaction ID let one trade upload several self-signed receipts and make a
+-- key eligible on its own. A sighting is now one trade, and a key also needs
+-- enough distinct buyers and orders before it can be submitted.
+--
+-- Legacy sightings keep one row per trade and don't count towards buyers or
+-- orders (unknown), so keys seen before this migration must be sighted again.
+--
+-- ============================================================================
+
+DELETE FROM key_rotation_sightings s
+USING key_rotation_sightings other
+WHERE s."pkHash" = other."pkHash"
+  AND s."tradeId" = other."tradeId"
+  AND (s."seenAt", s."transactionId") > (other."seenAt", other."transactionId");
+
+ALTER TABLE key_rotation_sightings DROP CONSTRAINT IF EXISTS key_rotation_sightings_pkey;
+ALTER TABLE key_rotation_sightings ADD PRIMARY KEY ("pkHash", "tradeId");
+ALTER TABLE key_rotation_sightings ADD COLUMN IF NOT EXISTS "buyer" VARCHAR(42);
+ALTER TABLE key_rotation_sightings ADD COLUMN IF NOT EXISTS "orderId" VARCHAR(66);
+
+ALTER TABLE pending_key_rotations ADD COLUMN IF NOT EXISTS "buyers" INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE pending_key_rotations ADD COLUMN IF NOT EXISTS "orders" INTEGER NOT NULL DEFAULT 0;
+
+UPDATE pending_key_rotations r
+SET "sightings" = (SELECT COUNT(*) FROM key_rotation_sightings s WHERE s."pkHash" = r."pkHash");
+
+COMMENT ON COLUMN pending_key_rotations."sightings" IS 'Distinct trades with a valid receipt signed by the key';
+COMMENT ON COLUMN pending_key_rotations."buyers" IS 'Distinct buyers among the sighting trades';
+COMMENT ON COLUMN pending_key_rotations."orders" IS 'Distinct orders among the sighting trades';
+COMMENT ON TABLE key_rotation_sightings IS 'Trades with a valid receipt signed by a pending key (one row per trade)';
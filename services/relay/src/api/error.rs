@@ -4,6 +4,8 @@ use axum::{
     Json,
 };
 use serde_json::json;
+use crate::blockchain::client::EthereumClientError;
+use crate::blockchain::errors::ContractRevert;
 use crate::db::DbError;
 
 /// API error type that can be converted to HTTP responses
@@ -15,6 +17,9 @@ pub enum ApiError {
     /// Blockchain errors
     BlockchainError(String),
     
+    /// Decoded contract revert (custom error from escrow/verifier ABI)
+    ContractRevert(ContractRevert),
+    
     /// Invalid request (validation errors)
     BadRequest(String),
     
@@ -38,8 +43,21 @@ impl From<DbError> for ApiError {
     }
 }
 
+impl From<EthereumClientError> for ApiError {
+    fn from(err: EthereumClientError) -> Self {
+        match err {
+            EthereumClientError::Reverted(revert) => ApiError::ContractRevert(revert),
+            EthereumClientError::StillPending(tx_hash) => ApiError::BadRequest(format!(
+                "Transaction {:#x} is still pending. Please wait for it to be mined.", tx_hash
+            )),
+            _ => ApiError::BlockchainError(err.to_string()),
+        }
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
+        let mut code = None;
         let (status, error_message) = match self {
             ApiError::Database(err) => {
                 // Log the actual database error for debugging
@@ -63,14 +81,29 @@ impl IntoResponse for ApiError {
                 tracing::error!("Internal error: {}", msg);
                 (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
             }
+            ApiError::ContractRevert(revert) => {
+                code = Some(revert.code());
+                let status = if revert.is_client_error() {
+                    StatusCode::BAD_REQUEST
+                } else if revert.code() == "CONTRACT_PAUSED" {
+                    StatusCode::SERVICE_UNAVAILABLE
+                } else {
+                    tracing::error!("Contract revert: {}", revert);
+                    StatusCode::BAD_GATEWAY
+                };
+                (status, revert.message())
+            }
         };
 
-        let body = Json(json!({
+        let mut body = json!({
             "error": error_message,
             "status": status.as_u16(),
-        }));
+        });
+        if let Some(code) = code {
+            body["code"] = json!(code);
+        }
 
-        (status, body).into_response()
+        (status, Json(body)).into_response()
     }
 }
 
@@ -0,0 +1,126 @@
+//! Live trade update streams - SSE and WebSocket
+//!
+//! Both endpoints send a snapshot of the trade first, then every TradeEvent for
+//! that trade from the internal broadcast bus (settlement state, Axiom proof status,
+//! submitProof tx sent/confirmed, on-chain status from the event listener). A client
+//! that falls behind gets a `lagged` event/message and should refetch the trade.
+
+use std::convert::Infallible;
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Response,
+    },
+};
+use futures_util::stream::{self, Stream, StreamExt};
+
+use crate::api::{error::ApiResult, state::AppState};
+use crate::trade_events::{TradeEvent, TradeSubscription};
+
+/// Current trade status + settlement state, sent first on every stream
+async fn snapshot(state: &AppState, trade_id: &str) -> ApiResult<TradeEvent> {
+    let trade = state.db.get_trade(trade_id).await?;
+    let settlement_state = state.db.get_settlement_state(trade_id).await?;
+
+    Ok(TradeEvent::Snapshot {
+        trade_id: trade.trade_id,
+        status: trade.status,
+        settlement_state,
+    })
+}
+
+fn to_sse_event(event: &TradeEvent) -> Event {
+    Event::default()
+        .event(event.name())
+        .json_data(event)
+        .unwrap_or_else(|_| Event::default().event("error").data("serialization failed"))
+}
+
+/// GET /api/trades/:trade_id/events
+/// Server-Sent Events stream of live trade updates
+pub async fn trade_events_sse(
+    State(state): State<AppState>,
+    Path(trade_id): Path<String>,
+) -> ApiResult<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    // Subscribe before the snapshot so no update falls in between
+    let subscription = state.trade_events.subscribe(&trade_id);
+    let initial = snapshot(&state, &trade_id).await?;
+
+    tracing::info!("📡 SSE subscriber for trade {}", trade_id);
+
+    let updates = stream::unfold(subscription, |mut subscription| async move {
+        let event = subscription.next().await?;
+        if let TradeEvent::Lagged { trade_id, skipped } = &event {
+            // Client should refetch GET /api/trades/:id
+            tracing::warn!("⚠️ SSE subscriber for trade {} lagged by {} events", trade_id, skipped);
+        }
+        Some((Ok(to_sse_event(&event)), subscription))
+    });
+
+    let stream = stream::once(async move { Ok(to_sse_event(&initial)) }).chain(updates);
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::new().interval(Duration::from_secs(15))))
+}
+
+/// GET /api/trades/:trade_id/ws
+/// WebSocket stream of live trade updates (same JSON payloads as SSE, tagged by "type")
+pub async fn trade_events_ws(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Path(trade_id): Path<String>,
+) -> ApiResult<Response> {
+    // Subscribe before the snapshot (as SSE) so no update falls in between, and fail
+    // with a normal HTTP error for unknown trades, before upgrading
+    let subscription = state.trade_events.subscribe(&trade_id);
+    let initial = snapshot(&state, &trade_id).await?;
+
+    Ok(ws.on_upgrade(move |socket| handle_trade_socket(socket, subscription, trade_id, initial)))
+}
+
+async fn handle_trade_socket(
+    mut socket: WebSocket,
+    mut subscription: TradeSubscription,
+    trade_id: String,
+    initial: TradeEvent,
+) {
+    tracing::info!("📡 WebSocket subscriber for trade {}", trade_id);
+
+    if send_event(&mut socket, &initial).await.is_err() {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            event = subscription.next() => match event {
+                Some(event) => {
+                    if let TradeEvent::Lagged { skipped, .. } = &event {
+                        // Sent like SSE's lagged event - client should refetch GET /api/trades/:id
+                        tracing::warn!("⚠️ WebSocket subscriber for trade {} lagged by {} events", trade_id, skipped);
+                    }
+                    if send_event(&mut socket, &event).await.is_err() {
+                        break;
+                    }
+                }
+                None => break,
+            },
+            // Client messages are ignored; only used to detect disconnects
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    tracing::info!("📴 WebSocket subscriber for trade {} disconnected", trade_id);
+}
+
+async fn send_event(socket: &mut WebSocket, event: &TradeEvent) -> Result<(), axum::Error> {
+    let payload = serde_json::to_string(event).unwrap_or_default();
+    socket.send(Message::Text(payload)).await
+}
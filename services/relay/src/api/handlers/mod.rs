@@ -5,8 +5,10 @@
 //! - trades.rs: Read-only trade queries
 //! - settlement.rs: PDF validation and proof submission
 //! - account.rs: Account settings (email notifications) - account-based, not role-based
+//! - events.rs: Live trade updates (SSE / WebSocket)
 
 pub mod account;
+pub mod events;
 pub mod orders;
 pub mod trades;
 pub mod settlement;
@@ -23,13 +25,14 @@ use chrono::Utc;
 use crate::api::{
     error::{ApiError, ApiResult},
     state::AppState,
-    types::HealthResponse,
+    types::{HealthResponse, ProgramHealth, ProverHealth},
 };
 
 // Re-export handlers
 pub use orders::{get_active_orders, get_order, get_order_activities, get_order_by_private_code, set_order_visibility, submit_payment_info};
-pub use trades::{get_trade_handler, get_trades_by_buyer_handler, get_trades_by_seller_handler, create_trade_handler};
-pub use settlement::{validate_handler, settle_handler};
+pub use trades::{get_trade_handler, get_trades_by_buyer_handler, get_trades_by_seller_handler, create_trade_handler, fill_order_calldata_handler};
+pub use events::{trade_events_sse, trade_events_ws};
+pub use settlement::{validate_handler, settle_handler, resubmit_handler, submit_proof_calldata_handler};
 
 /// Health check endpoint
 pub async fn health_check(State(state): State<AppState>) -> ApiResult<Json<HealthResponse>> {
@@ -37,15 +40,66 @@ pub async fn health_check(State(state): State<AppState>) -> ApiResult<Json<Healt
         Ok(_) => "healthy",
         Err(_) => "unhealthy",
     };
-
+    
+    let prover = prover_health(&state).await;
+    let status = if prover.status == "mismatch" { "degraded" } else { "ok" };
+    
     Ok(Json(HealthResponse {
-        status: "ok".to_string(),
+        status: status.to_string(),
         database: db_status.to_string(),
         orderbook: "read-only".to_string(),
+        prover,
         timestamp: Utc::now().to_rfc3339(),
     }))
 }
 
+/// Configured Axiom programs checked against the on-chain verifier commitments
+async fn prover_health(state: &AppState) -> ProverHealth {
+    let Some(programs) = state.programs.as_ref() else {
+        return ProverHealth {
+            status: "unconfigured".to_string(),
+            active_program_id: None,
+            onchain_app_exe_commit: None,
+            onchain_app_vm_commit: None,
+            programs: Vec::new(),
+            error: None,
+        };
+    };
+    
+    let registry = programs.read().await;
+    let mut programs: Vec<ProgramHealth> = registry.programs().into_iter()
+        .map(|program| ProgramHealth {
+            program_id: program.program_id.clone(),
+            app_exe_commit: Some(format!("0x{}", hex::encode(program.commitments.app_exe_commit))),
+            app_vm_commit: Some(format!("0x{}", hex::encode(program.commitments.app_vm_commit))),
+            error: None,
+        })
+        .collect();
+    programs.extend(registry.unresolved().iter().map(|(program_id, error)| ProgramHealth {
+        program_id: program_id.clone(),
+        app_exe_commit: None,
+        app_vm_commit: None,
+        error: Some(error.clone()),
+    }));
+    drop(registry);
+    
+    let onchain = state.onchain_program_commitments(false).await;
+    let active = state.active_program().await;
+    let (status, error) = match &active {
+        Ok(_) => ("matched", None),
+        Err(e) => ("mismatch", Some(e.clone())),
+    };
+    
+    ProverHealth {
+        status: status.to_string(),
+        active_program_id: active.ok().map(|program| program.program_id),
+        onchain_app_exe_commit: onchain.as_ref().ok().map(|c| format!("0x{}", hex::encode(c.app_exe_commit))),
+        onchain_app_vm_commit: onchain.as_ref().ok().map(|c| format!("0x{}", hex::encode(c.app_vm_commit))),
+        programs,
+        error,
+    }
+}
+
 /// Debug database endpoint - returns all orders and trades for debugging purposes
 /// GET /api/debug/database
 pub async fn debug_database(State(state): State<AppState>) -> ApiResult<Json<serde_json::Value>> {
@@ -78,6 +132,56 @@ pub async fn get_contract_config(
     Ok(Json(serde_json::json!(config)))
 }
 
+/// GET /api/admin/key-rotations - Governed Alipay key rotations, newest first
+pub async fn get_key_rotations(State(state): State<AppState>) -> ApiResult<Json<serde_json::Value>> {
+    let rotations = state.db.get_key_rotations(&[]).await?;
+    
+    Ok(Json(serde_json::json!({
+        "min_receipts": state.key_rotation.min_receipts,
+        "delay_secs": state.key_rotation.delay.num_seconds(),
+        "pinned_root": state.key_rotation.pinned_root.is_some(),
+        "rotations": rotations,
+    })))
+}
+
+/// GET /api/admin/key-rotations/:pk_hash - One key rotation with its audit trail
+pub async fn get_key_rotation(
+    State(state): State<AppState>,
+    Path(pk_hash): Path<String>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let rotation = state.db.get_key_rotation(&pk_hash).await?
+        .ok_or_else(|| ApiError::NotFound(format!("Key rotation {} not found", pk_hash)))?;
+    let audit = state.db.get_key_rotation_audit(&pk_hash).await?;
+    
+    Ok(Json(serde_json::json!({
+        "rotation": rotation,
+        "audit": audit,
+    })))
+}
+
+/// GET /api/admin/relayer - Relayer balance, last hour's gas spend and recent transactions
+pub async fn get_relayer_status(State(state): State<AppState>) -> ApiResult<Json<serde_json::Value>> {
+    let blockchain_client = state.blockchain_client.as_ref()
+        .ok_or_else(|| ApiError::ServiceUnavailable("Blockchain not enabled".to_string()))?;
+    
+    let balance = blockchain_client.get_relayer_balance().await?;
+    let spend = crate::gas_budget::spend_status(&state).await
+        .map_err(ApiError::Database)?;
+    let transactions = state.db.get_recent_relayer_transactions(50).await?;
+    
+    Ok(Json(serde_json::json!({
+        "address": format!("{:#x}", blockchain_client.relayer_address()),
+        "balance_wei": balance.to_string(),
+        "low_balance_wei": state.gas_budget.low_balance.to_string(),
+        "low_balance": balance < state.gas_budget.low_balance,
+        "spent_last_hour_wei": spend.spent_last_hour.to_string(),
+        "pending_wei": spend.pending.to_string(),
+        "hourly_spend_cap_wei": spend.hourly_spend_cap.map(|cap| cap.to_string()),
+        "sponsoring": !spend.exceeded(),
+        "transactions": transactions,
+    })))
+}
+
 // ============ Admin Write Endpoints REMOVED for Security ============
 // All contract modifications must be done directly via cast/forge with the owner wallet.
 // This prevents public API from being exploited to modify contract state.
@@ -91,6 +195,9 @@ pub async fn get_contract_config(
 // - POST /api/admin/update-eth-price
 // - POST /api/admin/update-btc-price
 //
+// Key rotations are approved or rejected with the key-rotation CLI (database access),
+// never over the public API: key-rotation approve <pk_hash> --by <name>
+//
 // To modify contract config, use cast directly:
 // cast send --rpc-url $RPC --private-key $OWNER_KEY $CONTRACT "setMinTradeValue(uint256)" 10000
 
@@ -71,6 +71,8 @@ pub struct OrderDto {
     pub is_public: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub private_code: Option<String>,
+    /// Buyers must put the trade reference code in the Alipay memo (备注)
+    pub memo_required: bool,
 }
 
 /// List of orders response
@@ -215,6 +217,7 @@ fn order_to_dto(o: crate::db::models::DbOrder) -> OrderDto {
         created_at: o.created_at,
         is_public: o.is_public,
         private_code: o.private_code,
+        memo_required: o.memo_required,
     }
 }
 
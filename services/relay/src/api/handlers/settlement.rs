@@ -4,8 +4,14 @@
 //! 1. POST /validate - Upload PDF + Quick validation (~10 seconds)
 //! 2. POST /settle   - Generate proof + Submit to blockchain (~2-3 minutes)
 //!
+//! POST /resubmit re-sends a stored proof if submission failed - proofs are never regenerated.
+//! GET /calldata exports the same submitProof call unsigned, for self-submission.
+//!
+//! With SETTLEMENT_BATCH_WINDOW_SECS set, validated trades are queued and proved in
+//! batches (see crate::settlement_batch) - one proof and one submitBatchProof per batch.
+//!
 //! Data sources:
-//! - ORDER: alipay_name (line 20), alipay_id → masked (line 21)
+//! - ORDER: alipay_name (line 20), alipay_id → masked (line 21) - via on-chain accountLinesHash
 //! - TRADE: cny_amount (line 29)
 //! - PDF: transaction_id (line 25), payment_time (line 27)
 //! - CONTRACT: alipayPublicKeyHash
@@ -16,14 +22,36 @@ use axum::{
 };
 use serde::Serialize;
 use crate::api::{error::{ApiError, ApiResult}, state::AppState};
-use crate::axiom_prover::AxiomProver;
-use crate::blockchain::types::trade_id_to_bytes32;
+use crate::axiom_prover::{AxiomProver, GeneratedProof};
+use crate::axiom_prover::programs::AmountMode;
+use crate::axiom_prover::diagnostics::{ExpectedComponents, GuestDiagnostics};
+use crate::blockchain::client::EthereumClientError;
+use crate::blockchain::fees::FeeUrgency;
+use crate::blockchain::lync_z_escrow::BatchSettlement;
+use crate::blockchain::types::{trade_id_to_bytes32, UnsignedTransaction};
+use crate::db::models::{DbSettlementBatch, DbTrade, SettlementState};
+use crate::gas_budget;
+use crate::key_rotation::{self, KeySighting};
+use crate::key_set::KeyWitness;
+use crate::payment_time::{PaymentTime, PaymentTimeError};
+use crate::settlement_batch::{BatchItem, BatchReceiver};
+use crate::trade_events::TradeEvent;
 use crate::crypto::{
     compute_tx_id_hash,
+    compute_time_amount_hash,
+    compute_memo_hash,
+    compute_account_lines_hash_from_lines,
     compute_expected_hash_with_onchain_account_hash,
-    format_amount_line,
+    format_memo_line,
+    trade_memo_code,
 };
-use openvm::serde::to_vec as openvm_serialize;
+use ethers::types::H256;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use input_streams::{InputStreams, ReceiptInput};
+use pdf_core::amount::format_amount_line;
+use pdf_core::hints::ReceiptHints;
 
 // ============================================================================
 // PDF Parsing - Extract transaction_id, payment_time, and public key hash
@@ -31,9 +59,40 @@ use openvm::serde::to_vec as openvm_serialize;
 
 /// Extracted PDF fields for validation
 pub struct PdfExtractedFields {
+    pub account_name_line: String,  // Line 20 (账户名：...)
+    pub account_id_line: String,    // Line 21 (账号：..., masked)
     pub transaction_id: String,     // Line 25
     pub payment_time: String,       // Line 27
+    pub amount_line: String,        // Line 29 (小写：X.YY)
     pub public_key_der_hash: [u8; 32],
+    pub signature_valid: bool,      // PKCS#7 signature over the PDF verifies
+    pub lines: Vec<String>,         // All page 1 lines (memo position varies by receipt)
+}
+
+impl PdfExtractedFields {
+    /// 1-based line number of the line exactly matching `expected` (e.g. the memo line)
+    fn find_line(&self, expected: &str) -> Option<u32> {
+        self.lines.iter().position(|line| line == expected).map(|idx| idx as u32 + 1)
+    }
+    
+    /// Recipient hash of lines 20/21 (compared with the order's on-chain accountLinesHash)
+    fn account_lines_hash(&self) -> [u8; 32] {
+        compute_account_lines_hash_from_lines(&self.account_name_line, &self.account_id_line)
+    }
+    
+    fn pays_account(&self, account_lines_hash: &[u8; 32]) -> bool {
+        self.account_lines_hash() == *account_lines_hash
+    }
+    
+    /// The trade's memo line and its line number, or the rejection message
+    fn find_memo_line(&self, trade_id: &[u8; 32]) -> Result<(String, u32), String> {
+        let memo_code = trade_memo_code(trade_id);
+        let memo_line = format_memo_line(&memo_code);
+        match self.find_line(&memo_line) {
+            Some(line_number) => Ok((memo_line, line_number)),
+            None => Err(format!("The payment memo doesn't contain this trade's reference code. Expected memo: {}", memo_code)),
+        }
+    }
 }
 
 /// Parse Alipay PDF to extract account info, transaction_id, payment_time,
@@ -56,12 +115,16 @@ fn extract_pdf_fields(pdf_bytes: &[u8]) -> Result<PdfExtractedFields, String> {
             .ok_or_else(|| format!("Line {} not found (PDF has {} lines)", idx, lines.len()))
     };
     
-    // Extract only the lines we need for hash computation
-    // Line 20, 21 (account info) hash comes from blockchain, not PDF extraction
-    let transaction_id = get_line(25)?;  // Alipay transaction ID
-    let payment_time = get_line(27)?;    // Payment timestamp
+    // Extract the lines committed to by the guest program
+    // Lines 20/21 and 29 are only used for local pre-checks - the expected hash
+    // uses the on-chain accountLinesHash and the trade amount instead
+    let account_name_line = get_line(20)?; // Recipient account name
+    let account_id_line = get_line(21)?;   // Recipient account ID (masked)
+    let transaction_id = get_line(25)?;    // Alipay transaction ID
+    let payment_time = get_line(27)?;      // Payment timestamp
+    let amount_line = get_line(29)?;       // Payment amount
     
-    // Extract public key DER hash from PDF signature (optimistic key rotation)
+    // Extract public key DER hash from PDF signature (key commitment / key rotation)
     let pk_hash_vec = signature_validator::extract_public_key_hash(pdf_bytes)
         .map_err(|e| format!("Failed to extract public key hash: {}", e))?;
     
@@ -72,25 +135,23 @@ fn extract_pdf_fields(pdf_bytes: &[u8]) -> Result<PdfExtractedFields, String> {
     let mut public_key_der_hash = [0u8; 32];
     public_key_der_hash.copy_from_slice(&pk_hash_vec);
     
+    // Full signature verification (same check the guest program performs)
+    let signature_valid = signature_validator::verify_pdf_signature(pdf_bytes)
+        .map(|result| result.is_valid)
+        .unwrap_or(false);
+    
     Ok(PdfExtractedFields {
+        account_name_line,
+        account_id_line,
         transaction_id,
         payment_time,
+        amount_line,
         public_key_der_hash,
+        signature_valid,
+        lines: lines.iter().map(|s| s.to_string()).collect(),
     })
 }
 
-/// Parse payment time string (format: "YYYY-MM-DD HH:MM:SS") to Unix timestamp
-fn parse_payment_time(payment_time: &str) -> Result<u64, String> {
-    // Expected format: "2025-12-27 08:36:12"
-    use chrono::NaiveDateTime;
-    
-    let dt = NaiveDateTime::parse_from_str(payment_time, "%Y-%m-%d %H:%M:%S")
-        .map_err(|e| format!("Failed to parse payment time '{}': {}", payment_time, e))?;
-    
-    // Convert to UTC timestamp (Alipay uses UTC+8, so subtract 8 hours)
-    Ok(dt.and_utc().timestamp() as u64 - 8 * 3600)
-}
-
 // NOTE: Hash computation functions moved to crate::crypto::hash module
 
 // ============================================================================
@@ -104,10 +165,17 @@ pub struct ValidateResponse {
     pub actual_hash: String,
     pub message: String,
     /// Error/success code for frontend translation
-    /// Codes: SUCCESS, REPLAY_ATTACK, PAYMENT_TOO_OLD, HASH_MISMATCH
+    /// Codes: SUCCESS, REPLAY_ATTACK, PAYMENT_TOO_OLD, PAYMENT_AFTER_EXPIRY, PAYMENT_TIME_IN_FUTURE,
+    ///        INVALID_PAYMENT_TIME_FORMAT, AMOUNT_MISMATCH, RECIPIENT_MISMATCH, MEMO_MISMATCH, SIGNATURE_INVALID, RECEIPT_IN_USE, HASH_MISMATCH,
+    ///        KEY_ROTATION_PENDING, KEY_UNTRUSTED
     pub validation_code: String,
     pub transaction_id: String,
     pub payment_time: String,
+    /// HASH_MISMATCH with the diagnostic guest build only - which components differed
+    /// (pdf, signature, line_missing, amount_unparseable, amount_too_low,
+    ///  public_key, account, tx_id, time_amount, memo)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mismatched_components: Vec<String>,
 }
 
 /// POST /api/trades/:trade_id/validate
@@ -119,6 +187,11 @@ pub async fn validate_handler(
 ) -> ApiResult<Json<ValidateResponse>> {
     tracing::info!("⚡ Starting validation for trade {}", trade_id);
     
+    // Refuse up front if no configured program matches the on-chain verifier
+    // (its proofs would always revert)
+    let program = state.active_program().await
+        .map_err(|e| ApiError::ServiceUnavailable(format!("Prover program check failed: {}", e)))?;
+    
     // Step 1: Extract PDF from multipart
     let mut pdf_data: Option<Vec<u8>> = None;
     let mut filename: Option<String> = None;
@@ -149,80 +222,87 @@ pub async fn validate_handler(
     // Step 2: Extract transaction_id, payment_time, and public key hash from PDF
     let pdf_fields = extract_pdf_fields(&pdf_data)
         .map_err(|e| ApiError::BadRequest(format!("PDF parsing failed: {}", e)))?;
-    let transaction_id = pdf_fields.transaction_id;
-    let payment_time = pdf_fields.payment_time;
+    let transaction_id = pdf_fields.transaction_id.clone();
+    let payment_time = pdf_fields.payment_time.clone();
     let pdf_pk_hash = pdf_fields.public_key_der_hash;
     let pdf_pk_hash_hex = hex::encode(&pdf_pk_hash);
     tracing::info!("📋 Extracted: txid={}, time={}, pk_hash={}", transaction_id, payment_time, &pdf_pk_hash_hex[..16]);
     
-    // Step 3: Save PDF to database
-    state.db.save_trade_pdf(&trade_id, &pdf_data, &filename).await
+    // Step 3: Get trade (source of truth for line 29 amount)
+    let trade = state.db.get_trade(&trade_id).await
         .map_err(|e| ApiError::Database(e.to_string()))?;
+    ensure_receipt_replaceable(&state, &trade).await?;
     
-    // Step 4: Get trade (source of truth for line 29 amount)
-    let trade = state.db.get_trade(&trade_id).await
+    // Step 4: Save PDF to database
+    state.db.save_trade_pdf(&trade_id, &pdf_data, &filename).await
         .map_err(|e| ApiError::Database(e.to_string()))?;
+    record_settlement_state(&state, &trade_id, SettlementState::ReceiptUploaded, Some(&filename)).await;
+    
+    record_settlement_state(&state, &trade_id, SettlementState::Validating, None).await;
     
     // ===== PRE-CHECKS (before OpenVM execution) =====
     
-    // Pre-check 1: Verify transaction ID hasn't been used in any settled trade
+    // Pre-check 1: Verify the PDF signature (forged or edited receipts)
+    tracing::info!("🔍 Pre-check: Verifying PDF signature...");
+    if !pdf_fields.signature_valid {
+        tracing::warn!("❌ Pre-check failed: PDF signature invalid");
+        return reject_receipt(
+            &state, &trade_id, "SIGNATURE_INVALID",
+            "The receipt's digital signature is invalid. Please upload the original PDF downloaded from Alipay, without modifications.".to_string(),
+            transaction_id, payment_time,
+        ).await;
+    }
+    
+    // Pre-check 2: Verify transaction ID hasn't been used in any settled trade
     tracing::info!("🔍 Pre-check: Verifying transaction ID not already used...");
     let txid_used = state.db.is_transaction_id_used(&transaction_id).await
         .map_err(|e| ApiError::Database(e.to_string()))?;
     if txid_used {
         tracing::warn!("❌ Pre-check failed: Transaction ID {} already used", transaction_id);
-        // Clear PDF so user can try with a different receipt
-        if let Err(e) = state.db.clear_trade_pdf(&trade_id).await {
-            tracing::error!("Failed to clear PDF after pre-check failure: {}", e);
-        }
-        return Ok(Json(ValidateResponse {
-            valid: false,
-            expected_hash: String::new(),
-            actual_hash: String::new(),
-            message: "This payment receipt has already been used for another trade (replay attack detected).".to_string(),
-            validation_code: "REPLAY_ATTACK".to_string(),
-            transaction_id: transaction_id.clone(),
-            payment_time: payment_time.clone(),
-        }));
+        return reject_receipt(
+            &state, &trade_id, "REPLAY_ATTACK",
+            "This payment receipt has already been used for another trade (replay attack detected).".to_string(),
+            transaction_id, payment_time,
+        ).await;
     }
     
-    // Pre-check 2: Verify payment time is after trade creation
+    // Pre-check 3: Verify payment time (Asia/Shanghai) is within the trade's payment window,
+    // not in the future, and printed in the exact format the contract accepts
     tracing::info!("🔍 Pre-check: Verifying payment time is valid...");
-    let payment_timestamp = parse_payment_time(&payment_time)
-        .map_err(|e| ApiError::BadRequest(format!("Invalid payment time format: {}", e)))?;
-    
-    if payment_timestamp < trade.created_at as u64 {
-        tracing::warn!("❌ Pre-check failed: Payment time {} is before trade creation {}", payment_timestamp, trade.created_at);
-        // Clear PDF so user can try with a different receipt
-        if let Err(e) = state.db.clear_trade_pdf(&trade_id).await {
-            tracing::error!("Failed to clear PDF after pre-check failure: {}", e);
-        }
-        return Ok(Json(ValidateResponse {
-            valid: false,
-            expected_hash: String::new(),
-            actual_hash: String::new(),
-            message: format!("Payment was made before the trade was created. Receipt time: {}, Trade created: {}", payment_time, trade.created_at),
-            validation_code: "PAYMENT_TOO_OLD".to_string(),
-            transaction_id: transaction_id.clone(),
-            payment_time: payment_time.clone(),
-        }));
+    let payment_check = PaymentTime::parse(&payment_time).and_then(|time| {
+        time.check_window(trade.created_at, trade.expires_at, chrono::Utc::now().timestamp())?;
+        time.require_contract_format()?;
+        Ok(time)
+    });
+    
+    if let Err(e) = payment_check {
+        tracing::warn!("❌ Pre-check failed: {}", e);
+        let message = payment_time_message(&e, &payment_time, &trade);
+        return reject_receipt(&state, &trade_id, e.code(), message, transaction_id, payment_time).await;
     }
     
-    // Note: Recipient verification is handled by the ZK proof itself.
-    // The account_lines_hash from blockchain must match what the ZK circuit reads from the PDF.
-    
-    tracing::info!("✅ Pre-checks passed. Proceeding to OpenVM validation...");
-    
     let trade_amount_cents: u64 = trade.cny_amount.parse::<f64>()
         .map_err(|e| ApiError::Internal(format!("Invalid trade amount: {}", e)))?
         .round() as u64;
     
+    // Pre-check 4: Verify paid amount (line 29) matches the trade amount
+    // (exactly, or at least the trade amount when the guest runs in at-least mode)
+    let amount_mode = state.amount_mode;
+    tracing::info!("🔍 Pre-check: Verifying payment amount ({:?})...", amount_mode);
+    let expected_amount_line = format_amount_line(trade_amount_cents);
+    if let Err(message) = amount_mode.check(&pdf_fields.amount_line, trade_amount_cents) {
+        tracing::warn!("❌ Pre-check failed: Amount line '{}' vs expected '{}'", pdf_fields.amount_line, expected_amount_line);
+        return reject_receipt(&state, &trade_id, "AMOUNT_MISMATCH", message, transaction_id, payment_time).await;
+    }
+    
+    tracing::info!("✅ Pre-checks passed. Fetching on-chain recipient hash...");
+    
     // Step 5: Build expected lines for hash computation
     // - line25, line27 from PDF; line29 from trade amount
     // - account_lines_hash fetched directly from blockchain
     let line25 = transaction_id.clone();
     let line27 = payment_time.clone();
-    let line29 = format_amount_line(trade_amount_cents);
+    let line29 = expected_amount_line;
     
     // Step 6: Fetch account_lines_hash directly from blockchain
     // This avoids masking edge cases by using the hash that was computed by the frontend
@@ -235,145 +315,334 @@ pub async fn validate_handler(
     let onchain_account_hash_hex = format!("0x{}", hex::encode(onchain_account_hash));
     tracing::info!("📋 Fetched account_lines_hash from blockchain: {}", onchain_account_hash_hex);
     
-    // Compute expected hash using the on-chain account_lines_hash
-    let expected_hash = compute_expected_hash_with_onchain_account_hash(
-        &onchain_account_hash_hex, &line25, &line27, &line29, &pdf_pk_hash_hex
-    ).map_err(|e| ApiError::Internal(format!("Hash computation failed: {}", e)))?;
-    
-    // Step 7: Generate input streams for Axiom
-    let input_streams = generate_openvm_streams(&pdf_data)
-        .map_err(|e| ApiError::Internal(format!("Stream generation failed: {}", e)))?;
-    
-    // Step 8: Cache input streams
-    {
-        let mut cache = state.input_streams_cache.write().await;
-        cache.insert(trade_id.clone(), input_streams.clone());
+    // Pre-check 5: Verify recipient (lines 20/21) matches the order's on-chain accountLinesHash
+    tracing::info!("🔍 Pre-check: Verifying payment recipient...");
+    if !pdf_fields.pays_account(&onchain_account_hash) {
+        tracing::warn!(
+            "❌ Pre-check failed: Recipient hash 0x{} != on-chain 0x{}",
+            hex::encode(pdf_fields.account_lines_hash()), hex::encode(onchain_account_hash)
+        );
+        return reject_receipt(
+            &state, &trade_id, "RECIPIENT_MISMATCH",
+            "The payment was sent to a different account than the seller's. Please check the recipient and pay the correct account.".to_string(),
+            transaction_id, payment_time,
+        ).await;
     }
     
-    // Save transaction_id and payment_time to database
-    state.db.update_trade_payment_info(&trade_id, &transaction_id, &payment_time).await
-        .map_err(|e| ApiError::Database(format!("Failed to save payment info: {}", e)))?;
-    
-    // Step 9: Call Axiom execute mode (fast ~10 seconds)
-    let api_key = std::env::var("AXIOM_API_KEY")
-        .map_err(|_| ApiError::Internal("AXIOM_API_KEY not set".to_string()))?;
-    let program_id = std::env::var("AXIOM_PROGRAM_ID")
-        .map_err(|_| ApiError::Internal("AXIOM_PROGRAM_ID not set".to_string()))?;
-    
-    let axiom = AxiomProver::new(api_key, String::new(), program_id);
-    
-    tracing::info!("🚀 Running Axiom execute mode...");
-    let actual_hash = axiom.execute_program(&trade_id, input_streams).await
-        .map_err(|e| ApiError::Internal(format!("Axiom execution failed: {}", e)))?;
-    
-    // Step 10: Compare hashes
-    let valid = expected_hash.as_slice() == actual_hash.as_slice();
+    // Pre-check 6: Memo-bound trades must carry the trade reference code in the memo (备注)
+    let trade_id_bytes = trade_id_to_bytes32(&trade_id)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid trade ID: {}", e)))?;
+    let memo_required = blockchain_client.is_trade_memo_required(trade_id_bytes).await
+        .map_err(|e| ApiError::Internal(format!("Failed to fetch memo requirement from blockchain: {}", e)))?;
+    
+    let memo = if memo_required {
+        tracing::info!("🔍 Pre-check: Verifying payment memo...");
+        match pdf_fields.find_memo_line(&trade_id_bytes) {
+            Ok((memo_line, line_number)) => Some((memo_line, line_number)),
+            Err(message) => {
+                tracing::warn!("❌ Pre-check failed: {}", message);
+                return reject_receipt(&state, &trade_id, "MEMO_MISMATCH", message, transaction_id, payment_time).await;
+            }
+        }
+    } else {
+        None
+    };
     
-    tracing::info!("{}", if valid { "🎯 VALID" } else { "❌ INVALID" });
+    tracing::info!("✅ Recipient verified. Reserving receipt...");
+    
+    // Reserve the transaction ID for this trade before the paid Axiom run, so two
+    // pending trades can never validate (and prove) the same receipt concurrently
+    if let Some(holder) = state.db.reserve_receipt(&transaction_id, &trade_id).await? {
+        tracing::warn!("❌ Receipt {} already reserved by trade {}", transaction_id, holder);
+        return reject_receipt(
+            &state, &trade_id, "RECEIPT_IN_USE",
+            "This payment receipt is already being used for another trade. Each receipt can only settle one trade.".to_string(),
+            transaction_id, payment_time,
+        ).await;
+    }
     
-    // Step 11: If valid, check key rotation and spawn background settlement
-    if valid {
-        // Get blockchain client for key management
-        let blockchain_client = state.blockchain_client
-            .as_ref()
-            .ok_or_else(|| ApiError::ServiceUnavailable("Blockchain not enabled".to_string()))?;
+    tracing::info!("✅ Receipt reserved. Proceeding to OpenVM validation...");
+    
+    // Every error from here on releases the reservation (the outcomes below release
+    // it themselves, or keep it for the settlement they start)
+    let mut failure_code = "VALIDATION_ERROR";
+    let outcome: ApiResult<Json<ValidateResponse>> = async {
+        // Key commitment: the PDF's key hash, or the trusted key-set root with this key in
+        // the set (a new key only joins the shared set once this receipt validates)
+        let candidate_key_set = match &state.key_set {
+            Some(trusted) => Some(trusted.read().await.with_key(pdf_pk_hash)),
+            None => None,
+        };
+        let (key_commitment, key_witness) = match &candidate_key_set {
+            Some(key_set) => {
+                let root = key_set.root()
+                    .ok_or_else(|| ApiError::Internal("Empty key set".to_string()))?;
+                (root, key_set.witness(&pdf_pk_hash))
+            }
+            None => (pdf_pk_hash, None),
+        };
+        let key_commitment_hex = hex::encode(key_commitment);
+        
+        // Compute expected hash using the on-chain account_lines_hash
+        let expected_hash = compute_expected_hash_with_onchain_account_hash(
+            &onchain_account_hash_hex, &line25, &line27, &line29, &key_commitment_hex,
+            memo.as_ref().map(|(memo_line, _)| memo_line.as_str()),
+        ).map_err(|e| ApiError::Internal(format!("Hash computation failed: {}", e)))?;
+        
+        // Step 7: Generate input streams for Axiom
+        let input_streams = generate_openvm_streams(
+            &pdf_data,
+            memo.as_ref().map(|(_, line_number)| *line_number),
+            amount_mode,
+            trade_amount_cents,
+            key_witness.as_ref(),
+        );
         
-        // OPTIMISTIC KEY ROTATION - Compare PDF's public key hash with on-chain hash
-        // Done AFTER validation to not delay the user's response
-        let contract_pk_hash = blockchain_client.get_alipay_public_key_hash().await
-            .map_err(|e| ApiError::Internal(format!("Failed to get contract pk hash: {}", e)))?;
+        // Step 8: Cache input streams
+        {
+            let mut cache = state.input_streams_cache.write().await;
+            cache.insert(trade_id.clone(), input_streams.clone());
+        }
+        
+        // Save transaction_id and payment_time to database
+        state.db.update_trade_payment_info(&trade_id, &transaction_id, &payment_time).await
+            .map_err(|e| ApiError::Database(format!("Failed to save payment info: {}", e)))?;
+        
+        // Step 9: Call Axiom execute mode (fast ~10 seconds)
+        let api_key = std::env::var("AXIOM_API_KEY")
+            .map_err(|_| ApiError::Internal("AXIOM_API_KEY not set".to_string()))?;
+        // Execute mode may run the diagnostic guest build (failure bitmask + sub-hashes);
+        // proving always uses the program matching the on-chain verifier
+        let program_id = std::env::var("AXIOM_DIAGNOSTIC_PROGRAM_ID")
+            .unwrap_or_else(|_| program.program_id.clone());
+        
+        let axiom = AxiomProver::new(api_key, String::new(), program_id);
+        
+        // Execute as a batch of one - the output is this receipt's expected hash
+        let guest_input = InputStreams::assemble(std::slice::from_ref(&input_streams))
+            .map_err(|e| ApiError::Internal(format!("Stream generation failed: {}", e)))?;
+        
+        tracing::info!("🚀 Running Axiom execute mode...");
+        let public_values = match axiom.execute_program(&trade_id, guest_input).await {
+            Ok(public_values) => public_values,
+            Err(e) => {
+                failure_code = "EXECUTION_FAILED";
+                return Err(ApiError::Internal(format!("Axiom execution failed: {}", e)));
+            }
+        };
+        
+        // Step 10: Compare hashes
+        let actual_hash = &public_values[..32];
+        let valid = expected_hash.as_slice() == actual_hash;
         
-        if pdf_pk_hash != contract_pk_hash {
-            let contract_pk_hash_hex = hex::encode(&contract_pk_hash);
-            tracing::warn!(
-                "🔑 Key rotation detected! PDF hash: {}, Contract hash: {}",
-                pdf_pk_hash_hex, contract_pk_hash_hex
-            );
+        tracing::info!("{}", if valid { "🎯 VALID" } else { "❌ INVALID" });
+        
+        // Step 11: If valid, check key rotation and spawn background settlement
+        if valid {
+            record_settlement_state(&state, &trade_id, SettlementState::Validated, None).await;
+        
+            // Get blockchain client for key management
+            let blockchain_client = state.blockchain_client
+                .as_ref()
+                .ok_or_else(|| ApiError::ServiceUnavailable("Blockchain not enabled".to_string()))?;
+        
+            // GOVERNED KEY ROTATION - Compare the key commitment (PDF's public key hash or
+            // key-set root) with the on-chain hash. A receipt signed by a key the verifier
+            // doesn't trust can't settle: the key is quarantined and this receipt counts
+            // towards its rotation, which is submitted later (see key_rotation)
+            let contract_pk_hash = blockchain_client.get_alipay_public_key_hash().await
+                .map_err(|e| ApiError::Internal(format!("Failed to get contract pk hash: {}", e)))?;
+        
+            if key_commitment != contract_pk_hash {
+                tracing::warn!(
+                    "🔑 Untrusted key! Key commitment: {}, Contract hash: {}",
+                    key_commitment_hex, hex::encode(contract_pk_hash)
+                );
+                state.input_streams_cache.write().await.remove(&trade_id);
             
-            // OPTIMISTIC KEY ROTATION: Auto-update the contract with the new key hash
-            // This is safe because:
-            // 1. The PDF signature was already validated against the embedded public key
-            // 2. The key hash comes from a verified Alipay-signed PDF
-            // 3. This is NOT exposed via any public API endpoint
-            tracing::info!("🔄 Updating contract with new public key hash...");
-            match blockchain_client.update_public_key_hash(pdf_pk_hash).await {
-                Ok(tx_hash) => {
-                    tracing::info!("✅ Public key hash updated on-chain! TX: {:#x}", tx_hash);
-                    
-                    // Send admin alert email (fire-and-forget)
-                    if let Some(email_service) = crate::email::EmailService::from_env() {
-                        let old_hash = format!("0x{}", contract_pk_hash_hex);
-                        let new_hash = format!("0x{}", pdf_pk_hash_hex);
-                        let trade_id_clone = trade_id.clone();
-                        tokio::spawn(async move {
-                            if let Err(e) = email_service.send_key_rotation_alert(
-                                &old_hash,
-                                &new_hash,
-                                &trade_id_clone,
-                            ).await {
-                                tracing::error!("Failed to send key rotation alert: {}", e);
-                            }
-                        });
-                    }
-                }
-                Err(e) => {
-                    // Key update failed, but validation passed - log error but don't fail
-                    // The proof will still work since we use the PDF's key hash
-                    tracing::error!("❌ Failed to update public key hash: {}. Continuing with settlement.", e);
+                let sighting = key_rotation::record_sighting(&state, &pdf_data, pdf_pk_hash, &transaction_id, &trade).await
+                    .map_err(|e| ApiError::Database(format!("Failed to record key sighting: {}", e)))?;
+                let (code, message) = match sighting {
+                    KeySighting::Pending { sightings, required } => ("KEY_ROTATION_PENDING", format!(
+                        "This receipt is signed by a new Alipay key that is still under review (seen on {} of {} trades). Please try again later.",
+                        sightings.min(required), required
+                    )),
+                    KeySighting::Rejected => ("KEY_UNTRUSTED", "This receipt is signed by an Alipay key that is not trusted.".to_string()),
+                    KeySighting::ChainInvalid(_) => ("KEY_UNTRUSTED", "This receipt's signing certificate is not issued by Alipay's certificate authority.".to_string()),
+                };
+                return reject_receipt(&state, &trade_id, code, message, transaction_id, payment_time).await;
+            }
+        
+            // Queue for a batch proof if batching is enabled, otherwise prove this trade alone
+            let item = BatchItem {
+                trade_id: trade_id.clone(),
+                transaction_id: transaction_id.clone(),
+                payment_time: payment_time.clone(),
+            };
+            let unbatched = match &state.settlement_batcher {
+                Some(batcher) => batcher.enqueue(item).err(),
+                None => Some(item),
+            };
+        
+            match unbatched {
+                Some(item) => {
+                    // Spawn background task for proof generation and settlement
+                    let state_clone = state.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = run_background_settlement(
+                            state_clone,
+                            item.trade_id,
+                            item.transaction_id,
+                            item.payment_time,
+                        ).await {
+                            tracing::error!("❌ Background settlement failed for trade: {}", e);
+                        }
+                    });
                 }
+                None => tracing::info!("🧺 Trade {} queued for batch proving", trade_id),
             }
-        }
         
-        // Spawn background task for proof generation and settlement
-        let state_clone = state.clone();
-        let trade_id_clone = trade_id.clone();
-        let transaction_id_clone = transaction_id.clone();
-        let payment_time_clone = payment_time.clone();
+            return Ok(Json(ValidateResponse {
+                valid: true,
+                expected_hash: hex::encode(&expected_hash),
+                actual_hash: hex::encode(actual_hash),
+                message: "PDF validated! Proof generation started. You can safely leave this page - we'll complete the settlement automatically.".to_string(),
+                validation_code: "SUCCESS".to_string(),
+                transaction_id,
+                payment_time,
+                mismatched_components: Vec::new(),
+            }));
+        }
         
-        tokio::spawn(async move {
-            if let Err(e) = run_background_settlement(
-                state_clone,
-                trade_id_clone,
-                transaction_id_clone,
-                payment_time_clone,
-            ).await {
-                tracing::error!("❌ Background settlement failed for trade: {}", e);
+        // Validation failed - diagnose which component differed (diagnostic build only)
+        let mismatched_components: Vec<String> = match GuestDiagnostics::parse(&public_values) {
+            Some(diagnostics) => {
+                let expected = ExpectedComponents {
+                    pk_hash: key_commitment,
+                    account_lines_hash: onchain_account_hash,
+                    tx_id_hash: compute_tx_id_hash(&line25),
+                    time_amount_hash: compute_time_amount_hash(&line27, &line29),
+                    memo_hash: memo.as_ref().map(|(memo_line, _)| compute_memo_hash(memo_line)),
+                };
+                diagnostics.diagnose(&expected).into_iter().map(String::from).collect()
             }
-        });
+            None => Vec::new(),
+        };
+        let detail = (!mismatched_components.is_empty())
+            .then(|| format!("Mismatched: {}", mismatched_components.join(", ")));
+        if let Some(detail) = &detail {
+            tracing::warn!("🔬 [{}] {}", trade_id, detail);
+        }
+        
+        // Clear PDF so user can retry with a different one
+        record_settlement_state(&state, &trade_id, SettlementState::failed("HASH_MISMATCH"), detail.as_deref()).await;
+        release_receipt(&state, &trade_id).await;
+        if let Err(e) = state.db.clear_trade_pdf(&trade_id).await {
+            tracing::error!("Failed to clear PDF after validation failure: {}", e);
+        }
+        
+        // Also clear from input streams cache
+        {
+            let mut cache = state.input_streams_cache.write().await;
+            cache.remove(&trade_id);
+        }
+        
+        let mut message = "Validation failed. PDF content doesn't match trade details. Please try again with the correct receipt.".to_string();
+        if let Some(detail) = detail {
+            message = format!("{} ({})", message, detail);
+        }
         
-        return Ok(Json(ValidateResponse {
-            valid: true,
+        Ok(Json(ValidateResponse {
+            valid: false,
             expected_hash: hex::encode(&expected_hash),
-            actual_hash: hex::encode(&actual_hash),
-            message: "PDF validated! Proof generation started. You can safely leave this page - we'll complete the settlement automatically.".to_string(),
-            validation_code: "SUCCESS".to_string(),
+            actual_hash: hex::encode(actual_hash),
+            message,
+            validation_code: "HASH_MISMATCH".to_string(),
             transaction_id,
             payment_time,
-        }));
+            mismatched_components,
+        }))
+    }.await;
+    
+    if let Err(e) = &outcome {
+        record_settlement_state(&state, &trade_id, SettlementState::failed(failure_code), Some(&format!("{:?}", e))).await;
+        release_receipt(&state, &trade_id).await;
+        state.input_streams_cache.write().await.remove(&trade_id);
     }
-    
-    // Validation failed - clear PDF so user can retry with a different one
-    if let Err(e) = state.db.clear_trade_pdf(&trade_id).await {
-        tracing::error!("Failed to clear PDF after validation failure: {}", e);
+    outcome
+}
+
+/// Refuse a new receipt while the trade's proof is being made or submitted, or is
+/// stored - reserving the new receipt would release the one that proof is for
+async fn ensure_receipt_replaceable(state: &AppState, trade: &DbTrade) -> ApiResult<()> {
+    if state.proof_in_progress.read().await.contains(&trade.trade_id) {
+        return Err(ApiError::BadRequest(
+            "This trade's receipt is already being proved. Please wait for the settlement to finish.".to_string(),
+        ));
+    }
+    if StoredProof::from_trade(trade).is_some() || stored_batch_for_trade(state, &trade.trade_id).await?.is_some() {
+        return Err(ApiError::BadRequest(format!(
+            "This trade already has a proof for its receipt. Settle it with /resubmit, or from your own wallet (GET /api/trades/{}/calldata).",
+            trade.trade_id
+        )));
     }
+    Ok(())
+}
+
+/// Reject a receipt that failed a local pre-check
+/// Clears the PDF so the user can retry with a different receipt, and records failed:<code>
+async fn reject_receipt(
+    state: &AppState,
+    trade_id: &str,
+    validation_code: &str,
+    message: String,
+    transaction_id: String,
+    payment_time: String,
+) -> ApiResult<Json<ValidateResponse>> {
+    record_settlement_state(state, trade_id, SettlementState::failed(validation_code), Some(&message)).await;
+    release_receipt(state, trade_id).await;
     
-    // Also clear from input streams cache
-    {
-        let mut cache = state.input_streams_cache.write().await;
-        cache.remove(&trade_id);
+    if let Err(e) = state.db.clear_trade_pdf(trade_id).await {
+        tracing::error!("Failed to clear PDF after pre-check failure: {}", e);
     }
     
     Ok(Json(ValidateResponse {
         valid: false,
-        expected_hash: hex::encode(&expected_hash),
-        actual_hash: hex::encode(&actual_hash),
-        message: "Validation failed. PDF content doesn't match trade details. Please try again with the correct receipt.".to_string(),
-        validation_code: "HASH_MISMATCH".to_string(),
+        expected_hash: String::new(),
+        actual_hash: String::new(),
+        message,
+        validation_code: validation_code.to_string(),
         transaction_id,
         payment_time,
+        mismatched_components: Vec::new(),
     }))
 }
 
+/// User-facing message for a payment time pre-check failure
+fn payment_time_message(error: &PaymentTimeError, payment_time: &str, trade: &DbTrade) -> String {
+    match error {
+        PaymentTimeError::InvalidFormat(_) | PaymentTimeError::NotContractFormat(_) => format!(
+            "This receipt's payment time format isn't supported for on-chain settlement. Receipt time: {}", payment_time
+        ),
+        PaymentTimeError::BeforeTradeCreated { .. } => format!(
+            "Payment was made before the trade was created. Receipt time: {}, Trade created: {}", payment_time, trade.created_at
+        ),
+        PaymentTimeError::AfterExpiry { .. } => format!(
+            "Payment was made after the trade expired. Receipt time: {}, Trade expired: {}", payment_time, trade.expires_at
+        ),
+        PaymentTimeError::InFuture { .. } => format!(
+            "The receipt's payment time is in the future. Receipt time: {}", payment_time
+        ),
+    }
+}
+
+/// Release the receipt reserved by a trade so another trade (or a retry) can claim it
+async fn release_receipt(state: &AppState, trade_id: &str) {
+    if let Err(e) = state.db.release_receipt_reservation(trade_id).await {
+        tracing::error!("Failed to release receipt reservation for trade {}: {}", trade_id, e);
+    }
+}
+
 /// Background task for proof generation and blockchain settlement
 /// Called automatically when validation passes - user doesn't need to wait
 async fn run_background_settlement(
@@ -384,44 +653,18 @@ async fn run_background_settlement(
 ) -> Result<(), String> {
     tracing::info!("🚀 [Background] Starting proof generation for trade {}", trade_id);
     
-    // Check if already in progress (prevent duplicates)
-    {
-        let mut in_progress = state.proof_in_progress.write().await;
-        if in_progress.contains(&trade_id) {
+    // Skip if already in progress (prevent duplicates)
+    let trade_ids = [trade_id.clone()];
+    let inner = {
+        let (state, trade_id) = (state.clone(), trade_id.clone());
+        async move { run_background_settlement_inner(&state, &trade_id, &transaction_id, &payment_time).await }
+    };
+    match with_settlement_claim(&state, &trade_ids, inner).await {
+        Some(result) => result,
+        None => {
             tracing::info!("⏭️ [Background] Trade {} already being processed, skipping", trade_id);
-            return Ok(());
+            Ok(())
         }
-        in_progress.insert(trade_id.clone());
-    }
-    
-    // Ensure we remove from in_progress when done (even on error)
-    let result = run_background_settlement_inner(&state, &trade_id, &transaction_id, &payment_time).await;
-    
-    {
-        let mut in_progress = state.proof_in_progress.write().await;
-        in_progress.remove(&trade_id);
-    }
-    
-    result
-}
-
-/// Parse contract error selector and return user-friendly error code
-/// Error selectors from LyncZEscrow.sol:
-/// - 0x25c394f4 = TransactionIdAlreadyUsed (receipt already used - replay attack)
-/// - 0x5f3f6cfc = TradeNotPending (trade already settled or cancelled)
-/// - 0xace5e8ce = TradeExpiredError (payment window closed)
-/// - 0xd611c318 = ProofVerificationFailed (ZK proof invalid)
-fn parse_contract_error(error_msg: &str) -> Option<&'static str> {
-    if error_msg.contains("0x25c394f4") {
-        Some("ALREADY_USED")
-    } else if error_msg.contains("0x5f3f6cfc") {
-        Some("NOT_PENDING")
-    } else if error_msg.contains("0xace5e8ce") {
-        Some("EXPIRED")
-    } else if error_msg.contains("0xd611c318") {
-        Some("VERIFICATION_FAILED")
-    } else {
-        None
     }
 }
 
@@ -431,9 +674,9 @@ async fn run_background_settlement_inner(
     transaction_id: &str,
     payment_time: &str,
 ) -> Result<(), String> {
-    let blockchain_client = state.blockchain_client
-        .as_ref()
-        .ok_or_else(|| "Blockchain not enabled".to_string())?;
+    if state.blockchain_client.is_none() {
+        return Err("Blockchain not enabled".to_string());
+    }
     
     // Get input streams from cache
     let input_streams = {
@@ -441,18 +684,39 @@ async fn run_background_settlement_inner(
         cache.get(trade_id).cloned()
     }.ok_or_else(|| "Input streams not in cache".to_string())?;
     
-    // Generate EVM proof
-    let api_key = std::env::var("AXIOM_API_KEY")
-        .map_err(|_| "AXIOM_API_KEY not set".to_string())?;
-    let program_id = std::env::var("AXIOM_PROGRAM_ID")
-        .map_err(|_| "AXIOM_PROGRAM_ID not set".to_string())?;
+    tracing::info!("🔐 [Background] Generating ZK proof for trade {}...", trade_id);
+    let stored_proof = generate_and_store_proof(state, trade_id, input_streams).await?;
     
-    let axiom = AxiomProver::new(api_key, String::new(), program_id);
+    // Submit to blockchain (retries transient failures, proof is already persisted)
+    ensure_relayer_budget(state, &[trade_id.to_string()]).await?;
+    tracing::info!("📤 [Background] Submitting proof to blockchain...");
     
-    tracing::info!("🔐 [Background] Generating ZK proof for trade {}...", trade_id);
-    let proof = axiom.generate_evm_proof(trade_id, input_streams).await
-        .map_err(|e| format!("Proof generation failed: {}", e))?;
-    tracing::info!("✅ [Background] Proof generated: {}", proof.proof_id);
+    let tx_hash = submit_stored_proof(state, trade_id, transaction_id, payment_time, &stored_proof).await
+        .map_err(|e| format!("Blockchain submission failed: {}", e))?;
+    
+    tracing::info!("✅ [Background] Trade {} settled! tx_hash: {:#x}", trade_id, tx_hash);
+    Ok(())
+}
+
+/// Generate the EVM proof via Axiom and persist it (proving -> proof_ready)
+async fn generate_and_store_proof(
+    state: &AppState,
+    trade_id: &str,
+    input_streams: Vec<String>,
+) -> Result<StoredProof, String> {
+    let axiom = proving_client(state).await?;
+    let guest_input = InputStreams::assemble(&[input_streams])?;
+    
+    record_settlement_state(state, trade_id, SettlementState::Proving, None).await;
+    let proof = match axiom.generate_evm_proof(trade_id, guest_input).await {
+        Ok(proof) => proof,
+        Err(e) => {
+            let error = format!("Proof generation failed: {}", e);
+            record_settlement_state(state, trade_id, SettlementState::failed("PROOF_FAILED"), Some(&error)).await;
+            return Err(error);
+        }
+    };
+    tracing::info!("✅ Proof generated: {}", proof.proof_id);
     
     // Save proof to database
     let proof_json = serde_json::to_string(&proof.full_json)
@@ -467,57 +731,574 @@ async fn run_background_settlement_inner(
         &proof_json,
     ).await.map_err(|e| format!("DB save failed: {}", e))?;
     
-    // Submit to blockchain
-    tracing::info!("📤 [Background] Submitting proof to blockchain...");
+    record_settlement_state(state, trade_id, SettlementState::ProofReady, Some(&proof.proof_id)).await;
     
-    let trade_id_bytes = trade_id_to_bytes32(trade_id)
-        .map_err(|e| format!("Invalid trade ID: {}", e))?;
+    StoredProof::from_generated(&proof)
+}
+
+/// Axiom client for EVM proof generation (progress published to the trade event bus)
+///
+/// Proves with the program matching the on-chain verifier - fails before paying
+/// for a proof that would revert.
+async fn proving_client(state: &AppState) -> Result<AxiomProver, String> {
+    let api_key = std::env::var("AXIOM_API_KEY")
+        .map_err(|_| "AXIOM_API_KEY not set".to_string())?;
+    let program = state.active_program().await
+        .map_err(|e| format!("Prover program check failed: {}", e))?;
     
-    let mut user_public_values = [0u8; 32];
-    user_public_values.copy_from_slice(&proof.user_public_values);
+    Ok(AxiomProver::new(api_key, String::new(), program.program_id)
+        .with_event_bus(state.trade_events.clone()))
+}
+
+/// Record a settlement state transition and publish it to live subscribers
+///
+/// Failures never block settlement, but an unrecorded transition is not published
+/// either - clients only see states the persisted timeline has.
+async fn record_settlement_state(state: &AppState, trade_id: &str, settlement_state: SettlementState, detail: Option<&str>) {
+    tracing::info!("📍 Trade {} settlement state: {}", trade_id, settlement_state);
+    if let Err(e) = state.db.record_settlement_state(trade_id, &settlement_state, detail).await {
+        tracing::error!("❌ Failed to record settlement state {} for trade {}, not published: {}", settlement_state, trade_id, e);
+        return;
+    }
+    state.trade_events.publish(TradeEvent::SettlementState {
+        trade_id: trade_id.to_string(),
+        state: settlement_state.to_string(),
+        detail: detail.map(str::to_string),
+    });
+}
+
+// ============================================================================
+// Proof Submission - retry with backoff, resubmit from stored proof
+// ============================================================================
+
+/// Max submitProof attempts for failures before the broadcast (RPC errors, gas estimate)
+const SUBMIT_MAX_ATTEMPTS: u32 = 4;
+/// Backoff before the first retry, doubled on each subsequent attempt
+const SUBMIT_INITIAL_BACKOFF_SECS: u64 = 5;
+
+/// Proof calldata as persisted in the trades table by `save_trade_proof`
+struct StoredProof {
+    user_public_values: [u8; 32],
+    accumulator: Vec<u8>,
+    proof_data: Vec<u8>,
+}
+
+impl StoredProof {
+    fn new(user_public_values: &[u8], accumulator: Vec<u8>, proof_data: Vec<u8>) -> Result<Self, String> {
+        let user_public_values: [u8; 32] = user_public_values.try_into()
+            .map_err(|_| format!("Invalid user public values length: {}", user_public_values.len()))?;
+        Ok(Self { user_public_values, accumulator, proof_data })
+    }
+
+    fn from_generated(proof: &GeneratedProof) -> Result<Self, String> {
+        Self::new(&proof.user_public_values, proof.accumulator.clone(), proof.proof_data.clone())
+    }
+
+    /// Rebuild from the trade row - None if no proof has been saved yet
+    fn from_trade(trade: &DbTrade) -> Option<Result<Self, String>> {
+        match (&trade.proof_user_public_values, &trade.proof_accumulator, &trade.proof_data) {
+            (Some(public_values), Some(accumulator), Some(proof_data)) => {
+                Some(Self::new(public_values, accumulator.clone(), proof_data.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Rebuild from a settlement batch row - None if the batch proof isn't saved yet
+    fn from_batch(batch: &DbSettlementBatch) -> Option<Result<Self, String>> {
+        match (&batch.proof_user_public_values, &batch.proof_accumulator, &batch.proof_data) {
+            (Some(public_values), Some(accumulator), Some(proof_data)) => {
+                Some(Self::new(public_values, accumulator.clone(), proof_data.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Submit a stored proof, retrying transient failures with exponential backoff
+///
+/// Contract reverts are not retried - the decoded error code is saved as `settlement_error`.
+async fn submit_stored_proof(
+    state: &AppState,
+    trade_id: &str,
+    transaction_id: &str,
+    payment_time: &str,
+    proof: &StoredProof,
+) -> Result<H256, EthereumClientError> {
+    let blockchain_client = state.blockchain_client
+        .as_deref()
+        .ok_or_else(|| EthereumClientError::ProviderError("Blockchain not enabled".to_string()))?;
+    
+    let trade_id_bytes = trade_id_to_bytes32(trade_id)
+        .map_err(|e| EthereumClientError::ContractError(format!("Invalid trade ID: {}", e)))?;
     
     // Compute tx_id_hash from transaction_id (v4 privacy: txId never on-chain)
     let tx_id_hash = compute_tx_id_hash(transaction_id);
-    tracing::info!("🔐 [Background] tx_id_hash: 0x{}", hex::encode(tx_id_hash));
+    tracing::info!("🔐 tx_id_hash: 0x{}", hex::encode(tx_id_hash));
     
-    let submit_result = blockchain_client.submit_proof(
-        trade_id_bytes,
-        tx_id_hash,
-        payment_time.to_string(),
-        user_public_values,
-        proof.accumulator.clone(),
-        proof.proof_data.clone(),
-    ).await;
+    let trade_ids = [trade_id.to_string()];
+    let deadline = settlement_deadline(state, &trade_ids).await;
+    let result = submit_with_retries(state, &trade_ids, "submitProof", move || {
+        blockchain_client.submit_proof(
+            trade_id_bytes,
+            tx_id_hash,
+            payment_time.to_string(),
+            proof.user_public_values,
+            proof.accumulator.clone(),
+            proof.proof_data.clone(),
+            settlement_urgency(deadline),
+        )
+    }).await;
+    
+    record_submission_result(state, &trade_ids, &result).await;
+    result
+}
+
+/// Earliest expiry (unix seconds) among the trades being settled
+async fn settlement_deadline(state: &AppState, trade_ids: &[String]) -> Option<i64> {
+    let mut deadline: Option<i64> = None;
+    for trade_id in trade_ids {
+        match state.db.get_trade(trade_id).await {
+            Ok(trade) => deadline = Some(deadline.map_or(trade.expires_at, |d| d.min(trade.expires_at))),
+            Err(e) => tracing::warn!("Failed to load expiry of {}: {}", trade_id, e),
+        }
+    }
+    deadline
+}
+
+/// Fee urgency for a settlement attempt - raised as the trade nears expiry
+fn settlement_urgency(deadline: Option<i64>) -> FeeUrgency {
+    deadline
+        .map(|expires_at| FeeUrgency::for_deadline(expires_at, chrono::Utc::now().timestamp()))
+        .unwrap_or(FeeUrgency::Normal)
+}
+
+/// Send a settlement transaction, retrying transient failures with exponential backoff
+async fn submit_with_retries<F, Fut>(
+    state: &AppState,
+    trade_ids: &[String],
+    method: &str,
+    mut submit: F,
+) -> Result<H256, EthereumClientError>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<H256, EthereumClientError>>,
+{
+    // A transaction an earlier attempt gave up waiting on may still be mined
+    match state.db.get_pending_settlement_tx(trade_ids).await {
+        Ok(Some(tx_hash)) => {
+            tracing::warn!("⏳ {} for trade {} not sent: {} is still pending", method, trade_ids.join(","), tx_hash);
+            let tx_hash = tx_hash.parse::<H256>()
+                .map_err(|e| EthereumClientError::TransactionFailed(format!("Invalid pending tx hash {}: {}", tx_hash, e)))?;
+            return Err(EthereumClientError::StillPending(tx_hash));
+        }
+        Ok(None) => {}
+        Err(e) => {
+            return Err(EthereumClientError::TransactionFailed(format!("Cannot check pending settlement transactions: {}", e)));
+        }
+    }
     
-    match submit_result {
+    let mut attempt = 1;
+    loop {
+        for trade_id in trade_ids {
+            record_settlement_state(state, trade_id, SettlementState::Submitting, Some(&format!("attempt {}/{}", attempt, SUBMIT_MAX_ATTEMPTS))).await;
+        }
+        
+        let result = submit().await;
+        match (&result, retry_backoff_secs(&result, attempt)) {
+            (Err(e), Some(backoff)) => {
+                tracing::warn!(
+                    "🔁 {} attempt {}/{} failed for trade {}: {} - retrying in {}s",
+                    method, attempt, SUBMIT_MAX_ATTEMPTS, trade_ids.join(","), e, backoff
+                );
+                tokio::time::sleep(std::time::Duration::from_secs(backoff)).await;
+                attempt += 1;
+            }
+            (Err(e), None) => {
+                tracing::error!("❌ Blockchain submission failed for trade {} after {} attempt(s): {}", trade_ids.join(","), attempt, e);
+                return result;
+            }
+            (Ok(_), _) => return result,
+        }
+    }
+}
+
+/// Seconds to wait before retrying a failed submission (None = don't retry)
+///
+/// Only failures before the broadcast are retried. A transaction still pending after
+/// the confirm timeout is never resent - it keeps its nonce and may yet be mined.
+fn retry_backoff_secs(result: &Result<H256, EthereumClientError>, attempt: u32) -> Option<u64> {
+    match result {
+        Err(e) if e.is_transient() && attempt < SUBMIT_MAX_ATTEMPTS => {
+            Some(SUBMIT_INITIAL_BACKOFF_SECS << (attempt - 1))
+        }
+        _ => None,
+    }
+}
+
+/// Record the outcome of a settlement transaction for every trade it covers
+async fn record_submission_result(state: &AppState, trade_ids: &[String], result: &Result<H256, EthereumClientError>) {
+    match result {
         Ok(tx_hash) => {
-            tracing::info!("✅ [Background] Trade {} settled! tx_hash: {}", trade_id, tx_hash);
+            for trade_id in trade_ids {
+                record_settlement_state(state, trade_id, SettlementState::Settled, Some(&format!("{:#x}", tx_hash))).await;
+            }
             
             // Clean up input streams cache
-            {
-                let mut cache = state.input_streams_cache.write().await;
+            let mut cache = state.input_streams_cache.write().await;
+            for trade_id in trade_ids {
                 cache.remove(trade_id);
             }
-            
-            Ok(())
+        }
+        Err(EthereumClientError::StillPending(tx_hash)) => {
+            // Not failed: the transaction may still be mined (TradeSettled reconciles it)
+            for trade_id in trade_ids {
+                record_settlement_state(state, trade_id, SettlementState::Submitting, Some(&format!("{:#x} still pending", tx_hash))).await;
+            }
         }
         Err(e) => {
-            let error_msg = e.to_string();
-            tracing::error!("❌ [Background] Blockchain submission failed: {}", error_msg);
-            
-            // Parse contract error and save to database
-            if let Some(error_code) = parse_contract_error(&error_msg) {
-                tracing::info!("📝 [Background] Saving settlement error: {} for trade {}", error_code, trade_id);
-                if let Err(db_err) = state.db.save_trade_settlement_error(trade_id, error_code).await {
-                    tracing::error!("❌ [Background] Failed to save settlement error: {}", db_err);
+            let failure_code = e.revert().map(|r| r.code()).unwrap_or("SUBMIT_FAILED");
+            for trade_id in trade_ids {
+                record_settlement_state(state, trade_id, SettlementState::failed(failure_code), Some(&format!("{:?}", e))).await;
+                
+                // Save decoded contract error code to database
+                if let Some(error_code) = e.revert().map(|r| r.code()) {
+                    tracing::info!("📝 Saving settlement error: {} for trade {}", error_code, trade_id);
+                    if let Err(db_err) = state.db.save_trade_settlement_error(trade_id, error_code).await {
+                        tracing::error!("❌ Failed to save settlement error: {}", db_err);
+                    }
                 }
             }
-            
-            Err(format!("Blockchain submission failed: {}", error_msg))
         }
     }
 }
 
+// ============================================================================
+// Batch Settlement - one proof and one submitBatchProof for several trades
+// ============================================================================
+
+/// Batch worker: proves each batch of queued trades together
+/// Spawned by the API server when SETTLEMENT_BATCH_WINDOW_SECS is set
+pub async fn run_settlement_batches(state: AppState, mut receiver: BatchReceiver) {
+    tracing::info!(
+        "🧺 Settlement batching enabled (window {:?}, up to {} trades)",
+        receiver.config().window, receiver.config().max_size
+    );
+    
+    while let Some(items) = receiver.next_batch().await {
+        let state = state.clone();
+        tokio::spawn(async move {
+            settle_batch(state, items).await;
+        });
+    }
+}
+
+/// Prove and settle one batch of validated trades
+async fn settle_batch(state: AppState, items: Vec<BatchItem>) {
+    // Only trades whose input streams are still cached can join the proof
+    let mut batch: Vec<(BatchItem, Vec<String>)> = Vec::with_capacity(items.len());
+    {
+        let cache = state.input_streams_cache.read().await;
+        for item in items {
+            match cache.get(&item.trade_id) {
+                Some(streams) => batch.push((item.clone(), streams.clone())),
+                None => tracing::warn!("⚠️ [Batch] Trade {} has no cached input streams, dropped from batch", item.trade_id),
+            }
+        }
+    }
+    
+    // A lone trade settles the usual way (submitProof, proof stored on the trade)
+    if batch.len() == 1 {
+        let (item, _) = batch.remove(0);
+        if let Err(e) = run_background_settlement(state, item.trade_id, item.transaction_id, item.payment_time).await {
+            tracing::error!("❌ Background settlement failed for trade: {}", e);
+        }
+        return;
+    }
+    
+    // Skip trades already being proved or submitted (e.g. a manual /settle) - the claim
+    // is released when the batch ends, or on drop if it panics
+    let trade_ids: Vec<String> = batch.iter().map(|(item, _)| item.trade_id.clone()).collect();
+    let Some(claim) = SettlementClaim::acquire_free(&state.proof_in_progress, &trade_ids).await else {
+        return;
+    };
+    let mut unclaimed: HashSet<&String> = claim.trade_ids.iter().collect();
+    batch.retain(|(item, _)| unclaimed.remove(&item.trade_id));
+    
+    let items: Vec<BatchItem> = batch.iter().map(|(item, _)| item.clone()).collect();
+    let receipts: Vec<Vec<String>> = batch.into_iter().map(|(_, streams)| streams).collect();
+    
+    match settle_batch_inner(&state, &items, &receipts).await {
+        Ok(tx_hash) => tracing::info!("✅ [Batch] {} trades settled! tx_hash: {:#x}", items.len(), tx_hash),
+        Err(e) => tracing::error!("❌ [Batch] Settlement failed: {}", e),
+    }
+    
+    claim.release().await;
+}
+
+async fn settle_batch_inner(
+    state: &AppState,
+    items: &[BatchItem],
+    receipts: &[Vec<String>],
+) -> Result<H256, String> {
+    if state.blockchain_client.is_none() {
+        return Err("Blockchain not enabled".to_string());
+    }
+    
+    let trade_ids: Vec<String> = items.iter().map(|item| item.trade_id.clone()).collect();
+    let batch_id = format!("batch-{}", uuid::Uuid::new_v4());
+    state.db.create_settlement_batch(&batch_id, &trade_ids).await
+        .map_err(|e| format!("DB save failed: {}", e))?;
+    
+    let axiom = proving_client(state).await?;
+    let guest_input = InputStreams::assemble(receipts)?;
+    
+    tracing::info!("🔐 [Batch] Generating ZK proof for {} ({} trades)...", batch_id, trade_ids.len());
+    for trade_id in &trade_ids {
+        record_settlement_state(state, trade_id, SettlementState::Proving, Some(&batch_id)).await;
+    }
+    let proof = match axiom.generate_evm_proof(&batch_id, guest_input).await {
+        Ok(proof) => proof,
+        Err(e) => {
+            let error = format!("Batch proof generation failed: {}", e);
+            for trade_id in &trade_ids {
+                record_settlement_state(state, trade_id, SettlementState::failed("PROOF_FAILED"), Some(&error)).await;
+            }
+            return Err(error);
+        }
+    };
+    tracing::info!("✅ Batch proof generated: {}", proof.proof_id);
+    
+    // The proof belongs to the batch - it can only be resubmitted as a whole
+    state.db.save_settlement_batch_proof(
+        &batch_id,
+        &proof.user_public_values,
+        &proof.accumulator,
+        &proof.proof_data,
+        &proof.proof_id,
+    ).await.map_err(|e| format!("DB save failed: {}", e))?;
+    
+    for trade_id in &trade_ids {
+        record_settlement_state(state, trade_id, SettlementState::ProofReady, Some(&batch_id)).await;
+    }
+    
+    let stored_proof = StoredProof::from_generated(&proof)?;
+    ensure_relayer_budget(state, &trade_ids).await?;
+    submit_stored_batch(state, &batch_id, items, &stored_proof).await
+        .map_err(|e| format!("Blockchain submission failed: {}", e))
+}
+
+/// Submit a stored batch proof via submitBatchProof (same retry policy as submitProof)
+async fn submit_stored_batch(
+    state: &AppState,
+    batch_id: &str,
+    items: &[BatchItem],
+    proof: &StoredProof,
+) -> Result<H256, EthereumClientError> {
+    let blockchain_client = state.blockchain_client
+        .as_deref()
+        .ok_or_else(|| EthereumClientError::ProviderError("Blockchain not enabled".to_string()))?;
+    
+    // Leaf order = batch order
+    let settlements = items.iter()
+        .map(|item| {
+            let trade_id = trade_id_to_bytes32(&item.trade_id)
+                .map_err(|e| EthereumClientError::ContractError(format!("Invalid trade ID: {}", e)))?;
+            Ok(BatchSettlement {
+                trade_id,
+                tx_id_hash: compute_tx_id_hash(&item.transaction_id),
+                payment_time: item.payment_time.clone(),
+            })
+        })
+        .collect::<Result<Vec<_>, EthereumClientError>>()?;
+    
+    let trade_ids: Vec<String> = items.iter().map(|item| item.trade_id.clone()).collect();
+    let deadline = settlement_deadline(state, &trade_ids).await;
+    let result = submit_with_retries(state, &trade_ids, "submitBatchProof", move || {
+        blockchain_client.submit_batch_proof(
+            settlements.clone(),
+            proof.user_public_values,
+            proof.accumulator.clone(),
+            proof.proof_data.clone(),
+            settlement_urgency(deadline),
+        )
+    }).await;
+    
+    // A still-pending batch transaction is recorded too, so a resubmit finds it in flight
+    let batch_tx = match &result {
+        Ok(tx_hash) => Some(*tx_hash),
+        Err(e) => e.still_pending(),
+    };
+    if let Some(tx_hash) = batch_tx {
+        if let Err(e) = state.db.mark_settlement_batch_settled(batch_id, &format!("{:#x}", tx_hash)).await {
+            tracing::error!("Failed to record settlement tx for {}: {}", batch_id, e);
+        }
+    }
+    
+    record_submission_result(state, &trade_ids, &result).await;
+    result
+}
+
+/// Stored batch proof for a trade, if the batch can still settle as a whole
+///
+/// None if the trade was never batched, the batch proof isn't stored yet, or another
+/// trade in the batch is no longer pending (the whole batch would revert) - the trade
+/// then has to be proved on its own.
+async fn stored_batch_for_trade(
+    state: &AppState,
+    trade_id: &str,
+) -> ApiResult<Option<(DbSettlementBatch, Vec<BatchItem>, StoredProof)>> {
+    let Some(batch) = state.db.get_settlement_batch_for_trade(trade_id).await? else {
+        return Ok(None);
+    };
+    let Some(stored_proof) = StoredProof::from_batch(&batch) else {
+        return Ok(None);
+    };
+    let stored_proof = stored_proof.map_err(ApiError::Internal)?;
+    
+    let mut items = Vec::with_capacity(batch.trade_ids.len());
+    for batch_trade_id in &batch.trade_ids {
+        let trade = state.db.get_trade(batch_trade_id).await?;
+        match (trade.status, trade.transaction_id, trade.payment_time) {
+            (0, Some(transaction_id), Some(payment_time)) => items.push(BatchItem {
+                trade_id: batch_trade_id.clone(),
+                transaction_id,
+                payment_time,
+            }),
+            _ => {
+                tracing::warn!("⚠️ {} can no longer settle: trade {} is not pending", batch.batch_id, batch_trade_id);
+                return Ok(None);
+            }
+        }
+    }
+    
+    Ok(Some((batch, items, stored_proof)))
+}
+
+/// Resubmit a stored batch proof, guarding every trade in the batch against double submission
+async fn resubmit_batch(
+    state: &AppState,
+    batch: &DbSettlementBatch,
+    items: Vec<BatchItem>,
+    proof: StoredProof,
+) -> ApiResult<H256> {
+    let trade_ids: Vec<String> = items.iter().map(|item| item.trade_id.clone()).collect();
+    
+    tracing::info!("♻️ Resubmitting stored batch proof {} ({} trades)", batch.batch_id, items.len());
+    let work = {
+        let (state, batch_id) = (state.clone(), batch.batch_id.clone());
+        async move { submit_stored_batch(&state, &batch_id, &items, &proof).await }
+    };
+    let result = with_settlement_claim(state, &trade_ids, work).await
+        .ok_or_else(|| ApiError::BadRequest("Settlement already in progress. Please wait.".to_string()))?;
+    
+    Ok(result?)
+}
+
+/// Run `work` with the trades claimed in `proof_in_progress`, released on every exit
+///
+/// None (and `work` never runs) if any of the trades is already claimed - shared by
+/// background settlement, /settle, /resubmit and batches so a trade is never proved
+/// or submitted twice at once.
+async fn with_settlement_claim<F>(state: &AppState, trade_ids: &[String], work: F) -> Option<F::Output>
+where
+    F: std::future::Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    claim_and_run(&state.proof_in_progress, trade_ids, work).await
+}
+
+/// `work` runs in its own task holding the claim: a client disconnecting mid-/settle
+/// drops only the wait, so the proof or broadcast still finishes and the claim is
+/// released when it does (or when the task panics)
+async fn claim_and_run<F>(
+    in_progress: &Arc<RwLock<HashSet<String>>>,
+    trade_ids: &[String],
+    work: F,
+) -> Option<F::Output>
+where
+    F: std::future::Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let claim = SettlementClaim::acquire(in_progress, trade_ids).await?;
+    
+    let task = tokio::spawn(async move {
+        let output = work.await;
+        claim.release().await;
+        output
+    });
+    
+    match task.await {
+        Ok(output) => Some(output),
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+/// Trades claimed in `proof_in_progress` - released by `release`, or on drop if the
+/// holder never got there
+struct SettlementClaim {
+    in_progress: Arc<RwLock<HashSet<String>>>,
+    trade_ids: Vec<String>,
+}
+
+impl SettlementClaim {
+    /// None if any of the trades is already claimed
+    async fn acquire(in_progress: &Arc<RwLock<HashSet<String>>>, trade_ids: &[String]) -> Option<Self> {
+        let mut claimed = in_progress.write().await;
+        if trade_ids.iter().any(|trade_id| claimed.contains(trade_id)) {
+            return None;
+        }
+        claimed.extend(trade_ids.iter().cloned());
+        
+        Some(Self { in_progress: in_progress.clone(), trade_ids: trade_ids.to_vec() })
+    }
+    
+    /// Claim whichever of the trades are free (batches) - None if all are already claimed
+    async fn acquire_free(in_progress: &Arc<RwLock<HashSet<String>>>, trade_ids: &[String]) -> Option<Self> {
+        let mut claimed = in_progress.write().await;
+        let free: Vec<String> = trade_ids.iter()
+            .filter(|trade_id| claimed.insert((*trade_id).clone()))
+            .cloned()
+            .collect();
+        if free.is_empty() {
+            return None;
+        }
+        
+        Some(Self { in_progress: in_progress.clone(), trade_ids: free })
+    }
+    
+    async fn release(mut self) {
+        let trade_ids = std::mem::take(&mut self.trade_ids);
+        let mut claimed = self.in_progress.write().await;
+        for trade_id in &trade_ids {
+            claimed.remove(trade_id);
+        }
+    }
+}
+
+impl Drop for SettlementClaim {
+    fn drop(&mut self) {
+        if self.trade_ids.is_empty() {
+            return;
+        }
+        let trade_ids = std::mem::take(&mut self.trade_ids);
+        // Can't await the lock here - release from a task if it is contended
+        if let Ok(mut claimed) = self.in_progress.try_write() {
+            for trade_id in &trade_ids {
+                claimed.remove(trade_id);
+            }
+            return;
+        }
+        let in_progress = self.in_progress.clone();
+        tokio::spawn(async move {
+            let mut claimed = in_progress.write().await;
+            for trade_id in &trade_ids {
+                claimed.remove(trade_id);
+            }
+        });
+    }
+}
+
 // ============================================================================
 // Settlement Endpoint
 // ============================================================================
@@ -535,9 +1316,9 @@ pub async fn settle_handler(
     State(state): State<AppState>,
     Path(trade_id): Path<String>,
 ) -> ApiResult<Json<SettleResponse>> {
-    let blockchain_client = state.blockchain_client
-        .as_ref()
-        .ok_or_else(|| ApiError::ServiceUnavailable("Blockchain not enabled".to_string()))?;
+    if state.blockchain_client.is_none() {
+        return Err(ApiError::ServiceUnavailable("Blockchain not enabled".to_string()));
+    }
     
     let trade = state.db.get_trade(&trade_id).await
         .map_err(|e| ApiError::Database(e.to_string()))?;
@@ -552,140 +1333,431 @@ pub async fn settle_handler(
         }));
     }
     
-    // Check if proof generation is already in progress (has axiom_proof_id but not settled)
-    if trade.axiom_proof_id.is_some() && trade.status == 0 {
-        tracing::info!("⏭️ Trade {} already has proof in progress ({}), skipping duplicate request", 
-            trade_id, trade.axiom_proof_id.as_ref().unwrap());
-        return Err(ApiError::BadRequest("Proof generation already in progress. Please wait.".to_string()));
+    // Proved in a batch - resubmit the whole batch while it can still settle
+    if StoredProof::from_trade(&trade).is_none() {
+        if let Some((batch, items, batch_proof)) = stored_batch_for_trade(&state, &trade_id).await? {
+            ensure_sponsored_submission(&state, &trade_id).await?;
+            let tx_hash = resubmit_batch(&state, &batch, items, batch_proof).await?;
+            return Ok(Json(SettleResponse {
+                success: true,
+                tx_hash: format!("{:?}", tx_hash),
+                message: "Trade settled successfully!".to_string(),
+            }));
+        }
     }
     
-    tracing::info!("🔐 Starting settlement for trade {}", trade_id);
+    // Claim the trade before paying for a proof - concurrent /settle, /resubmit and
+    // background settlement calls for the same trade are refused
+    let trade_ids = [trade_id.clone()];
+    let work = {
+        let (state, trade_id) = (state.clone(), trade_id.clone());
+        async move { settle_trade(&state, &trade_id).await }
+    };
+    let response = with_settlement_claim(&state, &trade_ids, work).await
+        .ok_or_else(|| {
+            tracing::info!("⏭️ Trade {} already has proof in progress, skipping duplicate request", trade_id);
+            ApiError::BadRequest("Proof generation already in progress. Please wait.".to_string())
+        })??;
     
-    if trade.pdf_file.is_none() {
-        return Err(ApiError::BadRequest("No PDF uploaded. Call /validate first.".to_string()));
+    Ok(Json(response))
+}
+
+/// Prove (unless a proof is stored) and submit a claimed trade
+async fn settle_trade(state: &AppState, trade_id: &str) -> ApiResult<SettleResponse> {
+    // Reloaded under the claim - a concurrent call may have settled or proved it meanwhile
+    let trade = state.db.get_trade(trade_id).await
+        .map_err(|e| ApiError::Database(e.to_string()))?;
+    if trade.status == 1 {
+        return Ok(SettleResponse {
+            success: true,
+            tx_hash: trade.settlement_tx_hash.unwrap_or_default(),
+            message: "Trade already settled".to_string(),
+        });
     }
     
+    tracing::info!("🔐 Starting settlement for trade {}", trade_id);
+    
     let transaction_id = trade.transaction_id.clone()
         .ok_or_else(|| ApiError::BadRequest("No transaction_id. Call /validate first.".to_string()))?;
     let payment_time = trade.payment_time.clone()
         .ok_or_else(|| ApiError::BadRequest("No payment_time. Call /validate first.".to_string()))?;
     
-    let input_streams = {
-        let cache = state.input_streams_cache.read().await;
-        cache.get(&trade_id).cloned()
-    }.ok_or_else(|| ApiError::BadRequest("PDF not validated. Call /validate first.".to_string()))?;
+    // A paid-for proof is never regenerated - resubmit it if one is already stored
+    let stored_proof = match StoredProof::from_trade(&trade) {
+        Some(stored) => {
+            tracing::info!("♻️ Reusing stored proof {} for trade {}", trade.axiom_proof_id.as_deref().unwrap_or("-"), trade_id);
+            stored.map_err(ApiError::Internal)?
+        }
+        None => {
+            if trade.pdf_file.is_none() {
+                return Err(ApiError::BadRequest("No PDF uploaded. Call /validate first.".to_string()));
+            }
+            
+            let input_streams = {
+                let cache = state.input_streams_cache.read().await;
+                cache.get(trade_id).cloned()
+            }.ok_or_else(|| ApiError::BadRequest("PDF not validated. Call /validate first.".to_string()))?;
+            
+            tracing::info!("🚀 Generating ZK proof...");
+            generate_and_store_proof(state, trade_id, input_streams).await
+                .map_err(ApiError::Internal)?
+        }
+    };
     
-    // Generate EVM proof
-    let api_key = std::env::var("AXIOM_API_KEY")
-        .map_err(|_| ApiError::Internal("AXIOM_API_KEY not set".to_string()))?;
-    let program_id = std::env::var("AXIOM_PROGRAM_ID")
-        .map_err(|_| ApiError::Internal("AXIOM_PROGRAM_ID not set".to_string()))?;
+    // Submit to blockchain (the proof stays stored if gas sponsorship is paused)
+    ensure_sponsored_submission(state, trade_id).await?;
+    tracing::info!("📤 Submitting proof to blockchain...");
+    let tx_hash = submit_stored_proof(state, trade_id, &transaction_id, &payment_time, &stored_proof).await?;
     
-    let axiom = AxiomProver::new(api_key, String::new(), program_id);
+    tracing::info!("✅ Settlement complete: {:?}", tx_hash);
     
-    tracing::info!("🚀 Generating ZK proof...");
-    let proof = axiom.generate_evm_proof(&trade_id, input_streams).await
-        .map_err(|e| ApiError::Internal(format!("Proof generation failed: {}", e)))?;
-    tracing::info!("✅ Proof generated: {}", proof.proof_id);
+    Ok(SettleResponse {
+        success: true,
+        tx_hash: format!("{:?}", tx_hash),
+        message: "Trade settled successfully!".to_string(),
+    })
+}
+
+/// Refuse a relayer submission while the hourly spend cap is reached
+/// The stored proof can still be submitted from the buyer's wallet
+async fn ensure_sponsored_submission(state: &AppState, trade_id: &str) -> ApiResult<()> {
+    gas_budget::ensure_sponsorship_available(state).await.map_err(|e| {
+        ApiError::ServiceUnavailable(format!(
+            "{} Submit the proof from your own wallet (GET /api/trades/{}/calldata) or retry later.",
+            e, trade_id
+        ))
+    })
+}
+
+/// Stop an automatic submission (after /validate, batches) while the hourly spend cap
+/// is reached - records failed:SPEND_CAP_REACHED and keeps the stored proof for
+/// /resubmit or self-submission (/calldata)
+async fn ensure_relayer_budget(state: &AppState, trade_ids: &[String]) -> Result<(), String> {
+    if let Err(e) = gas_budget::ensure_sponsorship_available(state).await {
+        for trade_id in trade_ids {
+            record_settlement_state(state, trade_id, SettlementState::failed("SPEND_CAP_REACHED"), Some(&e)).await;
+        }
+        return Err(format!("Blockchain submission skipped: {}", e));
+    }
+    Ok(())
+}
+
+/// POST /api/trades/:trade_id/resubmit
+/// Resubmit the stored proof to the blockchain without regenerating it
+/// Used after a failed submission (RPC error, nonce issue, gas spike)
+pub async fn resubmit_handler(
+    State(state): State<AppState>,
+    Path(trade_id): Path<String>,
+) -> ApiResult<Json<SettleResponse>> {
+    if state.blockchain_client.is_none() {
+        return Err(ApiError::ServiceUnavailable("Blockchain not enabled".to_string()));
+    }
     
-    // Save proof to database
-    let proof_json = serde_json::to_string(&proof.full_json)
-        .map_err(|e| ApiError::Internal(format!("JSON serialization failed: {}", e)))?;
+    let trade = state.db.get_trade(&trade_id).await?;
     
-    state.db.save_trade_proof(
-        &trade_id,
-        &proof.user_public_values,
-        &proof.accumulator,
-        &proof.proof_data,
-        &proof.proof_id,
-        &proof_json,
-    ).await.map_err(|e| ApiError::Database(e.to_string()))?;
+    if trade.status == 1 {
+        tracing::info!("⏭️ Trade {} already settled, skipping resubmit", trade_id);
+        return Ok(Json(SettleResponse {
+            success: true,
+            tx_hash: trade.settlement_tx_hash.unwrap_or_default(),
+            message: "Trade already settled".to_string(),
+        }));
+    }
     
-    // Submit to blockchain
-    tracing::info!("📤 Submitting proof to blockchain...");
+    ensure_sponsored_submission(&state, &trade_id).await?;
     
-    let trade_id_bytes = trade_id_to_bytes32(&trade_id)
-        .map_err(|e| ApiError::BadRequest(format!("Invalid trade ID: {}", e)))?;
+    let stored_proof = match StoredProof::from_trade(&trade) {
+        Some(stored) => stored.map_err(ApiError::Internal)?,
+        None => {
+            // Proved in a batch - the batch proof can only be resubmitted as a whole
+            let (batch, items, batch_proof) = stored_batch_for_trade(&state, &trade_id).await?
+                .ok_or_else(|| ApiError::BadRequest("No stored proof for this trade. Call /settle first.".to_string()))?;
+            let tx_hash = resubmit_batch(&state, &batch, items, batch_proof).await?;
+            tracing::info!("✅ Batch resubmission complete: {:?}", tx_hash);
+            
+            return Ok(Json(SettleResponse {
+                success: true,
+                tx_hash: format!("{:?}", tx_hash),
+                message: "Trade settled successfully!".to_string(),
+            }));
+        }
+    };
+    let transaction_id = trade.transaction_id.clone()
+        .ok_or_else(|| ApiError::BadRequest("No transaction_id. Call /validate first.".to_string()))?;
+    let payment_time = trade.payment_time.clone()
+        .ok_or_else(|| ApiError::BadRequest("No payment_time. Call /validate first.".to_string()))?;
     
-    let mut user_public_values = [0u8; 32];
-    user_public_values.copy_from_slice(&proof.user_public_values);
+    // Share the in-progress guard with /settle and background settlement (prevents double submission)
+    tracing::info!("♻️ Resubmitting stored proof for trade {}", trade_id);
+    let trade_ids = [trade_id.clone()];
+    let work = {
+        let (state, trade_id) = (state.clone(), trade_id.clone());
+        async move { submit_stored_proof(&state, &trade_id, &transaction_id, &payment_time, &stored_proof).await }
+    };
+    let tx_hash = with_settlement_claim(&state, &trade_ids, work).await
+        .ok_or_else(|| ApiError::BadRequest("Settlement already in progress. Please wait.".to_string()))??;
+    tracing::info!("✅ Resubmission complete: {:?}", tx_hash);
     
-    // Compute tx_id_hash from transaction_id (v4 privacy: txId never on-chain)
+    Ok(Json(SettleResponse {
+        success: true,
+        tx_hash: format!("{:?}", tx_hash),
+        message: "Trade settled successfully!".to_string(),
+    }))
+}
+
+/// GET /api/trades/:trade_id/calldata
+/// Export unsigned submitProof calldata from the stored proof for self-submission
+/// 
+/// Lets the buyer settle from their own wallet if the relayer is down or out of gas.
+/// The event listener reconciles the trade from TradeSettled as usual.
+pub async fn submit_proof_calldata_handler(
+    State(state): State<AppState>,
+    Path(trade_id): Path<String>,
+) -> ApiResult<Json<UnsignedTransaction>> {
+    let blockchain_client = state.blockchain_client
+        .as_ref()
+        .ok_or_else(|| ApiError::ServiceUnavailable("Blockchain not enabled".to_string()))?;
+    
+    let trade = state.db.get_trade(&trade_id).await?;
+    
+    if trade.status != 0 {
+        return Err(ApiError::BadRequest("Trade is not pending".to_string()));
+    }
+    
+    let Some(stored_proof) = StoredProof::from_trade(&trade) else {
+        if stored_batch_for_trade(&state, &trade_id).await?.is_some() {
+            return Err(ApiError::BadRequest("This trade was proved in a batch, which settles all its trades at once. Use /resubmit instead.".to_string()));
+        }
+        return Err(ApiError::BadRequest("No stored proof for this trade. Call /validate first.".to_string()));
+    };
+    let stored_proof = stored_proof.map_err(ApiError::Internal)?;
+    let transaction_id = trade.transaction_id.clone()
+        .ok_or_else(|| ApiError::BadRequest("No transaction_id. Call /validate first.".to_string()))?;
+    let payment_time = trade.payment_time.clone()
+        .ok_or_else(|| ApiError::BadRequest("No payment_time. Call /validate first.".to_string()))?;
+    
+    let trade_id_bytes = trade_id_to_bytes32(&trade_id)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid trade ID: {}", e)))?;
     let tx_id_hash = compute_tx_id_hash(&transaction_id);
-    tracing::info!("🔐 tx_id_hash: 0x{}", hex::encode(tx_id_hash));
     
-    let tx_hash = blockchain_client.submit_proof(
+    let unsigned = blockchain_client.submit_proof_calldata(
         trade_id_bytes,
         tx_id_hash,
         payment_time,
-        user_public_values,
-        proof.accumulator,
-        proof.proof_data,
-    ).await.map_err(|e| {
-        let msg = e.to_string();
-        if msg.contains("PaymentDetailsMismatch") {
-            ApiError::BadRequest("Proof rejected: payment details mismatch".to_string())
-        } else if msg.contains("TradeExpired") {
-            ApiError::BadRequest("Trade expired".to_string())
-        } else if msg.contains("TradeAlreadySettled") {
-            ApiError::BadRequest("Trade already settled".to_string())
-        } else if msg.contains("TransactionIdAlreadyUsed") {
-            ApiError::BadRequest("Transaction ID already used".to_string())
-        } else {
-            ApiError::BlockchainError(msg)
-        }
-    })?;
+        stored_proof.user_public_values,
+        stored_proof.accumulator,
+        stored_proof.proof_data,
+    ).await?;
     
-    tracing::info!("✅ Settlement complete: {:?}", tx_hash);
+    tracing::info!("📝 Exported submitProof calldata for trade {} (gas_estimate={})", trade_id, unsigned.gas_estimate);
     
-    // Clear cache
-    {
-        let mut cache = state.input_streams_cache.write().await;
-        cache.remove(&trade_id);
-    }
-    
-    Ok(Json(SettleResponse {
-        success: true,
-        tx_hash: format!("{:?}", tx_hash),
-        message: "Trade settled successfully!".to_string(),
-    }))
+    Ok(Json(unsigned))
 }
 
 // ============================================================================
 // OpenVM Stream Generation
 // ============================================================================
 
-/// Generate OpenVM input streams for Axiom API
+/// Generate one receipt's OpenVM input streams for Axiom API
 /// 
-/// The guest program expects exactly:
-/// 1. PDF bytes (read_vec)
-/// 2. Line count (read)
-/// 3. Line numbers (read for each)
+/// Encoded by the shared `input_streams` crate (same layout as gen_input):
+/// PDF, line numbers (20, 21, 25, 27, 29 and the optional memo line), amount mode,
+/// key mode (+ key-set witness) and hint mode (+ page 1 object hints, so the guest
+/// parses only page 1's objects instead of the whole PDF).
 /// 
 /// The guest extracts lines from the PDF and computes the hash internally.
 /// Line text and pk_hash are NOT passed - the guest reads them from the PDF.
-fn generate_openvm_streams(pdf_bytes: &[u8]) -> Result<Vec<String>, String> {
-    let line_numbers: [u32; 5] = [20, 21, 25, 27, 29];
-    let mut streams = Vec::new();
-    
-    // Stream 1: PDF bytes (padded to 4-byte alignment)
-    let padding = (4 - (pdf_bytes.len() % 4)) % 4;
-    let mut pdf_padded = pdf_bytes.to_vec();
-    pdf_padded.extend(vec![0u8; padding]);
-    streams.push(format!("0x01{}", hex::encode(&pdf_padded)));
-    
-    // Stream 2: Line count
-    let line_count = line_numbers.len() as u32;
-    let count_bytes = openvm_serialize(&line_count).map_err(|e| format!("Serialize failed: {}", e))?;
-    let count_le: Vec<u8> = count_bytes.into_iter().flat_map(|w| w.to_le_bytes()).collect();
-    streams.push(format!("0x01{}", hex::encode(&count_le)));
-    
-    // Streams 3-7: Line numbers
-    for num in &line_numbers {
-        let num_bytes = openvm_serialize(num).map_err(|e| format!("Serialize failed: {}", e))?;
-        let num_le: Vec<u8> = num_bytes.into_iter().flat_map(|w| w.to_le_bytes()).collect();
-        streams.push(format!("0x01{}", hex::encode(&num_le)));
-    }
-    
-    Ok(streams)
+fn generate_openvm_streams(
+    pdf_bytes: &[u8],
+    memo_line: Option<u32>,
+    amount_mode: AmountMode,
+    trade_amount_cents: u64,
+    key_witness: Option<&KeyWitness>,
+) -> Vec<String> {
+    // Memo-bound trades add the memo line as a 6th line number (folded into the output hash)
+    let mut line_numbers: Vec<u32> = vec![20, 21, 25, 27, 29];
+    line_numbers.extend(memo_line);
+    let mut receipt = ReceiptInput::new(pdf_bytes.to_vec(), line_numbers);
+    
+    if amount_mode == AmountMode::AtLeast {
+        receipt = receipt.with_min_amount(trade_amount_cents);
+    }
+    if let Some(witness) = key_witness {
+        receipt = receipt.with_key_siblings(witness.siblings.clone());
+    }
+    
+    // Without hints the guest parses the whole PDF
+    match ReceiptHints::compute(pdf_bytes) {
+        Ok(hints) => receipt = receipt.with_hints(hints.to_bytes()),
+        Err(e) => tracing::warn!("⚠️ No prover hints for this PDF, guest parses it in full: {}", e),
+    }
+    
+    receipt.to_streams()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn receipt(lines: &[&str]) -> PdfExtractedFields {
+        PdfExtractedFields {
+            account_name_line: "账户名：张三".to_string(),
+            account_id_line: "账号：138******00".to_string(),
+            transaction_id: "2025010122001400001234567890".to_string(),
+            payment_time: "2025-01-01 12:00:00".to_string(),
+            amount_line: "小写：100.50".to_string(),
+            public_key_der_hash: [0u8; 32],
+            signature_valid: true,
+            lines: lines.iter().map(|line| line.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_only_unsent_submissions_are_retried() {
+        let unsent: Result<H256, EthereumClientError> = Err(EthereumClientError::NotBroadcast("rpc down".to_string()));
+        assert_eq!(retry_backoff_secs(&unsent, 1), Some(SUBMIT_INITIAL_BACKOFF_SECS));
+        assert_eq!(retry_backoff_secs(&unsent, 3), Some(SUBMIT_INITIAL_BACKOFF_SECS * 4));
+        assert_eq!(retry_backoff_secs(&unsent, SUBMIT_MAX_ATTEMPTS), None);
+        
+        // A confirm timeout leaves the transaction in flight - resending would settle twice
+        let pending: Result<H256, EthereumClientError> = Err(EthereumClientError::StillPending(H256::repeat_byte(1)));
+        assert_eq!(retry_backoff_secs(&pending, 1), None);
+        assert_eq!(retry_backoff_secs(&Ok(H256::zero()), 1), None);
+    }
+
+    #[test]
+    fn test_recipient_pre_check() {
+        let fields = receipt(&[]);
+        let hash = compute_account_lines_hash_from_lines("账户名：张三", "账号：138******00");
+        assert!(fields.pays_account(&hash));
+
+        let other = compute_account_lines_hash_from_lines("账户名：李四", "账号：138******00");
+        assert!(!fields.pays_account(&other));
+    }
+
+    #[test]
+    fn test_memo_pre_check() {
+        let trade_id = [0x11u8; 32];
+        let memo_line = format_memo_line(&trade_memo_code(&trade_id));
+
+        let fields = receipt(&["付款方式", &memo_line, "小写：100.50"]);
+        assert_eq!(fields.find_memo_line(&trade_id), Ok((memo_line.clone(), 2)));
+
+        // Another trade's code, or the code with extra text on the line
+        let message = fields.find_memo_line(&[0x22u8; 32]).unwrap_err();
+        assert!(message.contains(&trade_memo_code(&[0x22u8; 32])));
+        let padded = format!("{} ", memo_line);
+        assert!(receipt(&[&padded]).find_memo_line(&trade_id).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_settlements_request_one_proof() {
+        let in_progress = Arc::new(RwLock::new(HashSet::new()));
+        let proof_requests = Arc::new(AtomicUsize::new(0));
+        let trade_ids = ["0xaa".to_string()];
+
+        // Stands in for the paid Axiom proof request of /settle
+        let settle = || {
+            let proof_requests = proof_requests.clone();
+            claim_and_run(&in_progress, &trade_ids, async move {
+                proof_requests.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            })
+        };
+
+        let (first, second) = tokio::join!(settle(), settle());
+        assert_eq!(proof_requests.load(Ordering::SeqCst), 1);
+        assert!(first.is_some() != second.is_some());
+
+        // Released afterwards - a later call runs again
+        assert!(settle().await.is_some());
+        assert_eq!(proof_requests.load(Ordering::SeqCst), 2);
+        assert!(in_progress.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batch_claim_overlapping_single_trade() {
+        let in_progress = Arc::new(RwLock::new(HashSet::new()));
+        let batch = ["0xaa".to_string(), "0xbb".to_string()];
+        let single = ["0xbb".to_string()];
+
+        // A /resubmit of one trade while its batch is being resubmitted
+        let (batch_result, single_result) = tokio::join!(
+            claim_and_run(&in_progress, &batch, tokio::time::sleep(Duration::from_millis(50))),
+            async {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                claim_and_run(&in_progress, &single, async {}).await
+            },
+        );
+        assert!(batch_result.is_some());
+        assert!(single_result.is_none());
+        assert!(in_progress.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batch_claims_free_trades_and_releases_on_panic() {
+        let in_progress = Arc::new(RwLock::new(HashSet::from(["0xbb".to_string()])));
+        let batch = ["0xaa".to_string(), "0xbb".to_string(), "0xcc".to_string()];
+
+        // 0xbb is being settled on its own - the batch proves the rest
+        let claim = SettlementClaim::acquire_free(&in_progress, &batch).await.unwrap();
+        assert_eq!(claim.trade_ids, ["0xaa", "0xcc"]);
+        assert!(SettlementClaim::acquire_free(&in_progress, &batch).await.is_none());
+
+        // The batch task panics mid-proof - only its own claims are released
+        let panicked = tokio::spawn(async move {
+            let _claim = claim;
+            panic!("prover crashed");
+        }).await;
+        assert!(panicked.is_err());
+
+        tokio::task::yield_now().await;
+        assert_eq!(*in_progress.read().await, HashSet::from(["0xbb".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn test_dropped_settlement_finishes_and_releases_claim() {
+        let in_progress = Arc::new(RwLock::new(HashSet::new()));
+        let broadcasts = Arc::new(AtomicUsize::new(0));
+        let trade_ids = ["0xaa".to_string()];
+
+        // The client disconnects halfway through /settle - axum drops the handler future
+        let work = {
+            let broadcasts = broadcasts.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                broadcasts.fetch_add(1, Ordering::SeqCst);
+            }
+        };
+        let settle = claim_and_run(&in_progress, &trade_ids, work);
+        assert!(tokio::time::timeout(Duration::from_millis(10), settle).await.is_err());
+
+        // Still claimed while the work runs on, so a retry is refused
+        assert!(in_progress.read().await.contains("0xaa"));
+        assert!(claim_and_run(&in_progress, &trade_ids, async {}).await.is_none());
+
+        // The broadcast completes and the claim is released without the caller
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(broadcasts.load(Ordering::SeqCst), 1);
+        assert!(in_progress.read().await.is_empty());
+        assert!(claim_and_run(&in_progress, &trade_ids, async {}).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_panicking_settlement_releases_claim() {
+        let in_progress = Arc::new(RwLock::new(HashSet::new()));
+        let trade_ids = ["0xaa".to_string()];
+
+        let panicked = tokio::spawn({
+            let in_progress = in_progress.clone();
+            let trade_ids = trade_ids.clone();
+            async move { claim_and_run(&in_progress, &trade_ids, async { panic!("prover crashed") }).await }
+        }).await;
+        assert!(panicked.is_err());
+
+        tokio::task::yield_now().await;
+        assert!(in_progress.read().await.is_empty());
+    }
 }
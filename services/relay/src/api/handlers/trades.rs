@@ -2,6 +2,7 @@
 //! 
 //! Trade creation is done by the relay wallet on behalf of buyers.
 //! Buyers don't need to connect a wallet - the relay pays for gas.
+//! Power users can instead export unsigned calldata and submit from their own wallet.
 
 use axum::{
     extract::{Path, State},
@@ -14,13 +15,27 @@ use crate::api::{
     error::{ApiError, ApiResult},
     state::AppState,
 };
+use crate::blockchain::types::UnsignedTransaction;
+use crate::crypto::trade_memo_code;
+use crate::gas_budget;
+
+/// Trade details plus settlement sub-status and its timeline
+#[derive(Debug, Serialize)]
+pub struct TradeDetailResponse {
+    #[serde(flatten)]
+    pub trade: crate::db::models::DbTrade,
+    /// Current settlement state (receipt_uploaded, validating, ..., settled, failed:<code>)
+    pub settlement_state: Option<String>,
+    /// Settlement state transitions, oldest first
+    pub settlement_timeline: Vec<crate::db::models::DbSettlementEvent>,
+}
 
 /// GET /api/trades/:trade_id
-/// Get trade details by ID
+/// Get trade details by ID, including settlement state and timeline
 pub async fn get_trade_handler(
     Path(trade_id): Path<String>,
     State(state): State<AppState>,
-) -> ApiResult<Json<crate::db::models::DbTrade>> {
+) -> ApiResult<Json<TradeDetailResponse>> {
     // Query trade from database using dynamic query
     let trade = sqlx::query(
         r#"
@@ -31,7 +46,7 @@ pub async fn get_trade_handler(
             "escrowTxHash", "settlementTxHash", "syncedAt",
             pdf_file, pdf_filename, pdf_uploaded_at,
             proof_user_public_values, proof_accumulator, proof_data,
-            axiom_proof_id, proof_generated_at, proof_json, settlement_error
+            axiom_proof_id, proof_generated_at, proof_json, settlement_error, memo_code
         FROM trades
         WHERE "tradeId" = $1
         "#,
@@ -71,11 +86,19 @@ pub async fn get_trade_handler(
         proof_generated_at: trade.get("proof_generated_at"),
         proof_json: trade.get("proof_json"),
         settlement_error: trade.get("settlement_error"),
+        memo_code: trade.get("memo_code"),
         alipay_id: None,
         alipay_name: None,
     };
 
-    Ok(Json(db_trade))
+    let settlement_state = state.db.get_settlement_state(&trade_id).await?;
+    let settlement_timeline = state.db.get_settlement_events(&trade_id).await?;
+
+    Ok(Json(TradeDetailResponse {
+        trade: db_trade,
+        settlement_state,
+        settlement_timeline,
+    }))
 }
 
 /// GET /api/trades/buyer/:buyer_address
@@ -124,6 +147,7 @@ pub async fn get_trades_by_buyer_handler(
             t.proof_generated_at,
             t.proof_json,
             t.settlement_error,
+            t.memo_code,
             o.token,
             o."accountId" as "alipay_id",
             o."accountName" as "alipay_name"
@@ -169,6 +193,7 @@ pub async fn get_trades_by_buyer_handler(
                 proof_generated_at: row.get("proof_generated_at"),
                 proof_json: row.get("proof_json"),
                 settlement_error: row.get("settlement_error"),
+                memo_code: row.get("memo_code"),
                 token: Some(row.get("token")),
                 alipay_id: row.get("alipay_id"),
                 alipay_name: row.get("alipay_name"),
@@ -220,6 +245,7 @@ pub async fn get_trades_by_seller_handler(
             t.proof_generated_at,
             t.proof_json,
             t.settlement_error,
+            t.memo_code,
             COALESCE(t.token, o.token) as token,
             o."accountId" as "alipay_id",
             o."accountName" as "alipay_name"
@@ -265,6 +291,7 @@ pub async fn get_trades_by_seller_handler(
                 proof_generated_at: row.get("proof_generated_at"),
                 proof_json: row.get("proof_json"),
                 settlement_error: row.get("settlement_error"),
+                memo_code: row.get("memo_code"),
                 token: row.get("token"),
                 alipay_id: row.get("alipay_id"),
                 alipay_name: row.get("alipay_name"),
@@ -295,6 +322,9 @@ pub struct CreateTradeResponse {
     pub order_id: String,
     pub buyer: String,
     pub tx_hash: String,
+    /// Trade reference code for the Alipay memo (only when the order requires a memo)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo_code: Option<String>,
     pub message: String,
 }
 
@@ -317,6 +347,44 @@ pub async fn create_trade_handler(
     let blockchain_client = state.blockchain_client.as_ref()
         .ok_or_else(|| ApiError::ServiceUnavailable("Blockchain client not available".to_string()))?;
 
+    let (order_id, buyer_address, fiat_amount) = parse_create_trade_request(&request)?;
+
+    gas_budget::ensure_sponsorship_available(&state).await.map_err(|e| {
+        ApiError::ServiceUnavailable(format!(
+            "{} Create the trade from your own wallet (POST /api/trades/create/calldata) or retry later.",
+            e
+        ))
+    })?;
+
+    // Call fillOrder on-chain via relay wallet
+    let (tx_hash, trade_id, memo_required) = blockchain_client
+        .fill_order(order_id, buyer_address, fiat_amount)
+        .await?;
+
+    let trade_id_hex = format!("0x{}", hex::encode(trade_id));
+    let tx_hash_hex = format!("{:#x}", tx_hash);
+
+    let memo_code = memo_required.then(|| trade_memo_code(&trade_id));
+
+    tracing::info!(
+        "Trade created: trade_id={}, tx_hash={}, memo_code={:?}",
+        trade_id_hex,
+        tx_hash_hex,
+        memo_code
+    );
+
+    Ok(Json(CreateTradeResponse {
+        trade_id: trade_id_hex,
+        order_id: request.order_id,
+        buyer: request.buyer_address,
+        tx_hash: tx_hash_hex,
+        memo_code,
+        message: "Trade created successfully".to_string(),
+    }))
+}
+
+/// Parse and validate fillOrder arguments (order ID, buyer, whole-yuan fiat amount)
+fn parse_create_trade_request(request: &CreateTradeRequest) -> ApiResult<([u8; 32], Address, U256)> {
     // Parse order ID (bytes32)
     let order_id_hex = request.order_id.strip_prefix("0x").unwrap_or(&request.order_id);
     let order_id_bytes = hex::decode(order_id_hex)
@@ -342,25 +410,50 @@ pub async fn create_trade_handler(
         return Err(ApiError::BadRequest("fiat_amount must be whole yuan (divisible by 100)".to_string()));
     }
 
-    // Call fillOrder on-chain via relay wallet
-    let (tx_hash, trade_id) = blockchain_client.fill_order(order_id, buyer_address, fiat_amount)
-        .await
-        .map_err(|e| ApiError::BlockchainError(format!("fillOrder failed: {}", e)))?;
+    Ok((order_id, buyer_address, fiat_amount))
+}
 
-    let trade_id_hex = format!("0x{}", hex::encode(trade_id));
-    let tx_hash_hex = format!("{:#x}", tx_hash);
+/// Request body for exporting fillOrder calldata
+#[derive(Debug, Deserialize)]
+pub struct FillOrderCalldataRequest {
+    #[serde(flatten)]
+    pub trade: CreateTradeRequest,
+    /// Sender used for gas estimation (defaults to the buyer)
+    pub from: Option<String>,
+}
+
+/// POST /api/trades/create/calldata
+/// Export unsigned fillOrder calldata for self-submission
+/// 
+/// Same validation as /api/trades/create, but nothing is sent - the buyer submits
+/// from their own wallet. The event listener picks up TradeCreated either way.
+/// If the order requires a memo, the trade's memo_code is available once the listener
+/// syncs TradeMemoRequired (it is derived from the tradeId, unknown until the tx is mined).
+pub async fn fill_order_calldata_handler(
+    State(state): State<AppState>,
+    Json(request): Json<FillOrderCalldataRequest>,
+) -> ApiResult<Json<UnsignedTransaction>> {
+    let blockchain_client = state.blockchain_client.as_ref()
+        .ok_or_else(|| ApiError::ServiceUnavailable("Blockchain client not available".to_string()))?;
+
+    let (order_id, buyer_address, fiat_amount) = parse_create_trade_request(&request.trade)?;
+
+    let from: Address = match &request.from {
+        Some(from) => from.parse()
+            .map_err(|e| ApiError::BadRequest(format!("Invalid from address: {}", e)))?,
+        None => buyer_address,
+    };
+
+    let unsigned = blockchain_client
+        .fill_order_calldata(order_id, buyer_address, fiat_amount, from)
+        .await?;
 
     tracing::info!(
-        "Trade created: trade_id={}, tx_hash={}",
-        trade_id_hex,
-        tx_hash_hex
+        "📝 Exported fillOrder calldata: order_id={}, buyer={:#x}, gas_estimate={}",
+        request.trade.order_id,
+        buyer_address,
+        unsigned.gas_estimate
     );
 
-    Ok(Json(CreateTradeResponse {
-        trade_id: trade_id_hex,
-        order_id: request.order_id,
-        buyer: request.buyer_address,
-        tx_hash: tx_hash_hex,
-        message: "Trade created successfully".to_string(),
-    }))
+    Ok(Json(unsigned))
 }
@@ -17,6 +17,13 @@ use crate::api::{handlers, state::AppState};
 /// - GET  /api/trades/buyer/:addr      - Get trades by buyer
 /// - POST /api/trades/:id/validate     - Upload PDF + quick validation (~10s)
 /// - POST /api/trades/:id/settle       - Generate proof + submit (~2-3 min)
+/// - POST /api/trades/:id/resubmit     - Resubmit stored proof (no regeneration)
+/// - GET  /api/trades/:id/calldata     - Unsigned submitProof calldata (self-submission)
+/// - GET  /api/trades/:id/events       - Live trade updates (SSE)
+/// - GET  /api/trades/:id/ws           - Live trade updates (WebSocket)
+/// - POST /api/trades/create/calldata  - Unsigned fillOrder calldata (self-submission)
+/// - GET  /api/admin/key-rotations     - Governed Alipay key rotations (read-only)
+/// - GET  /api/admin/relayer           - Relayer balance, gas spend and transactions (read-only)
 pub fn create_router(state: AppState) -> Router {
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -37,6 +44,7 @@ pub fn create_router(state: AppState) -> Router {
         
         // Trades
         .route("/api/trades/create", post(handlers::create_trade_handler))
+        .route("/api/trades/create/calldata", post(handlers::fill_order_calldata_handler))
         .route("/api/trades/:trade_id", get(handlers::get_trade_handler))
         .route("/api/trades/buyer/:buyer_address", get(handlers::get_trades_by_buyer_handler))
         .route("/api/trades/seller/:seller_address", get(handlers::get_trades_by_seller_handler))
@@ -44,6 +52,12 @@ pub fn create_router(state: AppState) -> Router {
         // Settlement (2-step flow)
         .route("/api/trades/:trade_id/validate", post(handlers::validate_handler))
         .route("/api/trades/:trade_id/settle", post(handlers::settle_handler))
+        .route("/api/trades/:trade_id/resubmit", post(handlers::resubmit_handler))
+        .route("/api/trades/:trade_id/calldata", get(handlers::submit_proof_calldata_handler))
+        
+        // Live trade updates
+        .route("/api/trades/:trade_id/events", get(handlers::trade_events_sse))
+        .route("/api/trades/:trade_id/ws", get(handlers::trade_events_ws))
         
         // Debug endpoints (for development)
         .route("/api/debug/database", get(handlers::debug_database))
@@ -51,6 +65,9 @@ pub fn create_router(state: AppState) -> Router {
         // Admin endpoints (read-only - all write operations removed for security)
         // Contract modifications must be done directly via cast/forge with owner wallet
         .route("/api/admin/config", get(handlers::get_contract_config))
+        .route("/api/admin/key-rotations", get(handlers::get_key_rotations))
+        .route("/api/admin/key-rotations/:pk_hash", get(handlers::get_key_rotation))
+        .route("/api/admin/relayer", get(handlers::get_relayer_status))
         
         // Trade file endpoints
         .route("/api/trades/:trade_id/pdf", get(handlers::get_trade_pdf))
@@ -2,9 +2,15 @@ use std::sync::Arc;
 use std::collections::{HashMap, HashSet};
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
+use crate::axiom_prover::programs::{AmountMode, Program, ProgramCommitments, ProgramRegistry};
 use crate::db::Database;
 use crate::blockchain::client::EthereumClient;
 use crate::blockchain::types::ContractConfig;
+use crate::gas_budget::GasBudgetPolicy;
+use crate::key_rotation::KeyRotationPolicy;
+use crate::key_set::KeySet;
+use crate::settlement_batch::SettlementBatcher;
+use crate::trade_events::TradeEventBus;
 
 /// Cache entry with expiration
 pub struct CachedConfig {
@@ -22,7 +28,7 @@ pub struct AppState {
     /// Blockchain client for Ethereum interaction (optional for testing)
     pub blockchain_client: Option<Arc<EthereumClient>>,
     
-    /// In-memory cache for input streams (trade_id -> 46 hex strings)
+    /// In-memory cache for input streams (trade_id -> the receipt's hex streams)
     /// Used to avoid regenerating input streams between validation and proof generation
     pub input_streams_cache: Arc<RwLock<HashMap<String, Vec<String>>>>,
     
@@ -32,6 +38,28 @@ pub struct AppState {
     
     /// Set of trade IDs currently generating proofs (prevents duplicate requests)
     pub proof_in_progress: Arc<RwLock<HashSet<String>>>,
+    
+    /// Broadcast bus for live trade updates (SSE / WebSocket subscribers)
+    pub trade_events: TradeEventBus,
+    
+    /// Batching window for proof generation (None = each trade proved on its own)
+    pub settlement_batcher: Option<SettlementBatcher>,
+    
+    /// Trusted Alipay key set (None = the verifier stores a single key hash)
+    pub key_set: Option<Arc<RwLock<KeySet>>>,
+    
+    /// Axiom program versions keyed by commitments (None = not resolved at startup)
+    /// Programs that failed to resolve are added later by `programs::retry_unresolved`
+    pub programs: Option<Arc<RwLock<ProgramRegistry>>>,
+    
+    /// How the guest matches the paid amount (AMOUNT_MATCH_MODE, parsed at startup)
+    pub amount_mode: AmountMode,
+    
+    /// When a quarantined Alipay key may be submitted on-chain
+    pub key_rotation: Arc<KeyRotationPolicy>,
+    
+    /// Relayer hourly spend cap and low-balance threshold
+    pub gas_budget: Arc<GasBudgetPolicy>,
 }
 
 impl AppState {
@@ -56,6 +84,13 @@ impl AppState {
             input_streams_cache: Arc::new(RwLock::new(HashMap::new())),
             config_cache: Arc::new(RwLock::new(None)),
             proof_in_progress: Arc::new(RwLock::new(HashSet::new())),
+            trade_events: TradeEventBus::new(),
+            settlement_batcher: None,
+            key_set: None,
+            programs: None,
+            amount_mode: AmountMode::Exact,
+            key_rotation: Arc::new(KeyRotationPolicy::default()),
+            gas_budget: Arc::new(GasBudgetPolicy::default()),
         })
     }
     
@@ -65,6 +100,86 @@ impl AppState {
         self
     }
     
+    /// Set settlement batcher (optional, queues validated trades for batch proving)
+    pub fn with_settlement_batcher(mut self, batcher: SettlementBatcher) -> Self {
+        self.settlement_batcher = Some(batcher);
+        self
+    }
+    
+    /// Set trusted key set (optional, proofs commit the key-set root instead of the key hash)
+    pub fn with_key_set(mut self, key_set: KeySet) -> Self {
+        self.key_set = Some(Arc::new(RwLock::new(key_set)));
+        self
+    }
+    
+    /// Set Axiom program registry (resolved at startup, checked against the verifier)
+    pub fn with_program_registry(mut self, programs: ProgramRegistry) -> Self {
+        self.programs = Some(Arc::new(RwLock::new(programs)));
+        self
+    }
+    
+    /// Set amount mode (default: exact)
+    pub fn with_amount_mode(mut self, amount_mode: AmountMode) -> Self {
+        self.amount_mode = amount_mode;
+        self
+    }
+    
+    /// Set key rotation policy (default: 3 receipts, 24h delay, no pinned root)
+    pub fn with_key_rotation_policy(mut self, policy: KeyRotationPolicy) -> Self {
+        self.key_rotation = Arc::new(policy);
+        self
+    }
+    
+    /// Set relayer gas budget (default: no spend cap, 0.01 ETH low-balance alert)
+    pub fn with_gas_budget_policy(mut self, policy: GasBudgetPolicy) -> Self {
+        self.gas_budget = Arc::new(policy);
+        self
+    }
+    
+    /// Axiom program matching the on-chain AlipayVerifier commitments
+    ///
+    /// Refreshes the cached config once on a mismatch (the verifier may just have
+    /// been upgraded), then remembers the mismatch for the config TTL or until a
+    /// program resolves. Errors mean proofs would revert - refuse to validate or prove.
+    pub async fn active_program(&self) -> Result<Program, String> {
+        let programs = self.programs.as_ref()
+            .ok_or_else(|| "Axiom programs not resolved (AXIOM_API_KEY / AXIOM_PROGRAM_IDS)".to_string())?;
+        
+        let onchain = self.onchain_program_commitments(false).await?;
+        {
+            let registry = programs.read().await;
+            if let Ok(program) = registry.select(&onchain) {
+                return Ok(program.clone());
+            }
+            if let Some(error) = registry.cached_mismatch(Self::CONFIG_CACHE_TTL) {
+                return Err(error.to_string());
+            }
+        }
+        
+        let onchain = self.onchain_program_commitments(true).await?;
+        let mut registry = programs.write().await;
+        let result = registry.select(&onchain).cloned();
+        if let Err(e) = &result {
+            let error = match registry.unresolved().len() {
+                0 => e.clone(),
+                unresolved => format!("{} ({} programs unresolved, retrying)", e, unresolved),
+            };
+            registry.record_mismatch(error.clone());
+            return Err(error);
+        }
+        result
+    }
+    
+    /// AlipayVerifier's appExeCommit / appVmCommit (from the cached config)
+    pub async fn onchain_program_commitments(&self, force_refresh: bool) -> Result<ProgramCommitments, String> {
+        let config = self.get_config(force_refresh).await?;
+        let onchain = ProgramCommitments::from_hex(&config.app_exe_commit, &config.app_vm_commit)?;
+        if onchain.app_exe_commit == [0u8; 32] {
+            return Err("AlipayVerifier commitments unavailable (verifier not set?)".to_string());
+        }
+        Ok(onchain)
+    }
+    
     /// Get cached config or fetch fresh from blockchain
     pub async fn get_config(&self, force_refresh: bool) -> Result<ContractConfig, String> {
         let blockchain_client = self.blockchain_client.as_ref()
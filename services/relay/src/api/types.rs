@@ -6,5 +6,35 @@ pub struct HealthResponse {
     pub status: String,
     pub database: String,
     pub orderbook: String,
+    pub prover: ProverHealth,
     pub timestamp: String,
 }
+
+/// Axiom program vs on-chain verifier commitments
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProverHealth {
+    /// "matched" | "mismatch" | "unconfigured"
+    pub status: String,
+    /// Program used for validation and proving (when matched)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_program_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub onchain_app_exe_commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub onchain_app_vm_commit: Option<String>,
+    /// Configured programs (resolved ones with commitments, unresolved ones with an error)
+    pub programs: Vec<ProgramHealth>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgramHealth {
+    pub program_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_exe_commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_vm_commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
@@ -0,0 +1,155 @@
+//! Diagnostic guest output (execute mode only)
+//!
+//! The guest's `diagnostic` build reveals, after the 32-byte output, a failure bitmask
+//! and the sub-hashes of the receipt (see guest main.rs). Comparing them with the
+//! relay's expected sub-hashes tells which component of a HASH_MISMATCH differed,
+//! without revealing any receipt plaintext.
+
+/// Public values revealed by the diagnostic build (7 x 32-byte slots)
+pub const DIAGNOSTIC_PUBLIC_VALUES_LEN: usize = 224;
+
+// Failure bitmask - mirrors FAIL_* in the guest
+const FAIL_PDF: u32 = 1 << 0;
+const FAIL_SIGNATURE: u32 = 1 << 1;
+const FAIL_LINE_MISSING: u32 = 1 << 2;
+const FAIL_AMOUNT_UNPARSEABLE: u32 = 1 << 3;
+const FAIL_AMOUNT_TOO_LOW: u32 = 1 << 4;
+
+/// Failure bits and their component names, in reporting order
+const FAILURES: &[(u32, &str)] = &[
+    (FAIL_PDF, "pdf"),
+    (FAIL_SIGNATURE, "signature"),
+    (FAIL_LINE_MISSING, "line_missing"),
+    (FAIL_AMOUNT_UNPARSEABLE, "amount_unparseable"),
+    (FAIL_AMOUNT_TOO_LOW, "amount_too_low"),
+];
+
+/// Failure bitmask and sub-hashes of the first receipt, as revealed by the guest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuestDiagnostics {
+    pub failures: u32,
+    pub pk_hash: [u8; 32],
+    pub account_lines_hash: [u8; 32],
+    pub tx_id_hash: [u8; 32],
+    pub time_amount_hash: [u8; 32],
+    /// All zero when no memo line was requested
+    pub memo_hash: [u8; 32],
+}
+
+/// Sub-hashes the relay expects for a receipt (same inputs as the expected output hash)
+#[derive(Debug, Clone)]
+pub struct ExpectedComponents {
+    pub pk_hash: [u8; 32],
+    pub account_lines_hash: [u8; 32],
+    pub tx_id_hash: [u8; 32],
+    pub time_amount_hash: [u8; 32],
+    pub memo_hash: Option<[u8; 32]>,
+}
+
+impl GuestDiagnostics {
+    /// Parse the diagnostic slots - None for the production build's 32-byte output
+    pub fn parse(public_values: &[u8]) -> Option<Self> {
+        if public_values.len() < DIAGNOSTIC_PUBLIC_VALUES_LEN {
+            return None;
+        }
+
+        let slot = |index: usize| -> [u8; 32] {
+            let mut bytes = [0u8; 32];
+            bytes.copy_from_slice(&public_values[index * 32..(index + 1) * 32]);
+            bytes
+        };
+        let failure_slot = slot(1);
+
+        Some(Self {
+            failures: u32::from_le_bytes([failure_slot[0], failure_slot[1], failure_slot[2], failure_slot[3]]),
+            pk_hash: slot(2),
+            account_lines_hash: slot(3),
+            tx_id_hash: slot(4),
+            time_amount_hash: slot(5),
+            memo_hash: slot(6),
+        })
+    }
+
+    /// Components that explain a mismatch: guest failures first, then differing sub-hashes
+    ///
+    /// Names: pdf, signature, line_missing, amount_unparseable, amount_too_low,
+    /// public_key, account, tx_id, time_amount, memo
+    pub fn diagnose(&self, expected: &ExpectedComponents) -> Vec<&'static str> {
+        let mut components: Vec<&'static str> = FAILURES
+            .iter()
+            .filter(|(bit, _)| self.failures & bit != 0)
+            .map(|(_, name)| *name)
+            .collect();
+
+        let expected_memo = expected.memo_hash.unwrap_or([0u8; 32]);
+        for (name, actual, expected) in [
+            ("public_key", &self.pk_hash, &expected.pk_hash),
+            ("account", &self.account_lines_hash, &expected.account_lines_hash),
+            ("tx_id", &self.tx_id_hash, &expected.tx_id_hash),
+            ("time_amount", &self.time_amount_hash, &expected.time_amount_hash),
+            ("memo", &self.memo_hash, &expected_memo),
+        ] {
+            if actual != expected {
+                components.push(name);
+            }
+        }
+
+        components
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn public_values(failures: u32, hashes: [[u8; 32]; 5]) -> Vec<u8> {
+        let mut values = vec![0x42; 32];
+        let mut failure_slot = [0u8; 32];
+        failure_slot[..4].copy_from_slice(&failures.to_le_bytes());
+        values.extend_from_slice(&failure_slot);
+        for hash in hashes {
+            values.extend_from_slice(&hash);
+        }
+        values
+    }
+
+    fn expected() -> ExpectedComponents {
+        ExpectedComponents {
+            pk_hash: [1; 32],
+            account_lines_hash: [2; 32],
+            tx_id_hash: [3; 32],
+            time_amount_hash: [4; 32],
+            memo_hash: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_requires_diagnostic_build() {
+        assert_eq!(GuestDiagnostics::parse(&[0u8; 32]), None);
+
+        let values = public_values(FAIL_SIGNATURE, [[1; 32], [2; 32], [3; 32], [4; 32], [0; 32]]);
+        assert_eq!(values.len(), DIAGNOSTIC_PUBLIC_VALUES_LEN);
+        let diagnostics = GuestDiagnostics::parse(&values).unwrap();
+        assert_eq!(diagnostics.failures, FAIL_SIGNATURE);
+        assert_eq!(diagnostics.tx_id_hash, [3; 32]);
+        assert_eq!(diagnostics.memo_hash, [0; 32]);
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let matching = GuestDiagnostics::parse(&public_values(0, [[1; 32], [2; 32], [3; 32], [4; 32], [0; 32]])).unwrap();
+        assert!(matching.diagnose(&expected()).is_empty());
+
+        let wrong_amount = GuestDiagnostics::parse(&public_values(
+            FAIL_AMOUNT_TOO_LOW,
+            [[1; 32], [2; 32], [3; 32], [9; 32], [0; 32]],
+        )).unwrap();
+        assert_eq!(wrong_amount.diagnose(&expected()), vec!["amount_too_low", "time_amount"]);
+
+        // Memo expected but the guest hashed a different line
+        let mut with_memo = expected();
+        with_memo.memo_hash = Some([5; 32]);
+        let wrong_memo = GuestDiagnostics::parse(&public_values(0, [[1; 32], [7; 32], [3; 32], [4; 32], [6; 32]])).unwrap();
+        assert_eq!(wrong_memo.diagnose(&with_memo), vec!["account", "memo"]);
+    }
+}
@@ -4,6 +4,13 @@ use serde::Deserialize;
 use std::time::Duration;
 use tokio::time::sleep;
 
+use crate::trade_events::{TradeEvent, TradeEventBus};
+
+pub mod diagnostics;
+pub mod programs;
+
+use programs::ProgramCommitments;
+
 const AXIOM_API_BASE: &str = "https://api.axiom.xyz";
 
 /// Axiom Prover client
@@ -13,6 +20,7 @@ pub struct AxiomProver {
     config_id: String,  // Reserved for future use
     program_id: String,
     client: reqwest::Client,
+    events: Option<TradeEventBus>,
 }
 
 impl AxiomProver {
@@ -22,10 +30,20 @@ impl AxiomProver {
             config_id,
             program_id,
             client: reqwest::Client::new(),
+            events: None,
         }
     }
     
-    /// Execute program (fast validation mode) - returns output hash only
+    /// Publish proof status transitions to the trade event bus
+    pub fn with_event_bus(mut self, events: TradeEventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+    
+    /// Execute program (fast validation mode) - returns all public values
+    ///
+    /// The first 32 bytes are the output hash; the diagnostic guest build appends
+    /// more (see `diagnostics`).
     pub async fn execute_program(&self, trade_id: &str, input_streams: Vec<String>) -> Result<Vec<u8>> {
         tracing::info!("⚡ [{}] Starting Axiom program execution (validation mode)", trade_id);
         tracing::info!("📋 [{}] Input streams count: {}", trade_id, input_streams.len());
@@ -72,8 +90,8 @@ impl AxiomProver {
             return Err(anyhow!("public_values is neither a string nor an array. Value: {:?}", result["public_values"]));
         };
         
-        if public_values.len() != 32 {
-            return Err(anyhow!("Invalid public_values size: expected 32 bytes, got {}", public_values.len()));
+        if public_values.len() < 32 {
+            return Err(anyhow!("Invalid public_values size: expected at least 32 bytes, got {}", public_values.len()));
         }
         
         tracing::info!("📥 [{}] Execution result: {} bytes", trade_id, public_values.len());
@@ -91,7 +109,7 @@ impl AxiomProver {
         tracing::info!("📤 [{}] Proof request submitted, proof_id: {}", trade_id, proof_id);
         
         // Step 2: Poll for completion
-        self.poll_proof_status(&proof_id).await?;
+        self.poll_proof_status(trade_id, &proof_id).await?;
         tracing::info!("✅ [{}] Proof generation completed: {}", trade_id, proof_id);
         
         // Step 3: Download proof
@@ -140,10 +158,11 @@ impl AxiomProver {
     }
     
     /// Poll proof status until completion or timeout
-    async fn poll_proof_status(&self, proof_id: &str) -> Result<()> {
+    async fn poll_proof_status(&self, trade_id: &str, proof_id: &str) -> Result<()> {
         let max_attempts = 120; // 120 attempts * 10 seconds = 20 minutes max
         let mut attempt = 0;
         let mut delay_secs = 10;
+        let mut last_state: Option<String> = None;
         
         loop {
             attempt += 1;
@@ -173,6 +192,18 @@ impl AxiomProver {
             
             tracing::info!("Proof status: {} (type: {})", status_response.state, status_response.proof_type);
             
+            // Publish transitions only (not every poll)
+            if last_state.as_deref() != Some(status_response.state.as_str()) {
+                if let Some(events) = &self.events {
+                    events.publish(TradeEvent::ProofStatus {
+                        trade_id: trade_id.to_string(),
+                        proof_id: proof_id.to_string(),
+                        status: status_response.state.clone(),
+                    });
+                }
+                last_state = Some(status_response.state.clone());
+            }
+            
             match status_response.state.as_str() {
                 // According to Axiom API docs, the terminal success state is "Succeeded"
                 "Succeeded" => {
@@ -444,3 +475,35 @@ impl AxiomProver {
         Ok(response.json().await?)
     }
 }
+
+// ============================================================================
+// Program API Methods (commitment checks)
+// ============================================================================
+
+impl AxiomProver {
+    /// Commitments of an uploaded program (appExeCommit / appVmCommit)
+    pub async fn get_program_commitments(&self, program_id: &str) -> Result<ProgramCommitments> {
+        let response = self.client
+            .get(format!("{}/v1/programs/{}", AXIOM_API_BASE, program_id))
+            .header("Axiom-API-Key", &self.api_key)
+            .send()
+            .await?;
+        
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await?;
+            return Err(anyhow!("Failed to get program {} ({}): {}", program_id, status, error_text));
+        }
+        
+        let program: serde_json::Value = response.json().await?;
+        tracing::debug!("📊 Program {}: {}", program_id, program);
+        
+        let field = |name: &str| {
+            program[name]
+                .as_str()
+                .ok_or_else(|| anyhow!("Program {} has no {} (not built yet?)", program_id, name))
+        };
+        ProgramCommitments::from_hex(field("app_exe_commit")?, field("app_vm_commit")?)
+            .map_err(|e| anyhow!("Program {}: {}", program_id, e))
+    }
+}
@@ -0,0 +1,354 @@
+//! Axiom program versions - keyed by their commitments
+//!
+//! A proof only verifies on-chain if the proving program's commitments equal the
+//! AlipayVerifier's `appExeCommit` (guest program) and `appVmCommit` (OpenVM
+//! version). A mis-deployed AXIOM_PROGRAM_ID therefore produces proofs that always
+//! revert, after paying for them.
+//!
+//! The relay resolves the commitments of every configured program at startup and,
+//! before validating or proving, picks the program matching the on-chain verifier.
+//! Several program versions can be configured, so a verifier upgrade switches to the
+//! matching version without a redeploy; no match refuses the request.
+//!
+//! Programs that failed to resolve (e.g. the Axiom API was down during boot) are
+//! retried in the background with backoff. A failed match is remembered for the
+//! config TTL, so refused requests don't each refetch the verifier config.
+//!
+//! Configured by AXIOM_PROGRAM_IDS (comma-separated) and/or AXIOM_PROGRAM_ID. The
+//! guest's amount mode is AMOUNT_MATCH_MODE (exact | at_least), also read at startup.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use pdf_core::amount::{format_amount_line, parse_amount_cents};
+use tokio::sync::RwLock;
+
+use super::AxiomProver;
+
+/// First wait before retrying unresolved programs (doubled after every failed round)
+const RETRY_INITIAL_DELAY: Duration = Duration::from_secs(30);
+/// Longest wait between retries
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(1800);
+
+/// A program's commitments (as stored by AlipayVerifier)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProgramCommitments {
+    pub app_exe_commit: [u8; 32],
+    pub app_vm_commit: [u8; 32],
+}
+
+/// A configured Axiom program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub program_id: String,
+    pub commitments: ProgramCommitments,
+}
+
+/// Configured programs, keyed by commitments, plus those that failed to resolve
+#[derive(Debug, Clone, Default)]
+pub struct ProgramRegistry {
+    programs: HashMap<ProgramCommitments, Program>,
+    unresolved: Vec<(String, String)>,
+    /// Last failed match against the verifier and when it was checked
+    mismatch: Option<(String, Instant)>,
+}
+
+/// How the paid amount (line 29) is matched against the trade amount
+///
+/// Must agree with the guest's amount mode: in at-least mode the guest commits the
+/// canonical trade amount line when the receipt paid enough, so the on-chain
+/// expected hash is the same in both modes. Parsed once at startup, next to the
+/// programs it is proved with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmountMode {
+    /// Receipt amount must equal the trade amount
+    Exact,
+    /// Receipt amount must be at least the trade amount (overpayments accepted)
+    AtLeast,
+}
+
+impl AmountMode {
+    /// Read AMOUNT_MATCH_MODE ("exact" | "at_least", default exact) - other values are refused
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Same as `from_env`, reading the variable through `lookup`
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        match lookup("AMOUNT_MATCH_MODE").as_deref().map(str::trim) {
+            None | Some("") | Some("exact") => Ok(Self::Exact),
+            Some("at_least") => Ok(Self::AtLeast),
+            Some(other) => Err(format!("Invalid AMOUNT_MATCH_MODE: {} (expected exact or at_least)", other)),
+        }
+    }
+
+    /// Check the receipt's amount line (29) as the guest will, or return the rejection message
+    ///
+    /// Exact mode compares the bytes the guest hashes - a line with stray whitespace
+    /// would pass a trimmed compare and then prove a hash the contract rejects.
+    pub fn check(&self, amount_line: &str, trade_amount_cents: u64) -> Result<(), String> {
+        let expected = format_amount_line(trade_amount_cents);
+        match self {
+            Self::Exact if amount_line == expected => Ok(()),
+            Self::Exact => Err(format!("Payment amount doesn't match the trade. Receipt: {:?}, Expected: {}", amount_line, expected)),
+            Self::AtLeast if parse_amount_cents(amount_line).is_some_and(|paid| paid >= trade_amount_cents) => Ok(()),
+            Self::AtLeast => Err(format!("Payment amount is less than the trade amount. Receipt: {}, Expected at least: {}", amount_line.trim(), expected)),
+        }
+    }
+}
+
+impl ProgramCommitments {
+    /// Parse hex commitments (optional 0x prefix, 32 bytes each)
+    pub fn from_hex(app_exe_commit: &str, app_vm_commit: &str) -> Result<Self, String> {
+        let parse = |name: &str, value: &str| -> Result<[u8; 32], String> {
+            hex::decode(value.trim_start_matches("0x"))
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| format!("Invalid {}: {}", name, value))
+        };
+        Ok(Self {
+            app_exe_commit: parse("app_exe_commit", app_exe_commit)?,
+            app_vm_commit: parse("app_vm_commit", app_vm_commit)?,
+        })
+    }
+}
+
+impl ProgramRegistry {
+    /// Program IDs from AXIOM_PROGRAM_IDS and AXIOM_PROGRAM_ID (deduplicated, in order)
+    pub fn program_ids_from_env() -> Vec<String> {
+        let ids = std::env::var("AXIOM_PROGRAM_IDS").unwrap_or_default();
+        let single = std::env::var("AXIOM_PROGRAM_ID").unwrap_or_default();
+
+        let mut program_ids: Vec<String> = Vec::new();
+        for id in ids.split(',').chain(std::iter::once(single.as_str())) {
+            let id = id.trim();
+            if !id.is_empty() && !program_ids.iter().any(|known| known == id) {
+                program_ids.push(id.to_string());
+            }
+        }
+        program_ids
+    }
+
+    /// Fetch every program's commitments from Axiom (failures are kept as unresolved)
+    pub async fn resolve(axiom: &AxiomProver, program_ids: &[String]) -> Self {
+        let mut registry = Self::default();
+        for program_id in program_ids {
+            match axiom.get_program_commitments(program_id).await {
+                Ok(commitments) => {
+                    tracing::info!(
+                        "🧩 Axiom program {}: appExeCommit 0x{}, appVmCommit 0x{}",
+                        program_id,
+                        hex::encode(commitments.app_exe_commit),
+                        hex::encode(commitments.app_vm_commit)
+                    );
+                    registry.insert(program_id.clone(), commitments);
+                }
+                Err(e) => {
+                    tracing::error!("❌ Failed to resolve Axiom program {}: {}", program_id, e);
+                    registry.insert_unresolved(program_id.clone(), e.to_string());
+                }
+            }
+        }
+        registry
+    }
+
+    /// Add a resolved program (a later program with the same commitments replaces it)
+    /// It may be the one the verifier needs, so a remembered mismatch is dropped
+    pub fn insert(&mut self, program_id: String, commitments: ProgramCommitments) {
+        self.unresolved.retain(|(unresolved_id, _)| *unresolved_id != program_id);
+        self.programs.insert(commitments, Program { program_id, commitments });
+        self.mismatch = None;
+    }
+
+    /// Record a program whose commitments could not be resolved (shown in /health)
+    /// A program failing again keeps one entry, with the latest error
+    pub fn insert_unresolved(&mut self, program_id: String, error: String) {
+        match self.unresolved.iter_mut().find(|(unresolved_id, _)| *unresolved_id == program_id) {
+            Some(entry) => entry.1 = error,
+            None => self.unresolved.push((program_id, error)),
+        }
+    }
+
+    /// Resolved programs, ordered by program ID
+    pub fn programs(&self) -> Vec<&Program> {
+        let mut programs: Vec<&Program> = self.programs.values().collect();
+        programs.sort_by(|a, b| a.program_id.cmp(&b.program_id));
+        programs
+    }
+
+    pub fn unresolved(&self) -> &[(String, String)] {
+        &self.unresolved
+    }
+
+    /// The last failed match, if it was checked less than `ttl` ago
+    pub fn cached_mismatch(&self, ttl: Duration) -> Option<&str> {
+        match &self.mismatch {
+            Some((error, checked_at)) if checked_at.elapsed() < ttl => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Remember a failed match (cleared when a program resolves)
+    pub fn record_mismatch(&mut self, error: String) {
+        self.mismatch = Some((error, Instant::now()));
+    }
+
+    /// Program whose commitments equal the on-chain verifier's
+    pub fn select(&self, onchain: &ProgramCommitments) -> Result<&Program, String> {
+        if let Some(program) = self.programs.get(onchain) {
+            return Ok(program);
+        }
+
+        let same_guest = self
+            .programs
+            .values()
+            .find(|program| program.commitments.app_exe_commit == onchain.app_exe_commit);
+        Err(match same_guest {
+            Some(program) => format!(
+                "Program {} matches appExeCommit but not appVmCommit (on-chain 0x{}, program 0x{})",
+                program.program_id,
+                hex::encode(onchain.app_vm_commit),
+                hex::encode(program.commitments.app_vm_commit)
+            ),
+            None => format!(
+                "No configured Axiom program matches on-chain appExeCommit 0x{} ({} programs configured)",
+                hex::encode(onchain.app_exe_commit),
+                self.programs.len()
+            ),
+        })
+    }
+}
+
+/// Retry the programs that failed to resolve, with backoff, until all are resolved
+pub async fn retry_unresolved(registry: &RwLock<ProgramRegistry>, axiom: &AxiomProver) {
+    let mut delay = RETRY_INITIAL_DELAY;
+    loop {
+        let program_ids: Vec<String> = registry.read().await.unresolved().iter()
+            .map(|(program_id, _)| program_id.clone())
+            .collect();
+        if program_ids.is_empty() {
+            return;
+        }
+
+        tokio::time::sleep(delay).await;
+        tracing::info!("🧩 Retrying {} unresolved Axiom programs", program_ids.len());
+        // Fetched without the lock - requests keep checking the programs already resolved
+        let resolved = ProgramRegistry::resolve(axiom, &program_ids).await;
+        let mut registry = registry.write().await;
+        for program in resolved.programs.into_values() {
+            registry.insert(program.program_id, program.commitments);
+        }
+        for (program_id, error) in resolved.unresolved {
+            registry.insert_unresolved(program_id, error);
+        }
+        delay = next_retry_delay(delay);
+    }
+}
+
+fn next_retry_delay(delay: Duration) -> Duration {
+    (delay * 2).min(RETRY_MAX_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commitments(exe: u8, vm: u8) -> ProgramCommitments {
+        ProgramCommitments {
+            app_exe_commit: [exe; 32],
+            app_vm_commit: [vm; 32],
+        }
+    }
+
+    #[test]
+    fn test_select_by_commitments() {
+        let mut registry = ProgramRegistry::default();
+        registry.insert("v1".to_string(), commitments(1, 9));
+        registry.insert("v2".to_string(), commitments(2, 9));
+
+        assert_eq!(registry.select(&commitments(2, 9)).unwrap().program_id, "v2");
+        assert_eq!(registry.select(&commitments(1, 9)).unwrap().program_id, "v1");
+
+        // Same guest built for another OpenVM version
+        let error = registry.select(&commitments(1, 8)).unwrap_err();
+        assert!(error.contains("v1") && error.contains("appVmCommit"), "{}", error);
+
+        assert!(registry.select(&commitments(3, 9)).is_err());
+        assert!(ProgramRegistry::default().select(&commitments(1, 9)).is_err());
+    }
+
+    #[test]
+    fn test_commitments_from_hex() {
+        let exe = format!("0x{}", "01".repeat(32));
+        let vm = "09".repeat(32);
+        assert_eq!(ProgramCommitments::from_hex(&exe, &vm).unwrap(), commitments(1, 9));
+        assert!(ProgramCommitments::from_hex("0x01", &vm).is_err());
+        assert!(ProgramCommitments::from_hex(&exe, "zz").is_err());
+    }
+
+    #[test]
+    fn test_resolved_program_replaces_unresolved() {
+        let mut registry = ProgramRegistry::default();
+        registry.insert_unresolved("v1".to_string(), "timeout".to_string());
+        registry.insert_unresolved("v1".to_string(), "502".to_string());
+        assert_eq!(registry.unresolved(), &[("v1".to_string(), "502".to_string())]);
+
+        let ttl = Duration::from_secs(900);
+        registry.record_mismatch("No configured Axiom program matches".to_string());
+        assert!(registry.cached_mismatch(ttl).is_some());
+
+        // Resolving on a retry may fix the match - the remembered mismatch goes
+        registry.insert("v1".to_string(), commitments(1, 9));
+        assert!(registry.unresolved().is_empty());
+        assert!(registry.cached_mismatch(ttl).is_none());
+        assert_eq!(registry.select(&commitments(1, 9)).unwrap().program_id, "v1");
+    }
+
+    #[test]
+    fn test_mismatch_expires() {
+        let mut registry = ProgramRegistry::default();
+        registry.record_mismatch("mismatch".to_string());
+        assert_eq!(registry.cached_mismatch(Duration::from_secs(900)), Some("mismatch"));
+        assert_eq!(registry.cached_mismatch(Duration::ZERO), None);
+    }
+
+    #[test]
+    fn test_exact_amount_compares_hashed_bytes() {
+        assert!(AmountMode::Exact.check("小写：100.50", 10050).is_ok());
+
+        // The guest hashes line 29 verbatim, so whitespace is a mismatch
+        for line in [" 小写：100.50", "小写：100.50 ", "小写： 100.50", "小写：100.5", "小写：1,00.50", "小写：100.51"] {
+            let message = AmountMode::Exact.check(line, 10050).unwrap_err();
+            assert!(message.contains("doesn't match"), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_amount_mode_from_lookup() {
+        let mode = |value: Option<&str>| AmountMode::from_lookup(|_| value.map(str::to_string));
+        assert_eq!(mode(None), Ok(AmountMode::Exact));
+        assert_eq!(mode(Some("exact")), Ok(AmountMode::Exact));
+        assert_eq!(mode(Some(" at_least ")), Ok(AmountMode::AtLeast));
+
+        // A typo must not silently prove in exact mode
+        let error = mode(Some("atleast")).unwrap_err();
+        assert!(error.contains("AMOUNT_MATCH_MODE"), "{}", error);
+    }
+
+    #[test]
+    fn test_at_least_amount() {
+        assert!(AmountMode::AtLeast.check("小写：100.50", 10050).is_ok());
+        assert!(AmountMode::AtLeast.check(" 小写：1,000.5 ", 10050).is_ok());
+
+        let message = AmountMode::AtLeast.check("小写：100.49", 10050).unwrap_err();
+        assert_eq!(message, "Payment amount is less than the trade amount. Receipt: 小写：100.49, Expected at least: 小写：100.50");
+        assert!(AmountMode::AtLeast.check("小写：abc", 10050).is_err());
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        assert_eq!(next_retry_delay(RETRY_INITIAL_DELAY), Duration::from_secs(60));
+        assert_eq!(next_retry_delay(Duration::from_secs(1200)), RETRY_MAX_DELAY);
+        assert_eq!(next_retry_delay(RETRY_MAX_DELAY), RETRY_MAX_DELAY);
+    }
+}
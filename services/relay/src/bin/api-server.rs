@@ -10,6 +10,13 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use lyncz_relay::{Config, AppState, create_router};
 use lyncz_relay::blockchain::client::EthereumClient;
 use lyncz_relay::blockchain::events::EventListener;
+use lyncz_relay::api::handlers::settlement::run_settlement_batches;
+use lyncz_relay::axiom_prover::AxiomProver;
+use lyncz_relay::axiom_prover::programs::{retry_unresolved, AmountMode, ProgramRegistry};
+use lyncz_relay::gas_budget::{run_balance_monitor, GasBudgetPolicy};
+use lyncz_relay::key_rotation::{run_key_rotations, KeyRotationPolicy};
+use lyncz_relay::key_set::{parse_key_hash, KeySet};
+use lyncz_relay::settlement_batch::{BatchConfig, SettlementBatcher};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -32,19 +39,56 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut state = AppState::new(&config.database_url).await?;
     tracing::info!("✅ Database connected");
 
+    // Trusted Alipay key set: seed keys plus keys learned from validated receipts
+    if let Some(mut key_set) = KeySet::from_env() {
+        for key in state.db.get_alipay_keys().await? {
+            match parse_key_hash(&key) {
+                Some(key) => { key_set.insert(key); }
+                None => tracing::warn!("⚠️ Ignoring invalid stored Alipay key hash: {}", key),
+            }
+        }
+        tracing::info!(
+            "🔑 Trusted key set: {} keys, root 0x{}",
+            key_set.len(),
+            key_set.root().map(hex::encode).unwrap_or_default()
+        );
+        state = state.with_key_set(key_set);
+    }
+
+    // Governed key rotation: quarantined keys are submitted after N receipts and a delay/approval
+    let key_rotation = KeyRotationPolicy::from_env()?;
+    state = state.with_key_rotation_policy(key_rotation);
+
+    // Relayer gas budget: hourly spend cap on sponsored endpoints, low-balance alerts
+    let gas_budget = GasBudgetPolicy::from_env()?;
+    state = state.with_gas_budget_policy(gas_budget);
+
     // Initialize blockchain client
-    if config.relayer_private_key.is_some() {
+    if config.relayer_signer.is_some() {
         match EthereumClient::from_config(&config).await {
             Ok(eth_client) => {
                 let escrow_address: ethers::types::Address = config.escrow_address.parse()?;
-                state = state.with_blockchain_client(Arc::new(eth_client));
+                let eth_client = eth_client
+                    .with_event_bus(state.trade_events.clone())
+                    .with_nonce_store(state.db.pool().clone());
+                let eth_client = Arc::new(eth_client);
+                state = state.with_blockchain_client(eth_client.clone());
                 tracing::info!("✅ Blockchain client initialized");
                 
+                // Resume transactions left pending by a previous run (replaced if stuck)
+                tokio::spawn(async move {
+                    eth_client.recover_pending_transactions().await;
+                });
+                
                 // Start event listener
                 let rpc_url = config.rpc_url.clone();
                 let db_pool = state.db.pool().clone();
                 
-                if let Ok(mut listener) = EventListener::new(&rpc_url, escrow_address, db_pool, None).await {
+                let trade_events = state.trade_events.clone();
+                if let Ok(listener) = EventListener::new(&rpc_url, escrow_address, db_pool, None).await {
+                    let mut listener = listener
+                        .with_event_bus(trade_events)
+                        .with_ws_url(config.rpc_ws_url.clone());
                     tokio::spawn(async move {
                         tracing::info!("🎧 Event listener started");
                         if let Err(e) = listener.start().await {
@@ -58,7 +102,57 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
     } else {
-        tracing::info!("⚠️ Blockchain disabled (no RELAYER_PRIVATE_KEY)");
+        tracing::info!("⚠️ Blockchain disabled (no relayer signer configured)");
+    }
+
+    // Guest amount mode: exact or at-least (an unknown value refuses to start)
+    let amount_mode = AmountMode::from_env()?;
+    tracing::info!("🧾 Amount match mode: {:?}", amount_mode);
+    state = state.with_amount_mode(amount_mode);
+
+    // Axiom program versions: resolve commitments, check them against the verifier
+    match std::env::var("AXIOM_API_KEY") {
+        Ok(api_key) => {
+            let program_ids = ProgramRegistry::program_ids_from_env();
+            let axiom = AxiomProver::new(api_key, String::new(), String::new());
+            let registry = ProgramRegistry::resolve(&axiom, &program_ids).await;
+            let has_unresolved = !registry.unresolved().is_empty();
+            state = state.with_program_registry(registry);
+            
+            // A transient Axiom API failure at boot must not refuse every request until a restart
+            if has_unresolved {
+                if let Some(programs) = state.programs.clone() {
+                    tokio::spawn(async move { retry_unresolved(&programs, &axiom).await });
+                }
+            }
+            
+            if state.blockchain_client.is_some() {
+                match state.active_program().await {
+                    Ok(program) => tracing::info!("✅ Axiom program {} matches the on-chain verifier", program.program_id),
+                    Err(e) => tracing::error!("❌ Prover program check failed, validation and proving are refused: {}", e),
+                }
+            }
+        }
+        Err(_) => tracing::warn!("⚠️ AXIOM_API_KEY not set - validation and proving disabled"),
+    }
+
+    // Batch proving: one proof for several validated trades (SETTLEMENT_BATCH_WINDOW_SECS)
+    if state.blockchain_client.is_some() {
+        if let Some(batch_config) = BatchConfig::from_env() {
+            let (batcher, receiver) = SettlementBatcher::new(batch_config);
+            state = state.with_settlement_batcher(batcher);
+            tokio::spawn(run_settlement_batches(state.clone(), receiver));
+        }
+    }
+
+    // Key rotation worker: promotes quarantined keys and submits eligible/approved ones
+    if state.blockchain_client.is_some() {
+        tokio::spawn(run_key_rotations(state.clone()));
+    }
+
+    // Relayer balance monitor: emails the admin on low balance or a tripped spend cap
+    if state.blockchain_client.is_some() {
+        tokio::spawn(run_balance_monitor(state.clone()));
     }
 
     let app = create_router(state);
@@ -72,6 +166,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("   GET  /api/trades/:id              Get trade");
     tracing::info!("   POST /api/trades/:id/validate     Upload PDF + validate (~10s)");
     tracing::info!("   POST /api/trades/:id/settle       Generate proof + submit (~2-3 min)");
+    tracing::info!("   GET  /api/trades/:id/events       Live trade updates (SSE)");
+    tracing::info!("   GET  /api/trades/:id/ws           Live trade updates (WebSocket)");
+    tracing::info!("   GET  /api/admin/key-rotations     Governed Alipay key rotations");
+    tracing::info!("   GET  /api/admin/relayer           Relayer balance and gas spend");
     
     axum::serve(listener, app).await?;
     Ok(())
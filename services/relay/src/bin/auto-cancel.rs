@@ -5,6 +5,7 @@
 //! 
 //! Runs as a separate process alongside the API server.
 //! The relay wallet pays gas fees for each cancellation (~0.0001 ETH on L2).
+//! Gas paid is recorded in relayer_transactions and counts towards the hourly spend cap.
 
 use std::sync::Arc;
 use std::time::Duration;
@@ -42,7 +43,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("✅ Database connected");
 
     // Initialize blockchain client
-    let eth_client = EthereumClient::from_config(&config).await?;
+    // Nonces are shared with the API server (same relayer key) through the database
+    let eth_client = EthereumClient::from_config(&config).await?
+        .with_nonce_store(db.pool().clone());
     let eth_client = Arc::new(eth_client);
     tracing::info!("✅ Blockchain client initialized");
     tracing::info!("   Relayer address: {:?}", eth_client.relayer_address());
@@ -121,6 +124,9 @@ async fn check_and_cancel_expired(
                 if let Err(e) = db.update_trade_status(trade_id, TRADE_STATUS_EXPIRED).await {
                     tracing::warn!("⚠️ Failed to update DB status for {}: {}", trade_id, e);
                 }
+                if let Err(e) = db.release_receipt_reservation(trade_id).await {
+                    tracing::warn!("⚠️ Failed to release receipt reservation for {}: {}", trade_id, e);
+                }
                 
                 cancelled_count += 1;
                 total_gas_wei += gas_cost.as_u128();
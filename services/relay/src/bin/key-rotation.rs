@@ -0,0 +1,107 @@
+//! Key Rotation Admin CLI for LyncZ
+//!
+//! Reviews quarantined Alipay signing keys (see lyncz_relay::key_rotation).
+//! Approving lets the relay submit a key once it meets the sighting threshold,
+//! without waiting for the delay - and without a pinned root certificate an
+//! approval is the only way a key is ever submitted. Rejecting means it is never
+//! submitted. Decisions are audited under --by.
+//!
+//! Talks to the database directly (DATABASE_URL) - there is no public API for this.
+//!
+//! Usage:
+//!   key-rotation list
+//!   key-rotation show <pk_hash>
+//!   key-rotation approve <pk_hash> --by <name> [--reason <text>]
+//!   key-rotation reject <pk_hash> --by <name> [--reason <text>]
+
+use lyncz_relay::Database;
+use lyncz_relay::db::models::KeyRotationStatus;
+use lyncz_relay::key_set::parse_key_hash;
+
+const USAGE: &str = "Usage: key-rotation list | show <pk_hash> | approve <pk_hash> --by <name> [--reason <text>] | reject <pk_hash> --by <name> [--reason <text>]";
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    let command = args.get(1).map(String::as_str).ok_or(USAGE)?;
+
+    let database_url = std::env::var("DATABASE_URL").map_err(|_| "DATABASE_URL not set")?;
+    let db = Database::new(&database_url).await?;
+    db.migrate().await?;
+
+    match command {
+        "list" => {
+            let rotations = db.get_key_rotations(&[]).await?;
+            if rotations.is_empty() {
+                println!("No key rotations");
+            }
+            for rotation in rotations {
+                println!(
+                    "{}  {:<11}  {} trades  {} buyers  {} orders  chain {}  first seen {}",
+                    rotation.pk_hash,
+                    rotation.status,
+                    rotation.sightings,
+                    rotation.buyers,
+                    rotation.orders,
+                    if rotation.chain_validated { "valid" } else { "unchecked" },
+                    rotation.first_seen_at.format("%Y-%m-%d %H:%M UTC")
+                );
+            }
+        }
+        "show" => {
+            let pk_hash = pk_hash_arg(&args)?;
+            let rotation = db.get_key_rotation(&pk_hash).await?
+                .ok_or_else(|| format!("No key rotation for {}", pk_hash))?;
+            println!("{:#?}", rotation);
+            println!();
+            for entry in db.get_key_rotation_audit(&pk_hash).await? {
+                println!(
+                    "{}  {:<13}  {:<12}  {}",
+                    entry.created_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                    entry.action,
+                    entry.actor,
+                    entry.detail.unwrap_or_default()
+                );
+            }
+        }
+        "approve" | "reject" => {
+            let pk_hash = pk_hash_arg(&args)?;
+            let admin = flag(&args, "--by").ok_or("--by <name> is required (recorded in the audit trail)")?;
+            let reason = flag(&args, "--reason");
+
+            // Approval skips the delay, never the sighting threshold; any unsubmitted key can be rejected
+            let (from, to): (&[KeyRotationStatus], _) = if command == "approve" {
+                (&[KeyRotationStatus::Quarantined, KeyRotationStatus::Eligible], KeyRotationStatus::Approved)
+            } else {
+                (&[KeyRotationStatus::Quarantined, KeyRotationStatus::Eligible, KeyRotationStatus::Approved], KeyRotationStatus::Rejected)
+            };
+
+            let actor = format!("admin:{}", admin);
+            if db.transition_key_rotation(&pk_hash, from, to, &actor, reason.as_deref(), None).await? {
+                println!("✅ {} {}", pk_hash, to);
+            } else {
+                let status = db.get_key_rotation(&pk_hash).await?
+                    .map(|rotation| rotation.status)
+                    .unwrap_or_else(|| "unknown key".to_string());
+                return Err(format!("Cannot {} {} (status: {})", command, pk_hash, status).into());
+            }
+        }
+        _ => return Err(USAGE.into()),
+    }
+
+    Ok(())
+}
+
+/// Key hash argument, normalized to lowercase 0x hex
+fn pk_hash_arg(args: &[String]) -> Result<String, String> {
+    let value = args.get(2).ok_or(USAGE)?;
+    let key = parse_key_hash(&value.to_lowercase())
+        .ok_or_else(|| format!("Invalid key hash: {}", value))?;
+    Ok(format!("0x{}", hex::encode(key)))
+}
+
+fn flag(args: &[String], name: &str) -> Option<String> {
+    args.windows(2)
+        .find(|pair| pair[0] == name)
+        .map(|pair| pair[1].clone())
+}
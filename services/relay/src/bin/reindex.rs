@@ -0,0 +1,240 @@
+//! Reindex CLI for LyncZ - rebuilds the database mirror from chain history
+//!
+//! Replays the escrow's logs through the event listener's handlers into a fresh or
+//! existing database (see lyncz_relay::reindex). Logs already applied are skipped;
+//! no emails are sent. Runs go in chunks of blocks, one transaction each, and
+//! resume where they stopped (--resume).
+//!
+//! --from defaults to ESCROW_DEPLOYMENT_BLOCK, else the deployment block is looked up
+//! with eth_getCode (archive node). --to defaults to the latest block below the reorg
+//! depth. On a fresh database the event listener then continues after --to.
+//!
+//! --dry-run replays all chunks in one transaction that is rolled back and prints how
+//! each order and trade would change over the whole range. Touched rows stay locked
+//! until the run ends, so keep dry runs to ranges the listener isn't syncing. Logs the
+//! database already applied are skipped as in a real run: a dry run previews the
+//! unapplied logs only and doesn't check applied rows against chain history.
+//!
+//! --baseline only records logs as applied: run it once over the history of a
+//! database synced before log dedupe (migration 012), or replays apply it twice.
+//!
+//! Usage:
+//!   lyncz-reindex [--from <block>] [--to <block>] [--chunk <blocks>] [--dry-run | --baseline]
+//!   lyncz-reindex --resume [<run id>]
+//!   lyncz-reindex --list
+
+use std::collections::BTreeMap;
+
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use lyncz_relay::{Config, Database};
+use lyncz_relay::blockchain::events::EventListener;
+use lyncz_relay::db::event_batch::EventBatch;
+use lyncz_relay::db::reindex_runs::PostgresReindexRunRepository;
+use lyncz_relay::reindex::{EntityChange, ReplayMode, ReplayReport};
+
+const USAGE: &str = "Usage: lyncz-reindex [--from <block>] [--to <block>] [--chunk <blocks>] [--dry-run | --baseline] | --resume [<run id>] | --list";
+
+/// Blocks per chunk (~1h on Base), small enough for eth_getLogs limits
+const DEFAULT_CHUNK_BLOCKS: u64 = 2_000;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Handler logs only with RUST_LOG - the CLI prints its own progress
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "warn".into()),
+        )
+        .with(tracing_subscriber::fmt::layer())
+        .init();
+
+    let args: Vec<String> = std::env::args().collect();
+
+    let config = Config::load()?;
+    let escrow_address: ethers::types::Address = config.escrow_address.parse()?;
+    let contract = format!("{:#x}", escrow_address).to_lowercase();
+
+    let db = Database::new(&config.database_url).await?;
+    db.migrate().await?;
+    let runs = PostgresReindexRunRepository::new(db.pool().clone());
+
+    if has(&args, "--list") {
+        let list = runs.list(&contract, 20).await?;
+        if list.is_empty() {
+            println!("No reindex runs");
+        }
+        for run in list {
+            println!(
+                "#{}  {:<8}  blocks {}-{}  next {}  {} applied, {} skipped, {} failed  {}",
+                run.id,
+                run.mode,
+                run.from_block,
+                run.to_block,
+                run.next_block,
+                run.logs_applied,
+                run.logs_skipped,
+                run.logs_failed,
+                match run.completed_at {
+                    Some(at) => format!("completed {}", at.format("%Y-%m-%d %H:%M UTC")),
+                    None => format!("unfinished (started {})", run.started_at.format("%Y-%m-%d %H:%M UTC")),
+                }
+            );
+        }
+        return Ok(());
+    }
+
+    let listener = EventListener::new(&config.rpc_url, escrow_address, db.pool().clone(), Some(0)).await?;
+
+    if has(&args, "--resume") {
+        let id = match args.iter().position(|arg| arg == "--resume").and_then(|i| args.get(i + 1)) {
+            Some(id) => Some(id.parse::<i64>().map_err(|_| format!("Invalid run id: {}", id))?),
+            None => None,
+        };
+        let run = runs.unfinished(&contract, id).await?.ok_or("No unfinished reindex run")?;
+        let mode = ReplayMode::parse(&run.mode).ok_or_else(|| format!("Unknown mode: {}", run.mode))?;
+        println!("🔁 Resuming run #{} ({}) at block {} of {}-{}", run.id, run.mode, run.next_block, run.from_block, run.to_block);
+        return replay(&listener, &db, &runs, &contract, run.id, mode, run.next_block as u64, run.to_block as u64, run.chunk_size as u64).await;
+    }
+
+    let mode = match (has(&args, "--dry-run"), has(&args, "--baseline")) {
+        (true, true) => return Err(USAGE.into()),
+        (true, false) => ReplayMode::DryRun,
+        (false, true) => ReplayMode::Baseline,
+        (false, false) => ReplayMode::Apply,
+    };
+    let from = match block_flag(&args, "--from")? {
+        Some(from) => from,
+        None => match std::env::var("ESCROW_DEPLOYMENT_BLOCK") {
+            Ok(block) => block.parse().map_err(|_| "Invalid ESCROW_DEPLOYMENT_BLOCK")?,
+            Err(_) => {
+                println!("🔎 Looking up the escrow deployment block...");
+                listener.deployment_block().await?
+            }
+        },
+    };
+    let to = match block_flag(&args, "--to")? {
+        Some(to) => to,
+        None => listener.safe_head().await?,
+    };
+    let chunk = block_flag(&args, "--chunk")?.unwrap_or(DEFAULT_CHUNK_BLOCKS).max(1);
+    if from > to {
+        return Err(format!("--from {} is after --to {}", from, to).into());
+    }
+
+    if mode == ReplayMode::DryRun {
+        return dry_run(&listener, &db, &contract, from, to, chunk).await;
+    }
+
+    let run = runs.create(&contract, mode.as_str(), from, to, chunk).await?;
+    println!("▶️ Run #{} ({}): blocks {}-{} in chunks of {}", run.id, mode.as_str(), from, to, chunk);
+    replay(&listener, &db, &runs, &contract, run.id, mode, from, to, chunk).await
+}
+
+/// Replay chunk by chunk, committing each together with the run's progress
+#[allow(clippy::too_many_arguments)]
+async fn replay(
+    listener: &EventListener,
+    db: &Database,
+    runs: &PostgresReindexRunRepository,
+    contract: &str,
+    run_id: i64,
+    mode: ReplayMode,
+    from: u64,
+    to: u64,
+    chunk: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut total = ReplayReport::default();
+    let mut next = from;
+
+    while next <= to {
+        let chunk_to = std::cmp::min(next.saturating_add(chunk - 1), to);
+        let mut batch = EventBatch::begin(db.pool(), contract).await?;
+        let report = listener.replay_range(&mut batch, next, chunk_to, mode, &mut BTreeMap::new()).await?;
+        batch.advance_reindex_run(run_id, chunk_to + 1, report.applied, report.skipped, report.failed).await?;
+        batch.commit(None).await?;
+
+        if report.logs > 0 {
+            println!(
+                "  blocks {}-{}: {} logs ({} applied, {} skipped, {} failed)",
+                next, chunk_to, report.logs, report.applied, report.skipped, report.failed
+            );
+        }
+        total.add(&report);
+        next = chunk_to + 1;
+    }
+
+    runs.complete(run_id).await?;
+    println!(
+        "✅ Run #{} complete: {} logs ({} applied, {} skipped, {} failed)",
+        run_id, total.logs, total.applied, total.skipped, total.failed
+    );
+    if total.failed > 0 {
+        println!("⚠️ {} logs failed - see failed_logs, the event listener retries them", total.failed);
+    }
+    if runs.init_sync_state(contract, to + 1).await? {
+        println!("🎧 Event listener will continue from block {}", to + 1);
+    }
+
+    Ok(())
+}
+
+/// Replay every chunk in one transaction that is never committed, then print each
+/// touched entity's differences (rows stay locked until the whole run ends)
+async fn dry_run(
+    listener: &EventListener,
+    db: &Database,
+    contract: &str,
+    from: u64,
+    to: u64,
+    chunk: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🧪 Dry run: blocks {}-{} (nothing is written)", from, to);
+
+    // One transaction for the whole run, so later chunks see the earlier chunks'
+    // replay; never committed. Snapshots keep each entity's state before its first log
+    let mut batch = EventBatch::begin(db.pool(), contract).await?;
+    let mut snapshots = BTreeMap::new();
+    let mut total = ReplayReport::default();
+    let mut next = from;
+
+    while next <= to {
+        let chunk_to = std::cmp::min(next.saturating_add(chunk - 1), to);
+        let report = listener.replay_range(&mut batch, next, chunk_to, ReplayMode::DryRun, &mut snapshots).await?;
+        println!("  blocks {}-{}: {} logs", next, chunk_to, report.logs);
+
+        total.add(&report);
+        next = chunk_to + 1;
+    }
+
+    let mut changes = 0;
+    for (id, before) in &snapshots {
+        let after = batch.snapshot(id).await?;
+        if let Some(change) = EntityChange::between(id, before.as_ref(), after.as_ref()) {
+            println!("{}", change);
+            changes += 1;
+        }
+    }
+    drop(batch);
+
+    println!(
+        "🧪 {} logs ({} would apply, {} already applied, {} would fail), {} orders/trades differ",
+        total.logs, total.applied, total.skipped, total.failed, changes
+    );
+    if total.skipped > 0 {
+        println!("ℹ️ {} already applied logs were not replayed - their rows are not checked against chain", total.skipped);
+    }
+
+    Ok(())
+}
+
+fn has(args: &[String], name: &str) -> bool {
+    args.iter().any(|arg| arg == name)
+}
+
+fn block_flag(args: &[String], name: &str) -> Result<Option<u64>, String> {
+    match args.windows(2).find(|pair| pair[0] == name) {
+        Some(pair) => pair[1].parse().map(Some).map_err(|_| format!("Invalid {}: {}", name, pair[1])),
+        None if has(args, name) => Err(USAGE.to_string()),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,48 @@
+//! Stand-in Remote Signer for LyncZ
+//!
+//! Serves the Web3Signer eth1 JSON-RPC subset the relay uses (`eth_accounts`,
+//! `eth_signTransaction`) from a local key, so the API server can run with
+//! RELAYER_SIGNER_URL in tests and local development. Production should point
+//! RELAYER_SIGNER_URL at Web3Signer (or another compatible signer) instead.
+//!
+//! Key: SIGNER_KEYSTORE_PATH + SIGNER_KEYSTORE_PASSPHRASE, or SIGNER_PRIVATE_KEY
+//! Listens on SIGNER_LISTEN (default 127.0.0.1:9000), signs for CHAIN_ID (default 8453).
+
+use ethers::signers::{LocalWallet, Signer};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use lyncz_relay::blockchain::signer::stand_in_router;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "info".into()),
+        )
+        .with(tracing_subscriber::fmt::layer())
+        .init();
+
+    let chain_id: u64 = std::env::var("CHAIN_ID").ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(8453);
+
+    let wallet = match std::env::var("SIGNER_KEYSTORE_PATH") {
+        Ok(path) => {
+            let passphrase = std::env::var("SIGNER_KEYSTORE_PASSPHRASE")
+                .map_err(|_| "SIGNER_KEYSTORE_PASSPHRASE not set")?;
+            LocalWallet::decrypt_keystore(&path, passphrase)?
+        }
+        Err(_) => std::env::var("SIGNER_PRIVATE_KEY")
+            .map_err(|_| "Set SIGNER_KEYSTORE_PATH or SIGNER_PRIVATE_KEY")?
+            .parse()?,
+    };
+    let wallet = wallet.with_chain_id(chain_id);
+
+    let listen = std::env::var("SIGNER_LISTEN").unwrap_or_else(|_| "127.0.0.1:9000".to_string());
+    let listener = tokio::net::TcpListener::bind(&listen).await?;
+    tracing::info!("🔑 Stand-in signer for {:#x} (chain {}) on http://{}", wallet.address(), chain_id, listen);
+
+    axum::serve(listener, stand_in_router(wallet)).await?;
+
+    Ok(())
+}
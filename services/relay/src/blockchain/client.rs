@@ -2,17 +2,27 @@
 //! 
 //! Simplified client - only handles:
 //! - submit_proof(): Relayer submits ZK proof to settle trades (pays gas)
+//! - submit_batch_proof(): One proof settling several trades
 //! - Read-only queries for validation
+//!
+//! Every relayer write goes through the shared transaction manager (tx_manager.rs):
+//! one nonce sequence per key, queued sends and stuck-transaction replacement.
+//! Fees come from the configured fee strategy (fees.rs) at a per-operation urgency.
 
 use ethers::prelude::*;
 use ethers::providers::{Http, Provider};
-use ethers::signers::{LocalWallet, Signer};
 use std::sync::Arc;
 use thiserror::Error;
 
-use super::{LyncZEscrow, AlipayVerifier, SimpleFeeCalculator};
-use super::types::ContractConfig;
+use super::{LyncZEscrow, AlipayVerifier, SimpleFeeCalculator, TradeMemoRequiredFilter};
+use super::lync_z_escrow::BatchSettlement;
+use super::errors::ContractRevert;
+use super::fees::{self, FeeUrgency};
+use super::signer::{self, RelayerSigner};
+use super::tx_manager::{PendingRelayerTx, RelayerTxManager, TxManagerConfig};
+use super::types::{ContractConfig, UnsignedTransaction};
 use crate::config::Config;
+use crate::trade_events::{TradeEvent, TradeEventBus};
 
 #[derive(Error, Debug)]
 pub enum EthereumClientError {
@@ -24,32 +34,93 @@ pub enum EthereumClientError {
     WalletError(String),
     #[error("Transaction failed: {0}")]
     TransactionFailed(String),
+    /// Failed before anything was broadcast (RPC error, gas estimate, rejected by the node)
+    #[error("Transaction not sent: {0}")]
+    NotBroadcast(String),
+    #[error("Contract reverted: {0}")]
+    Reverted(ContractRevert),
+    #[error("Transaction reverted: {0:#x}")]
+    TransactionReverted(H256),
+    /// Broadcast but not mined before the wait gave up - still tracked under this
+    /// hash (latest broadcast), so sending again would race it
+    #[error("Transaction still pending: {0:#x}")]
+    StillPending(H256),
 }
 
+impl EthereumClientError {
+    /// Decoded contract revert, if this error was caused by one
+    pub fn revert(&self) -> Option<&ContractRevert> {
+        match self {
+            Self::Reverted(revert) => Some(revert),
+            _ => None,
+        }
+    }
+
+    /// True if the failure may succeed on retry and retrying can't send a second transaction
+    ///
+    /// Only failures before the broadcast qualify: once a transaction may have been sent
+    /// (receipt wait failed, ambiguous send), a retry would race it with a new nonce.
+    /// Decoded reverts and mined reverts are deterministic and never retried.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::NotBroadcast(_))
+    }
+
+    /// Hash of a tracked transaction the failure left in flight
+    pub fn still_pending(&self) -> Option<H256> {
+        match self {
+            Self::StillPending(tx_hash) => Some(*tx_hash),
+            _ => None,
+        }
+    }
+
+    /// Mark an error raised before the broadcast as safe to retry
+    pub(crate) fn not_broadcast(self) -> Self {
+        match self {
+            Self::Reverted(_) | Self::NotBroadcast(_) => self,
+            other => Self::NotBroadcast(other.to_string()),
+        }
+    }
+
+    /// Map an ethers contract error, decoding custom errors from the escrow/verifier ABIs
+    fn from_contract<M: Middleware>(err: ContractError<M>, context: &str) -> Self {
+        match ContractRevert::from_contract_error(&err) {
+            Some(revert) => {
+                tracing::warn!("⛔ {} reverted: {}", context, revert);
+                Self::Reverted(revert)
+            }
+            None => Self::NotBroadcast(format!("{} failed: {}", context, err)),
+        }
+    }
+}
+
+// Calls are only estimated and encoded here - the tx manager signs and sends them
+type EscrowMiddleware = Provider<Http>;
+
 pub struct EthereumClient {
     provider: Arc<Provider<Http>>,
-    wallet: LocalWallet,
-    escrow_contract: LyncZEscrow<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    signer: Arc<dyn RelayerSigner>,
+    escrow_contract: LyncZEscrow<EscrowMiddleware>,
     chain_id: u64,
+    events: Option<TradeEventBus>,
+    txs: RelayerTxManager,
 }
 
-// Gas price constant for Base L2
-// Base network gas can fluctuate - using 0.03 gwei for reliable inclusion
-// (network has been seen at 0.018+ gwei during busy periods)
-const LOW_GAS_PRICE_WEI: u64 = 30_000_000; // 0.03 gwei
-
 impl EthereumClient {
     /// Create a new Ethereum client from config
     pub async fn from_config(config: &Config) -> Result<Self, EthereumClientError> {
-        let private_key = config.relayer_private_key.as_ref()
-            .ok_or_else(|| EthereumClientError::WalletError("RELAYER_PRIVATE_KEY not set".to_string()))?;
+        let signer_config = config.relayer_signer.as_ref().ok_or_else(|| {
+            EthereumClientError::WalletError(
+                "No relayer signer (set RELAYER_PRIVATE_KEY, RELAYER_KEYSTORE_PATH or RELAYER_SIGNER_URL)".to_string(),
+            )
+        })?;
+        let signer = signer::from_config(signer_config, config.chain_id).await?;
         
         let escrow_address: Address = config.escrow_address.parse()
             .map_err(|e| EthereumClientError::WalletError(format!("Invalid escrow address: {}", e)))?;
         
         Self::new(
             &config.rpc_url,
-            private_key,
+            signer,
             escrow_address,
             config.chain_id,
         ).await
@@ -57,35 +128,61 @@ impl EthereumClient {
 
     pub async fn new(
         rpc_url: &str,
-        private_key: &str,
+        signer: Arc<dyn RelayerSigner>,
         escrow_address: Address,
         chain_id: u64,
     ) -> Result<Self, EthereumClientError> {
         // Create provider
         let provider = Provider::<Http>::try_from(rpc_url)
             .map_err(|e| EthereumClientError::ProviderError(e.to_string()))?;
-
-        // Create wallet
-        let wallet: LocalWallet = private_key
-            .parse()
-            .map_err(|e| EthereumClientError::WalletError(format!("Invalid private key: {}", e)))?;
-        let wallet = wallet.with_chain_id(chain_id);
-
-        // Create signer middleware
-        let client = SignerMiddleware::new(provider.clone(), wallet.clone());
-        let client = Arc::new(client);
+        let provider = Arc::new(provider);
 
         // Create contract instance
-        let escrow_contract = LyncZEscrow::new(escrow_address, client.clone());
+        let escrow_contract = LyncZEscrow::new(escrow_address, provider.clone());
+
+        let fee_strategy = fees::strategy_from_env(provider.clone());
+        let txs = RelayerTxManager::new(
+            provider.clone(),
+            signer.clone(),
+            chain_id,
+            fee_strategy,
+            TxManagerConfig::from_env(),
+        );
 
         Ok(Self {
-            provider: Arc::new(provider),
-            wallet,
+            provider,
+            signer,
             escrow_contract,
             chain_id,
+            events: None,
+            txs,
         })
     }
 
+    /// Share relayer nonces and pending transactions through Postgres
+    /// Required whenever more than one process signs with the relayer key
+    pub fn with_nonce_store(mut self, pool: sqlx::PgPool) -> Self {
+        self.txs = self.txs.with_store(pool);
+        self
+    }
+
+    /// Watch transactions a previous run left pending (call once at startup)
+    pub async fn recover_pending_transactions(&self) -> usize {
+        self.txs.recover_pending().await
+    }
+
+    /// Publish submitProof tx sent / confirmed to the trade event bus
+    pub fn with_event_bus(mut self, events: TradeEventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    fn publish(&self, event: TradeEvent) {
+        if let Some(events) = &self.events {
+            events.publish(event);
+        }
+    }
+
     // ============ Core Function: Submit Proof ============
 
     /// Submit proof to settle a trade
@@ -95,6 +192,9 @@ impl EthereumClient {
     /// 
     /// Privacy: txIdHash is SHA256(25 || transactionId) - the plain text transaction ID
     /// never appears on-chain, only its hash is used for anti-replay.
+    ///
+    /// `urgency` prices the transaction (see FeeUrgency::for_deadline).
+    #[allow(clippy::too_many_arguments)]
     pub async fn submit_proof(
         &self,
         trade_id: [u8; 32],
@@ -103,6 +203,7 @@ impl EthereumClient {
         user_public_values: [u8; 32],
         accumulator: Vec<u8>,
         proof: Vec<u8>,
+        urgency: FeeUrgency,
     ) -> Result<H256, EthereumClientError> {
         tracing::info!(
             "Calling submitProof: trade_id={}, tx_id_hash={}, payment_time={}, user_public_values={}",
@@ -112,58 +213,90 @@ impl EthereumClient {
             hex::encode(user_public_values),
         );
 
-        let accumulator_bytes = Bytes::from(accumulator);
-        let proof_bytes = Bytes::from(proof);
-        
-        let mut call = self.escrow_contract.submit_proof(
+        let call = submit_proof_call(
+            &self.escrow_contract,
             trade_id,
             tx_id_hash,
             payment_time,
             user_public_values,
-            accumulator_bytes,
-            proof_bytes,
+            accumulator,
+            proof,
         );
 
-        // Estimate gas and configure transaction
-        let gas_estimate = call
-            .estimate_gas()
-            .await
-            .map_err(|e| {
-                EthereumClientError::ContractError(format!("Gas estimation failed: {}", e))
-            })?;
-        
-        call = call
-            .gas(gas_estimate * 120 / 100) // 20% buffer
-            .legacy()
-            .gas_price(U256::from(LOW_GAS_PRICE_WEI));
-        
-        let tx = call
-            .send()
-            .await
-            .map_err(|e| {
-                EthereumClientError::TransactionFailed(format!("submitProof failed: {}", e))
-            })?;
+        let trade_id_hex = format!("0x{}", hex::encode(trade_id));
+        let pending = self.send_call(call, "submitProof", Some(&trade_id_hex), urgency).await?;
 
-        let tx_hash = tx.tx_hash();
-        tracing::info!("submitProof tx sent: {:#x}", tx_hash);
+        self.publish(TradeEvent::TxSent {
+            trade_id: trade_id_hex.clone(),
+            tx_hash: format!("{:#x}", pending.tx_hash),
+        });
 
-        // Wait for confirmation
-        let receipt = tx
-            .await
-            .map_err(|e| {
-                EthereumClientError::TransactionFailed(format!("Transaction receipt error: {}", e))
-            })?
-            .ok_or_else(|| {
-                EthereumClientError::TransactionFailed("No receipt returned".to_string())
-            })?;
+        // Wait for confirmation (a replacement may be the one mined)
+        let receipt = self.confirm(pending).await?;
+        let tx_hash = receipt.transaction_hash;
 
-        if receipt.status != Some(U64::from(1)) {
-            return Err(EthereumClientError::TransactionFailed(
-                "Transaction reverted".to_string(),
-            ));
+        tracing::info!("✅ submitProof confirmed: {:#x}", tx_hash);
+
+        self.publish(TradeEvent::TxConfirmed {
+            trade_id: trade_id_hex,
+            tx_hash: format!("{:#x}", tx_hash),
+        });
+
+        Ok(tx_hash)
+    }
+
+    /// Submit one proof settling several trades
+    ///
+    /// Signature: submitBatchProof((tradeId, txIdHash, paymentTime)[], userPublicValues, accumulator, proof)
+    ///
+    /// userPublicValues is the batch root over the trades' expected hashes, in the same
+    /// order as `settlements`. The whole batch reverts if any trade is invalid.
+    pub async fn submit_batch_proof(
+        &self,
+        settlements: Vec<BatchSettlement>,
+        user_public_values: [u8; 32],
+        accumulator: Vec<u8>,
+        proof: Vec<u8>,
+        urgency: FeeUrgency,
+    ) -> Result<H256, EthereumClientError> {
+        let trade_ids: Vec<String> = settlements
+            .iter()
+            .map(|s| format!("0x{}", hex::encode(s.trade_id)))
+            .collect();
+        tracing::info!(
+            "Calling submitBatchProof: trades={}, user_public_values={}",
+            trade_ids.join(","),
+            hex::encode(user_public_values),
+        );
+
+        let call = self.escrow_contract.submit_batch_proof(
+            settlements,
+            user_public_values,
+            Bytes::from(accumulator),
+            Bytes::from(proof),
+        );
+
+        let pending = self.send_call(call, "submitBatchProof", None, urgency).await?;
+
+        for trade_id in &trade_ids {
+            self.publish(TradeEvent::TxSent {
+                trade_id: trade_id.clone(),
+                tx_hash: format!("{:#x}", pending.tx_hash),
+            });
         }
 
-        tracing::info!("✅ submitProof confirmed: {:#x}", tx_hash);
+        // Wait for confirmation (a replacement may be the one mined)
+        let receipt = self.confirm(pending).await?;
+        let tx_hash = receipt.transaction_hash;
+
+        tracing::info!("✅ submitBatchProof confirmed: {:#x} ({} trades)", tx_hash, trade_ids.len());
+
+        for trade_id in trade_ids {
+            self.publish(TradeEvent::TxConfirmed {
+                trade_id,
+                tx_hash: format!("{:#x}", tx_hash),
+            });
+        }
 
         Ok(tx_hash)
     }
@@ -171,7 +304,15 @@ impl EthereumClient {
     // ============ Read-Only Queries ============
 
     pub fn relayer_address(&self) -> Address {
-        self.wallet.address()
+        self.signer.address()
+    }
+
+    /// Relayer balance (wei) - pays gas for every sponsored transaction
+    pub async fn get_relayer_balance(&self) -> Result<U256, EthereumClientError> {
+        self.provider
+            .get_balance(self.signer.address(), None)
+            .await
+            .map_err(|e| EthereumClientError::ProviderError(e.to_string()))
     }
 
     pub fn chain_id(&self) -> u64 {
@@ -233,6 +374,15 @@ impl EthereumClient {
         Ok(order.7)
     }
 
+    /// Check if a trade's receipt must carry the memo code (its order required it when filled)
+    pub async fn is_trade_memo_required(&self, trade_id: [u8; 32]) -> Result<bool, EthereumClientError> {
+        self.escrow_contract
+            .trade_memo_required(trade_id)
+            .call()
+            .await
+            .map_err(|e| EthereumClientError::ContractError(e.to_string()))
+    }
+
     /// Check if trade exists on blockchain
     pub async fn trade_exists(&self, trade_id: [u8; 32]) -> Result<bool, EthereumClientError> {
         let trade = self
@@ -479,39 +629,14 @@ impl EthereumClient {
             ));
         }
         
-        // Create AlipayVerifier contract instance with signer
-        let wallet = self.wallet.clone().with_chain_id(self.chain_id);
-        let provider = Provider::<Http>::try_from(
-            self.provider.url().to_string()
-        ).map_err(|e| EthereumClientError::ProviderError(e.to_string()))?;
-        let client = SignerMiddleware::new(provider, wallet);
-        let client = Arc::new(client);
-        
-        let alipay_verifier = AlipayVerifier::new(verifier_address, client);
-        
-        // Create the call builder
+        // Same signer (and nonce sequence) as the escrow calls
+        let alipay_verifier = AlipayVerifier::new(verifier_address, self.escrow_contract.client());
         let call = alipay_verifier.update_public_key_hash(new_hash);
-        
-        // Estimate gas
-        let gas_estimate = call.estimate_gas().await
-            .map_err(|e| EthereumClientError::ContractError(format!("Gas estimation failed: {}", e)))?;
-        
-        // Send with gas buffer and standard gas price (0.03 gwei for Base L2)
-        let call = call.gas(gas_estimate * 120 / 100);
-        let call = call.legacy();
-        let call = call.gas_price(U256::from(LOW_GAS_PRICE_WEI));
-        let tx = call.send().await
-            .map_err(|e| EthereumClientError::TransactionFailed(format!("Failed to update public key hash: {}", e)))?;
-
-        let tx_hash = tx.tx_hash();
-        tracing::info!("updatePublicKeyHash tx sent: {:#x}", tx_hash);
-
-        // Wait for confirmation
-        tx.await.map_err(|e| {
-            EthereumClientError::TransactionFailed(format!("Transaction receipt error: {}", e))
-        })?;
 
-        Ok(tx_hash)
+        let pending = self.send_call(call, "updatePublicKeyHash", None, FeeUrgency::Normal).await?;
+        let receipt = self.confirm(pending).await?;
+
+        Ok(receipt.transaction_hash)
     }
 
     // ============ Trade Management ============
@@ -521,12 +646,15 @@ impl EthereumClient {
     /// 
     /// Signature: fillOrder(orderId, buyer, fiatAmount) returns (bytes32 tradeId)
     /// Note: fiatAmount is in cents and must be divisible by 100 (whole yuan only)
+    /// 
+    /// Returns the tx hash, the tradeId and whether the order bound the trade to the
+    /// payment memo (TradeMemoRequired emitted with the trade)
     pub async fn fill_order(
         &self,
         order_id: [u8; 32],
         buyer_address: Address,
         fiat_amount: U256,
-    ) -> Result<(H256, [u8; 32]), EthereumClientError> {
+    ) -> Result<(H256, [u8; 32], bool), EthereumClientError> {
         tracing::info!(
             "Calling fillOrder: order_id={}, buyer={:#x}, fiat_amount={}",
             hex::encode(order_id),
@@ -534,46 +662,11 @@ impl EthereumClient {
             fiat_amount,
         );
 
-        let mut call = self.escrow_contract.fill_order(order_id, buyer_address, fiat_amount);
+        let call = fill_order_call(&self.escrow_contract, order_id, buyer_address, fiat_amount);
 
-        // Estimate gas and configure transaction
-        let gas_estimate = call
-            .estimate_gas()
-            .await
-            .map_err(|e| {
-                EthereumClientError::ContractError(format!("Gas estimation failed: {}", e))
-            })?;
-        
-        call = call
-            .gas(gas_estimate * 120 / 100) // 20% buffer
-            .legacy()
-            .gas_price(U256::from(LOW_GAS_PRICE_WEI));
-        
-        let tx = call
-            .send()
-            .await
-            .map_err(|e| {
-                EthereumClientError::TransactionFailed(format!("fillOrder failed: {}", e))
-            })?;
-
-        let tx_hash = tx.tx_hash();
-        tracing::info!("fillOrder tx sent: {:#x}", tx_hash);
-
-        // Wait for confirmation
-        let receipt = tx
-            .await
-            .map_err(|e| {
-                EthereumClientError::TransactionFailed(format!("Transaction receipt error: {}", e))
-            })?
-            .ok_or_else(|| {
-                EthereumClientError::TransactionFailed("No receipt returned".to_string())
-            })?;
-
-        if receipt.status != Some(U64::from(1)) {
-            return Err(EthereumClientError::TransactionFailed(
-                "Transaction reverted".to_string(),
-            ));
-        }
+        let pending = self.send_call(call, "fillOrder", None, FeeUrgency::Normal).await?;
+        let receipt = self.confirm(pending).await?;
+        let tx_hash = receipt.transaction_hash;
 
         tracing::info!("✅ fillOrder confirmed: {:#x}", tx_hash);
 
@@ -601,7 +694,14 @@ impl EthereumClient {
             hex::encode(trade_id)
         );
 
-        Ok((tx_hash, trade_id))
+        // Memo-bound orders emit TradeMemoRequired in the same transaction
+        let memo_required = receipt.logs.iter()
+            .any(|log| log.topics.first() == Some(&TradeMemoRequiredFilter::signature()));
+
+        // The trade only exists once mined - attribute its gas now
+        self.txs.attribute_trade(tx_hash, &format!("0x{}", hex::encode(trade_id))).await;
+
+        Ok((tx_hash, trade_id, memo_required))
     }
 
     /// Cancel an expired trade and return funds to seller
@@ -617,38 +717,13 @@ impl EthereumClient {
             hex::encode(trade_id),
         );
 
-        // Use legacy transaction with standard gas price for Base L2
-        // Base L2 has very low gas costs, no need for priority fees
-        let call = self.escrow_contract
-            .cancel_expired_trade(trade_id)
-            .legacy()
-            .gas_price(U256::from(LOW_GAS_PRICE_WEI)); // 0.03 gwei - standard for Base L2
-
-        let tx = call
-            .send()
-            .await
-            .map_err(|e| {
-                EthereumClientError::TransactionFailed(format!("cancelExpiredTrade failed: {}", e))
-            })?;
-
-        let tx_hash = tx.tx_hash();
-        tracing::info!("cancelExpiredTrade tx sent: {:#x}", tx_hash);
+        // Nobody is waiting on a cancellation - cheapest fees
+        let call = self.escrow_contract.cancel_expired_trade(trade_id);
 
-        // Wait for confirmation
-        let receipt = tx
-            .await
-            .map_err(|e| {
-                EthereumClientError::TransactionFailed(format!("Transaction receipt error: {}", e))
-            })?
-            .ok_or_else(|| {
-                EthereumClientError::TransactionFailed("No receipt returned".to_string())
-            })?;
-
-        if receipt.status != Some(U64::from(1)) {
-            return Err(EthereumClientError::TransactionFailed(
-                "Transaction reverted".to_string(),
-            ));
-        }
+        let trade_id_hex = format!("0x{}", hex::encode(trade_id));
+        let pending = self.send_call(call, "cancelExpiredTrade", Some(&trade_id_hex), FeeUrgency::Low).await?;
+        let receipt = self.confirm(pending).await?;
+        let tx_hash = receipt.transaction_hash;
 
         // Calculate gas cost for return value
         let gas_used = receipt.gas_used.unwrap_or_default();
@@ -659,4 +734,211 @@ impl EthereumClient {
 
         Ok((tx_hash, gas_cost))
     }
+
+    // ============ Self-Submission (unsigned calldata) ============
+
+    /// Build unsigned fillOrder calldata so a buyer can create the trade from their own wallet
+    pub async fn fill_order_calldata(
+        &self,
+        order_id: [u8; 32],
+        buyer_address: Address,
+        fiat_amount: U256,
+        from: Address,
+    ) -> Result<UnsignedTransaction, EthereumClientError> {
+        let call = fill_order_call(&self.escrow_contract, order_id, buyer_address, fiat_amount);
+        self.build_unsigned(call, from, "fillOrder").await
+    }
+
+    /// Build unsigned submitProof calldata from a stored proof
+    ///
+    /// submitProof is permissionless, so gas is estimated from the relayer address.
+    pub async fn submit_proof_calldata(
+        &self,
+        trade_id: [u8; 32],
+        tx_id_hash: [u8; 32],
+        payment_time: String,
+        user_public_values: [u8; 32],
+        accumulator: Vec<u8>,
+        proof: Vec<u8>,
+    ) -> Result<UnsignedTransaction, EthereumClientError> {
+        let call = submit_proof_call(
+            &self.escrow_contract,
+            trade_id,
+            tx_id_hash,
+            payment_time,
+            user_public_values,
+            accumulator,
+            proof,
+        );
+        self.build_unsigned(call, self.signer.address(), "submitProof").await
+    }
+
+    /// Estimate gas (surfaces decoded reverts) and queue the call with the relayer nonce
+    async fn send_call<D: abi::Detokenize>(
+        &self,
+        call: ContractCall<EscrowMiddleware, D>,
+        operation: &str,
+        trade_id: Option<&str>,
+        urgency: FeeUrgency,
+    ) -> Result<PendingRelayerTx, EthereumClientError> {
+        let call = call.from(self.signer.address());
+        let gas_estimate = call
+            .estimate_gas()
+            .await
+            .map_err(|e| EthereumClientError::from_contract(e, "Gas estimation"))?;
+
+        let call = call.gas(gas_estimate * 120 / 100); // 20% buffer
+
+        self.txs.broadcast(operation, trade_id, call.tx, urgency).await
+    }
+
+    /// Wait for the transaction (or its replacement) to be mined; a reverted receipt is an error
+    async fn confirm(&self, pending: PendingRelayerTx) -> Result<TransactionReceipt, EthereumClientError> {
+        let receipt = self.txs.confirm(pending).await?;
+
+        if receipt.status != Some(U64::from(1)) {
+            return Err(EthereumClientError::TransactionReverted(receipt.transaction_hash));
+        }
+
+        Ok(receipt)
+    }
+
+    /// Estimate gas as `from` (surfaces decoded reverts) and export the calldata
+    async fn build_unsigned<D: abi::Detokenize>(
+        &self,
+        call: ContractCall<EscrowMiddleware, D>,
+        from: Address,
+        context: &str,
+    ) -> Result<UnsignedTransaction, EthereumClientError> {
+        let call = call.from(from);
+
+        let gas_estimate = call
+            .estimate_gas()
+            .await
+            .map_err(|e| EthereumClientError::from_contract(e, context))?;
+
+        let data = call.calldata().ok_or_else(|| {
+            EthereumClientError::ContractError(format!("{} calldata missing", context))
+        })?;
+
+        let gas_limit: U256 = gas_estimate * 120 / 100; // 20% buffer
+
+        Ok(unsigned_transaction(self.escrow_contract.address(), &data, gas_limit, self.chain_id))
+    }
+}
+
+/// fillOrder call - shared by relayer submission and the exported calldata
+fn fill_order_call(
+    escrow: &LyncZEscrow<EscrowMiddleware>,
+    order_id: [u8; 32],
+    buyer_address: Address,
+    fiat_amount: U256,
+) -> ContractCall<EscrowMiddleware, [u8; 32]> {
+    escrow.fill_order(order_id, buyer_address, fiat_amount)
+}
+
+/// submitProof call - shared by relayer submission and the exported calldata
+fn submit_proof_call(
+    escrow: &LyncZEscrow<EscrowMiddleware>,
+    trade_id: [u8; 32],
+    tx_id_hash: [u8; 32],
+    payment_time: String,
+    user_public_values: [u8; 32],
+    accumulator: Vec<u8>,
+    proof: Vec<u8>,
+) -> ContractCall<EscrowMiddleware, ()> {
+    escrow.submit_proof(
+        trade_id,
+        tx_id_hash,
+        payment_time,
+        user_public_values,
+        Bytes::from(accumulator),
+        Bytes::from(proof),
+    )
+}
+
+/// Unsigned escrow call as exported to the frontend
+fn unsigned_transaction(escrow: Address, calldata: &Bytes, gas_limit: U256, chain_id: u64) -> UnsignedTransaction {
+    UnsignedTransaction {
+        to: format!("{:#x}", escrow),
+        data: format!("0x{}", hex::encode(calldata)),
+        value: "0".to_string(),
+        gas_estimate: gas_limit.to_string(),
+        chain_id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::AbiDecode;
+    use crate::blockchain::lync_z_escrow::LyncZEscrowCalls;
+
+    fn escrow() -> LyncZEscrow<EscrowMiddleware> {
+        // Calldata is encoded locally - the provider is never called
+        let provider = Provider::<Http>::try_from("http://127.0.0.1:8545").unwrap();
+        LyncZEscrow::new(Address::repeat_byte(0xe5), Arc::new(provider))
+    }
+
+    /// Decode the exported `data` field against the abigen ABI
+    fn decode_exported(call: &ContractCall<EscrowMiddleware, impl abi::Detokenize>) -> (UnsignedTransaction, LyncZEscrowCalls) {
+        let unsigned = unsigned_transaction(Address::repeat_byte(0xe5), &call.calldata().unwrap(), 120_000.into(), 8453);
+        let data = hex::decode(unsigned.data.strip_prefix("0x").unwrap()).unwrap();
+        (unsigned, LyncZEscrowCalls::decode(data).unwrap())
+    }
+
+    #[test]
+    fn test_fill_order_calldata_round_trip() {
+        let escrow = escrow();
+        let buyer = Address::repeat_byte(0xb0);
+
+        let (unsigned, decoded) = decode_exported(&fill_order_call(&escrow, [7u8; 32], buyer, 50_000.into()));
+        assert_eq!(unsigned.to, format!("{:#x}", Address::repeat_byte(0xe5)));
+        assert_eq!((unsigned.value.as_str(), unsigned.gas_estimate.as_str(), unsigned.chain_id), ("0", "120000", 8453));
+        match decoded {
+            LyncZEscrowCalls::FillOrder(call) => {
+                assert_eq!(call.order_id, [7u8; 32]);
+                assert_eq!(call.buyer, buyer);
+                assert_eq!(call.fiat_amount, U256::from(50_000));
+            }
+            other => panic!("expected fillOrder, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_submit_proof_calldata_round_trip() {
+        let call = submit_proof_call(
+            &escrow(),
+            [1u8; 32],
+            [2u8; 32],
+            "2025-01-02 03:04:05".to_string(),
+            [3u8; 32],
+            vec![4u8; 12 * 32],
+            vec![5u8; 43 * 32],
+        );
+        match decode_exported(&call).1 {
+            LyncZEscrowCalls::SubmitProof(call) => {
+                assert_eq!(call.trade_id, [1u8; 32]);
+                assert_eq!(call.tx_id_hash, [2u8; 32]);
+                assert_eq!(call.payment_time, "2025-01-02 03:04:05");
+                assert_eq!(call.user_public_values, [3u8; 32]);
+                assert_eq!(call.accumulator.to_vec(), vec![4u8; 12 * 32]);
+                assert_eq!(call.proof.to_vec(), vec![5u8; 43 * 32]);
+            }
+            other => panic!("expected submitProof, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_only_unsent_failures_are_transient() {
+        let unsent = EthereumClientError::ProviderError("connection refused".to_string()).not_broadcast();
+        assert!(unsent.is_transient());
+        assert!(EthereumClientError::NotBroadcast("Gas estimation failed".to_string()).is_transient());
+
+        // The transaction may be pending or mined - a retry would send it twice
+        assert!(!EthereumClientError::TransactionFailed("receipt wait failed".to_string()).is_transient());
+        assert!(!EthereumClientError::ProviderError("eth_getTransactionReceipt failed".to_string()).is_transient());
+        assert!(!EthereumClientError::TransactionReverted(H256::zero()).is_transient());
+        assert!(!EthereumClientError::StillPending(H256::zero()).is_transient());
+    }
 }
@@ -0,0 +1,292 @@
+//! Typed decoding of LyncZEscrow / AlipayVerifier custom errors
+//!
+//! Revert data is decoded with the error enums generated by `abigen!`, so every
+//! custom error declared in the ABIs is recognised without hard-coded selectors.
+//! Each error maps to a stable code that the API returns to the frontend and
+//! stores in `trades.settlement_error`.
+//!
+//! AlipayVerifier errors (e.g. `HashMismatch`) bubble up unchanged through
+//! `submitProof`, so both enums are tried when decoding.
+
+use ethers::contract::{ContractError, ContractRevert as AbigenRevert};
+use ethers::providers::{Middleware, MiddlewareError, RpcError};
+
+use super::{AlipayVerifierErrors, LyncZEscrowErrors};
+
+/// A decoded custom error (or revert string) from the escrow or verifier contract
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContractRevert {
+    Escrow(LyncZEscrowErrors),
+    Verifier(AlipayVerifierErrors),
+}
+
+impl ContractRevert {
+    /// Decode raw revert data (4-byte selector + ABI-encoded arguments)
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if let Some(err) = LyncZEscrowErrors::decode_with_selector(data) {
+            return Some(Self::Escrow(err));
+        }
+        AlipayVerifierErrors::decode_with_selector(data).map(Self::Verifier)
+    }
+
+    /// Decode a revert from an ethers contract error
+    ///
+    /// Uses the structured revert data when ethers extracted it, and falls back to the
+    /// `data` field of the JSON-RPC error response (ethers only extracts it when the
+    /// message says "revert"). The error message itself is never scanned: it may
+    /// contain tx hashes or addresses that look like revert data.
+    pub fn from_contract_error<M: Middleware>(err: &ContractError<M>) -> Option<Self> {
+        if let Some(data) = err.as_revert() {
+            return Self::decode(data);
+        }
+        let response = match err {
+            ContractError::MiddlewareError { e } => MiddlewareError::as_error_response(e),
+            ContractError::ProviderError { e } => RpcError::as_error_response(e),
+            _ => None,
+        };
+        response.and_then(|response| response.data.as_ref()).and_then(Self::from_rpc_data)
+    }
+
+    /// Decode the `data` field of a JSON-RPC error response
+    ///
+    /// Either the hex revert data itself, or (some providers) an object wrapping it
+    /// under `data`.
+    pub fn from_rpc_data(data: &serde_json::Value) -> Option<Self> {
+        match data {
+            serde_json::Value::String(hex_str) => {
+                let bytes = hex::decode(hex_str.strip_prefix("0x")?).ok()?;
+                Self::decode(&bytes)
+            }
+            serde_json::Value::Object(object) => object.get("data").and_then(Self::from_rpc_data),
+            _ => None,
+        }
+    }
+
+    /// Stable error code returned by the API and stored as `settlement_error`
+    ///
+    /// NOTE: ALREADY_USED, NOT_PENDING, EXPIRED and VERIFICATION_FAILED are already
+    /// used by the frontend - do not rename them.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Escrow(err) => match err {
+                LyncZEscrowErrors::AmountAboveMaximum(_) => "AMOUNT_ABOVE_MAXIMUM",
+                LyncZEscrowErrors::AmountBelowMinimum(_) => "AMOUNT_BELOW_MINIMUM",
+                LyncZEscrowErrors::AmountExceedsAvailable(_) => "AMOUNT_EXCEEDS_AVAILABLE",
+                LyncZEscrowErrors::BatchRailMismatch(_) => "BATCH_RAIL_MISMATCH",
+                LyncZEscrowErrors::BatchTooLarge(_) => "BATCH_TOO_LARGE",
+                LyncZEscrowErrors::EmptyBatch(_) => "EMPTY_BATCH",
+                LyncZEscrowErrors::EnforcedPause(_) => "CONTRACT_PAUSED",
+                LyncZEscrowErrors::ExpectedPause(_) => "CONTRACT_NOT_PAUSED",
+                LyncZEscrowErrors::FeeCalculatorNotSet(_) => "FEE_CALCULATOR_NOT_SET",
+                LyncZEscrowErrors::FiatAmountMustBeWholeYuan(_) => "FIAT_AMOUNT_NOT_WHOLE_YUAN",
+                LyncZEscrowErrors::InvalidAccountLinesHash(_) => "INVALID_ACCOUNT_LINES_HASH",
+                LyncZEscrowErrors::InvalidAmount(_) => "INVALID_AMOUNT",
+                LyncZEscrowErrors::InvalidPaymentTimeFormat(_) => "INVALID_PAYMENT_TIME_FORMAT",
+                LyncZEscrowErrors::NoFeesToWithdraw(_) => "NO_FEES_TO_WITHDRAW",
+                LyncZEscrowErrors::NotAuthorized(_) => "NOT_AUTHORIZED",
+                LyncZEscrowErrors::OrderNotFound(_) => "ORDER_NOT_FOUND",
+                LyncZEscrowErrors::OwnableInvalidOwner(_) => "INVALID_OWNER",
+                LyncZEscrowErrors::OwnableUnauthorizedAccount(_) => "UNAUTHORIZED_ACCOUNT",
+                LyncZEscrowErrors::PaymentTooOld(_) => "PAYMENT_TOO_OLD",
+                LyncZEscrowErrors::ProofVerificationFailed(_) => "VERIFICATION_FAILED",
+                LyncZEscrowErrors::ReentrancyGuardReentrantCall(_) => "REENTRANT_CALL",
+                LyncZEscrowErrors::TradeExpiredError(_) => "EXPIRED",
+                LyncZEscrowErrors::TradeNotExpired(_) => "TRADE_NOT_EXPIRED",
+                LyncZEscrowErrors::TradeNotFound(_) => "TRADE_NOT_FOUND",
+                LyncZEscrowErrors::TradeNotPending(_) => "NOT_PENDING",
+                LyncZEscrowErrors::TransactionIdAlreadyUsed(_) => "ALREADY_USED",
+                LyncZEscrowErrors::TransferFailed(_) => "TRANSFER_FAILED",
+                LyncZEscrowErrors::VerifierNotSet(_) => "VERIFIER_NOT_SET",
+                LyncZEscrowErrors::WithdrawalExceedsAvailable(_) => "WITHDRAWAL_EXCEEDS_AVAILABLE",
+                LyncZEscrowErrors::RevertString(_) => "REVERTED",
+            },
+            Self::Verifier(err) => match err {
+                AlipayVerifierErrors::EmptyBatch(_) => "EMPTY_BATCH",
+                AlipayVerifierErrors::HashMismatch(_) => "HASH_MISMATCH",
+                AlipayVerifierErrors::OwnableInvalidOwner(_) => "INVALID_OWNER",
+                AlipayVerifierErrors::OwnableUnauthorizedAccount(_) => "UNAUTHORIZED_ACCOUNT",
+                AlipayVerifierErrors::ProofVerificationFailed(_) => "VERIFICATION_FAILED",
+                AlipayVerifierErrors::RevertString(_) => "REVERTED",
+            },
+        }
+    }
+
+    /// Human-readable description of the error
+    pub fn message(&self) -> String {
+        match self {
+            Self::Escrow(err) => match err {
+                LyncZEscrowErrors::AmountAboveMaximum(_) => "Trade amount is above the maximum trade value".to_string(),
+                LyncZEscrowErrors::AmountBelowMinimum(_) => "Trade amount is below the minimum trade value".to_string(),
+                LyncZEscrowErrors::AmountExceedsAvailable(_) => "Trade amount exceeds the order's available balance".to_string(),
+                LyncZEscrowErrors::BatchRailMismatch(_) => "All trades in a batch must use the same payment rail".to_string(),
+                LyncZEscrowErrors::BatchTooLarge(_) => "Batch exceeds the maximum number of trades".to_string(),
+                LyncZEscrowErrors::EmptyBatch(_) => "Batch contains no trades".to_string(),
+                LyncZEscrowErrors::EnforcedPause(_) => "Escrow contract is paused".to_string(),
+                LyncZEscrowErrors::ExpectedPause(_) => "Escrow contract is not paused".to_string(),
+                LyncZEscrowErrors::FeeCalculatorNotSet(_) => "Fee calculator not set in escrow contract".to_string(),
+                LyncZEscrowErrors::FiatAmountMustBeWholeYuan(_) => "Fiat amount must be whole yuan (divisible by 100)".to_string(),
+                LyncZEscrowErrors::InvalidAccountLinesHash(_) => "Invalid account lines hash".to_string(),
+                LyncZEscrowErrors::InvalidAmount(_) => "Invalid amount".to_string(),
+                LyncZEscrowErrors::InvalidPaymentTimeFormat(_) => "Payment time must be formatted as YYYY-MM-DD HH:MM:SS".to_string(),
+                LyncZEscrowErrors::NoFeesToWithdraw(_) => "No fees to withdraw".to_string(),
+                LyncZEscrowErrors::NotAuthorized(_) => "Caller is not authorized".to_string(),
+                LyncZEscrowErrors::OrderNotFound(_) => "Order not found on-chain".to_string(),
+                LyncZEscrowErrors::OwnableInvalidOwner(e) => format!("Invalid owner: {:#x}", e.owner),
+                LyncZEscrowErrors::OwnableUnauthorizedAccount(e) => format!("Account {:#x} is not the contract owner", e.account),
+                LyncZEscrowErrors::PaymentTooOld(e) => format!(
+                    "Payment was made before the trade was created (payment: {}, trade created: {})",
+                    e.payment_time, e.trade_created_at
+                ),
+                LyncZEscrowErrors::ProofVerificationFailed(_) => "ZK proof verification failed".to_string(),
+                LyncZEscrowErrors::ReentrancyGuardReentrantCall(_) => "Reentrant call rejected".to_string(),
+                LyncZEscrowErrors::TradeExpiredError(_) => "Trade has expired".to_string(),
+                LyncZEscrowErrors::TradeNotExpired(_) => "Trade has not expired yet".to_string(),
+                LyncZEscrowErrors::TradeNotFound(_) => "Trade not found on-chain".to_string(),
+                LyncZEscrowErrors::TradeNotPending(_) => "Trade is already settled or cancelled".to_string(),
+                LyncZEscrowErrors::TransactionIdAlreadyUsed(_) => "Payment receipt has already been used for another trade".to_string(),
+                LyncZEscrowErrors::TransferFailed(_) => "Token transfer failed".to_string(),
+                LyncZEscrowErrors::VerifierNotSet(_) => "Verifier not set for this payment rail".to_string(),
+                LyncZEscrowErrors::WithdrawalExceedsAvailable(_) => "Withdrawal exceeds the order's available balance".to_string(),
+                LyncZEscrowErrors::RevertString(s) => format!("Reverted: {}", s),
+            },
+            Self::Verifier(err) => match err {
+                AlipayVerifierErrors::EmptyBatch(_) => "Batch contains no trades".to_string(),
+                AlipayVerifierErrors::HashMismatch(e) => format!(
+                    "Proof output does not match payment details (expected: 0x{}, actual: 0x{})",
+                    hex::encode(e.expected),
+                    hex::encode(e.actual)
+                ),
+                AlipayVerifierErrors::OwnableInvalidOwner(e) => format!("Invalid owner: {:#x}", e.owner),
+                AlipayVerifierErrors::OwnableUnauthorizedAccount(e) => format!("Account {:#x} is not the verifier owner", e.account),
+                AlipayVerifierErrors::ProofVerificationFailed(_) => "ZK proof verification failed".to_string(),
+                AlipayVerifierErrors::RevertString(s) => format!("Reverted: {}", s),
+            },
+        }
+    }
+
+    /// True if the revert is caused by the request or the trade/order state,
+    /// rather than by relayer or contract configuration
+    pub fn is_client_error(&self) -> bool {
+        !matches!(
+            self.code(),
+            "CONTRACT_PAUSED"
+                | "CONTRACT_NOT_PAUSED"
+                | "FEE_CALCULATOR_NOT_SET"
+                | "VERIFIER_NOT_SET"
+                | "NOT_AUTHORIZED"
+                | "INVALID_OWNER"
+                | "UNAUTHORIZED_ACCOUNT"
+                | "REENTRANT_CALL"
+                | "BATCH_RAIL_MISMATCH"
+                | "BATCH_TOO_LARGE"
+                | "EMPTY_BATCH"
+                | "TRANSFER_FAILED"
+                | "REVERTED"
+        )
+    }
+}
+
+impl std::fmt::Display for ContractRevert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.message(), self.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::AbiEncode;
+    use ethers::providers::{Http, HttpClientError, JsonRpcError, Provider, ProviderError};
+    use crate::blockchain::lync_z_escrow;
+    use crate::blockchain::alipay_verifier;
+
+    #[test]
+    fn test_decode_escrow_error() {
+        let data = LyncZEscrowErrors::TransactionIdAlreadyUsed(lync_z_escrow::TransactionIdAlreadyUsed).encode();
+        let revert = ContractRevert::decode(&data).unwrap();
+        assert_eq!(revert.code(), "ALREADY_USED");
+    }
+
+    #[test]
+    fn test_decode_error_with_args() {
+        let data = LyncZEscrowErrors::PaymentTooOld(lync_z_escrow::PaymentTooOld {
+            payment_time: 100.into(),
+            trade_created_at: 200.into(),
+        })
+        .encode();
+        let revert = ContractRevert::decode(&data).unwrap();
+        assert_eq!(revert.code(), "PAYMENT_TOO_OLD");
+        assert!(revert.message().contains("payment: 100"));
+    }
+
+    #[test]
+    fn test_decode_verifier_error() {
+        let data = AlipayVerifierErrors::HashMismatch(alipay_verifier::HashMismatch {
+            expected: [1u8; 32],
+            actual: [2u8; 32],
+        })
+        .encode();
+        let revert = ContractRevert::decode(&data).unwrap();
+        assert_eq!(revert.code(), "HASH_MISMATCH");
+        assert!(revert.is_client_error());
+    }
+
+    #[test]
+    fn test_decode_from_rpc_data() {
+        // Selectors previously matched by hard-coded hex in settlement.rs
+        let cases = [
+            ("0x25c394f4", "ALREADY_USED"),
+            ("0x5f3f6cfc", "NOT_PENDING"),
+            ("0xace5e8ce", "EXPIRED"),
+            ("0xd611c318", "VERIFICATION_FAILED"),
+        ];
+        for (selector, code) in cases {
+            let data = serde_json::json!(selector);
+            assert_eq!(ContractRevert::from_rpc_data(&data).map(|r| r.code()), Some(code));
+
+            let nested = serde_json::json!({ "message": "reverted", "data": selector });
+            assert_eq!(ContractRevert::from_rpc_data(&nested).map(|r| r.code()), Some(code));
+        }
+
+        assert!(ContractRevert::from_rpc_data(&serde_json::json!("0x")).is_none());
+        assert!(ContractRevert::from_rpc_data(&serde_json::json!("Reverted 0x25c394f4")).is_none());
+        assert!(ContractRevert::from_rpc_data(&serde_json::Value::Null).is_none());
+    }
+
+    fn rpc_error(message: &str, data: serde_json::Value) -> ContractError<Provider<Http>> {
+        let response: JsonRpcError = serde_json::from_value(serde_json::json!({
+            "code": -32000,
+            "message": message,
+            "data": data,
+        }))
+        .unwrap();
+        ContractError::ProviderError {
+            e: ProviderError::JsonRpcClientError(Box::new(HttpClientError::JsonRpcError(response))),
+        }
+    }
+
+    #[test]
+    fn test_decode_from_contract_error() {
+        // Revert data in `data` even though the message doesn't say "revert"
+        let err = rpc_error("VM Exception while processing transaction", serde_json::json!("0x5f3f6cfc"));
+        assert_eq!(ContractRevert::from_contract_error(&err).map(|r| r.code()), Some("NOT_PENDING"));
+
+        // Hex in the message is a tx hash / address, not revert data
+        let err = rpc_error(
+            "replacement for tx 0x25c394f400000000000000000000000000000000000000000000000000000000 underpriced",
+            serde_json::Value::Null,
+        );
+        assert!(ContractRevert::from_contract_error(&err).is_none());
+
+        let err = ContractError::<Provider<Http>>::Revert(LyncZEscrowErrors::TradeExpiredError(lync_z_escrow::TradeExpiredError).encode().into());
+        assert_eq!(ContractRevert::from_contract_error(&err).map(|r| r.code()), Some("EXPIRED"));
+    }
+
+    #[test]
+    fn test_paused_is_not_client_error() {
+        let data = LyncZEscrowErrors::EnforcedPause(lync_z_escrow::EnforcedPause).encode();
+        let revert = ContractRevert::decode(&data).unwrap();
+        assert_eq!(revert.code(), "CONTRACT_PAUSED");
+        assert!(!revert.is_client_error());
+    }
+}
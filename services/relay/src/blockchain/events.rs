@@ -1,20 +1,37 @@
 //! Blockchain event listener for LyncZ escrow contract
 //! Syncs on-chain events to the database and sends email notifications
+//!
+//! Each synced block range is applied in one transaction together with its
+//! checkpoint. Logs are deduplicated by (txHash, logIndex); a log whose handler
+//! fails is dead-lettered and retried with backoff instead of being skipped.
+//!
+//! With RPC_WS_URL set, logs and heads arrive over `eth_subscribe` (see subscription.rs);
+//! gaps are backfilled with `eth_getLogs`, and polling takes over while the socket is down.
 
 use ethers::prelude::*;
-use ethers::providers::{Http, Provider};
+use ethers::abi::RawLog;
+use ethers::providers::{Http, Provider, Ws};
+use futures_util::StreamExt;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use thiserror::Error;
-use tokio::time::{interval, Duration};
+use tokio::time::{interval, Duration, Instant};
 
-use super::{OrderCreatedFilter, OrderWithdrawnFilter, TradeCreatedFilter, TradeSettledFilter, TradeExpiredFilter, ExchangeRateUpdatedFilter, AccountLinesHashUpdatedFilter};
+use super::subscription::LogBuffer;
+use super::{OrderCreatedFilter, OrderWithdrawnFilter, TradeCreatedFilter, TradeMemoRequiredFilter, TradeSettledFilter, TradeExpiredFilter, ExchangeRateUpdatedFilter, AccountLinesHashUpdatedFilter, OrderMemoRequirementUpdatedFilter};
 use crate::db::{
-    models::{DbOrder, DbTrade},
-    orders::{OrderRepository, PostgresOrderRepository},
+    event_batch::{BatchLog, EventBatch},
+    event_journal::{ChainRollback, PostgresEventJournalRepository},
+    failed_logs::PostgresFailedLogRepository,
+    models::{DbAppliedEvent, DbFailedLog, DbOrder, DbTrade, SettlementState},
+    orders::PostgresOrderRepository,
     trades::{TradeRepository, PostgresTradeRepository},
     account_emails::AccountEmailRepository,
 };
+use crate::crypto::trade_memo_code;
+use crate::reindex::{EntitySnapshot, ReplayMode, ReplayReport};
 use crate::email::{EmailService, EmailEvent, EmailInfo, format_token_amount};
+use crate::trade_events::{TradeEvent, TradeEventBus};
 
 #[derive(Error, Debug)]
 pub enum EventListenerError {
@@ -26,6 +43,12 @@ pub enum EventListenerError {
     EventDecodeError(String),
 }
 
+impl From<crate::db::DbError> for EventListenerError {
+    fn from(err: crate::db::DbError) -> Self {
+        EventListenerError::DatabaseError(err.to_string())
+    }
+}
+
 /// Configuration constants - UNIFIED POLLING (optimized for RPC cost)
 /// Single eth_getLogs call per cycle with no topic filter = ~75 CUs base
 /// Pay-as-you-go tier: no block range limits
@@ -33,9 +56,28 @@ pub enum EventListenerError {
 /// Base L2 produces ~1 block every 2 seconds
 /// With 6s polling: ~3 new blocks per cycle (normal operation)
 /// BLOCKS_PER_QUERY of 200 allows fast catch-up after restarts
+/// 
+/// Reorgs: two block headers per cycle (the range's first and last block). The first
+/// block's parent hash must match the stored hash of the last synced block; otherwise
+/// the events above the common ancestor are rolled back and replayed.
 const BLOCKS_PER_QUERY: u64 = 200;     // Max blocks per query (for catch-up)
-const MAX_REORG_DEPTH: u64 = 2;        // Wait 2 blocks for finality  
+const MAX_REORG_DEPTH: u64 = 2;        // Stay 2 blocks behind head (deeper reorgs are rolled back)
 const POLL_INTERVAL_SECS: u64 = 6;     // Poll every 6 seconds (~37M CUs/month)
+const REORG_RETENTION_BLOCKS: u64 = 43_200; // Keep block hashes and journaled events ~24h
+const REORG_SEARCH_LIMIT: i64 = 1_000;      // Stored blocks compared when looking for the ancestor
+const ORPHAN_GRACE_BLOCKS: u64 = 150;       // ~5 min for a rolled-back creation to be mined again
+
+/// Dead-lettered logs: retried after 1 min, doubling up to 1h, at most 10 attempts
+const FAILED_LOG_RETRY_BATCH: i64 = 20;
+const FAILED_LOG_MAX_ATTEMPTS: i32 = 10;
+const FAILED_LOG_MAX_BACKOFF_SECS: i64 = 3600;
+
+/// WebSocket subscription: a socket without a new head for 30s is considered dead;
+/// polling takes over and the socket is reconnected after 15s, doubling up to 5 min
+const WS_STALL_SECS: u64 = 30;
+const WS_RECONNECT_SECS: u64 = 15;
+const WS_MAX_RECONNECT_SECS: u64 = 300;
+const WS_HEALTHY_SECS: u64 = 300;          // A subscription that lived this long resets the backoff
 
 pub struct EventListener {
     provider: Arc<Provider<Http>>,
@@ -43,6 +85,8 @@ pub struct EventListener {
     db_pool: sqlx::PgPool,
     start_block: u64,
     email_service: Option<Arc<EmailService>>,
+    trade_events: Option<TradeEventBus>,
+    ws_url: Option<String>,
 }
 
 impl EventListener {
@@ -95,22 +139,75 @@ impl EventListener {
             db_pool,
             start_block,
             email_service,
+            trade_events: None,
+            ws_url: None,
         })
     }
 
+    /// Publish on-chain trade status changes to the trade event bus
+    pub fn with_event_bus(mut self, trade_events: TradeEventBus) -> Self {
+        self.trade_events = Some(trade_events);
+        self
+    }
+
+    /// Receive logs over a WebSocket subscription (polling remains the fallback)
+    pub fn with_ws_url(mut self, ws_url: Option<String>) -> Self {
+        self.ws_url = ws_url;
+        self
+    }
+
+    fn publish_trade_status(&self, trade_id: &str, status: i32) {
+        if let Some(trade_events) = &self.trade_events {
+            trade_events.publish(TradeEvent::TradeStatus {
+                trade_id: trade_id.to_string(),
+                status,
+            });
+        }
+    }
+
     /// Start the event listener (runs indefinitely)
     pub async fn start(&mut self) -> Result<(), EventListenerError> {
         tracing::info!("🚀 Starting event listener...");
 
+        let Some(ws_url) = self.ws_url.clone() else {
+            self.poll(None).await;
+            return Ok(());
+        };
+
+        let mut reconnect_secs = WS_RECONNECT_SECS;
+        loop {
+            let connected_at = Instant::now();
+            if let Err(e) = self.run_subscription(&ws_url).await {
+                tracing::warn!("🔌 WebSocket subscription down: {}", e);
+            }
+            if connected_at.elapsed() >= Duration::from_secs(WS_HEALTHY_SECS) {
+                reconnect_secs = WS_RECONNECT_SECS;
+            }
+
+            tracing::info!("🔁 Falling back to polling, reconnecting in {}s", reconnect_secs);
+            self.poll(Some(Instant::now() + Duration::from_secs(reconnect_secs))).await;
+            reconnect_secs = std::cmp::min(reconnect_secs * 2, WS_MAX_RECONNECT_SECS);
+        }
+    }
+
+    /// Poll eth_getLogs every POLL_INTERVAL_SECS (until `deadline`, or forever)
+    async fn poll(&mut self, deadline: Option<Instant>) {
         let mut poll_interval = interval(Duration::from_secs(POLL_INTERVAL_SECS));
         let mut consecutive_errors = 0u32;
 
         loop {
             poll_interval.tick().await;
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return;
+            }
 
             match self.sync_events().await {
                 Ok(_) => {
                     consecutive_errors = 0;
+                    
+                    if let Err(e) = self.retry_failed_logs().await {
+                        tracing::error!("❌ Failed log retry error: {}", e);
+                    }
                 }
                 Err(e) => {
                     consecutive_errors += 1;
@@ -127,6 +224,59 @@ impl EventListener {
         }
     }
 
+    /// Sync from an `eth_subscribe` stream of escrow logs and new heads
+    ///
+    /// Every new head syncs the next range: from the buffered logs when the subscription
+    /// covers it, otherwise (startup, gaps) with eth_getLogs. Returns when the socket
+    /// closes or stalls.
+    async fn run_subscription(&mut self, ws_url: &str) -> Result<(), EventListenerError> {
+        let ws = Provider::<Ws>::connect(ws_url)
+            .await
+            .map_err(|e| EventListenerError::ProviderError(e.to_string()))?;
+        let filter = Filter::new().address(self.contract_address);
+        let mut logs = ws
+            .subscribe_logs(&filter)
+            .await
+            .map_err(|e| EventListenerError::ProviderError(e.to_string()))?;
+        let mut heads = ws
+            .subscribe_blocks()
+            .await
+            .map_err(|e| EventListenerError::ProviderError(e.to_string()))?;
+        tracing::info!("🔌 Subscribed to escrow logs over WebSocket");
+
+        let mut buffer = LogBuffer::default();
+        loop {
+            tokio::select! {
+                log = logs.next() => match log {
+                    Some(log) => buffer.push_log(log),
+                    None => return Err(EventListenerError::ProviderError("Log subscription closed".to_string())),
+                },
+                head = heads.next() => {
+                    let Some(head) = head else {
+                        return Err(EventListenerError::ProviderError("Head subscription closed".to_string()));
+                    };
+                    let number = head.number.map(|n| n.as_u64()).unwrap_or_default();
+                    if !buffer.push_head(head) {
+                        tracing::warn!("🕳️ Subscription gap at block {}, backfilling with eth_getLogs", number);
+                    }
+                    
+                    match self.sync_to(number, Some(&buffer)).await {
+                        Ok(_) => {
+                            if let Err(e) = self.retry_failed_logs().await {
+                                tracing::error!("❌ Failed log retry error: {}", e);
+                            }
+                        }
+                        Err(e) => tracing::error!("❌ Event sync error: {}", e),
+                    }
+                    buffer.prune(self.start_block);
+                }
+                _ = tokio::time::sleep(Duration::from_secs(WS_STALL_SECS)) => {
+                    return Err(EventListenerError::ProviderError(format!("No new head for {}s", WS_STALL_SECS)));
+                }
+            }
+        }
+    }
+
     /// Sync events from blockchain to database using UNIFIED POLLING
     /// Makes ONE eth_getLogs call per cycle (no topic filter) for optimal RPC cost
     async fn sync_events(&mut self) -> Result<(), EventListenerError> {
@@ -137,6 +287,12 @@ impl EventListener {
             .map_err(|e| EventListenerError::ProviderError(e.to_string()))?
             .as_u64();
 
+        self.sync_to(current_block, None).await
+    }
+
+    /// Sync the next range below `current_block`, from the subscription buffer if it
+    /// covers the range, otherwise with block headers and eth_getLogs
+    async fn sync_to(&mut self, current_block: u64, buffer: Option<&LogBuffer>) -> Result<(), EventListenerError> {
         // Apply reorg protection (don't process very recent blocks)
         let safe_block = current_block.saturating_sub(MAX_REORG_DEPTH);
 
@@ -147,30 +303,74 @@ impl EventListener {
 
         // Process blocks in chunks
         let to_block = std::cmp::min(self.start_block + BLOCKS_PER_QUERY, safe_block);
+        let buffered = buffer.and_then(|buffer| buffer.range(self.start_block, to_block));
+
+        // REORG CHECK: the range must build on the last synced block. The last block's
+        // header is fetched before the logs, so logs served from an older fork are
+        // caught by the next cycle's parent check.
+        let journal = PostgresEventJournalRepository::new(self.db_pool.clone());
+        let contract = self.contract_key();
+        let (first_header, last_header) = match &buffered {
+            Some(range) => (range.first_header.clone(), range.last_header.clone()),
+            None => {
+                let last_header = self.block_header(to_block).await?;
+                let first_header = if to_block == self.start_block {
+                    last_header.clone()
+                } else {
+                    self.block_header(self.start_block).await?
+                };
+                (first_header, last_header)
+            }
+        };
+
+        let synced_block = self.start_block.saturating_sub(1);
+        if let Some(stored_hash) = journal.block_hash(&contract, synced_block).await? {
+            if format!("{:#x}", first_header.parent_hash) != stored_hash {
+                tracing::warn!(
+                    "🔀 Reorg detected: block {} no longer builds on synced block {} ({})",
+                    self.start_block,
+                    synced_block,
+                    stored_hash
+                );
+                return self.roll_back_reorg(&journal, synced_block).await;
+            }
+        }
 
         tracing::debug!(
-            "📊 Syncing blocks {} to {} (current: {})",
+            "📊 Syncing blocks {} to {} (current: {}, {})",
             self.start_block,
             to_block,
-            current_block
+            current_block,
+            if buffered.is_some() { "subscription" } else { "eth_getLogs" }
         );
 
-        // UNIFIED POLLING: Single eth_getLogs call for ALL events from this contract
-        // No topic filter = gets all events, route locally by topic0
-        let filter = Filter::new()
-            .address(self.contract_address)
-            .from_block(self.start_block)
-            .to_block(to_block);
-
-        let all_logs = self
-            .provider
-            .get_logs(&filter)
-            .await
-            .map_err(|e| EventListenerError::ProviderError(e.to_string()))?;
+        let all_logs = match buffered {
+            Some(range) => range.logs,
+            None => {
+                // UNIFIED POLLING: Single eth_getLogs call for ALL events from this contract
+                // No topic filter = gets all events, route locally by topic0
+                let filter = Filter::new()
+                    .address(self.contract_address)
+                    .from_block(self.start_block)
+                    .to_block(to_block);
+
+                let all_logs = self
+                    .provider
+                    .get_logs(&filter)
+                    .await
+                    .map_err(|e| EventListenerError::ProviderError(e.to_string()))?;
+
+                if !all_logs.is_empty() {
+                    tracing::info!("📦 Fetched {} total events in unified call", all_logs.len());
+                }
+                all_logs
+            }
+        };
 
-        if !all_logs.is_empty() {
-            tracing::info!("📦 Fetched {} total events in unified call", all_logs.len());
-        }
+        // ONE TRANSACTION per range: applied logs, journal, block hashes and the new
+        // checkpoint commit together - on any DB failure the range is synced again
+        let mut batch = EventBatch::begin(&self.db_pool, &contract).await?;
+        let mut after_commit = Vec::new();
 
         // Route logs to appropriate handlers based on topic0 (event signature)
         for log in all_logs {
@@ -179,49 +379,412 @@ impl EventListener {
             }
             
             let topic0 = log.topics[0];
+            let Some(event) = event_name(topic0) else {
+                tracing::debug!("Unknown event topic: {:?}", topic0);
+                continue;
+            };
             
-            // Route by event signature hash
-            if topic0 == OrderCreatedFilter::signature() {
-                if let Err(e) = self.handle_order_created(log).await {
-                    tracing::error!("❌ Failed to handle OrderCreated: {}", e);
-                }
-            } else if topic0 == OrderWithdrawnFilter::signature() {
-                if let Err(e) = self.handle_order_withdrawn(log).await {
-                    tracing::error!("❌ Failed to handle OrderWithdrawn: {}", e);
+            // Journaled with its raw log so a later reorg can undo it
+            let synced = SyncedLog::from_log(event, &log, to_block);
+            if let LogOutcome::Applied(follow_up) = self.apply_log(&mut batch, &synced, log).await? {
+                after_commit.push(follow_up);
+            }
+        }
+
+        // Update last synced block (its hash is the next cycle's parent check)
+        batch.record_block(to_block, &format!("{:#x}", last_header.hash.unwrap_or_default())).await?;
+        batch.commit(Some(to_block + 1)).await?;
+        self.start_block = to_block + 1;
+
+        // Notifications only for committed changes
+        for follow_up in after_commit {
+            self.run_after_commit(follow_up).await;
+        }
+
+        // Rolled-back creations that were never mined again
+        match journal.finalize_orphans(&contract, self.start_block.saturating_sub(ORPHAN_GRACE_BLOCKS)).await {
+            Ok(orphans) => {
+                for orphan in orphans {
+                    tracing::warn!("🗑️ {} {} was reorged out and never replayed - deleted", orphan.event, orphan.entity_id);
                 }
-            } else if topic0 == TradeCreatedFilter::signature() {
-                if let Err(e) = self.handle_trade_created(log).await {
-                    tracing::error!("❌ Failed to handle TradeCreated: {}", e);
+            }
+            Err(e) => tracing::error!("❌ Failed to clean up orphaned events: {}", e),
+        }
+        if let Err(e) = journal.prune(&contract, self.start_block.saturating_sub(REORG_RETENTION_BLOCKS)).await {
+            tracing::warn!("⚠️ Failed to prune reorg journal: {}", e);
+        }
+
+        Ok(())
+    }
+
+    /// Apply one log inside the range's transaction (under its own savepoint)
+    ///
+    /// Already-processed logs are skipped. A failing handler is rolled back to the
+    /// savepoint and dead-lettered; only database errors outside the handler abort
+    /// the whole range.
+    async fn apply_log(
+        &self,
+        batch: &mut EventBatch,
+        synced: &SyncedLog,
+        log: Log,
+    ) -> Result<LogOutcome, EventListenerError> {
+        let entry = synced.as_batch_log();
+        batch.savepoint().await?;
+        
+        if !batch.claim(&entry).await? {
+            batch.release().await?;
+            tracing::debug!("⏭️ {} {}:{} already processed", synced.event, synced.tx_hash, synced.log_index);
+            return Ok(LogOutcome::Skipped);
+        }
+        
+        match self.handle_log(batch, &synced.event, log).await {
+            Ok(follow_up) => {
+                batch.journal(&entry).await?;
+                if !synced.block_hash.is_empty() {
+                    batch.record_block(synced.block_number, &synced.block_hash).await?;
                 }
-            } else if topic0 == TradeSettledFilter::signature() {
-                if let Err(e) = self.handle_trade_settled(log).await {
-                    tracing::error!("❌ Failed to handle TradeSettled: {}", e);
+                batch.release().await?;
+                Ok(LogOutcome::Applied(follow_up))
+            }
+            Err(e) => {
+                batch.rollback_to_savepoint().await?;
+                tracing::error!(
+                    "❌ Failed to handle {} ({}:{}), dead-lettered for retry: {}",
+                    synced.event, synced.tx_hash, synced.log_index, e
+                );
+                batch.dead_letter(&entry, &e.to_string(), next_retry_at(1)).await?;
+                batch.release().await?;
+                Ok(LogOutcome::Failed)
+            }
+        }
+    }
+
+    /// Retry dead-lettered logs that are due, oldest first (each in its own transaction)
+    async fn retry_failed_logs(&self) -> Result<(), EventListenerError> {
+        let contract = self.contract_key();
+        let failed_logs = PostgresFailedLogRepository::new(self.db_pool.clone());
+        
+        for failed in failed_logs.due(&contract, FAILED_LOG_RETRY_BATCH).await? {
+            let synced = SyncedLog::from_failed(&failed);
+            let log = synced.to_log(self.contract_address)?;
+            let entry = synced.as_batch_log();
+            
+            let mut batch = EventBatch::begin(&self.db_pool, &contract).await?;
+            if !batch.claim(&entry).await? {
+                // Applied meanwhile (e.g. the range was replayed after a reorg)
+                batch.resolve_failed_log(failed.id).await?;
+                batch.commit(None).await?;
+                continue;
+            }
+            
+            match self.handle_log(&mut batch, &synced.event, log).await {
+                Ok(follow_up) => {
+                    batch.journal(&entry).await?;
+                    batch.resolve_failed_log(failed.id).await?;
+                    batch.commit(None).await?;
+                    tracing::info!(
+                        "♻️ Dead-lettered {} ({}:{}) applied on attempt {}",
+                        synced.event, synced.tx_hash, synced.log_index, failed.attempts + 1
+                    );
+                    self.run_after_commit(follow_up).await;
                 }
-            } else if topic0 == TradeExpiredFilter::signature() {
-                if let Err(e) = self.handle_trade_expired(log).await {
-                    tracing::error!("❌ Failed to handle TradeExpired: {}", e);
+                Err(e) => {
+                    drop(batch);
+                    let attempts = failed.attempts + 1;
+                    let retry_at = next_retry_at(attempts);
+                    if retry_at.is_none() {
+                        tracing::error!(
+                            "🚨 Giving up on {} ({}:{}) after {} attempts: {}",
+                            synced.event, synced.tx_hash, synced.log_index, attempts, e
+                        );
+                    } else {
+                        tracing::warn!(
+                            "⚠️ Retry {} of {} ({}:{}) failed: {}",
+                            attempts, synced.event, synced.tx_hash, synced.log_index, e
+                        );
+                    }
+                    failed_logs.reschedule(failed.id, &e.to_string(), retry_at).await?;
                 }
-            } else if topic0 == ExchangeRateUpdatedFilter::signature() {
-                if let Err(e) = self.handle_exchange_rate_updated(log).await {
-                    tracing::error!("❌ Failed to handle ExchangeRateUpdated: {}", e);
+            }
+        }
+        
+        Ok(())
+    }
+
+    /// Route a log to its handler by event name
+    async fn handle_log(
+        &self,
+        batch: &mut EventBatch,
+        event: &str,
+        log: Log,
+    ) -> Result<AfterCommit, EventListenerError> {
+        match event {
+            "OrderCreated" => self.handle_order_created(batch, log).await,
+            "OrderWithdrawn" => self.handle_order_withdrawn(batch, log).await,
+            "TradeCreated" => self.handle_trade_created(batch, log).await,
+            "TradeMemoRequired" => self.handle_trade_memo_required(batch, log).await,
+            "TradeSettled" => self.handle_trade_settled(batch, log).await,
+            "TradeExpired" => self.handle_trade_expired(batch, log).await,
+            "ExchangeRateUpdated" => self.handle_exchange_rate_updated(batch, log).await,
+            "AccountLinesHashUpdated" => self.handle_account_lines_hash_updated(log).await,
+            "OrderMemoRequirementUpdated" => self.handle_order_memo_requirement_updated(batch, log).await,
+            _ => Ok(AfterCommit::Nothing),
+        }
+    }
+
+    /// Notifications for a committed log (emails, live trade updates)
+    async fn run_after_commit(&self, follow_up: AfterCommit) {
+        match follow_up {
+            AfterCommit::Nothing => {}
+            AfterCommit::OrderCreated { order_id, seller, account_lines_hash } => {
+                self.notify_order_created(&order_id, &seller, &account_lines_hash).await;
+            }
+            AfterCommit::OrderWithdrawn { order_id, withdrawn_amount, remaining_amount } => {
+                self.notify_order_withdrawn(&order_id, withdrawn_amount, remaining_amount).await;
+            }
+            AfterCommit::ExchangeRateUpdated { order_id, old_rate, new_rate } => {
+                self.notify_exchange_rate_updated(&order_id, old_rate, new_rate).await;
+            }
+            AfterCommit::TradeStatus { trade_id, status } => {
+                self.publish_trade_status(&trade_id, status);
+            }
+            AfterCommit::TradeSettled { trade_id, tx_hash } => {
+                self.publish_trade_status(&trade_id, 1);
+                self.notify_trade_settled(&trade_id, &tx_hash).await;
+            }
+        }
+    }
+
+    // ================================================================
+    // REPLAY (lyncz-reindex)
+    // ================================================================
+
+    /// Latest block below the reorg depth
+    pub async fn safe_head(&self) -> Result<u64, EventListenerError> {
+        let current_block = self
+            .provider
+            .get_block_number()
+            .await
+            .map_err(|e| EventListenerError::ProviderError(e.to_string()))?
+            .as_u64();
+
+        Ok(current_block.saturating_sub(MAX_REORG_DEPTH))
+    }
+
+    /// Block the escrow contract was deployed in (binary search on eth_getCode,
+    /// needs an archive node)
+    pub async fn deployment_block(&self) -> Result<u64, EventListenerError> {
+        let head = self.safe_head().await?;
+        if !self.has_code_at(head).await? {
+            return Err(EventListenerError::ProviderError(format!("No contract code at {:#x}", self.contract_address)));
+        }
+
+        let (mut low, mut high) = (0, head);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.has_code_at(mid).await? {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+
+        Ok(low)
+    }
+
+    async fn has_code_at(&self, block: u64) -> Result<bool, EventListenerError> {
+        let code = self
+            .provider
+            .get_code(self.contract_address, Some(BlockId::Number(block.into())))
+            .await
+            .map_err(|e| EventListenerError::ProviderError(e.to_string()))?;
+
+        Ok(!code.is_empty())
+    }
+
+    /// Replay the logs of `from..=to` into `batch` (no notifications, sync checkpoint
+    /// untouched - the caller commits or, for a dry run, drops the batch)
+    ///
+    /// Dry runs record each touched order and trade in `snapshots` the first time it
+    /// is seen, before the replay changes it. Logs in processed_logs are never
+    /// re-applied, so a dry run only previews unapplied logs.
+    pub async fn replay_range(
+        &self,
+        batch: &mut EventBatch,
+        from: u64,
+        to: u64,
+        mode: ReplayMode,
+        snapshots: &mut BTreeMap<String, Option<EntitySnapshot>>,
+    ) -> Result<ReplayReport, EventListenerError> {
+        let filter = Filter::new()
+            .address(self.contract_address)
+            .from_block(from)
+            .to_block(to);
+        let all_logs = self
+            .provider
+            .get_logs(&filter)
+            .await
+            .map_err(|e| EventListenerError::ProviderError(e.to_string()))?;
+
+        let mut report = ReplayReport::default();
+        for log in all_logs {
+            let Some(event) = log.topics.first().and_then(|topic0| event_name(*topic0)) else {
+                continue;
+            };
+            let synced = SyncedLog::from_log(event, &log, to);
+            report.logs += 1;
+
+            if mode == ReplayMode::Baseline {
+                if batch.claim(&synced.as_batch_log()).await? {
+                    report.applied += 1;
+                } else {
+                    report.skipped += 1;
                 }
-            } else if topic0 == AccountLinesHashUpdatedFilter::signature() {
-                if let Err(e) = self.handle_account_lines_hash_updated(log).await {
-                    tracing::error!("❌ Failed to handle AccountLinesHashUpdated: {}", e);
+                continue;
+            }
+
+            if mode == ReplayMode::DryRun {
+                // Indexed IDs (order, trade); addresses match nothing
+                for id in &synced.topics[1..] {
+                    if !snapshots.contains_key(id) {
+                        let before = batch.snapshot(id).await?;
+                        snapshots.insert(id.clone(), before);
+                    }
                 }
-            } else {
-                tracing::debug!("Unknown event topic: {:?}", topic0);
+            }
+
+            match self.apply_log(batch, &synced, log).await? {
+                LogOutcome::Applied(_) => report.applied += 1,
+                LogOutcome::Skipped => report.skipped += 1,
+                LogOutcome::Failed => report.failed += 1,
             }
         }
 
-        // Update last synced block
-        self.start_block = to_block + 1;
-        Self::save_last_synced_block(&self.db_pool, &self.contract_address, self.start_block)
-            .await?;
+        Ok(report)
+    }
+
+    // ================================================================
+    // REORG ROLLBACK
+    // Walk back to the newest stored block that is still canonical (the common
+    // ancestor), undo the journaled events above it newest-first in one
+    // transaction, then resume syncing from the ancestor (the replay).
+    // ================================================================
+
+    async fn roll_back_reorg(
+        &mut self,
+        journal: &PostgresEventJournalRepository,
+        mismatched_block: u64,
+    ) -> Result<(), EventListenerError> {
+        let contract = self.contract_key();
+        let stored = journal.blocks_below(&contract, mismatched_block + 1, REORG_SEARCH_LIMIT).await?;
+        let listener = &*self;
+        let ancestor = common_ancestor(&stored, mismatched_block, |number| async move {
+            let canonical = listener.block_header(number).await?;
+            Ok(format!("{:#x}", canonical.hash.unwrap_or_default()))
+        })
+        .await?;
+
+        let (undone, reopened) = self.undo_above(journal, ancestor).await?;
 
+        tracing::warn!(
+            "🔄 Rolled back {} events above block {} (reorg at block {}), replaying from block {}",
+            undone,
+            ancestor,
+            mismatched_block,
+            ancestor + 1
+        );
+
+        for trade_id in reopened {
+            self.publish_trade_status(&trade_id, 0);
+        }
+        self.start_block = ancestor + 1;
         Ok(())
     }
 
+    /// Undo the journaled events above `ancestor` in one transaction
+    /// Returns the number of undone events and the trades that went back to PENDING
+    async fn undo_above(
+        &self,
+        journal: &PostgresEventJournalRepository,
+        ancestor: u64,
+    ) -> Result<(usize, Vec<String>), EventListenerError> {
+        let contract = self.contract_key();
+        let events = journal.events_after(&contract, ancestor).await?;
+        let mut rollback = journal.begin_rollback(&contract).await?;
+        let mut reopened = Vec::new();
+        for event in &events {
+            if self.undo_event(&mut rollback, event).await? {
+                reopened.extend(event.entity_id.clone());
+            }
+        }
+        rollback.commit(ancestor).await?;
+
+        Ok((events.len(), reopened))
+    }
+
+    /// Undo one journaled event's database effects
+    /// Returns true if a trade went back to PENDING
+    async fn undo_event(
+        &self,
+        rollback: &mut ChainRollback,
+        event: &DbAppliedEvent,
+    ) -> Result<bool, EventListenerError> {
+        let raw = raw_log(&event.topics, &event.data)?;
+
+        match event.event.as_str() {
+            "OrderWithdrawn" => {
+                let decoded: OrderWithdrawnFilter = decode_raw_log(&raw)?;
+                let order_id = format!("0x{}", hex::encode(decoded.order_id));
+                rollback.adjust_remaining_amount(&order_id, &decoded.withdrawn_amount.to_string()).await?;
+                rollback.delete_withdrawal(&order_id, &event.tx_hash).await?;
+            }
+            "TradeCreated" => {
+                // The trade row is kept (PDF, proof) - deleted later if never replayed
+                let decoded: TradeCreatedFilter = decode_raw_log(&raw)?;
+                let order_id = format!("0x{}", hex::encode(decoded.order_id));
+                let total_reserve = decoded.token_amount + decoded.fee_amount;
+                rollback.adjust_remaining_amount(&order_id, &total_reserve.to_string()).await?;
+            }
+            "TradeSettled" => {
+                let decoded: TradeSettledFilter = decode_raw_log(&raw)?;
+                let trade_id = format!("0x{}", hex::encode(decoded.trade_id));
+                rollback.reopen_trade(&trade_id).await?;
+                rollback.record_settlement_state(&trade_id, &SettlementState::failed("REORGED").to_string()).await?;
+                return Ok(true);
+            }
+            "TradeExpired" => {
+                let decoded: TradeExpiredFilter = decode_raw_log(&raw)?;
+                let trade_id = format!("0x{}", hex::encode(decoded.trade_id));
+                let order_id = format!("0x{}", hex::encode(decoded.order_id));
+                rollback.reopen_trade(&trade_id).await?;
+                rollback.adjust_remaining_amount(&order_id, &format!("-{}", decoded.total_returned)).await?;
+                if let Some(holder) = rollback.restore_receipt_reservation(&trade_id).await? {
+                    tracing::warn!(
+                        "⚠️ Trade {} reopened by a reorg, but its receipt is now reserved by trade {}",
+                        trade_id,
+                        holder
+                    );
+                }
+                return Ok(true);
+            }
+            "ExchangeRateUpdated" => {
+                let decoded: ExchangeRateUpdatedFilter = decode_raw_log(&raw)?;
+                let order_id = format!("0x{}", hex::encode(decoded.order_id));
+                rollback.set_exchange_rate(&order_id, &decoded.old_rate.to_string()).await?;
+            }
+            "OrderMemoRequirementUpdated" => {
+                let decoded: OrderMemoRequirementUpdatedFilter = decode_raw_log(&raw)?;
+                let order_id = format!("0x{}", hex::encode(decoded.order_id));
+                rollback.set_memo_required(&order_id, decoded.old_required).await?;
+            }
+            // OrderCreated keeps its row (payment info) until replayed or orphaned;
+            // the memo code is derived from the trade ID; hash updates are only logged
+            _ => {}
+        }
+
+        Ok(false)
+    }
+
     // ================================================================
     // EVENT HANDLER: OrderCreated (v4 - Privacy)
     // New signature: OrderCreated(bytes32 indexed orderId, address indexed seller, address indexed token, 
@@ -232,7 +795,7 @@ impl EventListener {
     //          Seller must call POST /api/orders/:orderId/payment-info to submit plain text.
     // ================================================================
 
-    async fn handle_order_created(&self, log: Log) -> Result<(), EventListenerError> {
+    async fn handle_order_created(&self, batch: &mut EventBatch, log: Log) -> Result<AfterCommit, EventListenerError> {
         // Decode event
         let event: OrderCreatedFilter = ethers::contract::parse_log(log)
             .map_err(|e| EventListenerError::EventDecodeError(e.to_string()))?;
@@ -263,8 +826,6 @@ impl EventListener {
 
         // DATABASE SYNC: Insert order
         // NOTE: accountId and accountName are empty - seller must call /payment-info endpoint
-        let order_repo = PostgresOrderRepository::new(self.db_pool.clone());
-        
         let db_order = DbOrder {
             order_id: order_id.clone(),
             seller: format!("{:#x}", event.seller).to_lowercase(),
@@ -279,89 +840,24 @@ impl EventListener {
             synced_at: chrono::Utc::now(),
             is_public: event.is_public,                // From on-chain event
             private_code: None,                        // Generated when seller sets visibility
+            memo_required: false,                      // Set by OrderMemoRequirementUpdated
         };
 
-        match order_repo.create(&db_order).await {
-            Ok(_) => {
-                tracing::info!("✅ Order {} synced to database (awaiting payment info)", order_id);
-            }
-            Err(e) => {
-                tracing::error!("❌ Database insert failed: {}", e);
-                return Err(EventListenerError::DatabaseError(e.to_string()));
-            }
-        }
-
-        // After syncing, check if payment info was submitted before the event (race condition)
-        // If so, verify the hash matches and send the order creation email
-        if let Ok(synced_order) = order_repo.get(&order_id).await {
-            if !synced_order.alipay_id.is_empty() && !synced_order.alipay_name.is_empty() {
-                tracing::info!("📬 Payment info already present for order {}, verifying hash...", order_id);
-                
-                // SECURITY: Verify the computed hash matches what's stored on-chain
-                let computed_hash = crate::crypto::compute_account_lines_hash(
-                    &synced_order.alipay_name,
-                    &synced_order.alipay_id,
-                );
-                let computed_hash_hex = format!("0x{}", hex::encode(computed_hash));
-                
-                if computed_hash_hex != account_lines_hash {
-                    tracing::error!(
-                        "🚨 HASH MISMATCH for order {}!\n  \
-                        On-chain hash: {}\n  \
-                        Computed hash: {}\n  \
-                        account_name: {}\n  \
-                        account_id: {}",
-                        order_id,
-                        account_lines_hash,
-                        computed_hash_hex,
-                        synced_order.alipay_name,
-                        synced_order.alipay_id
-                    );
-                    // Don't send email - something is wrong!
-                    return Ok(());
-                }
-                
-                tracing::info!("✅ Hash verified for order {}: {}", order_id, computed_hash_hex);
-                
-                // Get seller's email
-                let seller_lower = format!("{:#x}", event.seller).to_lowercase();
-                let email_repo = AccountEmailRepository::new(self.db_pool.clone());
-                if let Ok(Some(account_email)) = email_repo.get_if_enabled(&seller_lower).await {
-                    if let Some(ref email_service) = self.email_service {
-                        let token_symbol = get_token_symbol(&synced_order.token);
-                        let token_decimals = get_token_decimals(&synced_order.token);
-                        
-                        let _ = email_service.send_notification(
-                            &account_email.email,
-                            &account_email.language,
-                            crate::email::EmailEvent::OrderCreated,
-                            &crate::email::EmailInfo::OrderCreated {
-                                order_id: order_id.clone(),
-                                token_amount: format_token_amount(&synced_order.total_amount, token_decimals, ""),
-                                token_symbol,
-                                exchange_rate: synced_order.exchange_rate.clone(),
-                                account_id: synced_order.alipay_id.clone(),
-                                account_name: synced_order.alipay_name.clone(),
-                                rail: synced_order.rail,  // Pass rail number, template will localize
-                                is_private: !synced_order.is_public,
-                                private_code: synced_order.private_code.clone(),
-                            },
-                        ).await;
-                        
-                        tracing::info!("📧 Sent order created email to {} for order {}", account_email.email, order_id);
-                    }
-                }
-            }
-        }
+        batch.upsert_order(&db_order).await?;
+        tracing::info!("✅ Order {} synced to database (awaiting payment info)", order_id);
 
-        Ok(())
+        Ok(AfterCommit::OrderCreated {
+            order_id,
+            seller: db_order.seller,
+            account_lines_hash,
+        })
     }
 
     // ================================================================
     // EVENT HANDLER: OrderWithdrawn
     // ================================================================
 
-    async fn handle_order_withdrawn(&self, log: Log) -> Result<(), EventListenerError> {
+    async fn handle_order_withdrawn(&self, batch: &mut EventBatch, log: Log) -> Result<AfterCommit, EventListenerError> {
         let tx_hash = log.transaction_hash
             .map(|h| format!("{:#x}", h));
         
@@ -378,64 +874,31 @@ impl EventListener {
         );
 
         // DATABASE SYNC: Update remaining amount
-        let order_repo = PostgresOrderRepository::new(self.db_pool.clone());
         let delta = format!("-{}", event.withdrawn_amount);
-        
-        match order_repo.adjust_remaining_amount(&order_id, &delta).await {
-            Ok(_) => {
-                tracing::info!("✅ Order {} remaining amount adjusted", order_id);
-            }
-            Err(e) => {
-                tracing::error!("❌ Database update failed: {}", e);
-                return Err(EventListenerError::DatabaseError(e.to_string()));
-            }
-        }
+        batch.adjust_remaining_amount(&order_id, &delta).await?;
+        tracing::info!("✅ Order {} remaining amount adjusted", order_id);
 
         // DATABASE SYNC: Record withdrawal for activity timeline
-        use crate::db::withdrawals::PostgresWithdrawalRepository;
-        let withdrawal_repo = PostgresWithdrawalRepository::new(self.db_pool.clone());
-        match withdrawal_repo.create(
+        batch.insert_withdrawal(
             &order_id,
             &event.withdrawn_amount.to_string(),
             &event.remaining_amount.to_string(),
             tx_hash.as_deref(),
-        ).await {
-            Ok(_) => {
-                tracing::info!("✅ Withdrawal recorded for order {}", order_id);
-            }
-            Err(e) => {
-                tracing::error!("❌ Failed to record withdrawal: {}", e);
-                // Don't fail the whole handler - withdrawal is recorded for UI only
-            }
-        }
-
-        // Send email notification to seller
-        if let Ok(order) = order_repo.get(&order_id).await {
-            let token_symbol = get_token_symbol(&order.token);
-            let decimals = get_token_decimals(&order.token);
-            let formatted_withdrawn = format_token_amount(&event.withdrawn_amount.to_string(), decimals, "");
-            let formatted_remaining = format_token_amount(&event.remaining_amount.to_string(), decimals, "");
-            
-            self.send_email_notification(
-                EmailEvent::OrderWithdrawn,
-                &order.seller,
-                EmailInfo::OrderWithdrawn {
-                    order_id: order_id.clone(),
-                    withdrawn_amount: formatted_withdrawn,
-                    remaining_amount: formatted_remaining,
-                    token_symbol,
-                },
-            ).await;
-        }
+        ).await?;
+        tracing::info!("✅ Withdrawal recorded for order {}", order_id);
 
-        Ok(())
+        Ok(AfterCommit::OrderWithdrawn {
+            order_id,
+            withdrawn_amount: event.withdrawn_amount,
+            remaining_amount: event.remaining_amount,
+        })
     }
 
     // ================================================================
     // EVENT HANDLER: ExchangeRateUpdated
     // ================================================================
 
-    async fn handle_exchange_rate_updated(&self, log: Log) -> Result<(), EventListenerError> {
+    async fn handle_exchange_rate_updated(&self, batch: &mut EventBatch, log: Log) -> Result<AfterCommit, EventListenerError> {
         let tx_hash = log.transaction_hash
             .map(|h| format!("{:#x}", h));
         
@@ -453,35 +916,39 @@ impl EventListener {
         );
 
         // DATABASE SYNC: Update exchange rate
-        let order_repo = PostgresOrderRepository::new(self.db_pool.clone());
-        match order_repo.update_exchange_rate(&order_id, &event.new_rate.to_string()).await {
-            Ok(_) => {
-                tracing::info!("✅ Order {} exchange rate updated to {}", order_id, event.new_rate);
-            }
-            Err(e) => {
-                tracing::error!("❌ Database update failed: {}", e);
-                return Err(EventListenerError::DatabaseError(e.to_string()));
-            }
-        }
+        batch.update_exchange_rate(&order_id, &event.new_rate.to_string()).await?;
+        tracing::info!("✅ Order {} exchange rate updated to {}", order_id, event.new_rate);
 
-        // Send email notification to seller
-        if let Ok(order) = order_repo.get(&order_id).await {
-            // Format exchange rates (divide by 100 since stored in cents)
-            let old_rate = (event.old_rate.as_u64() as f64) / 100.0;
-            let new_rate = (event.new_rate.as_u64() as f64) / 100.0;
-            
-            self.send_email_notification(
-                EmailEvent::OrderUpdated,
-                &order.seller,
-                EmailInfo::ExchangeRateUpdated {
-                    order_id: order_id.clone(),
-                    old_rate: format!("{:.2}", old_rate),
-                    new_rate: format!("{:.2}", new_rate),
-                },
-            ).await;
-        }
+        Ok(AfterCommit::ExchangeRateUpdated {
+            order_id,
+            old_rate: event.old_rate,
+            new_rate: event.new_rate,
+        })
+    }
 
-        Ok(())
+    // ================================================================
+    // EVENT HANDLER: OrderMemoRequirementUpdated
+    // Signature: OrderMemoRequirementUpdated(bytes32 indexed orderId, bool oldRequired, bool newRequired)
+    // ================================================================
+
+    async fn handle_order_memo_requirement_updated(&self, batch: &mut EventBatch, log: Log) -> Result<AfterCommit, EventListenerError> {
+        let event: OrderMemoRequirementUpdatedFilter = ethers::contract::parse_log(log)
+            .map_err(|e| EventListenerError::EventDecodeError(e.to_string()))?;
+
+        let order_id = format!("0x{}", hex::encode(event.order_id));
+
+        tracing::info!(
+            "📝 OrderMemoRequirementUpdated:\n  order_id: {}\n  oldRequired: {}\n  newRequired: {}",
+            order_id,
+            event.old_required,
+            event.new_required
+        );
+
+        // DATABASE SYNC: Trades filled from now on carry the requirement (TradeMemoRequired)
+        batch.set_memo_required(&order_id, event.new_required).await?;
+        tracing::info!("✅ Order {} memo requirement set to {}", order_id, event.new_required);
+
+        Ok(AfterCommit::Nothing)
     }
 
     // ================================================================
@@ -493,7 +960,7 @@ impl EventListener {
     // Users must create a new order if they want different payment details.
     // ================================================================
 
-    async fn handle_account_lines_hash_updated(&self, log: Log) -> Result<(), EventListenerError> {
+    async fn handle_account_lines_hash_updated(&self, log: Log) -> Result<AfterCommit, EventListenerError> {
         let tx_hash = log.transaction_hash
             .map(|h| format!("{:#x}", h));
         
@@ -515,7 +982,7 @@ impl EventListener {
         // NOTE: Payment info updates are no longer allowed via the API.
         // This event is kept for auditing in case someone calls the contract directly.
 
-        Ok(())
+        Ok(AfterCommit::Nothing)
     }
 
     // ================================================================
@@ -525,7 +992,7 @@ impl EventListener {
     // NOTE: No more paymentNonce - transaction_id comes from PDF parsing
     // ================================================================
 
-    async fn handle_trade_created(&self, log: Log) -> Result<(), EventListenerError> {
+    async fn handle_trade_created(&self, batch: &mut EventBatch, log: Log) -> Result<AfterCommit, EventListenerError> {
         let tx_hash = log.transaction_hash
             .map(|h| format!("{:#x}", h))
             .unwrap_or_default();
@@ -557,14 +1024,8 @@ impl EventListener {
         );
 
         // DATABASE SYNC: Create trade record
-        let trade_repo = PostgresTradeRepository::new(self.db_pool.clone());
-        let order_repo = PostgresOrderRepository::new(self.db_pool.clone());
-        
         // Get order to fetch the rail (payment method)
-        let rail = match order_repo.get(&order_id).await {
-            Ok(order) => order.rail,
-            Err(_) => 0, // Default to ALIPAY if order not found
-        };
+        let rail = batch.order_rail(&order_id).await?.unwrap_or(0); // Default to ALIPAY if order not found
         
         let db_trade = DbTrade {
             trade_id: trade_id.clone(),
@@ -593,49 +1054,47 @@ impl EventListener {
             proof_generated_at: None,
             proof_json: None,
             settlement_error: None, // Set when blockchain submission fails
+            memo_code: None, // Set by TradeMemoRequired (emitted right after TradeCreated)
             alipay_id: None, // Will be fetched from order when needed
             alipay_name: None, // Will be fetched from order when needed
         };
 
-        match trade_repo.create(&db_trade).await {
-            Ok(_) => {
-                tracing::info!("✅ Trade {} created in database", trade_id);
-            }
-            Err(e) => {
-                tracing::error!("❌ Database insert failed: {}", e);
-                return Err(EventListenerError::DatabaseError(e.to_string()));
-            }
-        }
+        batch.insert_trade(&db_trade).await?;
+        tracing::info!("✅ Trade {} created in database", trade_id);
 
         // Adjust order remaining amount (tokenAmount + feeAmount from event)
         // Fee comes directly from blockchain event - no hardcoding needed!
         let total_reserve = event.token_amount + event.fee_amount;
         let delta = format!("-{}", total_reserve);
-        
-        match order_repo.adjust_remaining_amount(&order_id, &delta).await {
-            Ok(_) => {
-                tracing::info!("✅ Order {} remaining amount adjusted by -{} (token: {}, fee: {})", 
-                    order_id, total_reserve, event.token_amount, event.fee_amount);
-            }
-            Err(e) => {
-                tracing::error!("❌ Database update failed: {}", e);
-                return Err(EventListenerError::DatabaseError(e.to_string()));
-            }
-        }
+        batch.adjust_remaining_amount(&order_id, &delta).await?;
+        tracing::info!("✅ Order {} remaining amount adjusted by -{} (token: {}, fee: {})", 
+            order_id, total_reserve, event.token_amount, event.fee_amount);
 
-        // Send email notifications to both seller AND buyer
-        if let Ok(order) = order_repo.get(&order_id).await {
-            let token_symbol = get_token_symbol(&order.token);
-            let decimals = get_token_decimals(&order.token);
-            let buyer_address = format!("{:#x}", event.buyer).to_lowercase();
-            let formatted_token_amount = format_token_amount(&event.token_amount.to_string(), decimals, "");
-            
-            // NOTE: TradeCreated emails removed - users see pending trades in activity timeline instead
-            // (Both seller and buyer will see the trade in their respective order/purchase pages)
-            let _ = (buyer_address, formatted_token_amount, token_symbol); // Silence unused warnings
-        }
+        // NOTE: TradeCreated emails removed - users see pending trades in activity timeline instead
+        // (Both seller and buyer will see the trade in their respective order/purchase pages)
 
-        Ok(())
+        Ok(AfterCommit::TradeStatus { trade_id, status: 0 })
+    }
+
+    // ================================================================
+    // EVENT HANDLER: TradeMemoRequired
+    // Signature: TradeMemoRequired(bytes32 indexed tradeId)
+    // Emitted by fillOrder right after TradeCreated (same tx) when the order requires a memo
+    // ================================================================
+
+    async fn handle_trade_memo_required(&self, batch: &mut EventBatch, log: Log) -> Result<AfterCommit, EventListenerError> {
+        let event: TradeMemoRequiredFilter = ethers::contract::parse_log(log)
+            .map_err(|e| EventListenerError::EventDecodeError(e.to_string()))?;
+
+        let trade_id = format!("0x{}", hex::encode(event.trade_id));
+        let memo_code = trade_memo_code(&event.trade_id);
+
+        tracing::info!("📝 TradeMemoRequired:\n  trade_id: {}\n  memo_code: {}", trade_id, memo_code);
+
+        batch.set_memo_code(&trade_id, &memo_code).await?;
+
+        tracing::info!("✅ Trade {} memo code saved", trade_id);
+        Ok(AfterCommit::Nothing)
     }
 
     // ================================================================
@@ -643,7 +1102,7 @@ impl EventListener {
     // New signature: TradeSettled(bytes32 indexed tradeId, string transactionId)
     // ================================================================
 
-    async fn handle_trade_settled(&self, log: Log) -> Result<(), EventListenerError> {
+    async fn handle_trade_settled(&self, batch: &mut EventBatch, log: Log) -> Result<AfterCommit, EventListenerError> {
         let tx_hash = log.transaction_hash
             .map(|h| format!("{:#x}", h))
             .unwrap_or_default();
@@ -665,82 +1124,23 @@ impl EventListener {
 
         // DATABASE SYNC: Update trade status to SETTLED
         // NOTE: remainingAmount was already deducted at TradeCreated, no adjustment needed here
-        let trade_repo = PostgresTradeRepository::new(self.db_pool.clone());
-        
-        match trade_repo.update_status(&trade_id, 1).await {
-            Ok(_) => {
-                tracing::info!("✅ Trade {} status updated to SETTLED", trade_id);
-            }
-            Err(e) => {
-                tracing::error!("❌ Database update failed: {}", e);
-                return Err(EventListenerError::DatabaseError(e.to_string()));
-            }
-        }
+        // Settlement may have been self-submitted after a failed relayer attempt -
+        // any stale error is dropped so the trade looks the same as a relayer settlement
+        let settlement_tx = (!tx_hash.is_empty()).then_some(tx_hash.as_str());
+        batch.settle_trade(&trade_id, settlement_tx).await?;
+        tracing::info!("✅ Trade {} status updated to SETTLED", trade_id);
 
-        if !tx_hash.is_empty() {
-            if let Err(e) = trade_repo.update_settlement_tx(&trade_id, &tx_hash).await {
-                tracing::warn!("⚠️ Failed to update settlement tx hash: {}", e);
-            }
-        }
+        // Settlement state: covers self-submitted settlements the relay never saw
+        batch.record_settlement_state(&trade_id, &SettlementState::Settled.to_string(), settlement_tx).await?;
 
-        // Send email notifications to both seller AND buyer
-        if let Ok(trade) = trade_repo.get(&trade_id).await {
-            let order_repo = PostgresOrderRepository::new(self.db_pool.clone());
-            if let Ok(order) = order_repo.get(&trade.order_id).await {
-                let token_symbol = get_token_symbol(&order.token);
-                let decimals = get_token_decimals(&order.token);
-                let formatted_token_amount = format_token_amount(&trade.token_amount, decimals, "");
-                
-                // Use fee from database (stored from TradeCreated event) - blockchain is source of truth
-                let formatted_fee = match &trade.fee_amount {
-                    Some(fee) => format_token_amount(fee, decimals, ""),
-                    None => {
-                        // Fallback: calculate from current blockchain config if fee wasn't stored
-                        tracing::warn!("Trade {} missing fee_amount, falling back to 1%", trade_id);
-                        let token_amount_u256 = trade.token_amount.parse::<u128>().unwrap_or(0);
-                        format_token_amount(&(token_amount_u256 / 100).to_string(), decimals, "")
-                    }
-                };
-                
-                // Email to SELLER: Trade settled, payment received
-                self.send_email_notification(
-                    EmailEvent::TradeSettledSeller,
-                    &order.seller,
-                    EmailInfo::TradeSettledSeller {
-                        order_id: trade.order_id.clone(),
-                        trade_id: trade_id.clone(),
-                        token_amount: formatted_token_amount.clone(),
-                        token_symbol: token_symbol.clone(),
-                        cny_amount: trade.cny_amount.clone(),
-                        fee_amount: formatted_fee,
-                        buyer_address: trade.buyer.clone(),
-                        settlement_tx: tx_hash.clone(),
-                    },
-                ).await;
-                
-                // Email to BUYER: Your purchase is complete
-                self.send_email_notification(
-                    EmailEvent::TradeSettledBuyer,
-                    &trade.buyer,
-                    EmailInfo::TradeSettledBuyer {
-                        order_id: trade.order_id.clone(),
-                        trade_id: trade_id.clone(),
-                        token_amount: formatted_token_amount,
-                        token_symbol,
-                        settlement_tx: tx_hash.clone(),
-                    },
-                ).await;
-            }
-        }
-        
-        Ok(())
+        Ok(AfterCommit::TradeSettled { trade_id, tx_hash })
     }
 
     // ================================================================
     // EVENT HANDLER: TradeExpired
     // ================================================================
 
-    async fn handle_trade_expired(&self, log: Log) -> Result<(), EventListenerError> {
+    async fn handle_trade_expired(&self, batch: &mut EventBatch, log: Log) -> Result<AfterCommit, EventListenerError> {
         let event: TradeExpiredFilter = ethers::contract::parse_log(log)
             .map_err(|e| EventListenerError::EventDecodeError(e.to_string()))?;
 
@@ -754,36 +1154,178 @@ impl EventListener {
             event.total_returned
         );
 
-        // DATABASE SYNC: Update trade status to EXPIRED
-        let trade_repo = PostgresTradeRepository::new(self.db_pool.clone());
-        
-        match trade_repo.update_status(&trade_id, 2).await {
-            Ok(_) => {
-                tracing::info!("✅ Trade {} status updated to EXPIRED", trade_id);
-            }
-            Err(e) => {
-                tracing::error!("❌ Database update failed: {}", e);
-                return Err(EventListenerError::DatabaseError(e.to_string()));
-            }
+        // DATABASE SYNC: Update trade status to EXPIRED and free the receipt it reserved
+        batch.expire_trade(&trade_id).await?;
+        tracing::info!("✅ Trade {} status updated to EXPIRED", trade_id);
+
+        // Close out an in-flight settlement (only trades that started settling have a state)
+        if batch.settlement_state(&trade_id).await?.is_some() {
+            batch.record_settlement_state(&trade_id, &SettlementState::failed("EXPIRED").to_string(), None).await?;
         }
 
         // Add tokens back to order (includes fee)
+        batch.adjust_remaining_amount(&order_id, &event.total_returned.to_string()).await?;
+        tracing::info!("✅ Order {} remaining amount adjusted", order_id);
+
+        // NOTE: TradeExpired emails removed - users see expired trades in their activity timeline instead
+
+        Ok(AfterCommit::TradeStatus { trade_id, status: 2 })
+    }
+
+    // ================================================================
+    // AFTER-COMMIT NOTIFICATIONS
+    // ================================================================
+
+    /// Order creation email - only if payment info was submitted before the event
+    /// (race condition) and its hash matches the on-chain accountLinesHash
+    async fn notify_order_created(&self, order_id: &str, seller: &str, account_lines_hash: &str) {
         let order_repo = PostgresOrderRepository::new(self.db_pool.clone());
-        let delta = event.total_returned.to_string();
+        let Ok(synced_order) = order_repo.get(order_id).await else {
+            return;
+        };
+        if synced_order.alipay_id.is_empty() || synced_order.alipay_name.is_empty() {
+            return;
+        }
+
+        tracing::info!("📬 Payment info already present for order {}, verifying hash...", order_id);
         
-        match order_repo.adjust_remaining_amount(&order_id, &delta).await {
-            Ok(_) => {
-                tracing::info!("✅ Order {} remaining amount adjusted", order_id);
-            }
-            Err(e) => {
-                tracing::error!("❌ Database update failed: {}", e);
-                return Err(EventListenerError::DatabaseError(e.to_string()));
-            }
+        // SECURITY: Verify the computed hash matches what's stored on-chain
+        let computed_hash = crate::crypto::compute_account_lines_hash(
+            &synced_order.alipay_name,
+            &synced_order.alipay_id,
+        );
+        let computed_hash_hex = format!("0x{}", hex::encode(computed_hash));
+        
+        if computed_hash_hex != account_lines_hash {
+            tracing::error!(
+                "🚨 HASH MISMATCH for order {}!\n  \
+                On-chain hash: {}\n  \
+                Computed hash: {}\n  \
+                account_name: {}\n  \
+                account_id: {}",
+                order_id,
+                account_lines_hash,
+                computed_hash_hex,
+                synced_order.alipay_name,
+                synced_order.alipay_id
+            );
+            // Don't send email - something is wrong!
+            return;
         }
+        
+        tracing::info!("✅ Hash verified for order {}: {}", order_id, computed_hash_hex);
+        
+        self.send_email_notification(
+            EmailEvent::OrderCreated,
+            seller,
+            EmailInfo::OrderCreated {
+                order_id: order_id.to_string(),
+                token_amount: format_token_amount(&synced_order.total_amount, get_token_decimals(&synced_order.token), ""),
+                token_symbol: get_token_symbol(&synced_order.token),
+                exchange_rate: synced_order.exchange_rate.clone(),
+                account_id: synced_order.alipay_id.clone(),
+                account_name: synced_order.alipay_name.clone(),
+                rail: synced_order.rail,  // Pass rail number, template will localize
+                is_private: !synced_order.is_public,
+                private_code: synced_order.private_code.clone(),
+            },
+        ).await;
+    }
 
-        // NOTE: TradeExpired emails removed - users see expired trades in their activity timeline instead
+    async fn notify_order_withdrawn(&self, order_id: &str, withdrawn_amount: U256, remaining_amount: U256) {
+        let order_repo = PostgresOrderRepository::new(self.db_pool.clone());
+        if let Ok(order) = order_repo.get(order_id).await {
+            let token_symbol = get_token_symbol(&order.token);
+            let decimals = get_token_decimals(&order.token);
+            let formatted_withdrawn = format_token_amount(&withdrawn_amount.to_string(), decimals, "");
+            let formatted_remaining = format_token_amount(&remaining_amount.to_string(), decimals, "");
+            
+            self.send_email_notification(
+                EmailEvent::OrderWithdrawn,
+                &order.seller,
+                EmailInfo::OrderWithdrawn {
+                    order_id: order_id.to_string(),
+                    withdrawn_amount: formatted_withdrawn,
+                    remaining_amount: formatted_remaining,
+                    token_symbol,
+                },
+            ).await;
+        }
+    }
 
-        Ok(())
+    async fn notify_exchange_rate_updated(&self, order_id: &str, old_rate: U256, new_rate: U256) {
+        let order_repo = PostgresOrderRepository::new(self.db_pool.clone());
+        if let Ok(order) = order_repo.get(order_id).await {
+            // Format exchange rates (divide by 100 since stored in cents)
+            let old_rate = (old_rate.as_u64() as f64) / 100.0;
+            let new_rate = (new_rate.as_u64() as f64) / 100.0;
+            
+            self.send_email_notification(
+                EmailEvent::OrderUpdated,
+                &order.seller,
+                EmailInfo::ExchangeRateUpdated {
+                    order_id: order_id.to_string(),
+                    old_rate: format!("{:.2}", old_rate),
+                    new_rate: format!("{:.2}", new_rate),
+                },
+            ).await;
+        }
+    }
+
+    /// Settlement emails to both seller AND buyer
+    async fn notify_trade_settled(&self, trade_id: &str, tx_hash: &str) {
+        let trade_repo = PostgresTradeRepository::new(self.db_pool.clone());
+        let Ok(trade) = trade_repo.get(trade_id).await else {
+            return;
+        };
+        let order_repo = PostgresOrderRepository::new(self.db_pool.clone());
+        let Ok(order) = order_repo.get(&trade.order_id).await else {
+            return;
+        };
+
+        let token_symbol = get_token_symbol(&order.token);
+        let decimals = get_token_decimals(&order.token);
+        let formatted_token_amount = format_token_amount(&trade.token_amount, decimals, "");
+        
+        // Use fee from database (stored from TradeCreated event) - blockchain is source of truth
+        let formatted_fee = match &trade.fee_amount {
+            Some(fee) => format_token_amount(fee, decimals, ""),
+            None => {
+                // Fallback: calculate from current blockchain config if fee wasn't stored
+                tracing::warn!("Trade {} missing fee_amount, falling back to 1%", trade_id);
+                let token_amount_u256 = trade.token_amount.parse::<u128>().unwrap_or(0);
+                format_token_amount(&(token_amount_u256 / 100).to_string(), decimals, "")
+            }
+        };
+        
+        // Email to SELLER: Trade settled, payment received
+        self.send_email_notification(
+            EmailEvent::TradeSettledSeller,
+            &order.seller,
+            EmailInfo::TradeSettledSeller {
+                order_id: trade.order_id.clone(),
+                trade_id: trade_id.to_string(),
+                token_amount: formatted_token_amount.clone(),
+                token_symbol: token_symbol.clone(),
+                cny_amount: trade.cny_amount.clone(),
+                fee_amount: formatted_fee,
+                buyer_address: trade.buyer.clone(),
+                settlement_tx: tx_hash.to_string(),
+            },
+        ).await;
+        
+        // Email to BUYER: Your purchase is complete
+        self.send_email_notification(
+            EmailEvent::TradeSettledBuyer,
+            &trade.buyer,
+            EmailInfo::TradeSettledBuyer {
+                order_id: trade.order_id.clone(),
+                trade_id: trade_id.to_string(),
+                token_amount: formatted_token_amount,
+                token_symbol,
+                settlement_tx: tx_hash.to_string(),
+            },
+        ).await;
     }
 
     // ================================================================
@@ -833,6 +1375,18 @@ impl EventListener {
     // DATABASE HELPERS
     // ================================================================
 
+    fn contract_key(&self) -> String {
+        format!("{:#x}", self.contract_address).to_lowercase()
+    }
+
+    async fn block_header(&self, number: u64) -> Result<Block<H256>, EventListenerError> {
+        self.provider
+            .get_block(number)
+            .await
+            .map_err(|e| EventListenerError::ProviderError(e.to_string()))?
+            .ok_or_else(|| EventListenerError::ProviderError(format!("Block {} not found", number)))
+    }
+
     async fn get_last_synced_block(
         pool: &sqlx::PgPool,
         contract_address: &Address,
@@ -848,27 +1402,181 @@ impl EventListener {
 
         Ok(row.0 as u64)
     }
+}
 
-    async fn save_last_synced_block(
-        pool: &sqlx::PgPool,
-        contract_address: &Address,
-        block: u64,
-    ) -> Result<(), EventListenerError> {
-        let addr = format!("{:#x}", contract_address).to_lowercase();
-        sqlx::query(
-            "INSERT INTO event_sync_state (contract_address, last_synced_block) 
-             VALUES ($1, $2) 
-             ON CONFLICT (contract_address) 
-             DO UPDATE SET last_synced_block = $2",
-        )
-        .bind(&addr)
-        .bind(block as i64)
-        .execute(pool)
-        .await
-        .map_err(|e| EventListenerError::DatabaseError(e.to_string()))?;
+// ================================================================
+// LOG HELPERS
+// ================================================================
 
-        Ok(())
+/// Follow-up of an applied log, run once its transaction committed
+/// (emails and trade bus updates must not announce a rolled-back change)
+enum AfterCommit {
+    Nothing,
+    OrderCreated { order_id: String, seller: String, account_lines_hash: String },
+    OrderWithdrawn { order_id: String, withdrawn_amount: U256, remaining_amount: U256 },
+    ExchangeRateUpdated { order_id: String, old_rate: U256, new_rate: U256 },
+    TradeStatus { trade_id: String, status: i32 },
+    TradeSettled { trade_id: String, tx_hash: String },
+}
+
+/// Result of applying one log
+enum LogOutcome {
+    Applied(AfterCommit),
+    /// Already processed
+    Skipped,
+    /// Handler failed, dead-lettered
+    Failed,
+}
+
+/// A synced log in its stored form (processed_logs, applied_events, failed_logs)
+struct SyncedLog {
+    block_number: u64,
+    block_hash: String,
+    tx_hash: String,
+    log_index: u64,
+    event: String,
+    topics: Vec<String>,
+    data: String,
+}
+
+impl SyncedLog {
+    fn from_log(event: &str, log: &Log, fallback_block: u64) -> Self {
+        Self {
+            block_number: log.block_number.map(|n| n.as_u64()).unwrap_or(fallback_block),
+            block_hash: log.block_hash.map(|h| format!("{:#x}", h)).unwrap_or_default(),
+            tx_hash: log.transaction_hash.map(|h| format!("{:#x}", h)).unwrap_or_default(),
+            log_index: log.log_index.map(|i| i.as_u64()).unwrap_or_default(),
+            event: event.to_string(),
+            topics: log.topics.iter().map(|t| format!("{:#x}", t)).collect(),
+            data: format!("0x{}", hex::encode(&log.data)),
+        }
+    }
+
+    fn from_failed(failed: &DbFailedLog) -> Self {
+        Self {
+            block_number: failed.block_number as u64,
+            block_hash: failed.block_hash.clone(),
+            tx_hash: failed.tx_hash.clone(),
+            log_index: failed.log_index as u64,
+            event: failed.event.clone(),
+            topics: failed.topics.clone(),
+            data: failed.data.clone(),
+        }
     }
+
+    fn as_batch_log(&self) -> BatchLog<'_> {
+        BatchLog {
+            block_number: self.block_number,
+            block_hash: &self.block_hash,
+            tx_hash: &self.tx_hash,
+            log_index: self.log_index,
+            event: &self.event,
+            entity_id: self.topics.get(1).map(String::as_str), // Order or trade ID
+            topics: &self.topics,
+            data: &self.data,
+        }
+    }
+
+    /// Rebuild the ethers log for a handler (dead-letter retries)
+    fn to_log(&self, address: Address) -> Result<Log, EventListenerError> {
+        let raw = raw_log(&self.topics, &self.data)?;
+        let parse_hash = |hash: &str| hash.parse::<H256>().ok();
+
+        Ok(Log {
+            address,
+            topics: raw.topics,
+            data: raw.data.into(),
+            block_hash: parse_hash(&self.block_hash),
+            block_number: Some(self.block_number.into()),
+            transaction_hash: parse_hash(&self.tx_hash),
+            log_index: Some(self.log_index.into()),
+            ..Default::default()
+        })
+    }
+}
+
+/// Next retry of a dead-lettered log after `attempts` failures (None = give up)
+fn next_retry_at(attempts: i32) -> Option<chrono::DateTime<chrono::Utc>> {
+    failed_log_backoff_secs(attempts).map(|secs| chrono::Utc::now() + chrono::Duration::seconds(secs))
+}
+
+/// Delay before retrying a log that failed `attempts` times (None = give up)
+fn failed_log_backoff_secs(attempts: i32) -> Option<i64> {
+    if attempts >= FAILED_LOG_MAX_ATTEMPTS {
+        return None;
+    }
+
+    Some((60i64 << (attempts - 1).clamp(0, 16)).min(FAILED_LOG_MAX_BACKOFF_SECS))
+}
+
+/// Newest stored block whose hash is still canonical (`stored` is newest first)
+///
+/// A reorg deeper than every stored block rolls back below the oldest one.
+async fn common_ancestor<F, Fut>(
+    stored: &[(u64, String)],
+    mismatched_block: u64,
+    mut canonical_hash: F,
+) -> Result<u64, EventListenerError>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: std::future::Future<Output = Result<String, EventListenerError>>,
+{
+    for (number, stored_hash) in stored {
+        if canonical_hash(*number).await? == *stored_hash {
+            return Ok(*number);
+        }
+    }
+
+    // Deeper than every tracked block - everything tracked is undone
+    let oldest = stored.last().map(|(number, _)| *number).unwrap_or(mismatched_block);
+    tracing::error!(
+        "🚨 Reorg deeper than the {} tracked blocks - rolling back to block {}",
+        stored.len(),
+        oldest.saturating_sub(1)
+    );
+    Ok(oldest.saturating_sub(1))
+}
+
+/// Name of a handled escrow event (None = not mirrored in the database)
+fn event_name(topic0: H256) -> Option<&'static str> {
+    if topic0 == OrderCreatedFilter::signature() {
+        Some("OrderCreated")
+    } else if topic0 == OrderWithdrawnFilter::signature() {
+        Some("OrderWithdrawn")
+    } else if topic0 == TradeCreatedFilter::signature() {
+        Some("TradeCreated")
+    } else if topic0 == TradeMemoRequiredFilter::signature() {
+        Some("TradeMemoRequired")
+    } else if topic0 == TradeSettledFilter::signature() {
+        Some("TradeSettled")
+    } else if topic0 == TradeExpiredFilter::signature() {
+        Some("TradeExpired")
+    } else if topic0 == ExchangeRateUpdatedFilter::signature() {
+        Some("ExchangeRateUpdated")
+    } else if topic0 == AccountLinesHashUpdatedFilter::signature() {
+        Some("AccountLinesHashUpdated")
+    } else if topic0 == OrderMemoRequirementUpdatedFilter::signature() {
+        Some("OrderMemoRequirementUpdated")
+    } else {
+        None
+    }
+}
+
+/// Rebuild a raw log from its stored topics and data (0x hex)
+fn raw_log(topics: &[String], data: &str) -> Result<RawLog, EventListenerError> {
+    let topics = topics.iter()
+        .map(|topic| topic.parse::<H256>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| EventListenerError::EventDecodeError(format!("Stored topic: {}", e)))?;
+    let data = hex::decode(data.trim_start_matches("0x"))
+        .map_err(|e| EventListenerError::EventDecodeError(format!("Stored data: {}", e)))?;
+
+    Ok(RawLog { topics, data })
+}
+
+fn decode_raw_log<T: EthLogDecode>(raw: &RawLog) -> Result<T, EventListenerError> {
+    <T as EthLogDecode>::decode_log(raw)
+        .map_err(|e| EventListenerError::EventDecodeError(e.to_string()))
 }
 
 // ================================================================
@@ -896,3 +1604,393 @@ fn get_token_decimals(token_address: &str) -> u8 {
         _ => 18,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::Token;
+    use crate::db::test_db::{self, random_contract, random_id};
+    use crate::db::Database;
+
+    fn stored(blocks: &[(u64, &str)]) -> Vec<(u64, String)> {
+        blocks.iter().map(|(number, hash)| (*number, hash.to_string())).collect()
+    }
+
+    #[tokio::test]
+    async fn test_ancestor_is_newest_canonical_block() {
+        let stored = stored(&[(105, "0xb105"), (104, "0xb104"), (103, "0xa103"), (102, "0xa102")]);
+        let mut queried = Vec::new();
+
+        let ancestor = common_ancestor(&stored, 105, |number| {
+            queried.push(number);
+            let fork = if number <= 103 { "a" } else { "c" };
+            async move { Ok(format!("0x{}{}", fork, number)) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(ancestor, 103);
+        // Stops at the first match, newest first
+        assert_eq!(queried, vec![105, 104, 103]);
+    }
+
+    #[tokio::test]
+    async fn test_ancestor_below_every_stored_block() {
+        let stored = stored(&[(105, "0xb105"), (104, "0xb104")]);
+        let ancestor = common_ancestor(&stored, 105, |number| async move { Ok(format!("0xc{}", number)) })
+            .await
+            .unwrap();
+        assert_eq!(ancestor, 103);
+
+        // Nothing stored: only the mismatched block itself is undone
+        let ancestor = common_ancestor(&[], 105, |_| async { Ok(String::new()) }).await.unwrap();
+        assert_eq!(ancestor, 104);
+    }
+
+    #[tokio::test]
+    async fn test_ancestor_search_surfaces_provider_errors() {
+        let stored = stored(&[(105, "0xb105"), (104, "0xb104")]);
+        let result = common_ancestor(&stored, 105, |_| async {
+            Err(EventListenerError::ProviderError("Block 105 not found".to_string()))
+        })
+        .await;
+        assert!(matches!(result, Err(EventListenerError::ProviderError(_))));
+    }
+
+    #[test]
+    fn test_failed_log_backoff() {
+        let backoffs: Vec<_> = (1..=FAILED_LOG_MAX_ATTEMPTS).map(failed_log_backoff_secs).collect();
+        assert_eq!(backoffs, vec![
+            Some(60), Some(120), Some(240), Some(480), Some(960), Some(1920),
+            Some(3600), Some(3600), Some(3600), None,
+        ]);
+        assert_eq!(failed_log_backoff_secs(0), Some(60));
+        assert_eq!(failed_log_backoff_secs(FAILED_LOG_MAX_ATTEMPTS + 5), None);
+
+        let retry_at = next_retry_at(1).unwrap() - chrono::Utc::now();
+        assert!((59..=60).contains(&retry_at.num_seconds()), "{}", retry_at);
+        assert!(next_retry_at(FAILED_LOG_MAX_ATTEMPTS).is_none());
+    }
+
+    #[test]
+    fn test_synced_log_round_trip() {
+        let (order, trade) = (random_id(), random_id());
+        let log = Log { address: Address::from_low_u64_be(0xe5), ..trade_created(&trade, &order, 42, 7) };
+
+        let synced = SyncedLog::from_log("TradeCreated", &log, 99);
+        assert_eq!(synced.block_number, 42);
+        assert_eq!(synced.log_index, 7);
+        assert_eq!(synced.as_batch_log().entity_id, Some(trade.as_str()));
+
+        let rebuilt = synced.to_log(log.address).unwrap();
+        assert_eq!(rebuilt, log);
+        let decoded: TradeCreatedFilter = ethers::contract::parse_log(rebuilt).unwrap();
+        assert_eq!(format!("0x{}", hex::encode(decoded.order_id)), order);
+
+        // As stored in failed_logs
+        let failed = DbFailedLog {
+            id: 1,
+            block_number: synced.block_number as i64,
+            block_hash: synced.block_hash.clone(),
+            tx_hash: synced.tx_hash.clone(),
+            log_index: synced.log_index as i64,
+            event: synced.event.clone(),
+            topics: synced.topics.clone(),
+            data: synced.data.clone(),
+            error: "Order not found".to_string(),
+            attempts: 1,
+        };
+        assert_eq!(SyncedLog::from_failed(&failed).to_log(log.address).unwrap(), log);
+
+        // Pending logs carry no block number - the synced range's end stands in
+        let pending = Log { block_number: None, ..log };
+        assert_eq!(SyncedLog::from_log("TradeCreated", &pending, 99).block_number, 99);
+    }
+
+    // ============ Undo round-trips (TEST_DATABASE_URL) ============
+
+    fn bytes32(id: &str) -> Token {
+        Token::FixedBytes(hex::decode(id.trim_start_matches("0x")).unwrap())
+    }
+
+    fn block_hash(number: u64) -> H256 {
+        H256::from_low_u64_be(number)
+    }
+
+    /// Encode an escrow event the way the node returns it
+    fn escrow_log(name: &str, values: Vec<Token>, block: u64, log_index: u64) -> Log {
+        let event = super::super::LYNCZESCROW_ABI.event(name).unwrap();
+        let mut topics = vec![event.signature()];
+        let mut data = Vec::new();
+        for (input, value) in event.inputs.iter().zip(values) {
+            if input.indexed {
+                topics.push(H256::from_slice(&ethers::abi::encode(&[value])));
+            } else {
+                data.push(value);
+            }
+        }
+
+        Log {
+            topics,
+            data: ethers::abi::encode(&data).into(),
+            block_number: Some(block.into()),
+            block_hash: Some(block_hash(block)),
+            transaction_hash: random_id().parse().ok(),
+            log_index: Some(log_index.into()),
+            ..Default::default()
+        }
+    }
+
+    fn order_created(order_id: &str, total: u64, block: u64) -> Log {
+        escrow_log("OrderCreated", vec![
+            bytes32(order_id),
+            Token::Address(Address::from_low_u64_be(0xa1)),
+            Token::Address(Address::from_low_u64_be(0xb2)),
+            Token::Uint(total.into()),
+            Token::Uint(720.into()),
+            Token::Uint(0.into()),
+            bytes32(&random_id()),
+            Token::Bool(true),
+        ], block, 0)
+    }
+
+    fn trade_created(trade_id: &str, order_id: &str, block: u64, log_index: u64) -> Log {
+        escrow_log("TradeCreated", vec![
+            bytes32(trade_id),
+            bytes32(order_id),
+            Token::Address(Address::from_low_u64_be(0xc3)),
+            Token::Address(Address::from_low_u64_be(0xb2)),
+            Token::Uint(1_000.into()),
+            Token::Uint(10.into()),
+            Token::Uint(10_050.into()),
+            Token::Uint((chrono::Utc::now().timestamp() + 900).into()),
+        ], block, log_index)
+    }
+
+    async fn listener(db: &Database) -> EventListener {
+        let contract = random_contract().parse().unwrap();
+        EventListener::new("http://127.0.0.1:1", contract, db.pool().clone(), Some(1)).await.unwrap()
+    }
+
+    /// Sync one block's logs the way `sync_to` does
+    async fn sync_logs(listener: &EventListener, block: u64, logs: Vec<Log>) -> ReplayReport {
+        let mut batch = EventBatch::begin(&listener.db_pool, &listener.contract_key()).await.unwrap();
+        let mut report = ReplayReport::default();
+        for log in logs {
+            let event = event_name(log.topics[0]).unwrap();
+            let synced = SyncedLog::from_log(event, &log, block);
+            report.logs += 1;
+            match listener.apply_log(&mut batch, &synced, log).await.unwrap() {
+                LogOutcome::Applied(_) => report.applied += 1,
+                LogOutcome::Skipped => report.skipped += 1,
+                LogOutcome::Failed => report.failed += 1,
+            }
+        }
+        batch.record_block(block, &format!("{:#x}", block_hash(block))).await.unwrap();
+        batch.commit(Some(block + 1)).await.unwrap();
+        report
+    }
+
+    async fn sync_block(listener: &EventListener, block: u64, logs: Vec<Log>) {
+        let report = sync_logs(listener, block, logs).await;
+        assert_eq!(report.applied, report.logs, "{:?}", report);
+    }
+
+    async fn roll_back_to(listener: &EventListener, ancestor: u64) -> Vec<String> {
+        let journal = PostgresEventJournalRepository::new(listener.db_pool.clone());
+        let (_, reopened) = listener.undo_above(&journal, ancestor).await.unwrap();
+        reopened
+    }
+
+    async fn snapshot(db: &Database, entity_id: &str) -> Option<EntitySnapshot> {
+        let mut batch = EventBatch::begin(db.pool(), &random_contract()).await.unwrap();
+        batch.snapshot(entity_id).await.unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_undo_order_updates() {
+        let db = test_db::connect().await;
+        let listener = listener(&db).await;
+        let order = random_id();
+        sync_block(&listener, 10, vec![order_created(&order, 100_000, 10)]).await;
+        let before = snapshot(&db, &order).await;
+
+        sync_block(&listener, 11, vec![
+            escrow_log("OrderWithdrawn", vec![bytes32(&order), Token::Uint(30_000.into()), Token::Uint(70_000.into())], 11, 0),
+            escrow_log("ExchangeRateUpdated", vec![bytes32(&order), Token::Uint(720.into()), Token::Uint(750.into())], 11, 1),
+            escrow_log("OrderMemoRequirementUpdated", vec![bytes32(&order), Token::Bool(false), Token::Bool(true)], 11, 2),
+        ]).await;
+        assert_ne!(snapshot(&db, &order).await, before);
+
+        assert!(roll_back_to(&listener, 10).await.is_empty());
+        // Remaining amount, withdrawal, rate and memo requirement all restored
+        assert_eq!(snapshot(&db, &order).await, before);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_undo_trade_created() {
+        let db = test_db::connect().await;
+        let listener = listener(&db).await;
+        let (order, trade) = (random_id(), random_id());
+        sync_block(&listener, 10, vec![order_created(&order, 100_000, 10)]).await;
+        let before = snapshot(&db, &order).await;
+
+        sync_block(&listener, 11, vec![
+            trade_created(&trade, &order, 11, 0),
+            escrow_log("TradeMemoRequired", vec![bytes32(&trade)], 11, 1),
+        ]).await;
+        assert_eq!(db.get_order(&order).await.unwrap().remaining_amount, "98990");
+
+        assert!(roll_back_to(&listener, 10).await.is_empty());
+        assert_eq!(snapshot(&db, &order).await, before);
+        // The trade row is kept until it is replayed or found orphaned
+        assert_eq!(db.get_trade(&trade).await.unwrap().status, 0);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_undo_trade_settled() {
+        let db = test_db::connect().await;
+        let listener = listener(&db).await;
+        let (order, trade) = (random_id(), random_id());
+        sync_block(&listener, 10, vec![order_created(&order, 100_000, 10), trade_created(&trade, &order, 10, 1)]).await;
+
+        sync_block(&listener, 11, vec![escrow_log("TradeSettled", vec![bytes32(&trade), bytes32(&random_id())], 11, 0)]).await;
+        assert_eq!(db.get_trade(&trade).await.unwrap().status, 1);
+
+        assert_eq!(roll_back_to(&listener, 10).await, vec![trade.clone()]);
+        let reopened = db.get_trade(&trade).await.unwrap();
+        assert_eq!(reopened.status, 0);
+        assert_eq!(reopened.settlement_tx_hash, None);
+        let (_, fields) = snapshot(&db, &trade).await.unwrap();
+        assert!(fields.contains(&SettlementState::failed("REORGED").to_string()), "{}", fields);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_undo_trade_expired_restores_receipt() {
+        let db = test_db::connect().await;
+        let listener = listener(&db).await;
+        let (order, trade, other) = (random_id(), random_id(), random_id());
+        sync_block(&listener, 10, vec![
+            order_created(&order, 100_000, 10),
+            trade_created(&trade, &order, 10, 1),
+            trade_created(&other, &order, 10, 2),
+        ]).await;
+        let receipt = random_id();
+        assert_eq!(db.reserve_receipt(&receipt, &trade).await.unwrap(), None);
+        db.update_trade_payment_info(&trade, &receipt, "2026-10-18 12:00:00").await.unwrap();
+        let before = snapshot(&db, &order).await;
+
+        sync_block(&listener, 11, vec![
+            escrow_log("TradeExpired", vec![bytes32(&trade), bytes32(&order), Token::Uint(1_010.into())], 11, 0),
+        ]).await;
+        assert_eq!(db.get_trade(&trade).await.unwrap().status, 2);
+
+        assert_eq!(roll_back_to(&listener, 10).await, vec![trade.clone()]);
+        assert_eq!(db.get_trade(&trade).await.unwrap().status, 0);
+        assert_eq!(snapshot(&db, &order).await, before);
+        // The reopened trade holds its receipt again
+        assert_eq!(db.reserve_receipt(&receipt, &other).await.unwrap(), Some(trade));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_undo_expiry_keeps_receipt_taken_over() {
+        let db = test_db::connect().await;
+        let listener = listener(&db).await;
+        let (order, trade, other) = (random_id(), random_id(), random_id());
+        sync_block(&listener, 10, vec![
+            order_created(&order, 100_000, 10),
+            trade_created(&trade, &order, 10, 1),
+            trade_created(&other, &order, 10, 2),
+        ]).await;
+        let receipt = random_id();
+        assert_eq!(db.reserve_receipt(&receipt, &trade).await.unwrap(), None);
+        db.update_trade_payment_info(&trade, &receipt, "2026-10-18 12:00:00").await.unwrap();
+
+        sync_block(&listener, 11, vec![
+            escrow_log("TradeExpired", vec![bytes32(&trade), bytes32(&order), Token::Uint(1_010.into())], 11, 0),
+        ]).await;
+        // Another trade validated the freed receipt before the reorg
+        assert_eq!(db.reserve_receipt(&receipt, &other).await.unwrap(), None);
+
+        assert_eq!(roll_back_to(&listener, 10).await, vec![trade.clone()]);
+        assert_eq!(db.reserve_receipt(&receipt, &trade).await.unwrap(), Some(other));
+    }
+
+    // ============ Dedupe and dead letters (TEST_DATABASE_URL) ============
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_claimed_logs_are_skipped_on_replay() {
+        let db = test_db::connect().await;
+        let listener = listener(&db).await;
+        let order = random_id();
+        let logs = vec![
+            order_created(&order, 100_000, 20),
+            escrow_log("OrderWithdrawn", vec![bytes32(&order), Token::Uint(30_000.into()), Token::Uint(70_000.into())], 20, 1),
+        ];
+        sync_block(&listener, 20, logs.clone()).await;
+
+        // The same logs again (overlapping range, lyncz-reindex)
+        let report = sync_logs(&listener, 20, logs).await;
+        assert_eq!(report, ReplayReport { logs: 2, applied: 0, skipped: 2, failed: 0 });
+        assert_eq!(db.get_order(&order).await.unwrap().remaining_amount, "70000");
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_failing_log_is_dead_lettered_and_retried() {
+        let db = test_db::connect().await;
+        let listener = listener(&db).await;
+        let contract = listener.contract_key();
+        let (order, missing) = (random_id(), random_id());
+
+        let report = sync_logs(&listener, 20, vec![
+            order_created(&order, 100_000, 20),
+            // Order not mirrored (yet) - the handler fails
+            escrow_log("OrderWithdrawn", vec![bytes32(&missing), Token::Uint(30_000.into()), Token::Uint(70_000.into())], 20, 1),
+            escrow_log("ExchangeRateUpdated", vec![bytes32(&order), Token::Uint(720.into()), Token::Uint(750.into())], 20, 2),
+        ]).await;
+        assert_eq!(report, ReplayReport { logs: 3, applied: 2, skipped: 0, failed: 1 });
+
+        // The rest of the range and the checkpoint committed
+        assert_eq!(db.get_order(&order).await.unwrap().exchange_rate, "750");
+        assert_eq!(EventListener::get_last_synced_block(db.pool(), &listener.contract_address).await.unwrap(), 21);
+
+        let failed_logs = PostgresFailedLogRepository::new(db.pool().clone());
+        assert!(failed_logs.due(&contract, 10).await.unwrap().is_empty(), "retried before its backoff");
+        sqlx::query(r#"UPDATE failed_logs SET "nextRetryAt" = NOW() WHERE "contractAddress" = $1"#)
+            .bind(&contract)
+            .execute(db.pool())
+            .await
+            .unwrap();
+        let due = failed_logs.due(&contract, 10).await.unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!((due[0].event.as_str(), due[0].log_index, due[0].attempts), ("OrderWithdrawn", 1, 1));
+
+        // Still failing: rescheduled with the next backoff
+        listener.retry_failed_logs().await.unwrap();
+        assert!(failed_logs.due(&contract, 10).await.unwrap().is_empty());
+        sqlx::query(r#"UPDATE failed_logs SET "nextRetryAt" = NOW() WHERE "contractAddress" = $1"#)
+            .bind(&contract)
+            .execute(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(failed_logs.due(&contract, 10).await.unwrap()[0].attempts, 2);
+
+        // Applied once the order is there
+        sync_block(&listener, 21, vec![order_created(&missing, 100_000, 21)]).await;
+        listener.retry_failed_logs().await.unwrap();
+        assert_eq!(db.get_order(&missing).await.unwrap().remaining_amount, "70000");
+        let remaining: i64 = sqlx::query_scalar(r#"SELECT COUNT(*) FROM failed_logs WHERE "contractAddress" = $1"#)
+            .bind(&contract)
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(remaining, 0);
+    }
+}
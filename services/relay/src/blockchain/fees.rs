@@ -0,0 +1,444 @@
+//! Fee strategies for relayer transactions
+//!
+//! FEE_STRATEGY selects how fees are priced:
+//! - `eip1559` (default): priority fee from `eth_feeHistory` at a percentile chosen by
+//!   the operation's urgency, max fee = next base fee x FEE_BASE_FEE_MULTIPLIER + tip
+//! - `legacy`: fixed gas price (FEE_LEGACY_GAS_PRICE_WEI, 0.03 gwei by default)
+//!
+//! Both are capped by FEE_MAX_FEE_PER_GAS_WEI, which also bounds stuck-transaction
+//! replacements (see tx_manager.rs).
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use ethers::prelude::*;
+use ethers::providers::{Http, Provider};
+use ethers::types::transaction::eip2718::TypedTransaction;
+
+use super::client::EthereumClientError;
+
+/// Settlements this close to the trade's expiry are priced as urgent
+const URGENT_SETTLEMENT_WINDOW_SECS: i64 = 600;
+
+/// Least fee increase (percent) nodes accept for a same-nonce replacement -
+/// anything less is rejected as "replacement transaction underpriced"
+pub const MIN_REPLACEMENT_BUMP: u64 = 10;
+
+/// How quickly a transaction needs to be mined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeUrgency {
+    /// Nobody is waiting (expired-trade cancellation)
+    Low,
+    Normal,
+    /// Settlement close to the trade's expiry
+    High,
+}
+
+/// Fees of one transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxFees {
+    Legacy {
+        gas_price: U256,
+    },
+    Eip1559 {
+        max_fee_per_gas: U256,
+        max_priority_fee_per_gas: U256,
+    },
+}
+
+/// Prices relayer transactions
+#[async_trait]
+pub trait FeeStrategy: Send + Sync {
+    /// Fees for a new transaction
+    async fn fees(&self, urgency: FeeUrgency) -> Result<TxFees, EthereumClientError>;
+
+    /// Highest fee per gas any transaction (or replacement) may pay
+    fn max_fee_per_gas(&self) -> U256;
+}
+
+#[derive(Debug, Clone)]
+pub struct FeeConfig {
+    /// Blocks of fee history sampled
+    pub history_blocks: u64,
+    /// Priority fee percentile for low / normal / high urgency
+    pub percentiles: [f64; 3],
+    /// Headroom over the next block's base fee
+    pub base_fee_multiplier: u64,
+    pub min_priority_fee: U256,
+    pub max_priority_fee: U256,
+    pub max_fee_per_gas: U256,
+    /// Gas price for the legacy strategy (and when fee history is unavailable)
+    pub legacy_gas_price: U256,
+}
+
+/// Fixed legacy gas price
+pub struct LegacyFeeStrategy {
+    config: FeeConfig,
+}
+
+/// EIP-1559 fees from `eth_feeHistory`
+pub struct Eip1559FeeStrategy {
+    provider: Arc<Provider<Http>>,
+    config: FeeConfig,
+}
+
+impl FeeUrgency {
+    /// Urgency of settling a trade that expires at `expires_at` (unix seconds)
+    pub fn for_deadline(expires_at: i64, now: i64) -> Self {
+        if expires_at - now <= URGENT_SETTLEMENT_WINDOW_SECS {
+            Self::High
+        } else {
+            Self::Normal
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::High => "high",
+        }
+    }
+}
+
+impl TxFees {
+    /// Most this transaction can pay per gas
+    pub fn max_fee_per_gas(&self) -> U256 {
+        match self {
+            Self::Legacy { gas_price } => *gas_price,
+            Self::Eip1559 { max_fee_per_gas, .. } => *max_fee_per_gas,
+        }
+    }
+
+    pub fn max_priority_fee_per_gas(&self) -> Option<U256> {
+        match self {
+            Self::Legacy { .. } => None,
+            Self::Eip1559 { max_priority_fee_per_gas, .. } => Some(*max_priority_fee_per_gas),
+        }
+    }
+
+    /// Fees a transaction was signed with (None for other transaction types)
+    pub fn of(tx: &TypedTransaction) -> Option<Self> {
+        match tx {
+            TypedTransaction::Legacy(tx) => Some(Self::Legacy { gas_price: tx.gas_price? }),
+            TypedTransaction::Eip1559(tx) => Some(Self::Eip1559 {
+                max_fee_per_gas: tx.max_fee_per_gas?,
+                max_priority_fee_per_gas: tx.max_priority_fee_per_gas?,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Replacement fees: every fee raised by `percent` (at least 1 wei) and capped,
+    /// None once the cap leaves either fee less than MIN_REPLACEMENT_BUMP above its current value
+    pub fn bumped(&self, percent: u64, cap: U256) -> Option<Self> {
+        match self {
+            Self::Legacy { gas_price } => Some(Self::Legacy { gas_price: bump(*gas_price, percent, cap)? }),
+            Self::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas } => {
+                let max_fee_per_gas = bump(*max_fee_per_gas, percent, cap)?;
+                let max_priority_fee_per_gas = bump(*max_priority_fee_per_gas, percent, max_fee_per_gas)?;
+                Some(Self::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas })
+            }
+        }
+    }
+
+    /// The same call priced with these fees (legacy calls become EIP-1559 and back)
+    pub fn apply(&self, tx: &TypedTransaction) -> TypedTransaction {
+        let mut priced: TypedTransaction = match *self {
+            Self::Legacy { gas_price } => TransactionRequest::new().gas_price(gas_price).into(),
+            Self::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas } => Eip1559TransactionRequest::new()
+                .max_fee_per_gas(max_fee_per_gas)
+                .max_priority_fee_per_gas(max_priority_fee_per_gas)
+                .into(),
+        };
+
+        if let Some(from) = tx.from() {
+            priced.set_from(*from);
+        }
+        if let Some(to) = tx.to() {
+            priced.set_to(to.clone());
+        }
+        if let Some(data) = tx.data() {
+            priced.set_data(data.clone());
+        }
+        if let Some(gas) = tx.gas() {
+            priced.set_gas(*gas);
+        }
+        if let Some(value) = tx.value() {
+            priced.set_value(*value);
+        }
+        if let Some(nonce) = tx.nonce() {
+            priced.set_nonce(*nonce);
+        }
+        if let Some(chain_id) = tx.chain_id() {
+            priced.set_chain_id(chain_id);
+        }
+        priced
+    }
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        Self {
+            history_blocks: 10,
+            percentiles: [20.0, 50.0, 90.0],
+            base_fee_multiplier: 2,
+            min_priority_fee: U256::from(1_000_000u64),     // 0.001 gwei
+            max_priority_fee: U256::from(500_000_000u64),   // 0.5 gwei
+            max_fee_per_gas: U256::from(2_000_000_000u64),  // 2 gwei
+            // Base network gas can fluctuate - 0.03 gwei has been reliable
+            // (network has been seen at 0.018+ gwei during busy periods)
+            legacy_gas_price: U256::from(30_000_000u64),    // 0.03 gwei
+        }
+    }
+}
+
+impl FeeConfig {
+    /// Defaults overridden by FEE_HISTORY_BLOCKS, FEE_PERCENTILE_LOW, FEE_PERCENTILE,
+    /// FEE_PERCENTILE_HIGH, FEE_BASE_FEE_MULTIPLIER, FEE_MIN_PRIORITY_FEE_WEI,
+    /// FEE_MAX_PRIORITY_FEE_WEI, FEE_MAX_FEE_PER_GAS_WEI and FEE_LEGACY_GAS_PRICE_WEI
+    pub fn from_env() -> Self {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Same as `from_env`, reading each variable through `lookup`
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        fn parse<T: std::str::FromStr>(lookup: &dyn Fn(&str) -> Option<String>, name: &str) -> Option<T> {
+            let value = lookup(name)?;
+            let parsed = value.trim().parse().ok();
+            if parsed.is_none() {
+                tracing::warn!("⚠️ Ignoring invalid {}: {}", name, value);
+            }
+            parsed
+        }
+        let wei = |name: &str| parse::<u128>(&lookup, name).map(U256::from);
+        let percentile = |name: &str| parse::<f64>(&lookup, name).map(|value| value.clamp(0.0, 100.0));
+
+        let defaults = Self::default();
+        Self {
+            history_blocks: parse::<u64>(&lookup, "FEE_HISTORY_BLOCKS").map(|blocks| blocks.clamp(1, 1024)).unwrap_or(defaults.history_blocks),
+            percentiles: [
+                percentile("FEE_PERCENTILE_LOW").unwrap_or(defaults.percentiles[0]),
+                percentile("FEE_PERCENTILE").unwrap_or(defaults.percentiles[1]),
+                percentile("FEE_PERCENTILE_HIGH").unwrap_or(defaults.percentiles[2]),
+            ],
+            base_fee_multiplier: parse::<u64>(&lookup, "FEE_BASE_FEE_MULTIPLIER").map(|multiplier| multiplier.max(1)).unwrap_or(defaults.base_fee_multiplier),
+            min_priority_fee: wei("FEE_MIN_PRIORITY_FEE_WEI").unwrap_or(defaults.min_priority_fee),
+            max_priority_fee: wei("FEE_MAX_PRIORITY_FEE_WEI").unwrap_or(defaults.max_priority_fee),
+            max_fee_per_gas: wei("FEE_MAX_FEE_PER_GAS_WEI").unwrap_or(defaults.max_fee_per_gas),
+            legacy_gas_price: wei("FEE_LEGACY_GAS_PRICE_WEI").unwrap_or(defaults.legacy_gas_price),
+        }
+    }
+
+    fn percentile(&self, urgency: FeeUrgency) -> f64 {
+        match urgency {
+            FeeUrgency::Low => self.percentiles[0],
+            FeeUrgency::Normal => self.percentiles[1],
+            FeeUrgency::High => self.percentiles[2],
+        }
+    }
+
+    fn legacy_fees(&self) -> TxFees {
+        TxFees::Legacy { gas_price: self.legacy_gas_price.min(self.max_fee_per_gas) }
+    }
+}
+
+/// Strategy selected by FEE_STRATEGY (`eip1559` or `legacy`)
+pub fn strategy_from_env(provider: Arc<Provider<Http>>) -> Arc<dyn FeeStrategy> {
+    let config = FeeConfig::from_env();
+    match std::env::var("FEE_STRATEGY").unwrap_or_default().to_lowercase().as_str() {
+        "legacy" => Arc::new(LegacyFeeStrategy::new(config)),
+        "" | "eip1559" => Arc::new(Eip1559FeeStrategy::new(provider, config)),
+        other => {
+            tracing::warn!("⚠️ Unknown FEE_STRATEGY {}, using eip1559", other);
+            Arc::new(Eip1559FeeStrategy::new(provider, config))
+        }
+    }
+}
+
+impl LegacyFeeStrategy {
+    pub fn new(config: FeeConfig) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait]
+impl FeeStrategy for LegacyFeeStrategy {
+    async fn fees(&self, _urgency: FeeUrgency) -> Result<TxFees, EthereumClientError> {
+        Ok(self.config.legacy_fees())
+    }
+
+    fn max_fee_per_gas(&self) -> U256 {
+        self.config.max_fee_per_gas
+    }
+}
+
+impl Eip1559FeeStrategy {
+    pub fn new(provider: Arc<Provider<Http>>, config: FeeConfig) -> Self {
+        Self { provider, config }
+    }
+}
+
+#[async_trait]
+impl FeeStrategy for Eip1559FeeStrategy {
+    async fn fees(&self, urgency: FeeUrgency) -> Result<TxFees, EthereumClientError> {
+        let history = match self.provider
+            .fee_history(self.config.history_blocks, BlockNumber::Latest, &[self.config.percentile(urgency)])
+            .await
+        {
+            Ok(history) => history,
+            Err(e) => {
+                tracing::warn!("⚠️ eth_feeHistory failed, using the legacy gas price: {}", e);
+                return Ok(self.config.legacy_fees());
+            }
+        };
+
+        // The last base fee is the next block's
+        let Some(next_base_fee) = history.base_fee_per_gas.last().copied().filter(|fee| !fee.is_zero()) else {
+            tracing::warn!("⚠️ No base fee in fee history, using the legacy gas price");
+            return Ok(self.config.legacy_fees());
+        };
+        let rewards: Vec<U256> = history.reward.iter().filter_map(|block| block.first().copied()).collect();
+
+        let fees = eip1559_fees(next_base_fee, &rewards, &self.config);
+        if next_base_fee > fees.max_fee_per_gas() {
+            tracing::warn!(
+                "⚠️ Base fee {} wei is above FEE_MAX_FEE_PER_GAS_WEI ({} wei) - transactions wait until it drops",
+                next_base_fee, self.config.max_fee_per_gas
+            );
+        }
+        Ok(fees)
+    }
+
+    fn max_fee_per_gas(&self) -> U256 {
+        self.config.max_fee_per_gas
+    }
+}
+
+/// Tip = median of the sampled blocks' rewards at the urgency percentile (within the
+/// configured bounds); max fee = base fee x multiplier + tip, capped
+fn eip1559_fees(next_base_fee: U256, rewards: &[U256], config: &FeeConfig) -> TxFees {
+    let mut rewards = rewards.to_vec();
+    rewards.sort();
+    let tip = rewards.get(rewards.len() / 2).copied().unwrap_or_default()
+        .max(config.min_priority_fee)
+        .min(config.max_priority_fee);
+
+    let max_fee_per_gas = (next_base_fee * config.base_fee_multiplier + tip).min(config.max_fee_per_gas);
+    TxFees::Eip1559 {
+        max_fee_per_gas,
+        max_priority_fee_per_gas: tip.min(max_fee_per_gas),
+    }
+}
+
+/// `percent` more (at least 1 wei), capped; None if the capped value is too small a
+/// raise for nodes to accept the replacement
+fn bump(current: U256, percent: u64, cap: U256) -> Option<U256> {
+    let bumped = (current * (100 + percent) / 100).max(current + 1).min(cap);
+    let min_replacement = current * (100 + MIN_REPLACEMENT_BUMP) / 100;
+    (bumped > current && bumped >= min_replacement).then_some(bumped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gwei(value: f64) -> U256 {
+        U256::from((value * 1e9) as u64)
+    }
+
+    #[test]
+    fn test_eip1559_fees() {
+        let config = FeeConfig::default();
+        let rewards = [gwei(0.002), gwei(0.01), gwei(0.004)];
+
+        // Median tip, 2x base fee headroom
+        assert_eq!(
+            eip1559_fees(gwei(0.05), &rewards, &config),
+            TxFees::Eip1559 { max_fee_per_gas: gwei(0.104), max_priority_fee_per_gas: gwei(0.004) }
+        );
+
+        // Empty blocks still tip the minimum; congestion is capped
+        assert_eq!(eip1559_fees(gwei(0.05), &[], &config).max_priority_fee_per_gas(), Some(config.min_priority_fee));
+        assert_eq!(eip1559_fees(gwei(5.0), &rewards, &config).max_fee_per_gas(), config.max_fee_per_gas);
+    }
+
+    #[test]
+    fn test_config_from_lookup() {
+        let config = FeeConfig::from_lookup(|name| match name {
+            "FEE_PERCENTILE_LOW" => Some(" 10 ".to_string()),
+            "FEE_PERCENTILE_HIGH" => Some("150".to_string()),
+            "FEE_PERCENTILE" => Some("median".to_string()),
+            "FEE_MAX_FEE_PER_GAS_WEI" => Some("5000000000".to_string()),
+            "FEE_MAX_PRIORITY_FEE_WEI" => Some("-1".to_string()),
+            "FEE_BASE_FEE_MULTIPLIER" => Some("0".to_string()),
+            _ => None,
+        });
+        let defaults = FeeConfig::default();
+
+        // Percentiles are clamped to 0-100; invalid values keep the default
+        assert_eq!(config.percentiles, [10.0, defaults.percentiles[1], 100.0]);
+        assert_eq!(config.max_fee_per_gas, gwei(5.0));
+        assert_eq!(config.max_priority_fee, defaults.max_priority_fee);
+        assert_eq!(config.base_fee_multiplier, 1);
+        assert_eq!(config.history_blocks, defaults.history_blocks);
+    }
+
+    #[test]
+    fn test_urgency_for_deadline() {
+        assert_eq!(FeeUrgency::for_deadline(10_000, 10_000 - 3600), FeeUrgency::Normal);
+        assert_eq!(FeeUrgency::for_deadline(10_000, 10_000 - 300), FeeUrgency::High);
+        assert_eq!(FeeUrgency::for_deadline(10_000, 10_001), FeeUrgency::High);
+    }
+
+    #[test]
+    fn test_bumped_fees() {
+        let cap = U256::from(1_000u64);
+        let legacy = TxFees::Legacy { gas_price: U256::from(100u64) };
+        assert_eq!(legacy.bumped(20, cap), Some(TxFees::Legacy { gas_price: U256::from(120u64) }));
+        // Tiny prices still move; capped, then no further replacement
+        assert_eq!(TxFees::Legacy { gas_price: U256::from(1u64) }.bumped(20, cap).unwrap().max_fee_per_gas(), U256::from(2u64));
+        assert_eq!(TxFees::Legacy { gas_price: U256::from(900u64) }.bumped(20, cap).unwrap().max_fee_per_gas(), cap);
+        assert_eq!(TxFees::Legacy { gas_price: cap }.bumped(20, cap), None);
+
+        let dynamic = TxFees::Eip1559 { max_fee_per_gas: U256::from(900u64), max_priority_fee_per_gas: U256::from(850u64) };
+        assert_eq!(
+            dynamic.bumped(20, cap),
+            Some(TxFees::Eip1559 { max_fee_per_gas: cap, max_priority_fee_per_gas: cap })
+        );
+    }
+
+    #[test]
+    fn test_capped_bump_too_small_to_replace() {
+        let cap = U256::from(1_000u64);
+
+        // 950 -> 1000 is only 5% more: the node would reject the replacement
+        assert_eq!(TxFees::Legacy { gas_price: U256::from(950u64) }.bumped(20, cap), None);
+        // Exactly MIN_REPLACEMENT_BUMP is enough
+        assert_eq!(TxFees::Legacy { gas_price: U256::from(909u64) }.bumped(20, cap).unwrap().max_fee_per_gas(), cap);
+
+        // Max fee can still rise 20%, but the tip clamped to it rises under 10%
+        let tip_capped = TxFees::Eip1559 { max_fee_per_gas: U256::from(800u64), max_priority_fee_per_gas: U256::from(900u64) };
+        assert_eq!(tip_capped.bumped(20, cap), None);
+        let max_fee_capped = TxFees::Eip1559 { max_fee_per_gas: U256::from(950u64), max_priority_fee_per_gas: U256::from(10u64) };
+        assert_eq!(max_fee_capped.bumped(20, cap), None);
+    }
+
+    #[test]
+    fn test_apply_keeps_call() {
+        let call: TypedTransaction = TransactionRequest::new()
+            .to(Address::repeat_byte(0x11))
+            .data(Bytes::from(vec![0xde, 0xad]))
+            .gas(50_000u64)
+            .gas_price(1u64)
+            .into();
+        let fees = TxFees::Eip1559 { max_fee_per_gas: U256::from(10u64), max_priority_fee_per_gas: U256::from(2u64) };
+
+        let priced = fees.apply(&call);
+        assert!(matches!(priced, TypedTransaction::Eip1559(_)));
+        assert_eq!(TxFees::of(&priced), Some(fees));
+        assert_eq!(priced.to(), call.to());
+        assert_eq!(priced.data(), call.data());
+        assert_eq!(priced.gas(), call.gas());
+    }
+}
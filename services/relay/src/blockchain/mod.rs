@@ -2,7 +2,12 @@
 // LyncZ: Multi-rail escrow with ZK verification
 
 pub mod client;
+pub mod errors;
 pub mod events;
+pub mod fees;
+pub mod signer;
+pub mod subscription;
+pub mod tx_manager;
 pub mod types;
 
 use ethers::prelude::abigen;
@@ -0,0 +1,394 @@
+//! Relayer signers - where the relayer key lives
+//!
+//! - `LocalKeySigner`: key held in-process, from RELAYER_PRIVATE_KEY or an encrypted
+//!   JSON keystore (RELAYER_KEYSTORE_PATH + passphrase)
+//! - `RemoteSigner`: Web3Signer-compatible JSON-RPC signer (`eth_accounts`,
+//!   `eth_signTransaction`), so the hot key never enters the API server
+//!
+//! `stand_in_router` serves the same JSON-RPC from a local key (the `relayer-signer`
+//! binary) for tests and local development.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::{extract::State, routing::{get, post}, Json, Router};
+use ethers::prelude::*;
+use ethers::signers::LocalWallet;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::rlp::Rlp;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::client::EthereumClientError;
+use crate::config::RelayerSignerConfig;
+
+/// Remote signer requests time out after this long
+const REMOTE_SIGNER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Signs relayer transactions
+#[async_trait]
+pub trait RelayerSigner: Send + Sync {
+    fn address(&self) -> Address;
+
+    /// Signature over the transaction's EIP-155 / typed sighash
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, EthereumClientError>;
+}
+
+/// Key held in-process
+pub struct LocalKeySigner {
+    wallet: LocalWallet,
+}
+
+/// Web3Signer-compatible remote signer
+pub struct RemoteSigner {
+    http: reqwest::Client,
+    url: String,
+    address: Address,
+}
+
+/// Transaction fields of `eth_signTransaction` (as Web3Signer accepts them)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignTransactionParams {
+    from: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_price: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_fee_per_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_priority_fee_per_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Bytes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<U256>,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    message: String,
+}
+
+/// Signer for the configured relayer key
+pub async fn from_config(
+    config: &RelayerSignerConfig,
+    chain_id: u64,
+) -> Result<Arc<dyn RelayerSigner>, EthereumClientError> {
+    let signer: Arc<dyn RelayerSigner> = match config {
+        RelayerSignerConfig::PrivateKey(key) => Arc::new(LocalKeySigner::from_private_key(key, chain_id)?),
+        RelayerSignerConfig::Keystore { path, passphrase } => {
+            Arc::new(LocalKeySigner::from_keystore(path, passphrase, chain_id)?)
+        }
+        RelayerSignerConfig::Remote { url, address } => {
+            let address = address.as_deref()
+                .map(|address| address.parse::<Address>()
+                    .map_err(|e| EthereumClientError::WalletError(format!("Invalid RELAYER_ADDRESS: {}", e))))
+                .transpose()?;
+            Arc::new(RemoteSigner::connect(url, address).await?)
+        }
+    };
+
+    tracing::info!("🔑 Relayer {:#x} ({})", signer.address(), config.kind());
+    Ok(signer)
+}
+
+// ============================================================================
+// Local key
+// ============================================================================
+
+impl LocalKeySigner {
+    pub fn new(wallet: LocalWallet) -> Self {
+        Self { wallet }
+    }
+
+    pub fn from_private_key(key: &str, chain_id: u64) -> Result<Self, EthereumClientError> {
+        let wallet: LocalWallet = key
+            .parse()
+            .map_err(|e| EthereumClientError::WalletError(format!("Invalid private key: {}", e)))?;
+        Ok(Self::new(wallet.with_chain_id(chain_id)))
+    }
+
+    /// Decrypt an encrypted JSON keystore (scrypt/pbkdf2, as written by geth or cast)
+    pub fn from_keystore(path: &str, passphrase: &str, chain_id: u64) -> Result<Self, EthereumClientError> {
+        let wallet = LocalWallet::decrypt_keystore(path, passphrase)
+            .map_err(|e| EthereumClientError::WalletError(format!("Cannot unlock keystore {}: {}", path, e)))?;
+        Ok(Self::new(wallet.with_chain_id(chain_id)))
+    }
+}
+
+#[async_trait]
+impl RelayerSigner for LocalKeySigner {
+    fn address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, EthereumClientError> {
+        self.wallet.sign_transaction(tx).await
+            .map_err(|e| EthereumClientError::WalletError(e.to_string()))
+    }
+}
+
+// ============================================================================
+// Remote signer (Web3Signer eth1 JSON-RPC)
+// ============================================================================
+
+impl RemoteSigner {
+    /// Connect to the signer and pick the relayer account
+    ///
+    /// With `address`, the signer must hold it; otherwise it must hold exactly one account.
+    pub async fn connect(url: &str, address: Option<Address>) -> Result<Self, EthereumClientError> {
+        let http = reqwest::Client::builder()
+            .timeout(REMOTE_SIGNER_TIMEOUT)
+            .build()
+            .map_err(|e| EthereumClientError::WalletError(e.to_string()))?;
+        let url = url.trim_end_matches('/').to_string();
+
+        let accounts: Vec<Address> = rpc(&http, &url, "eth_accounts", json!([])).await?;
+        let address = match (address, accounts.as_slice()) {
+            (Some(address), accounts) if accounts.contains(&address) => address,
+            (Some(address), _) => {
+                return Err(EthereumClientError::WalletError(format!("Remote signer does not hold {:#x}", address)));
+            }
+            (None, [address]) => *address,
+            (None, []) => return Err(EthereumClientError::WalletError("Remote signer holds no accounts".to_string())),
+            (None, _) => {
+                return Err(EthereumClientError::WalletError(
+                    "Remote signer holds several accounts - set RELAYER_ADDRESS".to_string(),
+                ));
+            }
+        };
+
+        Ok(Self { http, url, address })
+    }
+}
+
+#[async_trait]
+impl RelayerSigner for RemoteSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, EthereumClientError> {
+        let params = SignTransactionParams::from_tx(tx, self.address);
+        let raw: Bytes = rpc(&self.http, &self.url, "eth_signTransaction", json!([params])).await?;
+        signature_from_raw(tx, &raw, self.address)
+    }
+}
+
+async fn rpc<T: DeserializeOwned>(
+    http: &reqwest::Client,
+    url: &str,
+    method: &str,
+    params: Value,
+) -> Result<T, EthereumClientError> {
+    let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+    let response: RpcResponse<T> = http.post(url)
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| EthereumClientError::WalletError(format!("Remote signer {} failed: {}", method, e)))?
+        .json()
+        .await
+        .map_err(|e| EthereumClientError::WalletError(format!("Remote signer {} returned invalid JSON: {}", method, e)))?;
+
+    match (response.result, response.error) {
+        (_, Some(error)) => Err(EthereumClientError::WalletError(format!("Remote signer {}: {}", method, error.message))),
+        (Some(result), None) => Ok(result),
+        (None, None) => Err(EthereumClientError::WalletError(format!("Remote signer {} returned no result", method))),
+    }
+}
+
+/// Signature of a remotely signed transaction, checked to be `signer`'s over exactly `tx`
+/// (a different chain ID, nonce or fee would recover a different address)
+fn signature_from_raw(tx: &TypedTransaction, raw: &[u8], signer: Address) -> Result<Signature, EthereumClientError> {
+    let (_, signature) = TypedTransaction::decode_signed(&Rlp::new(raw))
+        .map_err(|e| EthereumClientError::WalletError(format!("Remote signer returned an invalid transaction: {}", e)))?;
+
+    match signature.recover(tx.sighash()) {
+        Ok(recovered) if recovered == signer => Ok(signature),
+        _ => Err(EthereumClientError::WalletError(
+            "Remote signer signed a different transaction (check its chain ID)".to_string(),
+        )),
+    }
+}
+
+impl SignTransactionParams {
+    fn from_tx(tx: &TypedTransaction, from: Address) -> Self {
+        let fees = match tx {
+            TypedTransaction::Eip1559(tx) => (None, tx.max_fee_per_gas, tx.max_priority_fee_per_gas),
+            _ => (tx.gas_price(), None, None),
+        };
+        Self {
+            from,
+            to: tx.to_addr().copied(),
+            gas: tx.gas().copied(),
+            gas_price: fees.0,
+            max_fee_per_gas: fees.1,
+            max_priority_fee_per_gas: fees.2,
+            value: tx.value().copied(),
+            data: tx.data().cloned(),
+            nonce: tx.nonce().copied(),
+        }
+    }
+
+    /// The transaction to sign (EIP-1559 if it carries a max fee, else legacy)
+    fn into_tx(self, chain_id: u64) -> TypedTransaction {
+        let mut tx: TypedTransaction = match (self.max_fee_per_gas, self.max_priority_fee_per_gas) {
+            (Some(max_fee), priority_fee) => Eip1559TransactionRequest::new()
+                .max_fee_per_gas(max_fee)
+                .max_priority_fee_per_gas(priority_fee.unwrap_or_default())
+                .into(),
+            (None, _) => {
+                let mut legacy = TransactionRequest::new();
+                legacy.gas_price = self.gas_price;
+                legacy.into()
+            }
+        };
+
+        tx.set_from(self.from);
+        tx.set_chain_id(chain_id);
+        if let Some(to) = self.to {
+            tx.set_to(to);
+        }
+        if let Some(gas) = self.gas {
+            tx.set_gas(gas);
+        }
+        if let Some(value) = self.value {
+            tx.set_value(value);
+        }
+        if let Some(data) = self.data {
+            tx.set_data(data);
+        }
+        if let Some(nonce) = self.nonce {
+            tx.set_nonce(nonce);
+        }
+        tx
+    }
+}
+
+// ============================================================================
+// Stand-in remote signer
+// ============================================================================
+
+/// Web3Signer-compatible JSON-RPC (`eth_accounts`, `eth_signTransaction`) over a local
+/// key, plus `GET /upcheck`. Signs with the wallet's chain ID, like Web3Signer's --chain-id.
+pub fn stand_in_router(wallet: LocalWallet) -> Router {
+    Router::new()
+        .route("/", post(stand_in_rpc))
+        .route("/upcheck", get(|| async { "OK" }))
+        .with_state(Arc::new(wallet))
+}
+
+async fn stand_in_rpc(State(wallet): State<Arc<LocalWallet>>, Json(request): Json<Value>) -> Json<Value> {
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request.get("method").and_then(Value::as_str).unwrap_or_default();
+
+    let result = match method {
+        "eth_accounts" => Ok(json!([wallet.address()])),
+        "eth_signTransaction" => stand_in_sign(&wallet, request.get("params")).await,
+        other => Err(format!("Method not supported: {}", other)),
+    };
+
+    Json(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(message) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32000, "message": message } }),
+    })
+}
+
+async fn stand_in_sign(wallet: &LocalWallet, params: Option<&Value>) -> Result<Value, String> {
+    let params = params.and_then(|params| params.get(0)).ok_or("Missing transaction")?;
+    let params: SignTransactionParams = serde_json::from_value(params.clone())
+        .map_err(|e| format!("Invalid transaction: {}", e))?;
+    if params.from != wallet.address() {
+        return Err(format!("Unknown account {:#x}", params.from));
+    }
+
+    let tx = params.into_tx(wallet.chain_id());
+    let signature = wallet.sign_transaction(&tx).await.map_err(|e| e.to_string())?;
+    Ok(json!(tx.rlp_signed(&signature)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn test_tx(from: Address) -> TypedTransaction {
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .to(Address::repeat_byte(0x11))
+            .data(Bytes::from(vec![0xde, 0xad, 0xbe, 0xef]))
+            .gas(80_000u64)
+            .nonce(7u64)
+            .max_fee_per_gas(100_000_000u64)
+            .max_priority_fee_per_gas(1_000_000u64)
+            .into();
+        tx.set_from(from);
+        tx.set_chain_id(8453u64);
+        tx
+    }
+
+    #[test]
+    fn test_sign_params_round_trip() {
+        let wallet: LocalWallet = TEST_KEY.parse().unwrap();
+        let tx = test_tx(wallet.address());
+
+        let params = SignTransactionParams::from_tx(&tx, wallet.address());
+        let json = serde_json::to_value(&params).unwrap();
+        assert!(json.get("gasPrice").is_none());
+        assert!(json.get("chainId").is_none());
+
+        let parsed: SignTransactionParams = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.into_tx(8453).sighash(), tx.sighash());
+    }
+
+    #[tokio::test]
+    async fn test_remote_signature_checked() {
+        let wallet = TEST_KEY.parse::<LocalWallet>().unwrap().with_chain_id(8453u64);
+        let tx = test_tx(wallet.address());
+        let signature = wallet.sign_transaction(&tx).await.unwrap();
+
+        // Re-encodes to the same signed transaction
+        let raw = tx.rlp_signed(&signature);
+        let decoded = signature_from_raw(&tx, &raw, wallet.address()).unwrap();
+        assert_eq!(tx.rlp_signed(&decoded), raw);
+
+        // Signed for another chain
+        let mut other_chain = tx.clone();
+        other_chain.set_chain_id(1u64);
+        let other_signature = wallet.sign_transaction(&other_chain).await.unwrap();
+        assert!(signature_from_raw(&tx, &other_chain.rlp_signed(&other_signature), wallet.address()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_with_stand_in() {
+        let wallet = TEST_KEY.parse::<LocalWallet>().unwrap().with_chain_id(8453u64);
+        let address = wallet.address();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, stand_in_router(wallet)).await.unwrap();
+        });
+
+        let signer = RemoteSigner::connect(&url, None).await.unwrap();
+        assert_eq!(signer.address(), address);
+        assert!(RemoteSigner::connect(&url, Some(Address::repeat_byte(0x22))).await.is_err());
+
+        let tx = test_tx(address);
+        let signature = signer.sign_transaction(&tx).await.unwrap();
+        assert_eq!(signature.recover(tx.sighash()).unwrap(), address);
+    }
+}
@@ -0,0 +1,201 @@
+//! WebSocket log subscription buffer
+//!
+//! With RPC_WS_URL set, the event listener subscribes to the escrow's logs and to new
+//! heads over `eth_subscribe`. Logs are buffered here and handed to the regular sync
+//! (reorg check, one transaction per range) once their blocks are below the reorg
+//! depth - no `eth_getLogs` or header calls while the subscription is healthy.
+//!
+//! A range is only served from the buffer if the subscription saw every head in it,
+//! each building on the previous one. Anything else - blocks before the subscription
+//! started, a skipped or re-emitted head - is a gap, backfilled with `eth_getLogs`.
+
+use std::collections::BTreeMap;
+
+use ethers::types::{Block, Log, H256};
+
+/// Logs and heads received over the subscription
+#[derive(Default)]
+pub struct LogBuffer {
+    /// First block whose logs are known to be complete (None before the first head)
+    covered_from: Option<u64>,
+    /// Consecutive heads, the chain the buffered logs are checked against
+    heads: BTreeMap<u64, Block<H256>>,
+    logs: Vec<Log>,
+}
+
+/// Subscription data for one block range
+pub struct BufferedRange {
+    pub first_header: Block<H256>,
+    pub last_header: Block<H256>,
+    pub logs: Vec<Log>,
+}
+
+impl LogBuffer {
+    /// Latest head received
+    pub fn tip(&self) -> Option<u64> {
+        self.heads.last_key_value().map(|(number, _)| *number)
+    }
+
+    /// Add a new head
+    ///
+    /// Returns false if it broke the covered chain (skipped or re-emitted head):
+    /// only the blocks after it are served from the buffer, earlier ones are backfilled.
+    pub fn push_head(&mut self, head: Block<H256>) -> bool {
+        let Some(number) = head.number.map(|n| n.as_u64()) else {
+            return true;
+        };
+
+        let builds_on_tip = self.heads.last_key_value().is_some_and(|(tip, tip_head)| {
+            *tip + 1 == number && tip_head.hash == Some(head.parent_hash)
+        });
+        self.heads.insert(number, head);
+        if builds_on_tip {
+            return true;
+        }
+
+        // Logs of this block may have arrived before the subscription (first head)
+        // or belong to another fork - start covering from the next block
+        let was_covered = self.covered_from.is_some();
+        self.covered_from = Some(number + 1);
+        self.heads.retain(|n, _| *n == number);
+        !was_covered
+    }
+
+    /// Add a log (a `removed` log is a reorged-out one and is dropped from the buffer)
+    pub fn push_log(&mut self, log: Log) {
+        if log.removed == Some(true) {
+            self.logs.retain(|buffered| {
+                buffered.transaction_hash != log.transaction_hash
+                    || buffered.log_index != log.log_index
+                    || buffered.block_hash != log.block_hash
+            });
+            return;
+        }
+        self.logs.push(log);
+    }
+
+    /// Heads and logs of `from..=to` (None if the range isn't fully covered)
+    ///
+    /// Logs whose block hash doesn't match the head are from another fork and skipped.
+    pub fn range(&self, from: u64, to: u64) -> Option<BufferedRange> {
+        if from < self.covered_from? {
+            return None;
+        }
+        let first_header = self.heads.get(&from)?.clone();
+        let last_header = self.heads.get(&to)?.clone();
+
+        let mut logs: Vec<Log> = self.logs.iter()
+            .filter(|log| {
+                let Some(number) = log.block_number.map(|n| n.as_u64()) else {
+                    return false;
+                };
+                (from..=to).contains(&number)
+                    && self.heads.get(&number).is_some_and(|head| head.hash.is_some() && head.hash == log.block_hash)
+            })
+            .cloned()
+            .collect();
+        logs.sort_by_key(|log| (log.block_number, log.log_index));
+
+        Some(BufferedRange { first_header, last_header, logs })
+    }
+
+    /// Forget blocks below `below` once they are synced (the tip is always kept)
+    pub fn prune(&mut self, below: u64) {
+        let keep_from = below.min(self.tip().unwrap_or(below));
+        self.heads.retain(|number, _| *number >= keep_from);
+        self.logs.retain(|log| log.block_number.is_some_and(|n| n.as_u64() >= below));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(number: u64, fork: u8) -> H256 {
+        let mut bytes = [fork; 32];
+        bytes[24..].copy_from_slice(&number.to_be_bytes());
+        H256::from(bytes)
+    }
+
+    fn head(number: u64, fork: u8, parent_fork: u8) -> Block<H256> {
+        Block {
+            number: Some(number.into()),
+            hash: Some(hash(number, fork)),
+            parent_hash: hash(number - 1, parent_fork),
+            ..Default::default()
+        }
+    }
+
+    fn log(number: u64, fork: u8, index: u64) -> Log {
+        Log {
+            block_number: Some(number.into()),
+            block_hash: Some(hash(number, fork)),
+            transaction_hash: Some(hash(index, 0xee)),
+            log_index: Some(index.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_covered_range() {
+        let mut buffer = LogBuffer::default();
+        assert!(buffer.push_head(head(100, 1, 1)));
+        buffer.push_log(log(101, 1, 7));
+        buffer.push_log(log(101, 1, 3));
+        assert!(buffer.push_head(head(101, 1, 1)));
+        assert!(buffer.push_head(head(102, 1, 1)));
+
+        // Block 100 may have lost logs sent before the subscription
+        assert!(buffer.range(100, 102).is_none());
+
+        let range = buffer.range(101, 102).unwrap();
+        assert_eq!(range.first_header.number, Some(101.into()));
+        assert_eq!(range.last_header.number, Some(102.into()));
+        let indexes: Vec<_> = range.logs.iter().map(|log| log.log_index.unwrap().as_u64()).collect();
+        assert_eq!(indexes, vec![3, 7]);
+
+        // Not received yet
+        assert!(buffer.range(101, 103).is_none());
+    }
+
+    #[test]
+    fn test_skipped_head_is_a_gap() {
+        let mut buffer = LogBuffer::default();
+        buffer.push_head(head(100, 1, 1));
+        buffer.push_head(head(101, 1, 1));
+        assert!(!buffer.push_head(head(103, 1, 1)));
+        buffer.push_head(head(104, 1, 1));
+
+        assert!(buffer.range(101, 104).is_none());
+        assert!(buffer.range(103, 104).is_none());
+        assert!(buffer.range(104, 104).is_some());
+        assert_eq!(buffer.tip(), Some(104));
+    }
+
+    #[test]
+    fn test_reorged_logs_are_dropped() {
+        let mut buffer = LogBuffer::default();
+        buffer.push_head(head(100, 1, 1));
+        buffer.push_head(head(101, 1, 1));
+        buffer.push_log(log(102, 1, 0));
+        buffer.push_log(log(102, 1, 1));
+        buffer.push_head(head(102, 1, 1));
+
+        // Block 102 is replaced: re-emitted head, one log explicitly removed
+        let mut removed = log(102, 1, 1);
+        removed.removed = Some(true);
+        buffer.push_log(removed);
+        assert!(!buffer.push_head(head(102, 2, 1)));
+        buffer.push_log(log(103, 2, 5));
+        buffer.push_head(head(103, 2, 2));
+
+        assert!(buffer.range(102, 103).is_none());
+        let range = buffer.range(103, 103).unwrap();
+        assert_eq!(range.logs.len(), 1);
+        assert_eq!(buffer.logs.len(), 2);
+
+        buffer.prune(104);
+        assert!(buffer.logs.is_empty());
+        assert_eq!(buffer.tip(), Some(103));
+    }
+}
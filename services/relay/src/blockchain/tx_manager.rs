@@ -0,0 +1,683 @@
+//! Relayer transaction manager - one nonce sequence and send queue per relayer key
+//!
+//! The API server and auto-cancel sign with the same key, so every write goes
+//! through here instead of its own `SignerMiddleware`:
+//! - sends are queued FIFO within a process and serialized across processes by a
+//!   Postgres advisory lock on the relayer address (`relayer_nonces`)
+//! - the nonce is the chain's pending nonce, or the stored next nonce while
+//!   transactions from the chain's nonce on are still tracked (the RPC node may
+//!   not have seen them yet)
+//! - every broadcast is tracked in `relayer_pending_transactions` until mined, and
+//!   transactions left pending by a previous run are watched again on startup. A send
+//!   that failed in transport is tracked under its locally computed hash, since the
+//!   node may have received it
+//! - fees come from the fee strategy (fees.rs) at the operation's urgency, and the
+//!   gas actually paid is recorded from the mined receipt (`relayer_transactions`)
+//! - a transaction not mined after RELAYER_TX_STUCK_SECS is replaced (same nonce,
+//!   RELAYER_TX_GAS_BUMP_PERCENT higher fees), at most RELAYER_TX_MAX_REPLACEMENTS
+//!   times and up to the strategy's max fee per gas
+//! - a wait that gives up after RELAYER_TX_CONFIRM_TIMEOUT_SECS returns
+//!   `StillPending` with the tracked hash - callers must not send again
+//!
+//! Without a database, nonces come from the chain and sends are only serialized
+//! within the process.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use ethers::prelude::*;
+use ethers::providers::{Http, Provider, RpcError};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use sqlx::PgPool;
+use tokio::sync::Mutex;
+
+use super::client::EthereumClientError;
+use super::fees::{FeeStrategy, FeeUrgency, TxFees, MIN_REPLACEMENT_BUMP};
+use super::signer::RelayerSigner;
+use crate::db::models::DbRelayerPendingTx;
+use crate::db::relayer_nonces::{GasPaid, NewPendingTx, PostgresRelayerNonceRepository};
+use crate::db::relayer_transactions::{NewRelayerTransaction, PostgresRelayerTransactionRepository};
+
+/// How often broadcasts are checked for a receipt
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Stuck-transaction replacement settings
+#[derive(Debug, Clone)]
+pub struct TxManagerConfig {
+    /// Replace a transaction not mined this long after its last broadcast
+    pub stuck_after: Duration,
+    /// Fee increase per replacement (nodes require at least 10%)
+    pub bump_percent: u64,
+    pub max_replacements: u32,
+    /// Give up waiting for a receipt after this long (the transaction stays tracked)
+    pub confirm_timeout: Duration,
+}
+
+/// A broadcast transaction awaiting its receipt
+#[derive(Debug, Clone)]
+pub struct PendingRelayerTx {
+    pub operation: String,
+    /// Trade the transaction is for (accounted in relayer_transactions)
+    pub trade_id: Option<String>,
+    pub nonce: U256,
+    /// First broadcast (replacements have their own hashes)
+    pub tx_hash: H256,
+    tx: TypedTransaction,
+    hashes: Vec<H256>,
+    fees: TxFees,
+    replacements: u32,
+    /// Row in relayer_pending_transactions (None without a database)
+    id: Option<i64>,
+}
+
+impl PendingRelayerTx {
+    /// Most recent broadcast (the first one until replaced)
+    pub fn latest_hash(&self) -> H256 {
+        self.hashes.last().copied().unwrap_or(self.tx_hash)
+    }
+}
+
+pub struct RelayerTxManager {
+    provider: Arc<Provider<Http>>,
+    signer: Arc<dyn RelayerSigner>,
+    chain_id: u64,
+    fees: Arc<dyn FeeStrategy>,
+    store: Option<PostgresRelayerNonceRepository>,
+    ledger: Option<PostgresRelayerTransactionRepository>,
+    queue: Mutex<()>,
+    config: TxManagerConfig,
+}
+
+impl Default for TxManagerConfig {
+    fn default() -> Self {
+        Self {
+            stuck_after: Duration::from_secs(60),
+            bump_percent: 20,
+            max_replacements: 5,
+            confirm_timeout: Duration::from_secs(1800),
+        }
+    }
+}
+
+impl TxManagerConfig {
+    /// Defaults overridden by RELAYER_TX_STUCK_SECS, RELAYER_TX_GAS_BUMP_PERCENT,
+    /// RELAYER_TX_MAX_REPLACEMENTS and RELAYER_TX_CONFIRM_TIMEOUT_SECS
+    pub fn from_env() -> Self {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Same as `from_env`, reading each variable through `lookup`
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        fn parse<T: std::str::FromStr>(lookup: &dyn Fn(&str) -> Option<String>, name: &str) -> Option<T> {
+            let value = lookup(name)?;
+            let parsed = value.trim().parse().ok();
+            if parsed.is_none() {
+                tracing::warn!("⚠️ Ignoring invalid {}: {}", name, value);
+            }
+            parsed
+        }
+
+        let defaults = Self::default();
+        Self {
+            stuck_after: parse(&lookup, "RELAYER_TX_STUCK_SECS").map(Duration::from_secs).unwrap_or(defaults.stuck_after),
+            bump_percent: parse::<u64>(&lookup, "RELAYER_TX_GAS_BUMP_PERCENT").map(|percent| percent.max(MIN_REPLACEMENT_BUMP)).unwrap_or(defaults.bump_percent),
+            max_replacements: parse(&lookup, "RELAYER_TX_MAX_REPLACEMENTS").unwrap_or(defaults.max_replacements),
+            confirm_timeout: parse(&lookup, "RELAYER_TX_CONFIRM_TIMEOUT_SECS").map(Duration::from_secs).unwrap_or(defaults.confirm_timeout),
+        }
+    }
+}
+
+impl RelayerTxManager {
+    pub fn new(
+        provider: Arc<Provider<Http>>,
+        signer: Arc<dyn RelayerSigner>,
+        chain_id: u64,
+        fees: Arc<dyn FeeStrategy>,
+        config: TxManagerConfig,
+    ) -> Self {
+        Self {
+            provider,
+            signer,
+            chain_id,
+            fees,
+            store: None,
+            ledger: None,
+            queue: Mutex::new(()),
+            config,
+        }
+    }
+
+    /// Persist nonces, pending transactions and gas paid (shared by every process using the key)
+    pub fn with_store(mut self, pool: PgPool) -> Self {
+        self.store = Some(PostgresRelayerNonceRepository::new(pool.clone()));
+        self.ledger = Some(PostgresRelayerTransactionRepository::new(pool));
+        self
+    }
+
+    /// Attribute a mined transaction to the trade it created (known only from its logs)
+    pub async fn attribute_trade(&self, tx_hash: H256, trade_id: &str) {
+        if let Some(ledger) = &self.ledger {
+            if let Err(e) = ledger.set_trade(&format!("{:#x}", tx_hash), trade_id).await {
+                tracing::error!("Failed to attribute {:#x} to trade {}: {}", tx_hash, trade_id, e);
+            }
+        }
+    }
+
+    /// Price `tx` for `urgency`, then sign and broadcast it with the next relayer nonce
+    /// (queued behind earlier sends)
+    pub async fn broadcast(
+        &self,
+        operation: &str,
+        trade_id: Option<&str>,
+        tx: TypedTransaction,
+        urgency: FeeUrgency,
+    ) -> Result<PendingRelayerTx, EthereumClientError> {
+        // Nothing is sent until sign_and_send - earlier failures are safe to retry
+        let fees = self.fees.fees(urgency).await.map_err(EthereumClientError::not_broadcast)?;
+        let mut tx = fees.apply(&tx);
+
+        let _queued = self.queue.lock().await;
+        let address = self.signer.address();
+        let address_hex = format!("{:#x}", address);
+
+        let mut lease = match &self.store {
+            Some(store) => Some(store.lease(&address_hex).await.map_err(|e| store_error(e).not_broadcast())?),
+            None => None,
+        };
+
+        let chain_nonce = self.provider
+            .get_transaction_count(address, Some(BlockNumber::Pending.into()))
+            .await
+            .map_err(|e| EthereumClientError::NotBroadcast(e.to_string()))?
+            .as_u64();
+        let (stored_next, tracked) = match lease.as_mut() {
+            Some(lease) => (
+                lease.next_nonce,
+                lease.pending_from(chain_nonce).await.map_err(|e| store_error(e).not_broadcast())? > 0,
+            ),
+            None => (None, false),
+        };
+        let nonce = select_nonce(chain_nonce, stored_next, tracked);
+
+        tx.set_from(address);
+        tx.set_nonce(nonce);
+        tx.set_chain_id(self.chain_id);
+        let tx_hash = self.sign_and_send(&tx, operation).await?;
+        tracing::info!(
+            "📤 {} tx sent: {:#x} (nonce {}, {} urgency, max fee {} wei)",
+            operation, tx_hash, nonce, urgency.as_str(), fees.max_fee_per_gas()
+        );
+
+        // The nonce is only stored once broadcast (a rejected send releases it)
+        let id = match lease {
+            Some(lease) => {
+                let tx_request = serde_json::to_string(&tx).unwrap_or_default();
+                let max_priority_fee = fees.max_priority_fee_per_gas().map(|fee| fee.to_string());
+                let pending = NewPendingTx {
+                    nonce,
+                    operation,
+                    trade_id,
+                    tx_hash: &format!("{:#x}", tx_hash),
+                    gas_price: &fees.max_fee_per_gas().to_string(),
+                    max_priority_fee: max_priority_fee.as_deref(),
+                    urgency: urgency.as_str(),
+                    tx_request: &tx_request,
+                };
+                match lease.commit(pending).await {
+                    Ok(id) => Some(id),
+                    Err(e) => {
+                        tracing::error!("Failed to track {} tx {:#x}: {}", operation, tx_hash, e);
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
+        Ok(PendingRelayerTx {
+            operation: operation.to_string(),
+            trade_id: trade_id.map(str::to_string),
+            nonce: U256::from(nonce),
+            tx_hash,
+            tx,
+            hashes: vec![tx_hash],
+            fees,
+            replacements: 0,
+            id,
+        })
+    }
+
+    /// Wait until one of the broadcasts is mined, replacing the transaction while stuck
+    ///
+    /// Returns the mined receipt, which may be a replacement's (check its status).
+    /// Gives up after the confirm timeout with `StillPending` - the transaction stays
+    /// tracked (and is watched again on restart), so the caller must not resend it.
+    pub async fn confirm(&self, mut pending: PendingRelayerTx) -> Result<TransactionReceipt, EthereumClientError> {
+        let started = Instant::now();
+        let mut last_broadcast = Instant::now();
+
+        loop {
+            tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+
+            if let Some(receipt) = self.find_receipt(&pending).await? {
+                return Ok(receipt);
+            }
+            if started.elapsed() >= self.config.confirm_timeout {
+                let latest = pending.latest_hash();
+                tracing::warn!(
+                    "⏳ {} tx {:#x} not mined after {}s - still tracked, not resending",
+                    pending.operation, latest, self.config.confirm_timeout.as_secs()
+                );
+                return Err(EthereumClientError::StillPending(latest));
+            }
+            if last_broadcast.elapsed() < self.config.stuck_after {
+                continue;
+            }
+
+            // Nonce used up without any of our broadcasts (e.g. a transaction sent outside the relay)
+            let mined_nonce = self.provider
+                .get_transaction_count(self.signer.address(), Some(BlockNumber::Latest.into()))
+                .await
+                .map_err(|e| EthereumClientError::ProviderError(e.to_string()))?;
+            if mined_nonce > pending.nonce {
+                if let Some(receipt) = self.find_receipt(&pending).await? {
+                    return Ok(receipt);
+                }
+                self.finish(&pending, "dropped", None, None).await;
+                return Err(EthereumClientError::TransactionFailed(format!(
+                    "{} nonce {} was used by another transaction", pending.operation, pending.nonce
+                )));
+            }
+
+            if pending.replacements < self.config.max_replacements {
+                match pending.fees.bumped(self.config.bump_percent, self.fees.max_fee_per_gas()) {
+                    Some(fees) => self.replace(&mut pending, fees).await,
+                    None => tracing::warn!(
+                        "⚠️ {} tx {:#x} stuck too close to the max fee cap to replace ({} wei, needs +{}%)",
+                        pending.operation, pending.tx_hash, pending.fees.max_fee_per_gas(), MIN_REPLACEMENT_BUMP
+                    ),
+                }
+            }
+            last_broadcast = Instant::now();
+        }
+    }
+
+    /// Watch transactions a previous run left pending (replacing stuck ones)
+    pub async fn recover_pending(&self) -> usize {
+        let Some(store) = &self.store else {
+            return 0;
+        };
+        let rows = match store.list_pending(&format!("{:#x}", self.signer.address())).await {
+            Ok(rows) => rows,
+            Err(e) => {
+                tracing::error!("❌ Failed to load pending relayer transactions: {}", e);
+                return 0;
+            }
+        };
+
+        let pending: Vec<PendingRelayerTx> = rows.into_iter().filter_map(|row| match pending_from_row(&row) {
+            Ok(pending) => Some(pending),
+            Err(e) => {
+                tracing::error!("❌ Cannot recover relayer tx {} (nonce {}): {}", row.tx_hash, row.nonce, e);
+                None
+            }
+        }).collect();
+        let count = pending.len();
+        if count > 0 {
+            tracing::info!("🔁 Watching {} relayer transactions left pending", count);
+        }
+
+        let results = futures_util::future::join_all(pending.into_iter().map(|pending| {
+            let operation = pending.operation.clone();
+            async move { (operation, self.confirm(pending).await) }
+        })).await;
+        for (operation, result) in results {
+            match result {
+                Ok(receipt) => tracing::info!("✅ Recovered {} tx mined: {:#x}", operation, receipt.transaction_hash),
+                Err(e) => tracing::warn!("⚠️ Recovered {} tx: {}", operation, e),
+            }
+        }
+        count
+    }
+
+    /// Sign and send `tx` - a JSON-RPC error response means the node rejected it (not
+    /// sent). A transport error is ambiguous, the node may have received it: the locally
+    /// computed hash is returned so the transaction is tracked (confirmed, replaced when
+    /// stuck, counted) like any other broadcast instead of its nonce being reused
+    async fn sign_and_send(&self, tx: &TypedTransaction, operation: &str) -> Result<H256, EthereumClientError> {
+        let signature = self.signer.sign_transaction(tx).await.map_err(EthereumClientError::not_broadcast)?;
+        let tx_hash = tx.hash(&signature);
+        match self.provider.send_raw_transaction(tx.rlp_signed(&signature)).await {
+            Ok(pending) => Ok(pending.tx_hash()),
+            Err(e) if RpcError::as_error_response(&e).is_some() => {
+                Err(EthereumClientError::NotBroadcast(format!("{} broadcast failed: {}", operation, e)))
+            }
+            Err(e) => {
+                tracing::warn!("⚠️ {} broadcast of {:#x} may not have reached the node, tracking it: {}", operation, tx_hash, e);
+                Ok(tx_hash)
+            }
+        }
+    }
+
+    /// Rebroadcast with the same nonce at higher `fees` (a rejection is retried next time it's stuck)
+    async fn replace(&self, pending: &mut PendingRelayerTx, fees: TxFees) {
+        let tx = fees.apply(&pending.tx);
+
+        match self.sign_and_send(&tx, &pending.operation).await {
+            Ok(tx_hash) => {
+                tracing::warn!(
+                    "⛽ {} nonce {} stuck - replaced {:#x} with {:#x} (max fee {} -> {} wei)",
+                    pending.operation, pending.nonce, pending.tx_hash, tx_hash,
+                    pending.fees.max_fee_per_gas(), fees.max_fee_per_gas()
+                );
+                pending.tx = tx;
+                pending.hashes.push(tx_hash);
+                pending.fees = fees;
+                pending.replacements += 1;
+
+                if let (Some(store), Some(id)) = (&self.store, pending.id) {
+                    let max_priority_fee = fees.max_priority_fee_per_gas().map(|fee| fee.to_string());
+                    let recorded = store.record_replacement(
+                        id,
+                        &format!("{:#x}", tx_hash),
+                        &fees.max_fee_per_gas().to_string(),
+                        max_priority_fee.as_deref(),
+                    ).await;
+                    if let Err(e) = recorded {
+                        tracing::error!("Failed to track replacement {:#x}: {}", tx_hash, e);
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("⚠️ Failed to replace {} nonce {}: {}", pending.operation, pending.nonce, e),
+        }
+    }
+
+    /// Receipt of whichever broadcast was mined (closes the tracked transaction with the gas paid)
+    async fn find_receipt(&self, pending: &PendingRelayerTx) -> Result<Option<TransactionReceipt>, EthereumClientError> {
+        for tx_hash in pending.hashes.iter().rev() {
+            let receipt = self.provider.get_transaction_receipt(*tx_hash).await
+                .map_err(|e| EthereumClientError::ProviderError(e.to_string()))?;
+            if let Some(receipt) = receipt {
+                let status = if receipt.status == Some(U64::from(1)) { "mined" } else { "reverted" };
+                let gas_used = receipt.gas_used.unwrap_or_default();
+                let effective_gas_price = receipt.effective_gas_price.unwrap_or_default();
+                let cost = gas_used * effective_gas_price;
+                tracing::info!(
+                    "⛽ {} {:#x} {}: {} gas at {} wei ({} wei)",
+                    pending.operation, receipt.transaction_hash, status, gas_used, effective_gas_price, cost
+                );
+
+                let paid = GasPaid {
+                    gas_used: gas_used.to_string(),
+                    effective_gas_price: effective_gas_price.to_string(),
+                    cost: cost.to_string(),
+                };
+                self.finish(pending, status, Some(receipt.transaction_hash), Some(&paid)).await;
+                self.account(pending, &receipt, status, &paid).await;
+                return Ok(Some(receipt));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Record the gas a mined transaction paid in the relayer ledger
+    async fn account(&self, pending: &PendingRelayerTx, receipt: &TransactionReceipt, status: &str, paid: &GasPaid) {
+        let Some(ledger) = &self.ledger else {
+            return;
+        };
+        let tx_hash = format!("{:#x}", receipt.transaction_hash);
+        let recorded = ledger.record(NewRelayerTransaction {
+            tx_hash: &tx_hash,
+            address: &format!("{:#x}", self.signer.address()),
+            operation: &pending.operation,
+            trade_id: pending.trade_id.as_deref(),
+            status,
+            gas_used: &paid.gas_used,
+            effective_gas_price: &paid.effective_gas_price,
+            gas_cost: &paid.cost,
+            block_number: receipt.block_number.map(|block| block.as_u64() as i64),
+        }).await;
+        if let Err(e) = recorded {
+            tracing::error!("Failed to account relayer tx {}: {}", tx_hash, e);
+        }
+    }
+
+    async fn finish(&self, pending: &PendingRelayerTx, status: &str, mined_tx_hash: Option<H256>, paid: Option<&GasPaid>) {
+        if let (Some(store), Some(id)) = (&self.store, pending.id) {
+            let mined_tx_hash = mined_tx_hash.map(|tx_hash| format!("{:#x}", tx_hash));
+            if let Err(e) = store.finish(id, status, mined_tx_hash.as_deref(), paid).await {
+                tracing::error!("Failed to close relayer tx {:#x}: {}", pending.tx_hash, e);
+            }
+        }
+    }
+}
+
+/// Next nonce: the chain's pending nonce, unless the stored sequence is ahead and
+/// tracked transactions cover the difference (the RPC node hasn't seen them yet).
+/// An untracked gap (a process died between storing and broadcasting) is reused.
+fn select_nonce(chain_pending: u64, stored_next: Option<u64>, tracked_from_chain_nonce: bool) -> u64 {
+    match stored_next {
+        Some(next) if next > chain_pending && tracked_from_chain_nonce => next,
+        _ => chain_pending,
+    }
+}
+
+fn pending_from_row(row: &DbRelayerPendingTx) -> Result<PendingRelayerTx, String> {
+    let parse_hash = |value: &str| value.parse::<H256>().map_err(|e| format!("invalid tx hash {}: {}", value, e));
+    let tx: TypedTransaction = serde_json::from_str(&row.tx_request).map_err(|e| format!("invalid txRequest: {}", e))?;
+    let fees = TxFees::of(&tx).ok_or("txRequest has no fees")?;
+    let hashes = row.tx_hashes.iter().map(|value| parse_hash(value)).collect::<Result<Vec<_>, _>>()?;
+
+    Ok(PendingRelayerTx {
+        operation: row.operation.clone(),
+        trade_id: row.trade_id.clone(),
+        nonce: U256::from(row.nonce as u64),
+        tx_hash: parse_hash(&row.tx_hash)?,
+        tx,
+        hashes,
+        fees,
+        replacements: row.replacements.max(0) as u32,
+        id: Some(row.id),
+    })
+}
+
+fn store_error(e: crate::db::DbError) -> EthereumClientError {
+    EthereumClientError::TransactionFailed(format!("Relayer nonce store error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::fees::{FeeConfig, LegacyFeeStrategy};
+    use super::super::signer::LocalKeySigner;
+    use axum::{response::IntoResponse, routing::post, Json, Router};
+    use serde_json::{json, Value};
+
+    /// JSON-RPC node that has never seen a receipt and reports `mined_nonce`
+    async fn fake_node(mined_nonce: u64) -> String {
+        let app = Router::new().route("/", post(move |Json(request): Json<Value>| async move {
+            let result = match request["method"].as_str() {
+                Some("eth_getTransactionCount") => json!(format!("{:#x}", mined_nonce)),
+                _ => Value::Null,
+            };
+            Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        url
+    }
+
+    /// JSON-RPC node whose connection fails on every eth_sendRawTransaction
+    async fn flaky_send_node() -> String {
+        let app = Router::new().route("/", post(|Json(request): Json<Value>| async move {
+            let result = match request["method"].as_str() {
+                Some("eth_getTransactionCount") => json!("0x0"),
+                Some("eth_sendRawTransaction") => {
+                    return (axum::http::StatusCode::BAD_GATEWAY, "upstream timeout").into_response();
+                }
+                _ => Value::Null,
+            };
+            Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })).into_response()
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        url
+    }
+
+    async fn local_hash(manager: &RelayerTxManager, tx: &TypedTransaction) -> H256 {
+        tx.hash(&manager.signer.sign_transaction(tx).await.unwrap())
+    }
+
+    fn manager(rpc_url: &str, config: TxManagerConfig) -> RelayerTxManager {
+        let provider = Arc::new(Provider::<Http>::try_from(rpc_url).unwrap());
+        RelayerTxManager::new(
+            provider,
+            Arc::new(LocalKeySigner::new(LocalWallet::new(&mut rand::thread_rng()))),
+            1,
+            Arc::new(LegacyFeeStrategy::new(FeeConfig::default())),
+            config,
+        )
+    }
+
+    fn pending(nonce: u64, hashes: Vec<H256>) -> PendingRelayerTx {
+        let tx: TypedTransaction = TransactionRequest::new().nonce(nonce).gas_price(1_000u64).into();
+        PendingRelayerTx {
+            operation: "submitProof".to_string(),
+            trade_id: Some("0xaa".to_string()),
+            nonce: U256::from(nonce),
+            tx_hash: hashes[0],
+            fees: TxFees::of(&tx).unwrap(),
+            tx,
+            replacements: hashes.len() as u32 - 1,
+            hashes,
+            id: None,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_confirm_timeout_is_still_pending() {
+        let url = fake_node(0).await;
+        let config = TxManagerConfig { confirm_timeout: Duration::ZERO, ..TxManagerConfig::default() };
+        let first = H256::repeat_byte(1);
+        let replacement = H256::repeat_byte(2);
+
+        let error = manager(&url, config).confirm(pending(0, vec![first, replacement])).await.unwrap_err();
+
+        // Keeps the latest broadcast and is not retried (a retry would send a second transaction)
+        assert_eq!(error.still_pending(), Some(replacement));
+        assert!(!error.is_transient());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_confirm_reports_nonce_used_elsewhere() {
+        // Stuck at once, and the chain has mined past our nonce without our hashes
+        let url = fake_node(1).await;
+        let config = TxManagerConfig { stuck_after: Duration::ZERO, ..TxManagerConfig::default() };
+
+        let error = manager(&url, config).confirm(pending(0, vec![H256::repeat_byte(1)])).await.unwrap_err();
+        assert!(matches!(error, EthereumClientError::TransactionFailed(ref message) if message.contains("used by another transaction")));
+        assert!(error.still_pending().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_ambiguous_send_is_tracked() {
+        let manager = manager(&flaky_send_node().await, TxManagerConfig::default());
+        let tx: TypedTransaction = TransactionRequest::new().to(Address::repeat_byte(1)).gas(21_000u64).into();
+
+        // The node may have the transaction - it is returned for confirm() like a send
+        let pending = manager.broadcast("submitProof", Some("0xaa"), tx, FeeUrgency::Normal).await.unwrap();
+        assert_eq!(pending.tx_hash, local_hash(&manager, &pending.tx).await);
+        assert_eq!(pending.nonce, U256::zero());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_ambiguous_send_keeps_its_nonce() {
+        let db = crate::db::test_db::connect().await;
+        let manager = manager(&flaky_send_node().await, TxManagerConfig::default()).with_store(db.pool().clone());
+        let address = format!("{:#x}", manager.signer.address());
+        let tx: TypedTransaction = TransactionRequest::new().to(Address::repeat_byte(1)).gas(21_000u64).into();
+
+        let pending = manager.broadcast("submitProof", Some("0xaa"), tx, FeeUrgency::Normal).await.unwrap();
+
+        // Tracked as pending (watched, replaced and counted), not rolled back
+        let store = PostgresRelayerNonceRepository::new(db.pool().clone());
+        let rows = store.list_pending(&address).await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].tx_hash, format!("{:#x}", pending.tx_hash));
+        assert_eq!(pending.id, Some(rows[0].id));
+
+        // The next send takes the following nonce
+        let next: TypedTransaction = TransactionRequest::new().to(Address::repeat_byte(1)).gas(21_000u64).into();
+        let next = manager.broadcast("fillOrder", None, next, FeeUrgency::Normal).await.unwrap();
+        assert_eq!(next.nonce, U256::one());
+    }
+
+    #[test]
+    fn test_latest_hash() {
+        let first = H256::repeat_byte(1);
+        assert_eq!(pending(0, vec![first]).latest_hash(), first);
+        assert_eq!(pending(0, vec![first, H256::repeat_byte(2)]).latest_hash(), H256::repeat_byte(2));
+    }
+
+    #[test]
+    fn test_pending_from_row() {
+        let tx: TypedTransaction = TransactionRequest::new().nonce(7).gas_price(1_000u64).into();
+        let row = DbRelayerPendingTx {
+            id: 42,
+            address: "0x00000000000000000000000000000000000000a1".to_string(),
+            nonce: 7,
+            operation: "submitProof".to_string(),
+            trade_id: Some("0xaa".to_string()),
+            tx_hash: format!("{:#x}", H256::repeat_byte(2)),
+            tx_hashes: vec![format!("{:#x}", H256::repeat_byte(1)), format!("{:#x}", H256::repeat_byte(2))],
+            gas_price: "1000".to_string(),
+            replacements: 1,
+            tx_request: serde_json::to_string(&tx).unwrap(),
+            status: "pending".to_string(),
+            created_at: chrono::Utc::now(),
+        };
+
+        let pending = pending_from_row(&row).unwrap();
+        assert_eq!(pending.nonce, U256::from(7));
+        assert_eq!(pending.latest_hash(), H256::repeat_byte(2));
+        assert_eq!(pending.hashes.len(), 2);
+        assert_eq!(pending.replacements, 1);
+        assert_eq!(pending.fees, TxFees::Legacy { gas_price: U256::from(1_000) });
+        assert_eq!(pending.id, Some(42));
+
+        let broken = DbRelayerPendingTx { tx_request: "{}".to_string(), ..row };
+        assert!(pending_from_row(&broken).is_err());
+    }
+
+    #[test]
+    fn test_config_from_lookup() {
+        let config = TxManagerConfig::from_lookup(|name| match name {
+            "RELAYER_TX_GAS_BUMP_PERCENT" => Some("5".to_string()),
+            "RELAYER_TX_CONFIRM_TIMEOUT_SECS" => Some("600".to_string()),
+            "RELAYER_TX_MAX_REPLACEMENTS" => Some("many".to_string()),
+            _ => None,
+        });
+
+        // Nodes reject replacements under 10%; invalid values keep the default
+        assert_eq!(config.bump_percent, 10);
+        assert_eq!(config.confirm_timeout, Duration::from_secs(600));
+        assert_eq!(config.max_replacements, TxManagerConfig::default().max_replacements);
+    }
+
+    #[test]
+    fn test_select_nonce() {
+        // Fresh address, or the chain caught up with the stored sequence
+        assert_eq!(select_nonce(7, None, false), 7);
+        assert_eq!(select_nonce(7, Some(7), false), 7);
+        assert_eq!(select_nonce(9, Some(7), true), 9);
+
+        // Tracked transactions the RPC node hasn't seen yet
+        assert_eq!(select_nonce(7, Some(9), true), 9);
+
+        // Stored sequence ahead with nothing tracked: fill the gap
+        assert_eq!(select_nonce(7, Some(9), false), 7);
+    }
+}
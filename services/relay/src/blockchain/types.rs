@@ -26,6 +26,16 @@ pub struct ContractConfig {
     pub fee_calculator_address: String, // Fee calculator contract address
 }
 
+/// Unsigned contract call for self-submission from the user's own wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnsignedTransaction {
+    pub to: String,                    // Escrow contract address
+    pub data: String,                  // ABI-encoded calldata (0x-prefixed)
+    pub value: String,                 // Always "0" - escrow calls are non-payable
+    pub gas_estimate: String,          // Estimated gas incl. 20% buffer
+    pub chain_id: u64,
+}
+
 /// Convert bytes32 string (0x-prefixed hex) to [u8; 32]
 /// Works for trade_id, order_id, or any bytes32 value
 fn bytes32_from_hex(hex_value: &str) -> Result<[u8; 32]> {
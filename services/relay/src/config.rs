@@ -19,10 +19,11 @@ pub struct Config {
     // Blockchain
     pub chain_id: u64,
     pub rpc_url: String,
+    pub rpc_ws_url: Option<String>,           // Event listener subscription (polls without it)
     pub escrow_address: String,
     
     // Relayer (for signing transactions)
-    pub relayer_private_key: Option<String>,
+    pub relayer_signer: Option<RelayerSignerConfig>,
     
     // Axiom API (for ZK proof generation)
     pub axiom_api_key: Option<String>,
@@ -55,13 +56,15 @@ impl Config {
         let rpc_url = env::var("RPC_URL")
             .unwrap_or_else(|_| "https://mainnet.base.org".to_string());
         
+        let rpc_ws_url = env::var("RPC_WS_URL").ok().filter(|url| !url.is_empty());
+        
         // Escrow contract address (required)
         let escrow_address = env::var("ESCROW_ADDRESS")
             .or_else(|_| env::var("ESCROW_CONTRACT_ADDRESS"))
             .map_err(|_| ConfigError::Missing("ESCROW_ADDRESS".to_string()))?;
         
-        // Relayer private key (for fillOrder, submitProof, cancelExpiredTrade)
-        let relayer_private_key = env::var("RELAYER_PRIVATE_KEY").ok();
+        // Relayer signer (for fillOrder, submitProof, cancelExpiredTrade)
+        let relayer_signer = RelayerSignerConfig::from_env()?;
         
         // Axiom API key (for ZK proof generation)
         let axiom_api_key = env::var("AXIOM_API_KEY").ok();
@@ -75,8 +78,9 @@ impl Config {
             api_port,
             chain_id,
             rpc_url,
+            rpc_ws_url,
             escrow_address,
-            relayer_private_key,
+            relayer_signer,
             axiom_api_key,
             resend_api_key,
         })
@@ -87,18 +91,93 @@ impl Config {
         tracing::info!("=== LyncZ Configuration ===");
         tracing::info!("Network: Chain ID {}", self.chain_id);
         tracing::info!("RPC: {}...", &self.rpc_url[..50.min(self.rpc_url.len())]);
+        tracing::info!("Events: {}", if self.rpc_ws_url.is_some() { "✅ WebSocket subscription" } else { "Polling" });
         tracing::info!("Escrow: {}", self.escrow_address);
-        tracing::info!("Relayer: {}", if self.relayer_private_key.is_some() { "✅ Set" } else { "❌ Not set" });
+        match &self.relayer_signer {
+            Some(signer) => tracing::info!("Relayer: ✅ {}", signer.kind()),
+            None => tracing::info!("Relayer: ❌ Not set"),
+        }
         tracing::info!("Axiom API: {}", if self.axiom_api_key.is_some() { "✅ Set" } else { "❌ Not set" });
         tracing::info!("Resend API: {}", if self.resend_api_key.is_some() { "✅ Set" } else { "❌ Not set" });
         tracing::info!("===========================");
     }
 }
 
+/// Where the relayer key lives
+///
+/// RELAYER_SIGNER picks one explicitly (`env`, `keystore` or `remote`); otherwise the
+/// first configured of RELAYER_SIGNER_URL, RELAYER_KEYSTORE_PATH and RELAYER_PRIVATE_KEY.
+#[derive(Clone)]
+pub enum RelayerSignerConfig {
+    /// RELAYER_PRIVATE_KEY - hot key in the process environment
+    PrivateKey(String),
+    /// RELAYER_KEYSTORE_PATH - encrypted JSON keystore, unlocked with
+    /// RELAYER_KEYSTORE_PASSPHRASE or the contents of RELAYER_KEYSTORE_PASSPHRASE_FILE
+    Keystore { path: String, passphrase: String },
+    /// RELAYER_SIGNER_URL - Web3Signer-compatible JSON-RPC signer holding the key;
+    /// RELAYER_ADDRESS selects the account if it holds several
+    Remote { url: String, address: Option<String> },
+}
+
+impl RelayerSignerConfig {
+    fn from_env() -> Result<Option<Self>, ConfigError> {
+        let kind = match env::var("RELAYER_SIGNER") {
+            Ok(kind) => kind.to_lowercase(),
+            Err(_) if env::var("RELAYER_SIGNER_URL").is_ok() => "remote".to_string(),
+            Err(_) if env::var("RELAYER_KEYSTORE_PATH").is_ok() => "keystore".to_string(),
+            Err(_) if env::var("RELAYER_PRIVATE_KEY").is_ok() => "env".to_string(),
+            Err(_) => return Ok(None),
+        };
+        
+        let signer = match kind.as_str() {
+            "env" => Self::PrivateKey(required("RELAYER_PRIVATE_KEY")?),
+            "keystore" => {
+                let passphrase = match env::var("RELAYER_KEYSTORE_PASSPHRASE_FILE") {
+                    Ok(file) => std::fs::read_to_string(&file)
+                        .map_err(|e| ConfigError::Invalid(format!("RELAYER_KEYSTORE_PASSPHRASE_FILE {}: {}", file, e)))?
+                        .trim_end_matches(['\r', '\n'])
+                        .to_string(),
+                    Err(_) => required("RELAYER_KEYSTORE_PASSPHRASE")?,
+                };
+                Self::Keystore { path: required("RELAYER_KEYSTORE_PATH")?, passphrase }
+            }
+            "remote" => Self::Remote {
+                url: required("RELAYER_SIGNER_URL")?,
+                address: env::var("RELAYER_ADDRESS").ok(),
+            },
+            other => return Err(ConfigError::Invalid(format!("RELAYER_SIGNER must be env, keystore or remote (got {})", other))),
+        };
+        Ok(Some(signer))
+    }
+    
+    /// Signer kind for logs
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::PrivateKey(_) => "env key",
+            Self::Keystore { .. } => "encrypted keystore",
+            Self::Remote { .. } => "remote signer",
+        }
+    }
+}
+
+// Never print key material
+impl std::fmt::Debug for RelayerSignerConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PrivateKey(_) => write!(f, "PrivateKey(..)"),
+            Self::Keystore { path, .. } => write!(f, "Keystore {{ path: {:?} }}", path),
+            Self::Remote { url, address } => write!(f, "Remote {{ url: {:?}, address: {:?} }}", url, address),
+        }
+    }
+}
+
+fn required(name: &str) -> Result<String, ConfigError> {
+    env::var(name).map_err(|_| ConfigError::Missing(name.to_string()))
+}
+
 #[derive(Debug)]
 pub enum ConfigError {
     Missing(String),
-    #[allow(dead_code)]
     Invalid(String),
 }
 
@@ -132,6 +132,33 @@ pub fn compute_time_amount_hash(payment_time: &str, amount_line: &str) -> [u8; 3
     Sha256::digest(&data).into()
 }
 
+// ============================================================================
+// Trade Memo (optional, line number varies by receipt)
+// ============================================================================
+
+/// Trade reference code the buyer puts in the Alipay memo (备注):
+/// first 8 bytes of the tradeId as uppercase hex. Mirrors `AlipayVerifier.memoLine`.
+/// 
+/// 64 bits, so no grinding of trade IDs can make another trade's code match.
+/// 
+/// # Example
+/// - tradeId `0x1a2b3c4d5e6f7081...` -> `"1A2B3C4D5E6F7081"`
+pub fn trade_memo_code(trade_id: &[u8; 32]) -> String {
+    hex::encode_upper(&trade_id[..8])
+}
+
+/// Format the memo line as it appears in the Alipay PDF (e.g., `"备注：1A2B3C4D5E6F7081"`)
+pub fn format_memo_line(memo_code: &str) -> String {
+    format!("备注：{}", memo_code)
+}
+
+/// Compute memo_hash = SHA256(memo_line)
+/// 
+/// The line number is not hashed - the memo's position varies across receipt types.
+pub fn compute_memo_hash(memo_line: &str) -> [u8; 32] {
+    Sha256::digest(memo_line.as_bytes()).into()
+}
+
 // ============================================================================
 // Final Output Hash (combines all sub-hashes)
 // ============================================================================
@@ -139,7 +166,7 @@ pub fn compute_time_amount_hash(payment_time: &str, amount_line: &str) -> [u8; 3
 /// Compute the final expected hash using account_lines_hash from blockchain.
 /// 
 /// ```text
-/// output = SHA256(is_valid || pk_hash || account_lines_hash || tx_id_hash || time_amount_hash)
+/// output = SHA256(is_valid || pk_hash || account_lines_hash || tx_id_hash || time_amount_hash [|| memo_hash])
 /// ```
 /// 
 /// This uses the pre-computed account_lines_hash from the blockchain,
@@ -151,12 +178,14 @@ pub fn compute_time_amount_hash(payment_time: &str, amount_line: &str) -> [u8; 3
 /// - `line27`: Payment time (no prefix)
 /// - `line29`: Amount with prefix (e.g., `"小写：1.00"`)
 /// - `pk_hash_hex`: Alipay public key hash (hex, no 0x prefix)
+/// - `memo_line`: Memo line (e.g., `"备注：1A2B3C4D5E6F7081"`) for memo-bound trades, None otherwise
 pub fn compute_expected_hash_with_onchain_account_hash(
     account_lines_hash_hex: &str,
     line25: &str,
     line27: &str,
     line29: &str,
     pk_hash_hex: &str,
+    memo_line: Option<&str>,
 ) -> Result<[u8; 32], String> {
     // Decode the on-chain account_lines_hash
     let account_lines_hash_clean = account_lines_hash_hex.strip_prefix("0x")
@@ -182,21 +211,57 @@ pub fn compute_expected_hash_with_onchain_account_hash(
     final_data.extend_from_slice(&account_lines_hash);
     final_data.extend_from_slice(&tx_id_hash);
     final_data.extend_from_slice(&time_amount_hash);
+    if let Some(memo_line) = memo_line {
+        final_data.extend_from_slice(&compute_memo_hash(memo_line));
+    }
     
     Ok(Sha256::digest(&final_data).into())
 }
 
 // ============================================================================
-// Helper: Format Amount Line (Line 29)
+// Batch Root (one proof for several receipts)
 // ============================================================================
 
-/// Format line 29 (amount) as it appears in Alipay PDF.
+/// Compute the batch root over per-receipt expected hashes.
 /// 
-/// # Example
-/// - `format_amount_line(10050)` -> `"小写：100.50"`
-pub fn format_amount_line(cny_cents: u64) -> String {
-    let amount_str = format!("{}.{:02}", cny_cents / 100, cny_cents % 100);
-    format!("小写：{}", amount_str)
+/// ```text
+/// leaf   = SHA256(0x00 || expected_hash)
+/// parent = SHA256(0x01 || left || right), an odd last node is carried up unchanged
+/// ```
+/// 
+/// Mirrors the guest's `batch_root` and `AlipayVerifier.batchRoot`. The prefixes keep
+/// an interior node from passing as a leaf. A single receipt's root is its own
+/// expected hash (what `verifyPayment` checks). Returns None for an empty batch.
+pub fn compute_batch_root(leaves: &[[u8; 32]]) -> Option<[u8; 32]> {
+    if let [single] = leaves {
+        return Some(*single);
+    }
+    let mut level: Vec<[u8; 32]> = leaves
+        .iter()
+        .map(|leaf| {
+            let mut hasher = Sha256::new();
+            hasher.update([0x00]);
+            hasher.update(leaf);
+            hasher.finalize().into()
+        })
+        .collect();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut hasher = Sha256::new();
+                    hasher.update([0x01]);
+                    hasher.update(left);
+                    hasher.update(right);
+                    hasher.finalize().into()
+                }
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    level.first().copied()
 }
 
 #[cfg(test)]
@@ -235,14 +300,6 @@ mod tests {
         assert_eq!(mask_alipay_account_id("a"), "a***");
     }
 
-    #[test]
-    fn test_format_amount_line() {
-        assert_eq!(format_amount_line(10050), "小写：100.50");
-        assert_eq!(format_amount_line(100), "小写：1.00");
-        assert_eq!(format_amount_line(35), "小写：0.35");
-        assert_eq!(format_amount_line(5), "小写：0.05");
-    }
-
     #[test]
     fn test_compute_account_lines_hash_deterministic() {
         // Same input should always produce same hash
@@ -364,4 +421,79 @@ mod tests {
         
         assert_eq!(hash1, hash2);
     }
+
+    #[test]
+    fn test_trade_memo_code() {
+        let mut trade_id = [0u8; 32];
+        trade_id[..8].copy_from_slice(&[0x1a, 0x2b, 0x3c, 0x4d, 0x5e, 0x6f, 0x70, 0x81]);
+        trade_id[8] = 0xff; // only the first 8 bytes are used
+        
+        assert_eq!(trade_memo_code(&trade_id), "1A2B3C4D5E6F7081");
+        assert_eq!(format_memo_line("1A2B3C4D5E6F7081"), "备注：1A2B3C4D5E6F7081");
+    }
+
+    #[test]
+    fn test_expected_hash_with_memo() {
+        let account_hash = format!("0x{}", "11".repeat(32));
+        let pk_hash = "22".repeat(32);
+        let memo_line = format_memo_line("1A2B3C4D5E6F7081");
+        
+        let without_memo = compute_expected_hash_with_onchain_account_hash(
+            &account_hash, "2025122722001", "2025-12-27 08:36:12", "小写：100.00", &pk_hash, None,
+        ).unwrap();
+        let with_memo = compute_expected_hash_with_onchain_account_hash(
+            &account_hash, "2025122722001", "2025-12-27 08:36:12", "小写：100.00", &pk_hash, Some(&memo_line),
+        ).unwrap();
+        
+        assert_ne!(without_memo, with_memo);
+        
+        // with_memo = SHA256(without_memo preimage || SHA256(memo_line))
+        let mut preimage = vec![0x01];
+        preimage.extend_from_slice(&[0x22; 32]);
+        preimage.extend_from_slice(&[0x11; 32]);
+        preimage.extend_from_slice(&compute_tx_id_hash("2025122722001"));
+        preimage.extend_from_slice(&compute_time_amount_hash("2025-12-27 08:36:12", "小写：100.00"));
+        let expected_without: [u8; 32] = Sha256::digest(&preimage).into();
+        assert_eq!(without_memo, expected_without);
+        
+        preimage.extend_from_slice(&compute_memo_hash(&memo_line));
+        let expected_with: [u8; 32] = Sha256::digest(&preimage).into();
+        assert_eq!(with_memo, expected_with);
+    }
+
+    #[test]
+    fn test_batch_root() {
+        let leaf = |hash: [u8; 32]| -> [u8; 32] {
+            Sha256::digest([&[0x00][..], &hash].concat()).into()
+        };
+        let node = |left: [u8; 32], right: [u8; 32]| -> [u8; 32] {
+            Sha256::digest([&[0x01][..], &left, &right].concat()).into()
+        };
+        let (a, b, c) = ([0xaa; 32], [0xbb; 32], [0xcc; 32]);
+        
+        assert_eq!(compute_batch_root(&[]), None);
+        assert_eq!(compute_batch_root(&[a]), Some(a));
+        assert_eq!(compute_batch_root(&[a, b]), Some(node(leaf(a), leaf(b))));
+        // Odd last node is carried up unchanged
+        assert_eq!(compute_batch_root(&[a, b, c]), Some(node(node(leaf(a), leaf(b)), leaf(c))));
+        assert_ne!(compute_batch_root(&[a, b]), compute_batch_root(&[b, a]));
+    }
+
+    #[test]
+    fn test_batch_root_interior_node_is_not_a_leaf() {
+        let (a, b, c, d) = ([0xaa; 32], [0xbb; 32], [0xcc; 32], [0xdd; 32]);
+        let root = compute_batch_root(&[a, b, c, d]).unwrap();
+        
+        // Without prefixes, [ab, cd] would prove the same root as [a, b, c, d]
+        let hash_pair = |left: [u8; 32], right: [u8; 32]| -> [u8; 32] {
+            Sha256::digest([&[0x01][..], &left, &right].concat()).into()
+        };
+        let leaf = |hash: [u8; 32]| -> [u8; 32] {
+            Sha256::digest([&[0x00][..], &hash].concat()).into()
+        };
+        let ab = hash_pair(leaf(a), leaf(b));
+        let cd = hash_pair(leaf(c), leaf(d));
+        assert_eq!(root, hash_pair(ab, cd));
+        assert_ne!(compute_batch_root(&[ab, cd]), Some(root));
+    }
 }
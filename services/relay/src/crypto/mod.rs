@@ -6,7 +6,12 @@ pub mod hash;
 pub use hash::{
     mask_alipay_account_id,
     compute_account_lines_hash,
+    compute_account_lines_hash_from_lines,
     compute_tx_id_hash,
+    compute_time_amount_hash,
+    compute_memo_hash,
     compute_expected_hash_with_onchain_account_hash,
-    format_amount_line,
+    compute_batch_root,
+    trade_memo_code,
+    format_memo_line,
 };
@@ -0,0 +1,42 @@
+use sqlx::PgPool;
+
+use super::DbResult;
+
+/// Repository for trusted Alipay signing key hashes (key-set leaves)
+pub struct PostgresAlipayKeyRepository {
+    pool: PgPool,
+}
+
+impl PostgresAlipayKeyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+    
+    /// All trusted key hashes (0x hex)
+    pub async fn list(&self) -> DbResult<Vec<String>> {
+        let keys = sqlx::query_scalar(
+            r#"SELECT "pkHash" FROM alipay_keys ORDER BY "pkHash""#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(keys)
+    }
+    
+    /// Add a key hash - returns false if it was already trusted
+    pub async fn insert(&self, pk_hash: &str, trade_id: &str) -> DbResult<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO alipay_keys ("pkHash", "firstTradeId")
+            VALUES ($1, $2)
+            ON CONFLICT ("pkHash") DO NOTHING
+            "#,
+        )
+        .bind(pk_hash)
+        .bind(trade_id)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+}
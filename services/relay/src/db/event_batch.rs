@@ -0,0 +1,431 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+
+use super::{DbError, DbResult};
+use super::models::{DbOrder, DbTrade};
+
+/// One synced block range (or a dead-letter retry) applied in a single transaction
+///
+/// Each log runs between `savepoint` and `release` - a failing handler is rolled back
+/// to its savepoint and dead-lettered while the rest of the range commits.
+/// Dropping the batch without `commit` rolls everything back.
+pub struct EventBatch {
+    tx: Transaction<'static, Postgres>,
+    contract_address: String,
+}
+
+/// A synced event log (position and raw content)
+pub struct BatchLog<'a> {
+    pub block_number: u64,
+    pub block_hash: &'a str,
+    pub tx_hash: &'a str,
+    pub log_index: u64,
+    pub event: &'a str,
+    /// Order or trade ID (first indexed topic)
+    pub entity_id: Option<&'a str>,
+    pub topics: &'a [String],
+    pub data: &'a str,
+}
+
+impl EventBatch {
+    pub async fn begin(pool: &PgPool, contract_address: &str) -> DbResult<Self> {
+        let tx = pool.begin().await?;
+        
+        Ok(Self {
+            tx,
+            contract_address: contract_address.to_string(),
+        })
+    }
+    
+    // ============ Per-log savepoints ============
+    
+    pub async fn savepoint(&mut self) -> DbResult<()> {
+        sqlx::query("SAVEPOINT event_log").execute(&mut *self.tx).await?;
+        Ok(())
+    }
+    
+    pub async fn release(&mut self) -> DbResult<()> {
+        sqlx::query("RELEASE SAVEPOINT event_log").execute(&mut *self.tx).await?;
+        Ok(())
+    }
+    
+    /// Undo everything the current log wrote
+    pub async fn rollback_to_savepoint(&mut self) -> DbResult<()> {
+        sqlx::query("ROLLBACK TO SAVEPOINT event_log").execute(&mut *self.tx).await?;
+        Ok(())
+    }
+    
+    // ============ Bookkeeping ============
+    
+    /// Claim a log by (txHash, logIndex) - false if it was already applied
+    pub async fn claim(&mut self, log: &BatchLog<'_>) -> DbResult<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO processed_logs ("txHash", "logIndex", "contractAddress", "blockNumber", "blockHash")
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT ("txHash", "logIndex") DO NOTHING
+            "#,
+        )
+        .bind(log.tx_hash)
+        .bind(log.log_index as i64)
+        .bind(&self.contract_address)
+        .bind(log.block_number as i64)
+        .bind(log.block_hash)
+        .execute(&mut *self.tx)
+        .await?;
+        
+        Ok(result.rows_affected() == 1)
+    }
+    
+    /// Journal an applied log so a reorg can undo it
+    pub async fn journal(&mut self, log: &BatchLog<'_>) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO applied_events
+                ("contractAddress", "blockNumber", "blockHash", "txHash", "logIndex", "event", "entityId", "topics", "data")
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(&self.contract_address)
+        .bind(log.block_number as i64)
+        .bind(log.block_hash)
+        .bind(log.tx_hash)
+        .bind(log.log_index as i64)
+        .bind(log.event)
+        .bind(log.entity_id)
+        .bind(log.topics)
+        .bind(log.data)
+        .execute(&mut *self.tx)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Store the hash of a synced block (checkpoint or block with events)
+    pub async fn record_block(&mut self, block_number: u64, block_hash: &str) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO event_sync_blocks ("contractAddress", "blockNumber", "blockHash")
+            VALUES ($1, $2, $3)
+            ON CONFLICT ("contractAddress", "blockNumber") DO UPDATE SET "blockHash" = $3, "createdAt" = NOW()
+            "#,
+        )
+        .bind(&self.contract_address)
+        .bind(block_number as i64)
+        .bind(block_hash)
+        .execute(&mut *self.tx)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Dead-letter a log whose handler failed (retried from `next_retry_at`, None = never)
+    pub async fn dead_letter(
+        &mut self,
+        log: &BatchLog<'_>,
+        error: &str,
+        next_retry_at: Option<DateTime<Utc>>,
+    ) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO failed_logs
+                ("contractAddress", "blockNumber", "blockHash", "txHash", "logIndex", "event", "topics", "data", "error", "nextRetryAt")
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT ("txHash", "logIndex") DO UPDATE SET "error" = $9, "updatedAt" = NOW()
+            "#,
+        )
+        .bind(&self.contract_address)
+        .bind(log.block_number as i64)
+        .bind(log.block_hash)
+        .bind(log.tx_hash)
+        .bind(log.log_index as i64)
+        .bind(log.event)
+        .bind(log.topics)
+        .bind(log.data)
+        .bind(error)
+        .bind(next_retry_at)
+        .execute(&mut *self.tx)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Remove a dead-lettered log once it was applied
+    pub async fn resolve_failed_log(&mut self, id: i64) -> DbResult<()> {
+        sqlx::query(r#"DELETE FROM failed_logs WHERE "id" = $1"#)
+            .bind(id)
+            .execute(&mut *self.tx)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Record a replayed chunk of a lyncz-reindex run, committed with the chunk
+    /// (counts are added to the run's totals)
+    pub async fn advance_reindex_run(
+        &mut self,
+        run_id: i64,
+        next_block: u64,
+        applied: u64,
+        skipped: u64,
+        failed: u64,
+    ) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE reindex_runs
+            SET "nextBlock" = $2,
+                "logsApplied" = "logsApplied" + $3,
+                "logsSkipped" = "logsSkipped" + $4,
+                "logsFailed" = "logsFailed" + $5,
+                "updatedAt" = NOW()
+            WHERE "id" = $1
+            "#,
+        )
+        .bind(run_id)
+        .bind(next_block as i64)
+        .bind(applied as i64)
+        .bind(skipped as i64)
+        .bind(failed as i64)
+        .execute(&mut *self.tx)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Commit, storing the next block to sync when the batch covered a block range
+    pub async fn commit(mut self, next_block: Option<u64>) -> DbResult<()> {
+        if let Some(next_block) = next_block {
+            sqlx::query(
+                "INSERT INTO event_sync_state (contract_address, last_synced_block)
+                 VALUES ($1, $2)
+                 ON CONFLICT (contract_address)
+                 DO UPDATE SET last_synced_block = $2, last_synced_at = NOW()",
+            )
+            .bind(&self.contract_address)
+            .bind(next_block as i64)
+            .execute(&mut *self.tx)
+            .await?;
+        }
+        
+        self.tx.commit().await?;
+        Ok(())
+    }
+    
+    /// Chain-mirrored fields of an order or trade as JSON (reindex dry runs compare them)
+    pub async fn snapshot(&mut self, entity_id: &str) -> DbResult<Option<(&'static str, String)>> {
+        let order: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT jsonb_build_object(
+                'seller', o."seller", 'token', o."token", 'totalAmount', o."totalAmount"::TEXT,
+                'remainingAmount', o."remainingAmount"::TEXT, 'exchangeRate', o."exchangeRate"::TEXT,
+                'rail', o."rail", 'isPublic', o."isPublic", 'memoRequired', o."memoRequired",
+                'withdrawals', (SELECT COUNT(*) FROM withdrawals w WHERE w."orderId" = o."orderId")
+            )::TEXT
+            FROM orders o WHERE o."orderId" = $1
+            "#,
+        )
+        .bind(entity_id)
+        .fetch_optional(&mut *self.tx)
+        .await?;
+        
+        if let Some(order) = order {
+            return Ok(Some(("order", order)));
+        }
+        
+        let trade: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT jsonb_build_object(
+                'orderId', "orderId", 'buyer', "buyer", 'tokenAmount', "tokenAmount"::TEXT,
+                'cnyAmount', "cnyAmount"::TEXT, 'feeAmount', "feeAmount"::TEXT, 'status', "status",
+                'escrowTxHash', "escrowTxHash", 'settlementTxHash', "settlementTxHash",
+                'memo_code', memo_code, 'settlement_state', settlement_state
+            )::TEXT
+            FROM trades WHERE "tradeId" = $1
+            "#,
+        )
+        .bind(entity_id)
+        .fetch_optional(&mut *self.tx)
+        .await?;
+        
+        Ok(trade.map(|trade| ("trade", trade)))
+    }
+    
+    // ============ Orders ============
+    
+    pub async fn upsert_order(&mut self, order: &DbOrder) -> DbResult<()> {
+        super::orders::upsert_order(&mut self.tx, order).await
+    }
+    
+    /// Payment rail of an order (None if it isn't synced)
+    pub async fn order_rail(&mut self, order_id: &str) -> DbResult<Option<i32>> {
+        let rail = sqlx::query_scalar(r#"SELECT "rail" FROM orders WHERE "orderId" = $1"#)
+            .bind(order_id)
+            .fetch_optional(&mut *self.tx)
+            .await?;
+        
+        Ok(rail)
+    }
+    
+    /// Adjust order remaining amount by delta (+ or -)
+    pub async fn adjust_remaining_amount(&mut self, order_id: &str, delta: &str) -> DbResult<()> {
+        let result = sqlx::query(
+            r#"UPDATE orders SET "remainingAmount" = "remainingAmount" + $1::NUMERIC WHERE "orderId" = $2"#,
+        )
+        .bind(delta)
+        .bind(order_id)
+        .execute(&mut *self.tx)
+        .await?;
+        
+        if result.rows_affected() == 0 {
+            return Err(DbError::OrderNotFound(order_id.to_string()));
+        }
+        
+        Ok(())
+    }
+    
+    pub async fn update_exchange_rate(&mut self, order_id: &str, new_rate: &str) -> DbResult<()> {
+        let result = sqlx::query(r#"UPDATE orders SET "exchangeRate" = $1::NUMERIC WHERE "orderId" = $2"#)
+            .bind(new_rate)
+            .bind(order_id)
+            .execute(&mut *self.tx)
+            .await?;
+        
+        if result.rows_affected() == 0 {
+            return Err(DbError::OrderNotFound(order_id.to_string()));
+        }
+        
+        Ok(())
+    }
+    
+    pub async fn set_memo_required(&mut self, order_id: &str, required: bool) -> DbResult<()> {
+        let result = sqlx::query(r#"UPDATE orders SET "memoRequired" = $1 WHERE "orderId" = $2"#)
+            .bind(required)
+            .bind(order_id)
+            .execute(&mut *self.tx)
+            .await?;
+        
+        if result.rows_affected() == 0 {
+            return Err(DbError::OrderNotFound(order_id.to_string()));
+        }
+        
+        Ok(())
+    }
+    
+    /// Record a withdrawal for the order activity timeline
+    pub async fn insert_withdrawal(
+        &mut self,
+        order_id: &str,
+        amount: &str,
+        remaining_after: &str,
+        tx_hash: Option<&str>,
+    ) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO withdrawals ("orderId", "amount", "remainingAfter", "txHash")
+            VALUES ($1, $2::NUMERIC, $3::NUMERIC, $4)
+            "#,
+        )
+        .bind(order_id)
+        .bind(amount)
+        .bind(remaining_after)
+        .bind(tx_hash)
+        .execute(&mut *self.tx)
+        .await?;
+        
+        Ok(())
+    }
+    
+    // ============ Trades ============
+    
+    pub async fn insert_trade(&mut self, trade: &DbTrade) -> DbResult<()> {
+        super::trades::insert_trade(&mut self.tx, trade).await
+    }
+    
+    pub async fn set_memo_code(&mut self, trade_id: &str, memo_code: &str) -> DbResult<()> {
+        let result = sqlx::query(r#"UPDATE trades SET memo_code = $1 WHERE "tradeId" = $2"#)
+            .bind(memo_code)
+            .bind(trade_id)
+            .execute(&mut *self.tx)
+            .await?;
+        
+        if result.rows_affected() == 0 {
+            return Err(DbError::TradeNotFound(trade_id.to_string()));
+        }
+        
+        Ok(())
+    }
+    
+    /// Mark a trade SETTLED, dropping any stale relayer settlement error
+    pub async fn settle_trade(&mut self, trade_id: &str, settlement_tx_hash: Option<&str>) -> DbResult<()> {
+        let result = sqlx::query(
+            r#"
+            UPDATE trades
+            SET "status" = 1,
+                "settlementTxHash" = COALESCE($2, "settlementTxHash"),
+                settlement_error = NULL
+            WHERE "tradeId" = $1
+            "#,
+        )
+        .bind(trade_id)
+        .bind(settlement_tx_hash)
+        .execute(&mut *self.tx)
+        .await?;
+        
+        if result.rows_affected() == 0 {
+            return Err(DbError::TradeNotFound(trade_id.to_string()));
+        }
+        
+        Ok(())
+    }
+    
+    /// Mark a trade EXPIRED and free the receipt it reserved
+    pub async fn expire_trade(&mut self, trade_id: &str) -> DbResult<()> {
+        let result = sqlx::query(r#"UPDATE trades SET "status" = 2 WHERE "tradeId" = $1"#)
+            .bind(trade_id)
+            .execute(&mut *self.tx)
+            .await?;
+        
+        if result.rows_affected() == 0 {
+            return Err(DbError::TradeNotFound(trade_id.to_string()));
+        }
+        
+        sqlx::query(r#"DELETE FROM receipt_reservations WHERE "tradeId" = $1"#)
+            .bind(trade_id)
+            .execute(&mut *self.tx)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Current settlement state of a trade (None if settlement never started)
+    pub async fn settlement_state(&mut self, trade_id: &str) -> DbResult<Option<String>> {
+        let state: Option<Option<String>> = sqlx::query_scalar(
+            r#"SELECT settlement_state FROM trades WHERE "tradeId" = $1"#,
+        )
+        .bind(trade_id)
+        .fetch_optional(&mut *self.tx)
+        .await?;
+        
+        Ok(state.flatten())
+    }
+    
+    /// Append to the settlement timeline and update the trade's current state
+    pub async fn record_settlement_state(&mut self, trade_id: &str, state: &str, detail: Option<&str>) -> DbResult<()> {
+        sqlx::query(
+            r#"INSERT INTO trade_settlement_events ("tradeId", "state", "detail") VALUES ($1, $2, $3)"#,
+        )
+        .bind(trade_id)
+        .bind(state)
+        .bind(detail)
+        .execute(&mut *self.tx)
+        .await?;
+        
+        sqlx::query(r#"UPDATE trades SET settlement_state = $1 WHERE "tradeId" = $2"#)
+            .bind(state)
+            .bind(trade_id)
+            .execute(&mut *self.tx)
+            .await?;
+        
+        Ok(())
+    }
+}
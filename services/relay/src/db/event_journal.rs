@@ -0,0 +1,321 @@
+use sqlx::{PgPool, Postgres, Transaction};
+
+use super::DbResult;
+use super::models::DbAppliedEvent;
+
+/// Repository for reorg tracking - synced block hashes and the applied-event journal
+/// (written by `EventBatch`). A reorg is undone through a `ChainRollback` (one
+/// transaction, committed at the ancestor)
+#[derive(Clone)]
+pub struct PostgresEventJournalRepository {
+    pool: PgPool,
+}
+
+/// Undo of the events above a common ancestor
+/// Dropping the rollback without `commit` leaves the database untouched
+pub struct ChainRollback {
+    tx: Transaction<'static, Postgres>,
+    contract_address: String,
+}
+
+/// Order or trade whose creation was rolled back and never replayed
+pub struct OrphanedEntity {
+    pub event: String,
+    pub entity_id: String,
+}
+
+impl PostgresEventJournalRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+    
+    /// Stored hash of a block (None if it was never checkpointed or carried no event)
+    pub async fn block_hash(&self, contract_address: &str, block_number: u64) -> DbResult<Option<String>> {
+        let hash = sqlx::query_scalar(
+            r#"SELECT "blockHash" FROM event_sync_blocks WHERE "contractAddress" = $1 AND "blockNumber" = $2"#,
+        )
+        .bind(contract_address)
+        .bind(block_number as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(hash)
+    }
+    
+    /// Stored blocks below `below`, newest first
+    pub async fn blocks_below(&self, contract_address: &str, below: u64, limit: i64) -> DbResult<Vec<(u64, String)>> {
+        let rows: Vec<(i64, String)> = sqlx::query_as(
+            r#"
+            SELECT "blockNumber", "blockHash" FROM event_sync_blocks
+            WHERE "contractAddress" = $1 AND "blockNumber" < $2
+            ORDER BY "blockNumber" DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(contract_address)
+        .bind(below as i64)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter().map(|(number, hash)| (number as u64, hash)).collect())
+    }
+    
+    /// Applied (not orphaned) events above `ancestor`, newest first - the undo order
+    pub async fn events_after(&self, contract_address: &str, ancestor: u64) -> DbResult<Vec<DbAppliedEvent>> {
+        let rows = sqlx::query_as::<_, DbAppliedEvent>(
+            r#"
+            SELECT "id", "blockNumber", "blockHash", "txHash", "logIndex", "event", "entityId", "topics", "data"
+            FROM applied_events
+            WHERE "contractAddress" = $1 AND "blockNumber" > $2 AND NOT "orphaned"
+            ORDER BY "blockNumber" DESC, "logIndex" DESC, "id" DESC
+            "#,
+        )
+        .bind(contract_address)
+        .bind(ancestor as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows)
+    }
+    
+    /// Start undoing a reorg
+    pub async fn begin_rollback(&self, contract_address: &str) -> DbResult<ChainRollback> {
+        let tx = self.pool.begin().await?;
+        
+        Ok(ChainRollback {
+            tx,
+            contract_address: contract_address.to_string(),
+        })
+    }
+    
+    /// Delete orders and trades whose rolled-back creation was not replayed
+    ///
+    /// Only once every orphaned event is below `replayed_below` (the replay has moved
+    /// past them, plus a grace period for the transaction to be mined again).
+    pub async fn finalize_orphans(&self, contract_address: &str, replayed_below: u64) -> DbResult<Vec<OrphanedEntity>> {
+        let mut tx = self.pool.begin().await?;
+        
+        let highest: Option<i64> = sqlx::query_scalar(
+            r#"SELECT MAX("blockNumber") FROM applied_events WHERE "contractAddress" = $1 AND "orphaned""#,
+        )
+        .bind(contract_address)
+        .fetch_one(&mut *tx)
+        .await?;
+        
+        match highest {
+            Some(highest) if (highest as u64) < replayed_below => {}
+            _ => return Ok(Vec::new()),
+        }
+        
+        let orphans: Vec<(String, String)> = sqlx::query_as(
+            r#"
+            SELECT DISTINCT o."event", o."entityId" FROM applied_events o
+            WHERE o."contractAddress" = $1 AND o."orphaned"
+              AND o."event" IN ('OrderCreated', 'TradeCreated') AND o."entityId" IS NOT NULL
+              AND NOT EXISTS (
+                  SELECT 1 FROM applied_events a
+                  WHERE a."contractAddress" = o."contractAddress" AND NOT a."orphaned"
+                    AND a."event" = o."event" AND a."entityId" = o."entityId"
+              )
+            "#,
+        )
+        .bind(contract_address)
+        .fetch_all(&mut *tx)
+        .await?;
+        
+        // Trades first - an orphaned order cascades to its trades and withdrawals
+        for (_, entity_id) in orphans.iter().filter(|(event, _)| event == "TradeCreated") {
+            sqlx::query(r#"DELETE FROM trades WHERE "tradeId" = $1"#)
+                .bind(entity_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        for (_, entity_id) in orphans.iter().filter(|(event, _)| event == "OrderCreated") {
+            sqlx::query(r#"DELETE FROM orders WHERE "orderId" = $1"#)
+                .bind(entity_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        
+        sqlx::query(r#"DELETE FROM applied_events WHERE "contractAddress" = $1 AND "orphaned""#)
+            .bind(contract_address)
+            .execute(&mut *tx)
+            .await?;
+        
+        tx.commit().await?;
+        
+        Ok(orphans
+            .into_iter()
+            .map(|(event, entity_id)| OrphanedEntity { event, entity_id })
+            .collect())
+    }
+    
+    /// Forget blocks and events older than the reorg window
+    /// (processed_logs is kept - it makes replays with lyncz-reindex idempotent)
+    pub async fn prune(&self, contract_address: &str, below: u64) -> DbResult<()> {
+        sqlx::query(r#"DELETE FROM event_sync_blocks WHERE "contractAddress" = $1 AND "blockNumber" < $2"#)
+            .bind(contract_address)
+            .bind(below as i64)
+            .execute(&self.pool)
+            .await?;
+        
+        sqlx::query(
+            r#"DELETE FROM applied_events WHERE "contractAddress" = $1 AND "blockNumber" < $2 AND NOT "orphaned""#,
+        )
+        .bind(contract_address)
+        .bind(below as i64)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+}
+
+impl ChainRollback {
+    /// Adjust an order's remaining amount by delta (+ or -)
+    pub async fn adjust_remaining_amount(&mut self, order_id: &str, delta: &str) -> DbResult<()> {
+        sqlx::query(
+            r#"UPDATE orders SET "remainingAmount" = "remainingAmount" + $1::NUMERIC WHERE "orderId" = $2"#,
+        )
+        .bind(delta)
+        .bind(order_id)
+        .execute(&mut *self.tx)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Remove the withdrawal recorded for an OrderWithdrawn
+    pub async fn delete_withdrawal(&mut self, order_id: &str, tx_hash: &str) -> DbResult<()> {
+        sqlx::query(r#"DELETE FROM withdrawals WHERE "orderId" = $1 AND "txHash" = $2"#)
+            .bind(order_id)
+            .bind(tx_hash)
+            .execute(&mut *self.tx)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Restore an order's previous exchange rate
+    pub async fn set_exchange_rate(&mut self, order_id: &str, rate: &str) -> DbResult<()> {
+        sqlx::query(r#"UPDATE orders SET "exchangeRate" = $1::NUMERIC WHERE "orderId" = $2"#)
+            .bind(rate)
+            .bind(order_id)
+            .execute(&mut *self.tx)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Restore an order's previous memo requirement
+    pub async fn set_memo_required(&mut self, order_id: &str, required: bool) -> DbResult<()> {
+        sqlx::query(r#"UPDATE orders SET "memoRequired" = $1 WHERE "orderId" = $2"#)
+            .bind(required)
+            .bind(order_id)
+            .execute(&mut *self.tx)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Put a settled or expired trade back to PENDING
+    pub async fn reopen_trade(&mut self, trade_id: &str) -> DbResult<()> {
+        sqlx::query(r#"UPDATE trades SET "status" = 0, "settlementTxHash" = NULL WHERE "tradeId" = $1"#)
+            .bind(trade_id)
+            .execute(&mut *self.tx)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Reserve an expired trade's receipt again (`expire_trade` released it)
+    ///
+    /// Returns Some(holder_trade_id) if another trade took the receipt meanwhile.
+    pub async fn restore_receipt_reservation(&mut self, trade_id: &str) -> DbResult<Option<String>> {
+        sqlx::query(
+            r#"
+            INSERT INTO receipt_reservations ("transactionId", "tradeId")
+            SELECT "transactionId", "tradeId" FROM trades
+            WHERE "tradeId" = $1 AND "transactionId" IS NOT NULL
+            ON CONFLICT ("transactionId") DO NOTHING
+            "#,
+        )
+        .bind(trade_id)
+        .execute(&mut *self.tx)
+        .await?;
+        
+        let holder: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT r."tradeId" FROM receipt_reservations r
+            JOIN trades t ON t."transactionId" = r."transactionId"
+            WHERE t."tradeId" = $1
+            "#,
+        )
+        .bind(trade_id)
+        .fetch_optional(&mut *self.tx)
+        .await?;
+        
+        Ok(holder.filter(|holder| holder != trade_id))
+    }
+    
+    /// Record a settlement state change caused by the rollback
+    pub async fn record_settlement_state(&mut self, trade_id: &str, state: &str) -> DbResult<()> {
+        sqlx::query(
+            r#"INSERT INTO trade_settlement_events ("tradeId", "state", "detail") VALUES ($1, $2, 'chain reorg')"#,
+        )
+        .bind(trade_id)
+        .bind(state)
+        .execute(&mut *self.tx)
+        .await?;
+        
+        sqlx::query(r#"UPDATE trades SET settlement_state = $1 WHERE "tradeId" = $2"#)
+            .bind(state)
+            .bind(trade_id)
+            .execute(&mut *self.tx)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Orphan the undone events, forget the blocks above `ancestor` and resume from there
+    pub async fn commit(mut self, ancestor: u64) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE applied_events SET "orphaned" = TRUE
+            WHERE "contractAddress" = $1 AND "blockNumber" > $2 AND NOT "orphaned"
+            "#,
+        )
+        .bind(&self.contract_address)
+        .bind(ancestor as i64)
+        .execute(&mut *self.tx)
+        .await?;
+        
+        // Undone logs are applied again (or retried) when their blocks are replayed
+        for table in ["event_sync_blocks", "processed_logs", "failed_logs"] {
+            sqlx::query(&format!(
+                r#"DELETE FROM {} WHERE "contractAddress" = $1 AND "blockNumber" > $2"#,
+                table
+            ))
+            .bind(&self.contract_address)
+            .bind(ancestor as i64)
+            .execute(&mut *self.tx)
+            .await?;
+        }
+        
+        // event_sync_state stores the next block to sync
+        sqlx::query(
+            "INSERT INTO event_sync_state (contract_address, last_synced_block)
+             VALUES ($1, $2)
+             ON CONFLICT (contract_address)
+             DO UPDATE SET last_synced_block = $2, last_synced_at = NOW()",
+        )
+        .bind(&self.contract_address)
+        .bind(ancestor as i64 + 1)
+        .execute(&mut *self.tx)
+        .await?;
+        
+        self.tx.commit().await?;
+        Ok(())
+    }
+}
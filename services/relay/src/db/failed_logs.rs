@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use super::DbResult;
+use super::models::DbFailedLog;
+
+/// Repository for the event dead-letter queue (logs whose handler failed)
+/// Logs are dead-lettered and resolved inside an `EventBatch`
+#[derive(Clone)]
+pub struct PostgresFailedLogRepository {
+    pool: PgPool,
+}
+
+impl PostgresFailedLogRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+    
+    /// Failed logs due for a retry, in chain order
+    pub async fn due(&self, contract_address: &str, limit: i64) -> DbResult<Vec<DbFailedLog>> {
+        let rows = sqlx::query_as::<_, DbFailedLog>(
+            r#"
+            SELECT "id", "blockNumber", "blockHash", "txHash", "logIndex", "event", "topics", "data", "error", "attempts"
+            FROM failed_logs
+            WHERE "contractAddress" = $1 AND "nextRetryAt" <= NOW()
+            ORDER BY "blockNumber", "logIndex"
+            LIMIT $2
+            "#,
+        )
+        .bind(contract_address)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows)
+    }
+    
+    /// Record a failed retry (next_retry_at None = give up, kept for inspection)
+    pub async fn reschedule(&self, id: i64, error: &str, next_retry_at: Option<DateTime<Utc>>) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE failed_logs
+            SET "error" = $2, "attempts" = "attempts" + 1, "nextRetryAt" = $3, "updatedAt" = NOW()
+            WHERE "id" = $1
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .bind(next_retry_at)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+}
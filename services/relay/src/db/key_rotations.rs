@@ -0,0 +1,269 @@
+use sqlx::PgPool;
+
+use super::DbResult;
+use super::models::{DbKeyRotation, DbKeyRotationAudit, KeyRotationStatus};
+
+const KEY_ROTATION_COLUMNS: &str = r#""pkHash", "status", "sightings", "buyers", "orders", "chainValidated", "firstTradeId",
+    "eligibleAt", "approvedBy", "approvedAt", "txHash", "submittedAt", "firstSeenAt", "updatedAt""#;
+
+/// Repository for governed Alipay key rotations (quarantine, sightings, audit trail)
+/// Every status change writes its audit row in the same transaction
+pub struct PostgresKeyRotationRepository {
+    pool: PgPool,
+}
+
+impl PostgresKeyRotationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+    
+    /// Count a valid receipt signed by a key - quarantines the key on first sight
+    /// A trade counts once per key, however many receipts (transaction IDs) it uploads;
+    /// the distinct buyers and orders of the counted trades are kept alongside
+    pub async fn record_sighting(
+        &self,
+        pk_hash: &str,
+        transaction_id: &str,
+        trade_id: &str,
+        buyer: &str,
+        order_id: &str,
+        chain_validated: bool,
+    ) -> DbResult<DbKeyRotation> {
+        let mut tx = self.pool.begin().await?;
+        
+        let quarantined = sqlx::query(
+            r#"
+            INSERT INTO pending_key_rotations ("pkHash", "firstTradeId")
+            VALUES ($1, $2)
+            ON CONFLICT ("pkHash") DO NOTHING
+            "#,
+        )
+        .bind(pk_hash)
+        .bind(trade_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() > 0;
+        
+        if quarantined {
+            insert_audit(&mut tx, pk_hash, "quarantined", "relay", Some(&format!("trade {}", trade_id))).await?;
+        }
+        
+        let new_sighting = sqlx::query(
+            r#"
+            INSERT INTO key_rotation_sightings ("pkHash", "transactionId", "tradeId", "buyer", "orderId")
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT ("pkHash", "tradeId") DO NOTHING
+            "#,
+        )
+        .bind(pk_hash)
+        .bind(transaction_id)
+        .bind(trade_id)
+        .bind(buyer.to_lowercase())
+        .bind(order_id.to_lowercase())
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() > 0;
+        
+        if new_sighting {
+            sqlx::query(
+                r#"
+                UPDATE pending_key_rotations r
+                SET "sightings" = counts.trades,
+                    "buyers" = counts.buyers,
+                    "orders" = counts.orders,
+                    "chainValidated" = "chainValidated" OR $2,
+                    "updatedAt" = NOW()
+                FROM (
+                    SELECT COUNT(*) AS trades,
+                           COUNT(DISTINCT "buyer") AS buyers,
+                           COUNT(DISTINCT "orderId") AS orders
+                    FROM key_rotation_sightings
+                    WHERE "pkHash" = $1
+                ) counts
+                WHERE r."pkHash" = $1
+                "#,
+            )
+            .bind(pk_hash)
+            .bind(chain_validated)
+            .execute(&mut *tx)
+            .await?;
+            
+            let detail = format!("trade {}, chain {}", trade_id, if chain_validated { "valid" } else { "unchecked" });
+            insert_audit(&mut tx, pk_hash, "sighting", "relay", Some(&detail)).await?;
+        }
+        
+        let rotation = sqlx::query_as::<_, DbKeyRotation>(&format!(
+            r#"SELECT {} FROM pending_key_rotations WHERE "pkHash" = $1"#,
+            KEY_ROTATION_COLUMNS
+        ))
+        .bind(pk_hash)
+        .fetch_one(&mut *tx)
+        .await?;
+        
+        tx.commit().await?;
+        Ok(rotation)
+    }
+    
+    /// Move a rotation to `to` if it is currently in one of `from` - returns false otherwise
+    /// Sets the matching timestamp (eligibleAt, approvedAt/approvedBy, submittedAt/txHash)
+    pub async fn transition(
+        &self,
+        pk_hash: &str,
+        from: &[KeyRotationStatus],
+        to: KeyRotationStatus,
+        actor: &str,
+        detail: Option<&str>,
+        tx_hash: Option<&str>,
+    ) -> DbResult<bool> {
+        let from: Vec<String> = from.iter().map(|status| status.to_string()).collect();
+        let mut tx = self.pool.begin().await?;
+        
+        let moved = sqlx::query(
+            r#"
+            UPDATE pending_key_rotations
+            SET "status" = $3,
+                "eligibleAt" = CASE WHEN $3 = 'eligible' THEN NOW() ELSE "eligibleAt" END,
+                "approvedBy" = CASE WHEN $3 IN ('approved', 'rejected') THEN $4 ELSE "approvedBy" END,
+                "approvedAt" = CASE WHEN $3 IN ('approved', 'rejected') THEN NOW() ELSE "approvedAt" END,
+                "txHash" = COALESCE($5, "txHash"),
+                "submittedAt" = CASE WHEN $3 = 'submitted' THEN NOW() ELSE "submittedAt" END,
+                "updatedAt" = NOW()
+            WHERE "pkHash" = $1 AND "status" = ANY($2)
+            "#,
+        )
+        .bind(pk_hash)
+        .bind(&from)
+        .bind(to.to_string())
+        .bind(actor)
+        .bind(tx_hash)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() > 0;
+        
+        if moved {
+            insert_audit(&mut tx, pk_hash, &to.to_string(), actor, detail).await?;
+        }
+        
+        tx.commit().await?;
+        Ok(moved)
+    }
+    
+    /// Record a step that doesn't change the status (chain_failed, submit_failed, ...)
+    pub async fn audit(&self, pk_hash: &str, action: &str, actor: &str, detail: Option<&str>) -> DbResult<()> {
+        let mut tx = self.pool.begin().await?;
+        insert_audit(&mut tx, pk_hash, action, actor, detail).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+    
+    pub async fn get(&self, pk_hash: &str) -> DbResult<Option<DbKeyRotation>> {
+        let rotation = sqlx::query_as::<_, DbKeyRotation>(&format!(
+            r#"SELECT {} FROM pending_key_rotations WHERE "pkHash" = $1"#,
+            KEY_ROTATION_COLUMNS
+        ))
+        .bind(pk_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(rotation)
+    }
+    
+    /// Rotations in any of the given statuses (all if empty), newest first
+    pub async fn list(&self, statuses: &[KeyRotationStatus]) -> DbResult<Vec<DbKeyRotation>> {
+        let statuses: Vec<String> = statuses.iter().map(|status| status.to_string()).collect();
+        let rotations = sqlx::query_as::<_, DbKeyRotation>(&format!(
+            r#"
+            SELECT {} FROM pending_key_rotations
+            WHERE cardinality($1::text[]) = 0 OR "status" = ANY($1)
+            ORDER BY "firstSeenAt" DESC
+            "#,
+            KEY_ROTATION_COLUMNS
+        ))
+        .bind(&statuses)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rotations)
+    }
+    
+    /// Audit trail of one key, oldest first
+    pub async fn audit_trail(&self, pk_hash: &str) -> DbResult<Vec<DbKeyRotationAudit>> {
+        let entries = sqlx::query_as::<_, DbKeyRotationAudit>(
+            r#"
+            SELECT "id", "pkHash", "action", "actor", "detail", "createdAt"
+            FROM key_rotation_audit
+            WHERE "pkHash" = $1
+            ORDER BY "id"
+            "#,
+        )
+        .bind(pk_hash)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(entries)
+    }
+}
+
+async fn insert_audit(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    pk_hash: &str,
+    action: &str,
+    actor: &str,
+    detail: Option<&str>,
+) -> DbResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO key_rotation_audit ("pkHash", "action", "actor", "detail")
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(pk_hash)
+    .bind(action)
+    .bind(actor)
+    .bind(detail)
+    .execute(&mut **tx)
+    .await?;
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::test_db::{self, random_id};
+    use crate::key_rotation::{KeyRotationPolicy, RotationStep};
+    
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_repeated_uploads_on_one_trade_count_once() {
+        let db = test_db::connect().await;
+        let policy = KeyRotationPolicy::default();
+        let pk_hash = random_id();
+        let trade = test_db::trade(&random_id(), &random_id());
+        
+        // Three self-signed receipts with different transaction IDs on the same trade
+        for _ in 0..3 {
+            let rotation = db.record_key_sighting(&pk_hash, &random_id(), &trade, false).await.unwrap();
+            assert_eq!((rotation.sightings, rotation.buyers, rotation.orders), (1, 1, 1));
+            assert_eq!(policy.next_step(&rotation, chrono::Utc::now()), RotationStep::Wait);
+        }
+        
+        // More trades by the same buyer still don't promote it
+        for _ in 0..2 {
+            let other = test_db::trade(&random_id(), &random_id());
+            db.record_key_sighting(&pk_hash, &random_id(), &other, false).await.unwrap();
+        }
+        let rotation = db.get_key_rotation(&pk_hash).await.unwrap().unwrap();
+        assert_eq!((rotation.sightings, rotation.buyers, rotation.orders), (3, 1, 3));
+        assert_eq!(policy.next_step(&rotation, chrono::Utc::now()), RotationStep::Wait);
+        
+        // Distinct trades by distinct buyers on distinct orders do
+        for buyer in ["0x00000000000000000000000000000000000000d4", "0x00000000000000000000000000000000000000e5"] {
+            let mut other = test_db::trade(&random_id(), &random_id());
+            other.buyer = buyer.to_string();
+            db.record_key_sighting(&pk_hash, &random_id(), &other, false).await.unwrap();
+        }
+        let rotation = db.get_key_rotation(&pk_hash).await.unwrap().unwrap();
+        assert_eq!((rotation.sightings, rotation.buyers, rotation.orders), (5, 3, 5));
+        assert_eq!(policy.next_step(&rotation, chrono::Utc::now()), RotationStep::Promote);
+    }
+}
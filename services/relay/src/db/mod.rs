@@ -1,9 +1,23 @@
 pub mod account_emails;
+pub mod alipay_keys;
+pub mod event_batch;
+pub mod event_journal;
+pub mod failed_logs;
+pub mod key_rotations;
 pub mod models;
 pub mod orders;
+pub mod receipt_reservations;
+pub mod reindex_runs;
+pub mod relayer_nonces;
+pub mod relayer_transactions;
+pub mod settlement_batches;
+pub mod settlement_events;
 pub mod trades;
 pub mod withdrawals;
 
+#[cfg(test)]
+pub(crate) mod test_db;
+
 use sqlx::postgres::{PgPool, PgPoolOptions};
 use std::time::Duration;
 use thiserror::Error;
@@ -239,4 +253,156 @@ impl Database {
         let repo = trades::PostgresTradeRepository::new(self.pool.clone());
         repo.get_all_by_order(order_id).await
     }
+    
+    // ===== Settlement State Methods (sub-status + timeline) =====
+    
+    /// Record a settlement state transition for a trade
+    pub async fn record_settlement_state(&self, trade_id: &str, state: &models::SettlementState, detail: Option<&str>) -> DbResult<()> {
+        let repo = settlement_events::PostgresSettlementEventRepository::new(self.pool.clone());
+        repo.record(trade_id, state, detail).await
+    }
+    
+    /// Get current settlement state for a trade
+    pub async fn get_settlement_state(&self, trade_id: &str) -> DbResult<Option<String>> {
+        let repo = settlement_events::PostgresSettlementEventRepository::new(self.pool.clone());
+        repo.current(trade_id).await
+    }
+    
+    /// Get settlement timeline for a trade (oldest first)
+    pub async fn get_settlement_events(&self, trade_id: &str) -> DbResult<Vec<models::DbSettlementEvent>> {
+        let repo = settlement_events::PostgresSettlementEventRepository::new(self.pool.clone());
+        repo.get_by_trade(trade_id).await
+    }
+    
+    // ===== Receipt Reservation Methods (one trade per receipt) =====
+    
+    /// Reserve a receipt transaction ID for a trade
+    /// Returns Some(holder_trade_id) if another trade already holds it
+    pub async fn reserve_receipt(&self, transaction_id: &str, trade_id: &str) -> DbResult<Option<String>> {
+        let repo = receipt_reservations::PostgresReceiptReservationRepository::new(self.pool.clone());
+        repo.reserve(transaction_id, trade_id).await
+    }
+    
+    /// Release the receipt reserved by a trade
+    pub async fn release_receipt_reservation(&self, trade_id: &str) -> DbResult<()> {
+        let repo = receipt_reservations::PostgresReceiptReservationRepository::new(self.pool.clone());
+        repo.release(trade_id).await
+    }
+    
+    // ===== Settlement Batch Methods (one proof for several trades) =====
+    
+    /// Create a settlement batch for trades in leaf order
+    pub async fn create_settlement_batch(&self, batch_id: &str, trade_ids: &[String]) -> DbResult<()> {
+        let repo = settlement_batches::PostgresSettlementBatchRepository::new(self.pool.clone());
+        repo.create(batch_id, trade_ids).await
+    }
+    
+    /// Save the batch proof
+    pub async fn save_settlement_batch_proof(
+        &self,
+        batch_id: &str,
+        user_public_values: &[u8],
+        accumulator: &[u8],
+        proof_data: &[u8],
+        proof_id: &str,
+    ) -> DbResult<()> {
+        let repo = settlement_batches::PostgresSettlementBatchRepository::new(self.pool.clone());
+        repo.save_proof(batch_id, user_public_values, accumulator, proof_data, proof_id).await
+    }
+    
+    /// Record the submitBatchProof transaction (mined, or still pending when the wait gave up)
+    pub async fn mark_settlement_batch_settled(&self, batch_id: &str, tx_hash: &str) -> DbResult<()> {
+        let repo = settlement_batches::PostgresSettlementBatchRepository::new(self.pool.clone());
+        repo.mark_settled(batch_id, tx_hash).await
+    }
+    
+    /// Most recent batch containing a trade
+    pub async fn get_settlement_batch_for_trade(&self, trade_id: &str) -> DbResult<Option<models::DbSettlementBatch>> {
+        let repo = settlement_batches::PostgresSettlementBatchRepository::new(self.pool.clone());
+        repo.get_for_trade(trade_id).await
+    }
+    
+    // ===== Trusted Alipay Key Methods (key-set leaves) =====
+    
+    /// All trusted Alipay key hashes (0x hex)
+    pub async fn get_alipay_keys(&self) -> DbResult<Vec<String>> {
+        let repo = alipay_keys::PostgresAlipayKeyRepository::new(self.pool.clone());
+        repo.list().await
+    }
+    
+    /// Trust a new Alipay key hash - returns false if it was already trusted
+    pub async fn add_alipay_key(&self, pk_hash: &str, trade_id: &str) -> DbResult<bool> {
+        let repo = alipay_keys::PostgresAlipayKeyRepository::new(self.pool.clone());
+        repo.insert(pk_hash, trade_id).await
+    }
+    
+    /// Count a trade's valid receipt signed by an untrusted key (quarantines it on first sight)
+    pub async fn record_key_sighting(&self, pk_hash: &str, transaction_id: &str, trade: &models::DbTrade, chain_validated: bool) -> DbResult<models::DbKeyRotation> {
+        let repo = key_rotations::PostgresKeyRotationRepository::new(self.pool.clone());
+        repo.record_sighting(pk_hash, transaction_id, &trade.trade_id, &trade.buyer, &trade.order_id, chain_validated).await
+    }
+    
+    /// Move a key rotation between statuses (audited) - returns false if it wasn't in `from`
+    pub async fn transition_key_rotation(
+        &self,
+        pk_hash: &str,
+        from: &[models::KeyRotationStatus],
+        to: models::KeyRotationStatus,
+        actor: &str,
+        detail: Option<&str>,
+        tx_hash: Option<&str>,
+    ) -> DbResult<bool> {
+        let repo = key_rotations::PostgresKeyRotationRepository::new(self.pool.clone());
+        repo.transition(pk_hash, from, to, actor, detail, tx_hash).await
+    }
+    
+    /// Audit a key rotation step that doesn't change its status
+    pub async fn audit_key_rotation(&self, pk_hash: &str, action: &str, actor: &str, detail: Option<&str>) -> DbResult<()> {
+        let repo = key_rotations::PostgresKeyRotationRepository::new(self.pool.clone());
+        repo.audit(pk_hash, action, actor, detail).await
+    }
+    
+    /// Get a key rotation by key hash (0x hex)
+    pub async fn get_key_rotation(&self, pk_hash: &str) -> DbResult<Option<models::DbKeyRotation>> {
+        let repo = key_rotations::PostgresKeyRotationRepository::new(self.pool.clone());
+        repo.get(pk_hash).await
+    }
+    
+    /// Key rotations in any of the given statuses (all if empty), newest first
+    pub async fn get_key_rotations(&self, statuses: &[models::KeyRotationStatus]) -> DbResult<Vec<models::DbKeyRotation>> {
+        let repo = key_rotations::PostgresKeyRotationRepository::new(self.pool.clone());
+        repo.list(statuses).await
+    }
+    
+    /// Audit trail of a key rotation, oldest first
+    pub async fn get_key_rotation_audit(&self, pk_hash: &str) -> DbResult<Vec<models::DbKeyRotationAudit>> {
+        let repo = key_rotations::PostgresKeyRotationRepository::new(self.pool.clone());
+        repo.audit_trail(pk_hash).await
+    }
+    
+    // ===== Relayer Gas Ledger Methods (spend cap + accounting) =====
+    
+    /// Total gas cost (wei, decimal string) of relayer transactions mined since `since`
+    pub async fn get_relayer_spend_since(&self, since: chrono::DateTime<chrono::Utc>) -> DbResult<String> {
+        let repo = relayer_transactions::PostgresRelayerTransactionRepository::new(self.pool.clone());
+        repo.spent_since(since).await
+    }
+    
+    /// Relayer transactions broadcast but not mined yet (every address)
+    pub async fn get_pending_relayer_transactions(&self) -> DbResult<Vec<models::DbRelayerPendingTx>> {
+        let repo = relayer_nonces::PostgresRelayerNonceRepository::new(self.pool.clone());
+        repo.list_all_pending().await
+    }
+    
+    /// Most recent relayer transactions, newest first
+    pub async fn get_recent_relayer_transactions(&self, limit: i64) -> DbResult<Vec<models::DbRelayerTransaction>> {
+        let repo = relayer_transactions::PostgresRelayerTransactionRepository::new(self.pool.clone());
+        repo.list_recent(limit).await
+    }
+    
+    /// Latest broadcast hash of a settlement transaction for any of `trade_ids` still awaiting its receipt
+    pub async fn get_pending_settlement_tx(&self, trade_ids: &[String]) -> DbResult<Option<String>> {
+        let repo = relayer_nonces::PostgresRelayerNonceRepository::new(self.pool.clone());
+        repo.pending_settlement(trade_ids).await
+    }
 }
@@ -17,6 +17,283 @@ pub struct DbWithdrawal {
     pub created_at: DateTime<Utc>,               // When withdrawal occurred
 }
 
+/// Database model for Settlement Event - one row per settlement sub-status transition
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DbSettlementEvent {
+    pub id: i64,                                 // Auto-increment ID (orders the timeline)
+    #[sqlx(rename = "tradeId")]
+    pub trade_id: String,                        // bytes32 reference to trade
+    pub state: String,                           // SettlementState as text
+    pub detail: Option<String>,                  // Context: proof id, tx hash, error message
+    #[sqlx(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,               // When the transition happened
+}
+
+/// Database model for Settlement Batch - one proof settling several trades
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DbSettlementBatch {
+    #[sqlx(rename = "batchId")]
+    pub batch_id: String,                        // Relay-assigned batch ID
+    #[sqlx(rename = "tradeIds")]
+    pub trade_ids: Vec<String>,                  // Trades in leaf order
+    #[sqlx(rename = "proofUserPublicValues")]
+    pub proof_user_public_values: Option<Vec<u8>>, // Batch root (32 bytes)
+    #[sqlx(rename = "proofAccumulator")]
+    pub proof_accumulator: Option<Vec<u8>>,      // Halo2 accumulator
+    #[sqlx(rename = "proofData")]
+    pub proof_data: Option<Vec<u8>>,             // Halo2 proof data
+    #[sqlx(rename = "axiomProofId")]
+    pub axiom_proof_id: Option<String>,          // Axiom API proof ID
+    #[sqlx(rename = "settlementTxHash")]
+    pub settlement_tx_hash: Option<String>,      // submitBatchProof transaction
+    #[sqlx(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Settlement sub-status of a pending trade
+/// Stored as text in trades.settlement_state and trade_settlement_events.state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SettlementState {
+    ReceiptUploaded,
+    Validating,
+    Validated,
+    Proving,
+    ProofReady,
+    Submitting,
+    Settled,
+    /// Failed with a stable error code (e.g. HASH_MISMATCH, ALREADY_USED)
+    Failed(String),
+}
+
+impl SettlementState {
+    pub fn failed(code: impl Into<String>) -> Self {
+        Self::Failed(code.into())
+    }
+    
+    /// Parse the stored text (inverse of Display)
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "receipt_uploaded" => Some(Self::ReceiptUploaded),
+            "validating" => Some(Self::Validating),
+            "validated" => Some(Self::Validated),
+            "proving" => Some(Self::Proving),
+            "proof_ready" => Some(Self::ProofReady),
+            "submitting" => Some(Self::Submitting),
+            "settled" => Some(Self::Settled),
+            _ => match value.strip_prefix("failed:") {
+                Some(code) if !code.is_empty() => Some(Self::Failed(code.to_string())),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for SettlementState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ReceiptUploaded => write!(f, "receipt_uploaded"),
+            Self::Validating => write!(f, "validating"),
+            Self::Validated => write!(f, "validated"),
+            Self::Proving => write!(f, "proving"),
+            Self::ProofReady => write!(f, "proof_ready"),
+            Self::Submitting => write!(f, "submitting"),
+            Self::Settled => write!(f, "settled"),
+            Self::Failed(code) => write!(f, "failed:{}", code),
+        }
+    }
+}
+
+/// Governed rotation status of an Alipay signing key
+/// Stored as text in pending_key_rotations.status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyRotationStatus {
+    /// Seen on too few receipts (or its chain failed) - never submitted
+    Quarantined,
+    /// Sighting threshold reached - submitted after the delay, or once approved
+    Eligible,
+    /// Approved by an admin - submitted without waiting for the delay
+    Approved,
+    /// On-chain key hash (or key-set root) updated
+    Submitted,
+    /// Rejected by an admin - never submitted
+    Rejected,
+}
+
+impl KeyRotationStatus {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "quarantined" => Some(Self::Quarantined),
+            "eligible" => Some(Self::Eligible),
+            "approved" => Some(Self::Approved),
+            "submitted" => Some(Self::Submitted),
+            "rejected" => Some(Self::Rejected),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for KeyRotationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Quarantined => write!(f, "quarantined"),
+            Self::Eligible => write!(f, "eligible"),
+            Self::Approved => write!(f, "approved"),
+            Self::Submitted => write!(f, "submitted"),
+            Self::Rejected => write!(f, "rejected"),
+        }
+    }
+}
+
+/// Database model for Pending Key Rotation - one row per Alipay key seen off the trusted set
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DbKeyRotation {
+    #[sqlx(rename = "pkHash")]
+    pub pk_hash: String,                         // SHA256 of the DER public key (0x hex)
+    pub status: String,                          // KeyRotationStatus as text
+    pub sightings: i32,                          // Distinct trades with a valid receipt signed by the key
+    pub buyers: i32,                             // Distinct buyers among those trades
+    pub orders: i32,                             // Distinct orders among those trades
+    #[sqlx(rename = "chainValidated")]
+    pub chain_validated: bool,                   // Chain checked against the pinned root
+    #[sqlx(rename = "firstTradeId")]
+    pub first_trade_id: Option<String>,          // Trade whose receipt introduced the key
+    #[sqlx(rename = "eligibleAt")]
+    pub eligible_at: Option<DateTime<Utc>>,      // When the sighting threshold was reached
+    #[sqlx(rename = "approvedBy")]
+    pub approved_by: Option<String>,             // Admin who approved or rejected
+    #[sqlx(rename = "approvedAt")]
+    pub approved_at: Option<DateTime<Utc>>,
+    #[sqlx(rename = "txHash")]
+    pub tx_hash: Option<String>,                 // updatePublicKeyHash transaction
+    #[sqlx(rename = "submittedAt")]
+    pub submitted_at: Option<DateTime<Utc>>,
+    #[sqlx(rename = "firstSeenAt")]
+    pub first_seen_at: DateTime<Utc>,
+    #[sqlx(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Database model for Key Rotation Audit - one row per rotation step
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DbKeyRotationAudit {
+    pub id: i64,                                 // Auto-increment ID (orders the trail)
+    #[sqlx(rename = "pkHash")]
+    pub pk_hash: String,
+    pub action: String,                          // quarantined, sighting, eligible, approved, submitted, ...
+    pub actor: String,                           // "relay", or the admin's name
+    pub detail: Option<String>,                  // Context: trade id, tx hash, error message
+    #[sqlx(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Database model for Relayer Pending Transaction - one row per relayer nonce in flight
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DbRelayerPendingTx {
+    pub id: i64,                                 // Auto-increment ID
+    pub address: String,                         // Relayer address (0x lowercase hex)
+    pub nonce: i64,
+    pub operation: String,                       // submitProof, fillOrder, cancelExpiredTrade, ...
+    #[sqlx(rename = "tradeId")]
+    pub trade_id: Option<String>,                // Trade the transaction is for (if one)
+    #[sqlx(rename = "txHash")]
+    pub tx_hash: String,                         // Latest broadcast
+    #[sqlx(rename = "txHashes")]
+    pub tx_hashes: Vec<String>,                  // Every broadcast for this nonce
+    #[sqlx(rename = "gasPrice")]
+    pub gas_price: String,                       // Gas price / max fee per gas of the latest broadcast (wei)
+    pub replacements: i32,
+    #[sqlx(rename = "txRequest")]
+    pub tx_request: String,                      // Unsigned transaction (JSON)
+    pub status: String,                          // pending/mined/reverted/dropped
+    #[sqlx(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Database model for Relayer Transaction - gas paid by one mined relayer transaction
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DbRelayerTransaction {
+    #[sqlx(rename = "txHash")]
+    pub tx_hash: String,
+    pub address: String,                         // Relayer address (0x lowercase hex)
+    pub operation: String,                       // submitProof, fillOrder, cancelExpiredTrade, ...
+    #[sqlx(rename = "tradeId")]
+    pub trade_id: Option<String>,                // NULL for batch settlements and key rotations
+    pub status: String,                          // mined/reverted
+    #[sqlx(rename = "gasUsed")]
+    pub gas_used: String,                        // NUMERIC(78,0) as string
+    #[sqlx(rename = "effectiveGasPrice")]
+    pub effective_gas_price: String,             // wei per gas
+    #[sqlx(rename = "gasCost")]
+    pub gas_cost: String,                        // wei
+    #[sqlx(rename = "blockNumber")]
+    pub block_number: Option<i64>,
+    #[sqlx(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Database model for Applied Event - journaled escrow event (raw log, undone on a reorg)
+#[derive(Debug, Clone, FromRow)]
+pub struct DbAppliedEvent {
+    pub id: i64,
+    #[sqlx(rename = "blockNumber")]
+    pub block_number: i64,
+    #[sqlx(rename = "blockHash")]
+    pub block_hash: String,
+    #[sqlx(rename = "txHash")]
+    pub tx_hash: String,
+    #[sqlx(rename = "logIndex")]
+    pub log_index: i64,
+    pub event: String,                           // OrderCreated, TradeSettled, ...
+    #[sqlx(rename = "entityId")]
+    pub entity_id: Option<String>,               // Order or trade ID
+    pub topics: Vec<String>,                     // 0x hex
+    pub data: String,                            // 0x hex
+}
+
+/// Database model for Failed Log - dead-lettered event log awaiting retry
+#[derive(Debug, Clone, FromRow)]
+pub struct DbFailedLog {
+    pub id: i64,
+    #[sqlx(rename = "blockNumber")]
+    pub block_number: i64,
+    #[sqlx(rename = "blockHash")]
+    pub block_hash: String,
+    #[sqlx(rename = "txHash")]
+    pub tx_hash: String,
+    #[sqlx(rename = "logIndex")]
+    pub log_index: i64,
+    pub event: String,
+    pub topics: Vec<String>,                     // 0x hex
+    pub data: String,                            // 0x hex
+    pub error: String,
+    pub attempts: i32,
+}
+
+/// Database model for Reindex Run - progress of a lyncz-reindex replay
+#[derive(Debug, Clone, FromRow)]
+pub struct DbReindexRun {
+    pub id: i64,
+    pub mode: String,                            // apply/baseline
+    #[sqlx(rename = "fromBlock")]
+    pub from_block: i64,
+    #[sqlx(rename = "toBlock")]
+    pub to_block: i64,
+    #[sqlx(rename = "chunkSize")]
+    pub chunk_size: i64,
+    #[sqlx(rename = "nextBlock")]
+    pub next_block: i64,
+    #[sqlx(rename = "logsApplied")]
+    pub logs_applied: i64,
+    #[sqlx(rename = "logsSkipped")]
+    pub logs_skipped: i64,
+    #[sqlx(rename = "logsFailed")]
+    pub logs_failed: i64,
+    #[sqlx(rename = "startedAt")]
+    pub started_at: DateTime<Utc>,
+    #[sqlx(rename = "completedAt")]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
 /// Database model for Account Email - notification settings (account-based, any wallet can be buyer or seller)
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct DbAccountEmail {
@@ -66,6 +343,10 @@ pub struct DbOrder {
     pub is_public: bool,                    // Whether order appears in public listings
     #[sqlx(rename = "privateCode")]
     pub private_code: Option<String>,       // 6-digit code for unlisted orders
+    
+    // Payment memo binding (synced from OrderMemoRequirementUpdated)
+    #[sqlx(rename = "memoRequired")]
+    pub memo_required: bool,                // New trades must carry the trade code in the memo (备注)
 }
 
 /// Database model for Trade - EXACTLY matches on-chain Trade struct
@@ -142,4 +423,34 @@ pub struct DbTrade {
     pub proof_json: Option<String>,          // Full Axiom EVM proof JSON
     #[sqlx(rename = "settlement_error")]
     pub settlement_error: Option<String>,    // Settlement error code if failed
+    #[sqlx(rename = "memo_code")]
+    pub memo_code: Option<String>,           // Trade reference code required in the Alipay memo (备注)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_settlement_state_round_trip() {
+        let states = [
+            SettlementState::ReceiptUploaded,
+            SettlementState::Validating,
+            SettlementState::Validated,
+            SettlementState::Proving,
+            SettlementState::ProofReady,
+            SettlementState::Submitting,
+            SettlementState::Settled,
+            SettlementState::failed("HASH_MISMATCH"),
+            SettlementState::failed("ALREADY_USED"),
+        ];
+        for state in states {
+            assert_eq!(SettlementState::parse(&state.to_string()), Some(state));
+        }
+        
+        assert_eq!(SettlementState::failed("REORGED").to_string(), "failed:REORGED");
+        assert_eq!(SettlementState::parse("failed:"), None);
+        assert_eq!(SettlementState::parse("Settled"), None);
+        assert_eq!(SettlementState::parse(""), None);
+    }
 }
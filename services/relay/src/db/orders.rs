@@ -1,5 +1,5 @@
 use async_trait::async_trait;
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use rust_decimal::Decimal;
 use std::str::FromStr;
 use rand::Rng;
@@ -50,7 +50,8 @@ impl PostgresOrderRepository {
                 "createdAt",
                 "syncedAt",
                 "isPublic",
-                "privateCode"
+                "privateCode",
+                "memoRequired"
             FROM orders
             WHERE "remainingAmount" > 0 AND "isPublic" = true
             ORDER BY CAST("exchangeRate" AS NUMERIC) ASC, "createdAt" ASC
@@ -87,6 +88,7 @@ impl PostgresOrderRepository {
             synced_at: row.get("syncedAt"),
             is_public: row.get("isPublic"),
             private_code: row.get("privateCode"),
+            memo_required: row.get("memoRequired"),
         }
     }
     
@@ -112,7 +114,8 @@ impl PostgresOrderRepository {
                 "createdAt",
                 "syncedAt",
                 "isPublic",
-                "privateCode"
+                "privateCode",
+                "memoRequired"
             FROM orders
             WHERE "remainingAmount" > 0 AND "isPublic" = true
             AND LOWER(token) = $1
@@ -151,7 +154,8 @@ impl PostgresOrderRepository {
                 "createdAt",
                 "syncedAt",
                 "isPublic",
-                "privateCode"
+                "privateCode",
+                "memoRequired"
             FROM orders
             WHERE "orderId" = $1
             "#,
@@ -181,7 +185,8 @@ impl PostgresOrderRepository {
                 "createdAt",
                 "syncedAt",
                 "isPublic",
-                "privateCode"
+                "privateCode",
+                "memoRequired"
             FROM orders
             WHERE "privateCode" = $1
             "#,
@@ -332,7 +337,8 @@ impl PostgresOrderRepository {
                 "createdAt",
                 "syncedAt",
                 "isPublic",
-                "privateCode"
+                "privateCode",
+                "memoRequired"
             FROM orders
             WHERE seller = $1
             ORDER BY "createdAt" DESC
@@ -354,54 +360,8 @@ impl PostgresOrderRepository {
 #[async_trait]
 impl OrderRepository for PostgresOrderRepository {
     async fn create(&self, order: &DbOrder) -> DbResult<()> {
-        // Use UPSERT to handle race condition:
-        // - If order doesn't exist: insert with provided values
-        // - If order exists (e.g., from payment-info endpoint): preserve existing accountId/accountName
-        sqlx::query(
-            r#"
-            INSERT INTO orders (
-                "orderId", "seller", "token", "totalAmount", "remainingAmount",
-                "exchangeRate", "rail", "accountId", "accountName", "createdAt", "isPublic"
-            )
-            VALUES ($1, $2, $3, $4::numeric, $5::numeric, $6::numeric, $7, $8, $9, $10, $11)
-            ON CONFLICT ("orderId") DO UPDATE SET
-                -- Update blockchain-authoritative fields
-                "seller" = EXCLUDED."seller",
-                "token" = EXCLUDED."token",
-                "totalAmount" = EXCLUDED."totalAmount",
-                "remainingAmount" = EXCLUDED."remainingAmount",
-                "exchangeRate" = EXCLUDED."exchangeRate",
-                "rail" = EXCLUDED."rail",
-                "createdAt" = EXCLUDED."createdAt",
-                "isPublic" = EXCLUDED."isPublic",
-                -- PRESERVE existing accountId/accountName if already set (race condition handling)
-                "accountId" = CASE 
-                    WHEN orders."accountId" IS NOT NULL AND orders."accountId" != '' 
-                    THEN orders."accountId" 
-                    ELSE EXCLUDED."accountId" 
-                END,
-                "accountName" = CASE 
-                    WHEN orders."accountName" IS NOT NULL AND orders."accountName" != '' 
-                    THEN orders."accountName" 
-                    ELSE EXCLUDED."accountName" 
-                END
-            "#,
-        )
-        .bind(&order.order_id)
-        .bind(&order.seller)
-        .bind(&order.token)
-        .bind(&order.total_amount)
-        .bind(&order.remaining_amount)
-        .bind(&order.exchange_rate)
-        .bind(order.rail)
-        .bind(&order.alipay_id)
-        .bind(&order.alipay_name)
-        .bind(order.created_at)
-        .bind(order.is_public)
-        .execute(&self.pool)
-        .await?;
-        
-        Ok(())
+        let mut conn = self.pool.acquire().await?;
+        upsert_order(&mut conn, order).await
     }
 
     async fn adjust_remaining_amount(&self, order_id: &str, delta: &str) -> DbResult<()> {
@@ -427,3 +387,55 @@ impl OrderRepository for PostgresOrderRepository {
         Ok(())
     }
 }
+
+/// Insert an order from OrderCreated (also used inside event-sync transactions)
+pub(crate) async fn upsert_order(conn: &mut PgConnection, order: &DbOrder) -> DbResult<()> {
+    // Use UPSERT to handle race condition:
+    // - If order doesn't exist: insert with provided values
+    // - If order exists (e.g., from payment-info endpoint): preserve existing accountId/accountName
+    sqlx::query(
+        r#"
+        INSERT INTO orders (
+            "orderId", "seller", "token", "totalAmount", "remainingAmount",
+            "exchangeRate", "rail", "accountId", "accountName", "createdAt", "isPublic"
+        )
+        VALUES ($1, $2, $3, $4::numeric, $5::numeric, $6::numeric, $7, $8, $9, $10, $11)
+        ON CONFLICT ("orderId") DO UPDATE SET
+            -- Update blockchain-authoritative fields
+            "seller" = EXCLUDED."seller",
+            "token" = EXCLUDED."token",
+            "totalAmount" = EXCLUDED."totalAmount",
+            "remainingAmount" = EXCLUDED."remainingAmount",
+            "exchangeRate" = EXCLUDED."exchangeRate",
+            "rail" = EXCLUDED."rail",
+            "createdAt" = EXCLUDED."createdAt",
+            "isPublic" = EXCLUDED."isPublic",
+            -- PRESERVE existing accountId/accountName if already set (race condition handling)
+            "accountId" = CASE 
+                WHEN orders."accountId" IS NOT NULL AND orders."accountId" != '' 
+                THEN orders."accountId" 
+                ELSE EXCLUDED."accountId" 
+            END,
+            "accountName" = CASE 
+                WHEN orders."accountName" IS NOT NULL AND orders."accountName" != '' 
+                THEN orders."accountName" 
+                ELSE EXCLUDED."accountName" 
+            END
+        "#,
+    )
+    .bind(&order.order_id)
+    .bind(&order.seller)
+    .bind(&order.token)
+    .bind(&order.total_amount)
+    .bind(&order.remaining_amount)
+    .bind(&order.exchange_rate)
+    .bind(order.rail)
+    .bind(&order.alipay_id)
+    .bind(&order.alipay_name)
+    .bind(order.created_at)
+    .bind(order.is_public)
+    .execute(conn)
+    .await?;
+    
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use sqlx::PgPool;
+
+use super::DbResult;
+
+/// Repository for receipt transaction ID reservations (one trade per receipt)
+pub struct PostgresReceiptReservationRepository {
+    pool: PgPool,
+}
+
+impl PostgresReceiptReservationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+    
+    /// Atomically reserve a receipt for a trade
+    /// 
+    /// Returns None if the trade now holds the receipt (including re-validation by the
+    /// same trade), or Some(holder_trade_id) if another trade already holds it.
+    /// Reservations held by expired trades are taken over.
+    pub async fn reserve(&self, transaction_id: &str, trade_id: &str) -> DbResult<Option<String>> {
+        let mut tx = self.pool.begin().await?;
+        
+        // Drop a stale reservation whose trade expired (listener may not have released it yet)
+        sqlx::query(
+            r#"
+            DELETE FROM receipt_reservations r
+            USING trades t
+            WHERE r."transactionId" = $1
+              AND r."tradeId" = t."tradeId"
+              AND t."status" = 2
+            "#,
+        )
+        .bind(transaction_id)
+        .execute(&mut *tx)
+        .await?;
+        
+        // No-op update on conflict so RETURNING yields the current holder
+        let holder: String = sqlx::query_scalar(
+            r#"
+            INSERT INTO receipt_reservations ("transactionId", "tradeId")
+            VALUES ($1, $2)
+            ON CONFLICT ("transactionId")
+            DO UPDATE SET "tradeId" = receipt_reservations."tradeId"
+            RETURNING "tradeId"
+            "#,
+        )
+        .bind(transaction_id)
+        .bind(trade_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        
+        if holder != trade_id {
+            tx.rollback().await?;
+            return Ok(Some(holder));
+        }
+        
+        // A trade holds at most one receipt - drop any earlier upload's reservation
+        sqlx::query(
+            r#"DELETE FROM receipt_reservations WHERE "tradeId" = $1 AND "transactionId" <> $2"#,
+        )
+        .bind(trade_id)
+        .bind(transaction_id)
+        .execute(&mut *tx)
+        .await?;
+        
+        tx.commit().await?;
+        Ok(None)
+    }
+    
+    /// Release the receipt reserved by a trade (validation failure or expiry)
+    pub async fn release(&self, trade_id: &str) -> DbResult<()> {
+        sqlx::query(
+            r#"DELETE FROM receipt_reservations WHERE "tradeId" = $1"#,
+        )
+        .bind(trade_id)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::test_db::{self, random_id};
+    
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_receipt_held_by_one_trade() {
+        let db = test_db::connect().await;
+        let (order, first, second) = (random_id(), random_id(), random_id());
+        test_db::insert_order_with_trades(&db, &order, &[&first, &second]).await;
+        let receipt = random_id();
+        
+        assert_eq!(db.reserve_receipt(&receipt, &first).await.unwrap(), None);
+        // Re-validating the same trade keeps the reservation
+        assert_eq!(db.reserve_receipt(&receipt, &first).await.unwrap(), None);
+        // Another pending trade can't take it
+        assert_eq!(db.reserve_receipt(&receipt, &second).await.unwrap(), Some(first.clone()));
+        
+        // Released (validation failed) - the other trade may use it
+        db.release_receipt_reservation(&first).await.unwrap();
+        assert_eq!(db.reserve_receipt(&receipt, &second).await.unwrap(), None);
+        assert_eq!(db.reserve_receipt(&receipt, &first).await.unwrap(), Some(second));
+    }
+    
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_expired_holder_is_taken_over() {
+        let db = test_db::connect().await;
+        let (order, expired, pending) = (random_id(), random_id(), random_id());
+        test_db::insert_order_with_trades(&db, &order, &[&expired, &pending]).await;
+        let receipt = random_id();
+        
+        assert_eq!(db.reserve_receipt(&receipt, &expired).await.unwrap(), None);
+        db.update_trade_status(&expired, 2).await.unwrap();
+        assert_eq!(db.reserve_receipt(&receipt, &pending).await.unwrap(), None);
+    }
+    
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_new_upload_replaces_earlier_receipt() {
+        let db = test_db::connect().await;
+        let (order, trade, other) = (random_id(), random_id(), random_id());
+        test_db::insert_order_with_trades(&db, &order, &[&trade, &other]).await;
+        let (old_receipt, new_receipt) = (random_id(), random_id());
+        
+        assert_eq!(db.reserve_receipt(&old_receipt, &trade).await.unwrap(), None);
+        assert_eq!(db.reserve_receipt(&new_receipt, &trade).await.unwrap(), None);
+        // The trade's earlier receipt is free again
+        assert_eq!(db.reserve_receipt(&old_receipt, &other).await.unwrap(), None);
+    }
+    
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_concurrent_reservations_have_one_winner() {
+        let db = test_db::connect().await;
+        let order = random_id();
+        let trades: Vec<String> = (0..8).map(|_| random_id()).collect();
+        let trade_refs: Vec<&str> = trades.iter().map(String::as_str).collect();
+        test_db::insert_order_with_trades(&db, &order, &trade_refs).await;
+        let receipt = random_id();
+        
+        let results = futures_util::future::join_all(
+            trades.iter().map(|trade| db.reserve_receipt(&receipt, trade)),
+        ).await;
+        let winners = results.into_iter().filter(|result| matches!(result, Ok(None))).count();
+        assert_eq!(winners, 1);
+    }
+}
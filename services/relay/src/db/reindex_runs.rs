@@ -0,0 +1,130 @@
+use sqlx::PgPool;
+
+use super::DbResult;
+use super::models::DbReindexRun;
+
+/// Repository for lyncz-reindex runs (resumable replays of chain history)
+#[derive(Clone)]
+pub struct PostgresReindexRunRepository {
+    pool: PgPool,
+}
+
+const RUN_COLUMNS: &str = r#""id", "mode", "fromBlock", "toBlock", "chunkSize", "nextBlock", "logsApplied", "logsSkipped", "logsFailed", "startedAt", "completedAt""#;
+
+impl PostgresReindexRunRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+    
+    pub async fn create(
+        &self,
+        contract_address: &str,
+        mode: &str,
+        from_block: u64,
+        to_block: u64,
+        chunk_size: u64,
+    ) -> DbResult<DbReindexRun> {
+        let run = sqlx::query_as::<_, DbReindexRun>(&format!(
+            r#"
+            INSERT INTO reindex_runs ("contractAddress", "mode", "fromBlock", "toBlock", "chunkSize", "nextBlock")
+            VALUES ($1, $2, $3, $4, $5, $3)
+            RETURNING {}
+            "#,
+            RUN_COLUMNS
+        ))
+        .bind(contract_address)
+        .bind(mode)
+        .bind(from_block as i64)
+        .bind(to_block as i64)
+        .bind(chunk_size as i64)
+        .fetch_one(&self.pool)
+        .await?;
+        
+        Ok(run)
+    }
+    
+    /// Latest unfinished run for a contract (or a specific run by ID)
+    pub async fn unfinished(&self, contract_address: &str, id: Option<i64>) -> DbResult<Option<DbReindexRun>> {
+        let run = sqlx::query_as::<_, DbReindexRun>(&format!(
+            r#"
+            SELECT {} FROM reindex_runs
+            WHERE "contractAddress" = $1 AND "completedAt" IS NULL AND ($2::BIGINT IS NULL OR "id" = $2)
+            ORDER BY "id" DESC
+            LIMIT 1
+            "#,
+            RUN_COLUMNS
+        ))
+        .bind(contract_address)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(run)
+    }
+    
+    pub async fn list(&self, contract_address: &str, limit: i64) -> DbResult<Vec<DbReindexRun>> {
+        let runs = sqlx::query_as::<_, DbReindexRun>(&format!(
+            r#"SELECT {} FROM reindex_runs WHERE "contractAddress" = $1 ORDER BY "id" DESC LIMIT $2"#,
+            RUN_COLUMNS
+        ))
+        .bind(contract_address)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(runs)
+    }
+    
+    pub async fn complete(&self, id: i64) -> DbResult<()> {
+        sqlx::query(r#"UPDATE reindex_runs SET "completedAt" = NOW(), "updatedAt" = NOW() WHERE "id" = $1"#)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Point the event listener past the replayed range if it never synced (fresh database)
+    pub async fn init_sync_state(&self, contract_address: &str, next_block: u64) -> DbResult<bool> {
+        let result = sqlx::query(
+            "INSERT INTO event_sync_state (contract_address, last_synced_block)
+             VALUES ($1, $2)
+             ON CONFLICT (contract_address) DO NOTHING",
+        )
+        .bind(contract_address)
+        .bind(next_block as i64)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::event_batch::EventBatch;
+    use crate::db::test_db::{self, random_contract};
+    
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_progress_commits_with_the_chunk() {
+        let db = test_db::connect().await;
+        let runs = PostgresReindexRunRepository::new(db.pool().clone());
+        let contract = random_contract();
+        let run = runs.create(&contract, "apply", 100, 299, 100).await.unwrap();
+        
+        // Chunk rolled back (crash before commit): the run resumes at the same block
+        let mut batch = EventBatch::begin(db.pool(), &contract).await.unwrap();
+        batch.advance_reindex_run(run.id, 200, 5, 1, 0).await.unwrap();
+        drop(batch);
+        let unfinished = runs.unfinished(&contract, Some(run.id)).await.unwrap().unwrap();
+        assert_eq!((unfinished.next_block, unfinished.logs_applied), (100, 0));
+        
+        let mut batch = EventBatch::begin(db.pool(), &contract).await.unwrap();
+        batch.advance_reindex_run(run.id, 200, 5, 1, 0).await.unwrap();
+        batch.commit(None).await.unwrap();
+        let unfinished = runs.unfinished(&contract, Some(run.id)).await.unwrap().unwrap();
+        assert_eq!((unfinished.next_block, unfinished.logs_applied, unfinished.logs_skipped), (200, 5, 1));
+    }
+}
@@ -0,0 +1,297 @@
+use sqlx::{PgPool, Postgres, Transaction};
+
+use super::DbResult;
+use super::models::DbRelayerPendingTx;
+
+/// Repository for relayer nonces and the transactions in flight
+/// Nonces are allocated through a `NonceLease` (advisory lock held until commit)
+#[derive(Clone)]
+pub struct PostgresRelayerNonceRepository {
+    pool: PgPool,
+}
+
+/// Exclusive right to allocate the next nonce of one relayer address
+/// Dropping the lease without `commit` rolls back and releases the lock
+pub struct NonceLease {
+    tx: Transaction<'static, Postgres>,
+    address: String,
+    /// Stored next nonce (None for an address never used)
+    pub next_nonce: Option<u64>,
+}
+
+/// A broadcast transaction to track under the leased nonce
+pub struct NewPendingTx<'a> {
+    pub nonce: u64,
+    pub operation: &'a str,
+    pub trade_id: Option<&'a str>,
+    pub tx_hash: &'a str,
+    /// Legacy gas price or EIP-1559 max fee per gas (wei)
+    pub gas_price: &'a str,
+    pub max_priority_fee: Option<&'a str>,
+    pub urgency: &'a str,
+    pub tx_request: &'a str,
+}
+
+/// Gas paid by the mined transaction (wei, from its receipt)
+pub struct GasPaid {
+    pub gas_used: String,
+    pub effective_gas_price: String,
+    pub cost: String,
+}
+
+impl PostgresRelayerNonceRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+    
+    /// Lock the address's nonce sequence (waits for other processes' sends)
+    pub async fn lease(&self, address: &str) -> DbResult<NonceLease> {
+        let mut tx = self.pool.begin().await?;
+        
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('relayer_nonce:' || $1))")
+            .bind(address)
+            .execute(&mut *tx)
+            .await?;
+        
+        let next_nonce: Option<i64> = sqlx::query_scalar(
+            r#"SELECT "nextNonce" FROM relayer_nonces WHERE "address" = $1"#,
+        )
+        .bind(address)
+        .fetch_optional(&mut *tx)
+        .await?;
+        
+        Ok(NonceLease {
+            tx,
+            address: address.to_string(),
+            next_nonce: next_nonce.map(|nonce| nonce as u64),
+        })
+    }
+    
+    /// Pending transactions of an address, lowest nonce first
+    pub async fn list_pending(&self, address: &str) -> DbResult<Vec<DbRelayerPendingTx>> {
+        let rows = sqlx::query_as::<_, DbRelayerPendingTx>(
+            r#"
+            SELECT "id", "address", "nonce", "operation", "tradeId", "txHash", "txHashes", "gasPrice"::TEXT AS "gasPrice",
+                   "replacements", "txRequest", "status", "createdAt"
+            FROM relayer_pending_transactions
+            WHERE "address" = $1 AND "status" = 'pending'
+            ORDER BY "nonce", "id"
+            "#,
+        )
+        .bind(address)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows)
+    }
+    
+    /// Pending transactions of every relayer address (gas they may still pay)
+    pub async fn list_all_pending(&self) -> DbResult<Vec<DbRelayerPendingTx>> {
+        let rows = sqlx::query_as::<_, DbRelayerPendingTx>(
+            r#"
+            SELECT "id", "address", "nonce", "operation", "tradeId", "txHash", "txHashes", "gasPrice"::TEXT AS "gasPrice",
+                   "replacements", "txRequest", "status", "createdAt"
+            FROM relayer_pending_transactions
+            WHERE "status" = 'pending'
+            ORDER BY "address", "nonce", "id"
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows)
+    }
+    
+    /// Latest broadcast hash of a pending transaction settling any of `trade_ids`
+    /// (None if nothing is in flight)
+    /// 
+    /// submitProof rows carry their trade; batch rows are found through the batch's
+    /// recorded submitBatchProof hash.
+    pub async fn pending_settlement(&self, trade_ids: &[String]) -> DbResult<Option<String>> {
+        let tx_hash = sqlx::query_scalar(
+            r#"
+            SELECT "txHash" FROM relayer_pending_transactions
+            WHERE "status" = 'pending'
+              AND ("tradeId" = ANY($1)
+                   OR "txHashes" && ARRAY(
+                       SELECT "settlementTxHash"::TEXT FROM settlement_batches
+                       WHERE "tradeIds" && $1 AND "settlementTxHash" IS NOT NULL
+                   ))
+            ORDER BY "id" DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(trade_ids)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(tx_hash)
+    }
+    
+    /// Record a replacement broadcast (same nonce, higher fees)
+    pub async fn record_replacement(
+        &self,
+        id: i64,
+        tx_hash: &str,
+        gas_price: &str,
+        max_priority_fee: Option<&str>,
+    ) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE relayer_pending_transactions
+            SET "txHash" = $2,
+                "txHashes" = array_append("txHashes", $2),
+                "gasPrice" = $3::NUMERIC,
+                "maxPriorityFeePerGas" = $4::NUMERIC,
+                "replacements" = "replacements" + 1,
+                "updatedAt" = NOW()
+            WHERE "id" = $1
+            "#,
+        )
+        .bind(id)
+        .bind(tx_hash)
+        .bind(gas_price)
+        .bind(max_priority_fee)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Close a pending transaction (mined, reverted or dropped) with the gas it paid
+    pub async fn finish(
+        &self,
+        id: i64,
+        status: &str,
+        mined_tx_hash: Option<&str>,
+        paid: Option<&GasPaid>,
+    ) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE relayer_pending_transactions
+            SET "status" = $2, "minedTxHash" = $3,
+                "gasUsed" = $4::NUMERIC, "effectiveGasPrice" = $5::NUMERIC, "gasCost" = $6::NUMERIC,
+                "updatedAt" = NOW()
+            WHERE "id" = $1
+            "#,
+        )
+        .bind(id)
+        .bind(status)
+        .bind(mined_tx_hash)
+        .bind(paid.map(|paid| paid.gas_used.as_str()))
+        .bind(paid.map(|paid| paid.effective_gas_price.as_str()))
+        .bind(paid.map(|paid| paid.cost.as_str()))
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+}
+
+impl NonceLease {
+    /// Pending transactions at or above `nonce` (none = nothing can fill a gap there)
+    pub async fn pending_from(&mut self, nonce: u64) -> DbResult<i64> {
+        let count = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM relayer_pending_transactions
+            WHERE "address" = $1 AND "status" = 'pending' AND "nonce" >= $2
+            "#,
+        )
+        .bind(&self.address)
+        .bind(nonce as i64)
+        .fetch_one(&mut *self.tx)
+        .await?;
+        
+        Ok(count)
+    }
+    
+    /// Store the next nonce and track the broadcast, then release the lock
+    /// Returns the pending transaction's ID
+    pub async fn commit(mut self, pending: NewPendingTx<'_>) -> DbResult<i64> {
+        sqlx::query(
+            r#"
+            INSERT INTO relayer_nonces ("address", "nextNonce")
+            VALUES ($1, $2)
+            ON CONFLICT ("address") DO UPDATE SET "nextNonce" = $2, "updatedAt" = NOW()
+            "#,
+        )
+        .bind(&self.address)
+        .bind(pending.nonce as i64 + 1)
+        .execute(&mut *self.tx)
+        .await?;
+        
+        let id = sqlx::query_scalar(
+            r#"
+            INSERT INTO relayer_pending_transactions
+                ("address", "nonce", "operation", "tradeId", "txHash", "txHashes", "gasPrice", "maxPriorityFeePerGas", "urgency", "txRequest")
+            VALUES ($1, $2, $3, $4, $5, ARRAY[$5], $6::NUMERIC, $7::NUMERIC, $8, $9)
+            RETURNING "id"
+            "#,
+        )
+        .bind(&self.address)
+        .bind(pending.nonce as i64)
+        .bind(pending.operation)
+        .bind(pending.trade_id)
+        .bind(pending.tx_hash)
+        .bind(pending.gas_price)
+        .bind(pending.max_priority_fee)
+        .bind(pending.urgency)
+        .bind(pending.tx_request)
+        .fetch_one(&mut *self.tx)
+        .await?;
+        
+        self.tx.commit().await?;
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    async fn track(repo: &PostgresRelayerNonceRepository, address: &str, nonce: u64, operation: &str, trade_id: Option<&str>) -> (i64, String) {
+        let tx_hash = test_db::random_id();
+        let lease = repo.lease(address).await.unwrap();
+        let id = lease.commit(NewPendingTx {
+            nonce,
+            operation,
+            trade_id,
+            tx_hash: &tx_hash,
+            gas_price: "1000",
+            max_priority_fee: None,
+            urgency: "normal",
+            tx_request: "{}",
+        }).await.unwrap();
+        (id, tx_hash)
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn test_pending_settlement_for_trade_and_batch() {
+        let db = test_db::connect().await;
+        let repo = PostgresRelayerNonceRepository::new(db.pool().clone());
+        let address = test_db::random_contract();
+        let (single, batched) = ([test_db::random_id()], [test_db::random_id()]);
+        
+        assert_eq!(repo.pending_settlement(&single).await.unwrap(), None);
+        
+        // submitProof rows carry their trade; a replacement's hash is the one reported
+        let (id, _) = track(&repo, &address, 0, "submitProof", Some(&single[0])).await;
+        let replacement = test_db::random_id();
+        repo.record_replacement(id, &replacement, "1200", None).await.unwrap();
+        assert_eq!(repo.pending_settlement(&single).await.unwrap(), Some(replacement.clone()));
+        
+        // Batch rows have no trade - found through the batch's recorded hash
+        let (_, batch_tx) = track(&repo, &address, 1, "submitBatchProof", None).await;
+        let batch_id = format!("batch-{}", uuid::Uuid::new_v4());
+        db.create_settlement_batch(&batch_id, &[batched[0].clone(), test_db::random_id()]).await.unwrap();
+        assert_eq!(repo.pending_settlement(&batched).await.unwrap(), None);
+        db.mark_settlement_batch_settled(&batch_id, &batch_tx).await.unwrap();
+        assert_eq!(repo.pending_settlement(&batched).await.unwrap(), Some(batch_tx));
+        
+        // Mined transactions no longer block
+        repo.finish(id, "mined", Some(&replacement), None).await.unwrap();
+        assert_eq!(repo.pending_settlement(&single).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use super::DbResult;
+use super::models::DbRelayerTransaction;
+
+/// Repository for the relayer gas ledger (one row per mined transaction)
+#[derive(Clone)]
+pub struct PostgresRelayerTransactionRepository {
+    pool: PgPool,
+}
+
+/// A mined relayer transaction and the gas it paid (amounts in wei, decimal strings)
+pub struct NewRelayerTransaction<'a> {
+    pub tx_hash: &'a str,
+    pub address: &'a str,
+    pub operation: &'a str,
+    pub trade_id: Option<&'a str>,
+    pub status: &'a str,
+    pub gas_used: &'a str,
+    pub effective_gas_price: &'a str,
+    pub gas_cost: &'a str,
+    pub block_number: Option<i64>,
+}
+
+impl PostgresRelayerTransactionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+    
+    /// Record a mined transaction (idempotent - a transaction is only counted once)
+    pub async fn record(&self, tx: NewRelayerTransaction<'_>) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO relayer_transactions
+                ("txHash", "address", "operation", "tradeId", "status", "gasUsed", "effectiveGasPrice", "gasCost", "blockNumber")
+            VALUES ($1, $2, $3, $4, $5, $6::NUMERIC, $7::NUMERIC, $8::NUMERIC, $9)
+            ON CONFLICT ("txHash") DO NOTHING
+            "#,
+        )
+        .bind(tx.tx_hash)
+        .bind(tx.address)
+        .bind(tx.operation)
+        .bind(tx.trade_id)
+        .bind(tx.status)
+        .bind(tx.gas_used)
+        .bind(tx.effective_gas_price)
+        .bind(tx.gas_cost)
+        .bind(tx.block_number)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Attribute a transaction to a trade known only once mined (fillOrder's new trade)
+    pub async fn set_trade(&self, tx_hash: &str, trade_id: &str) -> DbResult<()> {
+        sqlx::query(r#"UPDATE relayer_transactions SET "tradeId" = $2 WHERE "txHash" = $1"#)
+            .bind(tx_hash)
+            .bind(trade_id)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Total gas cost (wei) of transactions mined since `since`
+    pub async fn spent_since(&self, since: DateTime<Utc>) -> DbResult<String> {
+        let spent: String = sqlx::query_scalar(
+            r#"SELECT COALESCE(SUM("gasCost"), 0)::TEXT FROM relayer_transactions WHERE "createdAt" >= $1"#,
+        )
+        .bind(since)
+        .fetch_one(&self.pool)
+        .await?;
+        
+        Ok(spent)
+    }
+    
+    /// Most recent transactions, newest first
+    pub async fn list_recent(&self, limit: i64) -> DbResult<Vec<DbRelayerTransaction>> {
+        let rows = sqlx::query_as::<_, DbRelayerTransaction>(
+            r#"
+            SELECT "txHash", "address", "operation", "tradeId", "status",
+                   "gasUsed"::TEXT AS "gasUsed", "effectiveGasPrice"::TEXT AS "effectiveGasPrice",
+                   "gasCost"::TEXT AS "gasCost", "blockNumber", "createdAt"
+            FROM relayer_transactions
+            ORDER BY "createdAt" DESC
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows)
+    }
+}
@@ -0,0 +1,98 @@
+use sqlx::PgPool;
+
+use super::DbResult;
+use super::models::DbSettlementBatch;
+
+/// Repository for settlement batches (one proof settling several trades)
+pub struct PostgresSettlementBatchRepository {
+    pool: PgPool,
+}
+
+impl PostgresSettlementBatchRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+    
+    /// Create a batch - trade order is the proof's leaf order
+    pub async fn create(&self, batch_id: &str, trade_ids: &[String]) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO settlement_batches ("batchId", "tradeIds")
+            VALUES ($1, $2)
+            "#,
+        )
+        .bind(batch_id)
+        .bind(trade_ids)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Save the batch proof (batch root + Halo2 proof)
+    pub async fn save_proof(
+        &self,
+        batch_id: &str,
+        user_public_values: &[u8],
+        accumulator: &[u8],
+        proof_data: &[u8],
+        proof_id: &str,
+    ) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE settlement_batches
+            SET "proofUserPublicValues" = $2,
+                "proofAccumulator" = $3,
+                "proofData" = $4,
+                "axiomProofId" = $5,
+                "updatedAt" = NOW()
+            WHERE "batchId" = $1
+            "#,
+        )
+        .bind(batch_id)
+        .bind(user_public_values)
+        .bind(accumulator)
+        .bind(proof_data)
+        .bind(proof_id)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Record the submitBatchProof transaction hash
+    pub async fn mark_settled(&self, batch_id: &str, tx_hash: &str) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE settlement_batches
+            SET "settlementTxHash" = $2, "updatedAt" = NOW()
+            WHERE "batchId" = $1
+            "#,
+        )
+        .bind(batch_id)
+        .bind(tx_hash)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Most recent batch containing a trade (None if it was never batched)
+    pub async fn get_for_trade(&self, trade_id: &str) -> DbResult<Option<DbSettlementBatch>> {
+        let batch = sqlx::query_as::<_, DbSettlementBatch>(
+            r#"
+            SELECT "batchId", "tradeIds", "proofUserPublicValues", "proofAccumulator",
+                   "proofData", "axiomProofId", "settlementTxHash", "createdAt"
+            FROM settlement_batches
+            WHERE $1 = ANY("tradeIds")
+            ORDER BY "createdAt" DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(trade_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(batch)
+    }
+}
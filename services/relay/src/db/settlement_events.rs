@@ -0,0 +1,73 @@
+use sqlx::PgPool;
+
+use super::DbResult;
+use super::models::{DbSettlementEvent, SettlementState};
+
+/// Repository for settlement state transitions - current state on trades + history table
+pub struct PostgresSettlementEventRepository {
+    pool: PgPool,
+}
+
+impl PostgresSettlementEventRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+    
+    /// Record a transition: append to history and update trades.settlement_state atomically
+    pub async fn record(&self, trade_id: &str, state: &SettlementState, detail: Option<&str>) -> DbResult<()> {
+        let state = state.to_string();
+        let mut tx = self.pool.begin().await?;
+        
+        sqlx::query(
+            r#"
+            INSERT INTO trade_settlement_events ("tradeId", "state", "detail")
+            VALUES ($1, $2, $3)
+            "#,
+        )
+        .bind(trade_id)
+        .bind(&state)
+        .bind(detail)
+        .execute(&mut *tx)
+        .await?;
+        
+        sqlx::query(
+            r#"UPDATE trades SET settlement_state = $1 WHERE "tradeId" = $2"#,
+        )
+        .bind(&state)
+        .bind(trade_id)
+        .execute(&mut *tx)
+        .await?;
+        
+        tx.commit().await?;
+        Ok(())
+    }
+    
+    /// Current settlement state of a trade (None if settlement never started)
+    pub async fn current(&self, trade_id: &str) -> DbResult<Option<String>> {
+        let state: Option<Option<String>> = sqlx::query_scalar(
+            r#"SELECT settlement_state FROM trades WHERE "tradeId" = $1"#,
+        )
+        .bind(trade_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(state.flatten())
+    }
+    
+    /// Get the settlement timeline for a trade, oldest first
+    pub async fn get_by_trade(&self, trade_id: &str) -> DbResult<Vec<DbSettlementEvent>> {
+        let events = sqlx::query_as::<_, DbSettlementEvent>(
+            r#"
+            SELECT id, "tradeId", "state", "detail", "createdAt"
+            FROM trade_settlement_events
+            WHERE "tradeId" = $1
+            ORDER BY id ASC
+            "#,
+        )
+        .bind(trade_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(events)
+    }
+}
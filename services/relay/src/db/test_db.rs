@@ -0,0 +1,94 @@
+//! Postgres for database-backed tests
+//!
+//! These tests are `#[ignore]`d - run them against a scratch database with
+//! `TEST_DATABASE_URL=postgres://... cargo test -- --ignored`. Every test works on
+//! fresh random order/trade IDs, so they can share one database.
+
+use rand::RngCore;
+
+use super::Database;
+use super::event_batch::EventBatch;
+use super::models::{DbOrder, DbTrade};
+
+/// Connect to TEST_DATABASE_URL and run the migrations
+pub async fn connect() -> Database {
+    let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+    let db = Database::new(&url).await.expect("connect to TEST_DATABASE_URL");
+    db.migrate().await.expect("migrate test database");
+    db
+}
+
+/// Random bytes32 ID (0x-prefixed hex)
+pub fn random_id() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Random contract address, so sync state and journals of tests don't collide
+pub fn random_contract() -> String {
+    random_id()[..42].to_string()
+}
+
+pub fn order(order_id: &str, remaining_amount: &str) -> DbOrder {
+    DbOrder {
+        order_id: order_id.to_string(),
+        seller: "0x00000000000000000000000000000000000000a1".to_string(),
+        token: "0x00000000000000000000000000000000000000b2".to_string(),
+        total_amount: remaining_amount.to_string(),
+        remaining_amount: remaining_amount.to_string(),
+        exchange_rate: "720".to_string(),
+        rail: 0,
+        alipay_id: String::new(),
+        alipay_name: String::new(),
+        created_at: chrono::Utc::now().timestamp(),
+        synced_at: chrono::Utc::now(),
+        is_public: true,
+        private_code: None,
+        memo_required: false,
+    }
+}
+
+pub fn trade(trade_id: &str, order_id: &str) -> DbTrade {
+    DbTrade {
+        trade_id: trade_id.to_string(),
+        order_id: order_id.to_string(),
+        buyer: "0x00000000000000000000000000000000000000c3".to_string(),
+        token_amount: "1000".to_string(),
+        cny_amount: "10050".to_string(),
+        fee_amount: Some("10".to_string()),
+        rail: 0,
+        transaction_id: None,
+        payment_time: None,
+        created_at: chrono::Utc::now().timestamp(),
+        expires_at: chrono::Utc::now().timestamp() + 900,
+        status: 0,
+        synced_at: chrono::Utc::now(),
+        escrow_tx_hash: None,
+        settlement_tx_hash: None,
+        token: None,
+        alipay_id: None,
+        alipay_name: None,
+        pdf_file: None,
+        pdf_filename: None,
+        pdf_uploaded_at: None,
+        proof_user_public_values: None,
+        proof_accumulator: None,
+        proof_data: None,
+        axiom_proof_id: None,
+        proof_generated_at: None,
+        proof_json: None,
+        settlement_error: None,
+        memo_code: None,
+    }
+}
+
+/// Insert an order with one pending trade per ID
+pub async fn insert_order_with_trades(db: &Database, order_id: &str, trade_ids: &[&str]) {
+    let mut batch = EventBatch::begin(db.pool(), &random_contract()).await.unwrap();
+    batch.upsert_order(&order(order_id, "100000")).await.unwrap();
+    for trade_id in trade_ids {
+        batch.insert_trade(&trade(trade_id, order_id)).await.unwrap();
+    }
+    batch.commit(None).await.unwrap();
+}
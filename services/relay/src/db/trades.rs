@@ -1,5 +1,5 @@
 use async_trait::async_trait;
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use chrono::{DateTime, Utc};
 
 use super::{DbError, DbResult};
@@ -46,6 +46,12 @@ pub trait TradeRepository: Send + Sync {
     
     /// Save settlement error when blockchain submission fails
     async fn save_settlement_error(&self, trade_id: &str, error_code: &str) -> DbResult<()>;
+    
+    /// Clear settlement error once the trade is settled (e.g. self-submitted by the buyer)
+    async fn clear_settlement_error(&self, trade_id: &str) -> DbResult<()>;
+    
+    /// Save the memo code a memo-bound trade's receipt must carry (from TradeMemoRequired)
+    async fn set_memo_code(&self, trade_id: &str, memo_code: &str) -> DbResult<()>;
 }
 
 pub struct PostgresTradeRepository {
@@ -61,38 +67,8 @@ impl PostgresTradeRepository {
 #[async_trait]
 impl TradeRepository for PostgresTradeRepository {
     async fn create(&self, trade: &DbTrade) -> DbResult<()> {
-        // Use dynamic query to avoid SQLX offline cache issues
-        sqlx::query(
-            r#"
-            INSERT INTO trades (
-                "tradeId", "orderId", "buyer", "token", "tokenAmount", "cnyAmount", "feeAmount",
-                "rail", "transactionId", "paymentTime",
-                "createdAt", "expiresAt", "status",
-                "escrowTxHash", "settlementTxHash"
-            )
-            VALUES ($1, $2, $3, $4, $5::numeric, $6::numeric, $7::numeric, $8, $9, $10, $11, $12, $13, $14, $15)
-            ON CONFLICT ("tradeId") DO NOTHING
-            "#,
-        )
-        .bind(&trade.trade_id)
-        .bind(&trade.order_id)
-        .bind(&trade.buyer)
-        .bind(&trade.token)
-        .bind(&trade.token_amount)
-        .bind(&trade.cny_amount)
-        .bind(&trade.fee_amount)
-        .bind(trade.rail)
-        .bind(&trade.transaction_id)
-        .bind(&trade.payment_time)
-        .bind(trade.created_at)
-        .bind(trade.expires_at)
-        .bind(trade.status)
-        .bind(&trade.escrow_tx_hash)
-        .bind(&trade.settlement_tx_hash)
-        .execute(&self.pool)
-        .await?;
-        
-        Ok(())
+        let mut conn = self.pool.acquire().await?;
+        insert_trade(&mut conn, trade).await
     }
 
     async fn get(&self, trade_id: &str) -> DbResult<DbTrade> {
@@ -106,7 +82,7 @@ impl TradeRepository for PostgresTradeRepository {
                 "escrowTxHash", "settlementTxHash", "syncedAt",
                 pdf_file, pdf_filename, pdf_uploaded_at,
                 proof_user_public_values, proof_accumulator, proof_data,
-                axiom_proof_id, proof_generated_at, proof_json, settlement_error
+                axiom_proof_id, proof_generated_at, proof_json, settlement_error, memo_code
             FROM trades
             WHERE "tradeId" = $1
             "#,
@@ -144,6 +120,7 @@ impl TradeRepository for PostgresTradeRepository {
             proof_generated_at: row.get("proof_generated_at"),
             proof_json: row.get("proof_json"),
             settlement_error: row.get("settlement_error"),
+            memo_code: row.get("memo_code"),
             alipay_id: None, // Not available in single trade query
             alipay_name: None, // Not available in single trade query
         })
@@ -300,7 +277,7 @@ impl TradeRepository for PostgresTradeRepository {
                 "escrowTxHash", "settlementTxHash", "syncedAt",
                 pdf_file, pdf_filename, pdf_uploaded_at,
                 proof_user_public_values, proof_accumulator, proof_data,
-                axiom_proof_id, proof_generated_at, proof_json, settlement_error
+                axiom_proof_id, proof_generated_at, proof_json, settlement_error, memo_code
             FROM trades
             WHERE status = 0 AND "expiresAt" < EXTRACT(EPOCH FROM NOW())::bigint
             ORDER BY "expiresAt" ASC
@@ -339,6 +316,7 @@ impl TradeRepository for PostgresTradeRepository {
                 proof_generated_at: row.get("proof_generated_at"),
                 proof_json: row.get("proof_json"),
                 settlement_error: row.get("settlement_error"),
+                memo_code: row.get("memo_code"),
                 alipay_id: None, // Not needed for auto-cancellation
                 alipay_name: None, // Not needed for auto-cancellation
             });
@@ -356,7 +334,7 @@ impl TradeRepository for PostgresTradeRepository {
                 t."escrowTxHash", t."settlementTxHash", t."syncedAt",
                 t.pdf_file, t.pdf_filename, t.pdf_uploaded_at,
                 t.proof_user_public_values, t.proof_accumulator, t.proof_data,
-                t.axiom_proof_id, t.proof_generated_at, t.proof_json, t.settlement_error,
+                t.axiom_proof_id, t.proof_generated_at, t.proof_json, t.settlement_error, t.memo_code,
                 COALESCE(t.token, o.token) as token,
                 o."accountId" as "alipay_id",
                 o."accountName" as "alipay_name"
@@ -399,6 +377,7 @@ impl TradeRepository for PostgresTradeRepository {
                 proof_generated_at: row.get("proof_generated_at"),
                 proof_json: row.get("proof_json"),
                 settlement_error: row.get("settlement_error"),
+                memo_code: row.get("memo_code"),
                 alipay_id: row.get("alipay_id"),
                 alipay_name: row.get("alipay_name"),
             });
@@ -421,6 +400,33 @@ impl TradeRepository for PostgresTradeRepository {
 
         Ok(())
     }
+
+    async fn clear_settlement_error(&self, trade_id: &str) -> DbResult<()> {
+        sqlx::query(
+            r#"UPDATE trades SET settlement_error = NULL WHERE "tradeId" = $1"#,
+        )
+        .bind(trade_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_memo_code(&self, trade_id: &str, memo_code: &str) -> DbResult<()> {
+        let result = sqlx::query(
+            r#"UPDATE trades SET memo_code = $1 WHERE "tradeId" = $2"#,
+        )
+        .bind(memo_code)
+        .bind(trade_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(DbError::TradeNotFound(trade_id.to_string()));
+        }
+
+        Ok(())
+    }
     
     async fn is_transaction_id_used(&self, transaction_id: &str) -> DbResult<bool> {
         // Check if any SETTLED trade (status=1) has this transaction ID
@@ -464,7 +470,7 @@ impl PostgresTradeRepository {
                 t."settlementTxHash",
                 t.pdf_file, t.pdf_filename, t.pdf_uploaded_at,
                 t.proof_user_public_values, t.proof_accumulator, t.proof_data,
-                t.axiom_proof_id, t.proof_generated_at, t.proof_json, t.settlement_error,
+                t.axiom_proof_id, t.proof_generated_at, t.proof_json, t.settlement_error, t.memo_code,
                 COALESCE(t.token, o.token) as token,
                 o."accountId" as "alipay_id",
                 o."accountName" as "alipay_name"
@@ -508,6 +514,7 @@ impl PostgresTradeRepository {
                 proof_generated_at: row.get("proof_generated_at"),
                 proof_json: row.get("proof_json"),
                 settlement_error: row.get("settlement_error"),
+                memo_code: row.get("memo_code"),
                 alipay_id: row.get("alipay_id"),
                 alipay_name: row.get("alipay_name"),
             });
@@ -538,7 +545,7 @@ impl PostgresTradeRepository {
                 t."settlementTxHash",
                 t.pdf_file, t.pdf_filename, t.pdf_uploaded_at,
                 t.proof_user_public_values, t.proof_accumulator, t.proof_data,
-                t.axiom_proof_id, t.proof_generated_at, t.proof_json, t.settlement_error,
+                t.axiom_proof_id, t.proof_generated_at, t.proof_json, t.settlement_error, t.memo_code,
                 COALESCE(t.token, o.token) as token,
                 o."accountId" as "alipay_id",
                 o."accountName" as "alipay_name"
@@ -582,6 +589,7 @@ impl PostgresTradeRepository {
                 proof_generated_at: row.get("proof_generated_at"),
                 proof_json: row.get("proof_json"),
                 settlement_error: row.get("settlement_error"),
+                memo_code: row.get("memo_code"),
                 alipay_id: row.get("alipay_id"),
                 alipay_name: row.get("alipay_name"),
             });
@@ -589,3 +597,39 @@ impl PostgresTradeRepository {
         Ok(trades)
     }
 }
+
+/// Insert a trade from TradeCreated (also used inside event-sync transactions)
+pub(crate) async fn insert_trade(conn: &mut PgConnection, trade: &DbTrade) -> DbResult<()> {
+    // Use dynamic query to avoid SQLX offline cache issues
+    sqlx::query(
+        r#"
+        INSERT INTO trades (
+            "tradeId", "orderId", "buyer", "token", "tokenAmount", "cnyAmount", "feeAmount",
+            "rail", "transactionId", "paymentTime",
+            "createdAt", "expiresAt", "status",
+            "escrowTxHash", "settlementTxHash"
+        )
+        VALUES ($1, $2, $3, $4, $5::numeric, $6::numeric, $7::numeric, $8, $9, $10, $11, $12, $13, $14, $15)
+        ON CONFLICT ("tradeId") DO NOTHING
+        "#,
+    )
+    .bind(&trade.trade_id)
+    .bind(&trade.order_id)
+    .bind(&trade.buyer)
+    .bind(&trade.token)
+    .bind(&trade.token_amount)
+    .bind(&trade.cny_amount)
+    .bind(&trade.fee_amount)
+    .bind(trade.rail)
+    .bind(&trade.transaction_id)
+    .bind(&trade.payment_time)
+    .bind(trade.created_at)
+    .bind(trade.expires_at)
+    .bind(trade.status)
+    .bind(&trade.escrow_tx_hash)
+    .bind(&trade.settlement_tx_hash)
+    .execute(conn)
+    .await?;
+    
+    Ok(())
+}
@@ -237,30 +237,27 @@ impl EmailService {
         }
     }
     
-    /// Send admin alert for key rotation (optimistic workflow)
+    /// Send admin alert for a submitted key rotation (governed workflow, see key_rotation)
     pub async fn send_key_rotation_alert(
         &self,
         old_hash: &str,
         new_hash: &str,
         trade_id: &str,
     ) -> Result<(), String> {
-        let admin_email = std::env::var("ADMIN_ALERT_EMAIL")
-            .unwrap_or_else(|_| "anonysatoshi@proton.me".to_string());
-        
         let subject = "🔑 [LyncZ Alert] Alipay Public Key Rotated";
         let html = format!(r#"
 <!DOCTYPE html>
 <html>
 <head><meta charset="UTF-8"></head>
 <body style="font-family: system-ui, sans-serif; padding: 20px;">
-    <h2>🔑 Alipay Public Key Rotation Detected</h2>
-    <p>The Alipay public key has been <strong>automatically updated</strong> on the contract.</p>
-    <p><strong>Please verify this change immediately.</strong></p>
+    <h2>🔑 Alipay Public Key Rotation Submitted</h2>
+    <p>A quarantined Alipay public key passed the rotation policy and has been <strong>updated</strong> on the contract.</p>
+    <p><strong>Please verify this change.</strong></p>
     
     <h3>Details:</h3>
     <table style="border-collapse: collapse; margin: 20px 0;">
         <tr>
-            <td style="padding: 8px; border: 1px solid #ddd; background: #f9f9f9;"><strong>Triggered by Trade:</strong></td>
+            <td style="padding: 8px; border: 1px solid #ddd; background: #f9f9f9;"><strong>First Seen on Trade:</strong></td>
             <td style="padding: 8px; border: 1px solid #ddd; font-family: monospace;">{}</td>
         </tr>
         <tr>
@@ -273,18 +270,156 @@ impl EmailService {
         </tr>
     </table>
     
-    <p style="color: #dc2626;"><strong>Action Required:</strong> Verify the new key hash matches Alipay's current public key.</p>
-    
     <p style="color: #666; font-size: 12px; margin-top: 30px;">
-        This is an automated alert from LyncZ's optimistic key rotation system.
+        This is an automated alert from LyncZ's key rotation system. The full history is in key_rotation_audit.
     </p>
 </body>
 </html>
 "#, trade_id, old_hash, new_hash);
-
+        
+        self.send_admin_alert(subject, html).await?;
+        info!("📧 Key rotation alert sent (old: {}, new: {})", &old_hash[..16], &new_hash[..16]);
+        Ok(())
+    }
+    
+    /// Send admin alert for a newly quarantined key (first receipt signed by it)
+    pub async fn send_key_quarantine_alert(
+        &self,
+        pk_hash: &str,
+        trade_id: &str,
+    ) -> Result<(), String> {
+        let subject = "🔑 [LyncZ Alert] New Alipay Public Key Quarantined";
+        let html = format!(r#"
+<!DOCTYPE html>
+<html>
+<head><meta charset="UTF-8"></head>
+<body style="font-family: system-ui, sans-serif; padding: 20px;">
+    <h2>🔑 New Alipay Public Key Quarantined</h2>
+    <p>A validated receipt was signed by a key the contract doesn't trust. The key is <strong>quarantined</strong>: receipts signed by it are refused until its rotation is submitted.</p>
+    
+    <h3>Details:</h3>
+    <table style="border-collapse: collapse; margin: 20px 0;">
+        <tr>
+            <td style="padding: 8px; border: 1px solid #ddd; background: #f9f9f9;"><strong>Seen on Trade:</strong></td>
+            <td style="padding: 8px; border: 1px solid #ddd; font-family: monospace;">{}</td>
+        </tr>
+        <tr>
+            <td style="padding: 8px; border: 1px solid #ddd; background: #f9f9f9;"><strong>Key Hash:</strong></td>
+            <td style="padding: 8px; border: 1px solid #ddd; font-family: monospace; color: #f59e0b;">{}</td>
+        </tr>
+    </table>
+    
+    <p style="color: #dc2626;"><strong>Action Required:</strong> Verify the key belongs to Alipay, then approve it
+    (<code>key-rotation approve {} --by &lt;name&gt;</code>) or reject it (<code>key-rotation reject</code>).
+    Without a decision it is submitted once seen on enough receipts and the delay has passed.</p>
+    
+    <p style="color: #666; font-size: 12px; margin-top: 30px;">
+        This is an automated alert from LyncZ's key rotation system.
+    </p>
+</body>
+</html>
+"#, trade_id, pk_hash, pk_hash);
+        
+        self.send_admin_alert(subject, html).await?;
+        info!("📧 Key quarantine alert sent (key: {})", &pk_hash[..16]);
+        Ok(())
+    }
+    
+    /// Send alert when the relayer's ETH balance drops below the threshold
+    pub async fn send_low_balance_alert(
+        &self,
+        relayer_address: &str,
+        balance_wei: &str,
+        threshold_wei: &str,
+    ) -> Result<(), String> {
+        let subject = "⛽ [LyncZ Alert] Relayer Balance Low";
+        let html = format!(r#"
+<!DOCTYPE html>
+<html>
+<head><meta charset="UTF-8"></head>
+<body style="font-family: system-ui, sans-serif; padding: 20px;">
+    <h2>⛽ Relayer Balance Low</h2>
+    <p>The relayer pays gas for trade creation and proof submission. Once it runs dry, sponsored transactions fail.</p>
+    
+    <h3>Details:</h3>
+    <table style="border-collapse: collapse; margin: 20px 0;">
+        <tr>
+            <td style="padding: 8px; border: 1px solid #ddd; background: #f9f9f9;"><strong>Relayer:</strong></td>
+            <td style="padding: 8px; border: 1px solid #ddd; font-family: monospace;">{}</td>
+        </tr>
+        <tr>
+            <td style="padding: 8px; border: 1px solid #ddd; background: #f9f9f9;"><strong>Balance:</strong></td>
+            <td style="padding: 8px; border: 1px solid #ddd; font-family: monospace; color: #dc2626;">{} ETH</td>
+        </tr>
+        <tr>
+            <td style="padding: 8px; border: 1px solid #ddd; background: #f9f9f9;"><strong>Threshold:</strong></td>
+            <td style="padding: 8px; border: 1px solid #ddd; font-family: monospace;">{} ETH</td>
+        </tr>
+    </table>
+    
+    <p style="color: #dc2626;"><strong>Action Required:</strong> Top up the relayer address.</p>
+    
+    <p style="color: #666; font-size: 12px; margin-top: 30px;">
+        This is an automated alert from LyncZ's relayer monitor.
+    </p>
+</body>
+</html>
+"#, relayer_address, format_token_amount(balance_wei, 18, ""), format_token_amount(threshold_wei, 18, ""));
+        
+        self.send_admin_alert(subject, html).await?;
+        info!("📧 Low balance alert sent (relayer: {})", relayer_address);
+        Ok(())
+    }
+    
+    /// Send alert when the relayer's hourly spend cap trips and gas sponsorship pauses
+    pub async fn send_spend_cap_alert(
+        &self,
+        spent_wei: &str,
+        cap_wei: &str,
+    ) -> Result<(), String> {
+        let subject = "⛔ [LyncZ Alert] Relayer Spend Cap Reached";
+        let html = format!(r#"
+<!DOCTYPE html>
+<html>
+<head><meta charset="UTF-8"></head>
+<body style="font-family: system-ui, sans-serif; padding: 20px;">
+    <h2>⛔ Relayer Spend Cap Reached</h2>
+    <p>The relayer spent more gas in the last hour than allowed. Gas-sponsored endpoints return 503 until spending drops back under the cap; users can still submit transactions from their own wallets.</p>
+    
+    <h3>Details:</h3>
+    <table style="border-collapse: collapse; margin: 20px 0;">
+        <tr>
+            <td style="padding: 8px; border: 1px solid #ddd; background: #f9f9f9;"><strong>Spent (last hour):</strong></td>
+            <td style="padding: 8px; border: 1px solid #ddd; font-family: monospace; color: #dc2626;">{} ETH</td>
+        </tr>
+        <tr>
+            <td style="padding: 8px; border: 1px solid #ddd; background: #f9f9f9;"><strong>Hourly Cap:</strong></td>
+            <td style="padding: 8px; border: 1px solid #ddd; font-family: monospace;">{} ETH</td>
+        </tr>
+    </table>
+    
+    <p style="color: #dc2626;"><strong>Action Required:</strong> Check <code>/api/admin/relayer</code> for the transactions behind the spend.</p>
+    
+    <p style="color: #666; font-size: 12px; margin-top: 30px;">
+        This is an automated alert from LyncZ's relayer monitor.
+    </p>
+</body>
+</html>
+"#, format_token_amount(spent_wei, 18, ""), format_token_amount(cap_wei, 18, ""));
+        
+        self.send_admin_alert(subject, html).await?;
+        info!("📧 Spend cap alert sent (spent: {} wei)", spent_wei);
+        Ok(())
+    }
+    
+    /// Send an alert to ADMIN_ALERT_EMAIL
+    async fn send_admin_alert(&self, subject: &str, html: String) -> Result<(), String> {
+        let admin_email = std::env::var("ADMIN_ALERT_EMAIL")
+            .unwrap_or_else(|_| "anonysatoshi@proton.me".to_string());
+        
         let request = ResendEmailRequest {
             from: self.config.from_email.clone(),
-            to: vec![admin_email.clone()],
+            to: vec![admin_email],
             subject: subject.to_string(),
             html,
         };
@@ -299,15 +434,11 @@ impl EmailService {
             .map_err(|e| format!("Failed to send email request: {}", e))?;
         
         if response.status().is_success() {
-            info!(
-                "📧 Key rotation alert sent to {} (old: {}, new: {})",
-                admin_email, &old_hash[..16], &new_hash[..16]
-            );
             Ok(())
         } else {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            error!("❌ Failed to send key rotation alert: {} - {}", status, body);
+            error!("❌ Failed to send admin alert: {} - {}", status, body);
             Err(format!("Email API error: {} - {}", status, body))
         }
     }
@@ -0,0 +1,303 @@
+//! Relayer gas budget - balance monitoring and the hourly spend circuit breaker
+//!
+//! Every mined relayer transaction is recorded with the gas it paid in
+//! `relayer_transactions` (API server and auto-cancel alike):
+//! - once the last hour's gas cost plus what pending transactions may still pay
+//!   (gas limit x max fee per gas, from `relayer_pending_transactions`) reaches
+//!   RELAYER_HOURLY_SPEND_CAP_WEI, gas-sponsored endpoints (trade creation, settle,
+//!   resubmit) return 503 until it drops back under the cap, and automatic settlement
+//!   (after validate, batches) stops before submitting with failed:SPEND_CAP_REACHED,
+//!   keeping the proof - the self-submission calldata endpoints keep working
+//! - every RELAYER_BALANCE_CHECK_SECS the relayer balance is checked; below
+//!   RELAYER_LOW_BALANCE_WEI the admin is emailed (at most every 6 hours)
+//!
+//! Without a cap, spending is only monitored.
+
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::U256;
+
+use crate::api::AppState;
+use crate::db::models::DbRelayerPendingTx;
+
+/// Default low-balance alert threshold (0.01 ETH)
+pub const DEFAULT_LOW_BALANCE_WEI: u128 = 10_000_000_000_000_000;
+
+/// Default balance check interval (5 minutes)
+pub const DEFAULT_CHECK_INTERVAL_SECS: u64 = 300;
+
+/// Repeat low-balance / spend-cap alerts at most this often
+const ALERT_INTERVAL: Duration = Duration::from_secs(6 * 3600);
+
+/// Spend cap and low-balance threshold
+#[derive(Debug, Clone)]
+pub struct GasBudgetPolicy {
+    /// Max gas cost (wei) of the last hour before sponsoring stops (None = no cap)
+    pub hourly_spend_cap: Option<U256>,
+    pub low_balance: U256,
+    pub check_interval: Duration,
+}
+
+/// Last hour's spend against the cap
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpendStatus {
+    pub spent_last_hour: U256,
+    /// Most the transactions still pending can pay (gas limit x max fee per gas)
+    pub pending: U256,
+    pub hourly_spend_cap: Option<U256>,
+}
+
+impl Default for GasBudgetPolicy {
+    fn default() -> Self {
+        Self {
+            hourly_spend_cap: None,
+            low_balance: U256::from(DEFAULT_LOW_BALANCE_WEI),
+            check_interval: Duration::from_secs(DEFAULT_CHECK_INTERVAL_SECS),
+        }
+    }
+}
+
+impl GasBudgetPolicy {
+    /// Load from RELAYER_HOURLY_SPEND_CAP_WEI, RELAYER_LOW_BALANCE_WEI and RELAYER_BALANCE_CHECK_SECS
+    pub fn from_env() -> Result<Self, String> {
+        fn wei(name: &str) -> Result<Option<U256>, String> {
+            match std::env::var(name) {
+                Ok(value) => U256::from_dec_str(value.trim())
+                    .map(Some)
+                    .map_err(|_| format!("Invalid {}: {}", name, value)),
+                Err(_) => Ok(None),
+            }
+        }
+
+        let defaults = Self::default();
+        let check_interval = match std::env::var("RELAYER_BALANCE_CHECK_SECS") {
+            Ok(value) => value.trim().parse::<u64>()
+                .ok()
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs)
+                .ok_or_else(|| format!("Invalid RELAYER_BALANCE_CHECK_SECS: {}", value))?,
+            Err(_) => defaults.check_interval,
+        };
+
+        Ok(Self {
+            hourly_spend_cap: wei("RELAYER_HOURLY_SPEND_CAP_WEI")?,
+            low_balance: wei("RELAYER_LOW_BALANCE_WEI")?.unwrap_or(defaults.low_balance),
+            check_interval,
+        })
+    }
+}
+
+impl SpendStatus {
+    /// Mined spend plus what pending transactions may still pay
+    pub fn committed(&self) -> U256 {
+        self.spent_last_hour.saturating_add(self.pending)
+    }
+
+    /// True once the committed spend reached the cap
+    pub fn exceeded(&self) -> bool {
+        self.hourly_spend_cap.is_some_and(|cap| self.committed() >= cap)
+    }
+}
+
+/// Most a pending transaction can pay: its gas limit at the latest broadcast's max fee per gas
+pub fn pending_cost(tx: &DbRelayerPendingTx) -> Result<U256, String> {
+    let request: TypedTransaction = serde_json::from_str(&tx.tx_request)
+        .map_err(|e| format!("invalid txRequest: {}", e))?;
+    let gas = request.gas().ok_or("txRequest has no gas limit")?;
+    let max_fee = U256::from_dec_str(&tx.gas_price)
+        .map_err(|e| format!("invalid gas price {}: {}", tx.gas_price, e))?;
+    Ok(gas.saturating_mul(max_fee))
+}
+
+/// Gas cost of the last hour, plus pending transactions, against the cap
+pub async fn spend_status(state: &AppState) -> Result<SpendStatus, String> {
+    let since = Utc::now() - chrono::Duration::hours(1);
+    let spent = state.db.get_relayer_spend_since(since).await.map_err(|e| e.to_string())?;
+    let spent_last_hour = U256::from_dec_str(&spent).map_err(|e| format!("Invalid spend {}: {}", spent, e))?;
+
+    let mut pending = U256::zero();
+    for tx in state.db.get_pending_relayer_transactions().await.map_err(|e| e.to_string())? {
+        match pending_cost(&tx) {
+            Ok(cost) => pending = pending.saturating_add(cost),
+            Err(e) => tracing::warn!("⚠️ Cannot price pending relayer tx {}: {}", tx.tx_hash, e),
+        }
+    }
+
+    Ok(SpendStatus {
+        spent_last_hour,
+        pending,
+        hourly_spend_cap: state.gas_budget.hourly_spend_cap,
+    })
+}
+
+/// Refuse a gas-sponsored request once the hourly spend cap is reached
+///
+/// Fails open if the ledger can't be read (logged) - the cap guards against drain,
+/// not against database outages.
+pub async fn ensure_sponsorship_available(state: &AppState) -> Result<(), String> {
+    if state.gas_budget.hourly_spend_cap.is_none() {
+        return Ok(());
+    }
+
+    match spend_status(state).await {
+        Ok(status) if status.exceeded() => {
+            tracing::warn!(
+                "⛔ Relayer spend cap reached ({} wei in the last hour + {} wei pending / {} wei) - refusing sponsored request",
+                status.spent_last_hour,
+                status.pending,
+                status.hourly_spend_cap.unwrap_or_default()
+            );
+            Err("Gas sponsorship is paused (hourly relayer spend cap reached).".to_string())
+        }
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::error!("Failed to read relayer spend, allowing request: {}", e);
+            Ok(())
+        }
+    }
+}
+
+/// Background worker: check the relayer balance and spend, alerting the admin
+pub async fn run_balance_monitor(state: AppState) {
+    let Some(client) = state.blockchain_client.clone() else {
+        return;
+    };
+    let policy = state.gas_budget.clone();
+    tracing::info!(
+        "⛽ Relayer balance monitor started (low balance {} wei, hourly cap {}, every {}s)",
+        policy.low_balance,
+        policy.hourly_spend_cap.map(|cap| format!("{} wei", cap)).unwrap_or_else(|| "none".to_string()),
+        policy.check_interval.as_secs()
+    );
+
+    let mut last_balance_alert: Option<Instant> = None;
+    let mut last_cap_alert: Option<Instant> = None;
+    let mut cap_exceeded = false;
+
+    loop {
+        match client.get_relayer_balance().await {
+            Ok(balance) if balance < policy.low_balance => {
+                tracing::warn!(
+                    "⚠️ Relayer {:#x} balance low: {} wei (threshold {} wei)",
+                    client.relayer_address(), balance, policy.low_balance
+                );
+                if alert_due(last_balance_alert, Instant::now()) {
+                    last_balance_alert = Some(Instant::now());
+                    if let Some(email_service) = crate::email::EmailService::from_env() {
+                        let address = format!("{:#x}", client.relayer_address());
+                        if let Err(e) = email_service.send_low_balance_alert(&address, &balance.to_string(), &policy.low_balance.to_string()).await {
+                            tracing::error!("Failed to send low balance alert: {}", e);
+                        }
+                    }
+                }
+            }
+            Ok(_) => last_balance_alert = None,
+            Err(e) => tracing::error!("❌ Failed to check relayer balance: {}", e),
+        }
+
+        if policy.hourly_spend_cap.is_some() {
+            match spend_status(&state).await {
+                Ok(status) if status.exceeded() => {
+                    if !cap_exceeded {
+                        tracing::error!(
+                            "⛔ Relayer spend cap reached: {} wei in the last hour + {} wei pending - gas sponsorship paused",
+                            status.spent_last_hour, status.pending
+                        );
+                    }
+                    cap_exceeded = true;
+                    if alert_due(last_cap_alert, Instant::now()) {
+                        last_cap_alert = Some(Instant::now());
+                        if let Some(email_service) = crate::email::EmailService::from_env() {
+                            let spent = status.committed().to_string();
+                            let cap = status.hourly_spend_cap.unwrap_or_default().to_string();
+                            if let Err(e) = email_service.send_spend_cap_alert(&spent, &cap).await {
+                                tracing::error!("Failed to send spend cap alert: {}", e);
+                            }
+                        }
+                    }
+                }
+                Ok(_) => {
+                    if cap_exceeded {
+                        tracing::info!("✅ Relayer spend back under the hourly cap - gas sponsorship resumed");
+                    }
+                    cap_exceeded = false;
+                    last_cap_alert = None;
+                }
+                Err(e) => tracing::error!("❌ Failed to read relayer spend: {}", e),
+            }
+        }
+
+        tokio::time::sleep(policy.check_interval).await;
+    }
+}
+
+fn alert_due(last_alert: Option<Instant>, now: Instant) -> bool {
+    last_alert.is_none_or(|sent| now.duration_since(sent) >= ALERT_INTERVAL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spend_cap() {
+        let status = |spent: u64, pending: u64, cap: Option<u64>| SpendStatus {
+            spent_last_hour: U256::from(spent),
+            pending: U256::from(pending),
+            hourly_spend_cap: cap.map(U256::from),
+        };
+
+        assert!(!status(1_000, 0, None).exceeded());
+        assert!(!status(999, 0, Some(1_000)).exceeded());
+        assert!(status(1_000, 0, Some(1_000)).exceeded());
+        assert!(status(5_000, 0, Some(1_000)).exceeded());
+
+        // Transactions still in flight count against the cap
+        assert!(!status(400, 599, Some(1_000)).exceeded());
+        assert!(status(400, 600, Some(1_000)).exceeded());
+        assert!(status(0, 1_000, Some(1_000)).exceeded());
+    }
+
+    #[test]
+    fn test_pending_cost() {
+        use ethers::types::TransactionRequest;
+
+        let pending = |tx_request: String, gas_price: &str| DbRelayerPendingTx {
+            id: 1,
+            address: "0x00000000000000000000000000000000000000a1".to_string(),
+            nonce: 0,
+            operation: "submitProof".to_string(),
+            trade_id: None,
+            tx_hash: "0x01".to_string(),
+            tx_hashes: vec!["0x01".to_string()],
+            gas_price: gas_price.to_string(),
+            replacements: 0,
+            tx_request,
+            status: "pending".to_string(),
+            created_at: Utc::now(),
+        };
+        let request = |gas: Option<u64>| {
+            let mut tx: TypedTransaction = TransactionRequest::new().gas_price(100u64).into();
+            if let Some(gas) = gas {
+                tx.set_gas(gas);
+            }
+            serde_json::to_string(&tx).unwrap()
+        };
+
+        // Priced at the latest broadcast's max fee, not the request's original one
+        assert_eq!(pending_cost(&pending(request(Some(300_000)), "120")), Ok(U256::from(36_000_000u64)));
+        assert!(pending_cost(&pending(request(None), "120")).is_err());
+        assert!(pending_cost(&pending(request(Some(300_000)), "abc")).is_err());
+        assert!(pending_cost(&pending("{}".to_string(), "120")).is_err());
+    }
+
+    #[test]
+    fn test_alert_interval() {
+        let now = Instant::now();
+        assert!(alert_due(None, now));
+        assert!(!alert_due(Some(now), now + Duration::from_secs(60)));
+        assert!(alert_due(Some(now), now + ALERT_INTERVAL));
+    }
+}
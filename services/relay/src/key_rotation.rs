@@ -0,0 +1,386 @@
+//! Governed Alipay key rotation
+//!
+//! A receipt's signature only proves the PDF was signed by the key in its own
+//! certificate, so a single upload must never change the key the verifier trusts.
+//! A key the verifier doesn't trust is quarantined (`pending_key_rotations`) and
+//! receipts signed by it are refused until its rotation is submitted on-chain:
+//! - each trade with a valid receipt signed by it counts as one sighting, however
+//!   many receipts (transaction IDs, which come from the PDF) the trade uploads
+//! - with a pinned root certificate, a receipt only counts if the signing
+//!   certificate chains to that root (otherwise the receipt is refused)
+//! - once KEY_ROTATION_MIN_RECEIPTS trades, from as many distinct buyers and
+//!   orders, sighted it, the key becomes eligible
+//! - with a pinned root, an eligible key is submitted after KEY_ROTATION_DELAY_SECS;
+//!   without one, only an admin approval (`key-rotation approve`) submits it.
+//!   Approval skips the delay, never the threshold; a rejected key is never submitted
+//!
+//! Submitting updates the verifier's key hash (or the key-set root with the key
+//! added) and only then trusts the key locally. Every step is audited in
+//! `key_rotation_audit`.
+//!
+//! Configured by KEY_ROTATION_MIN_RECEIPTS (default 3), KEY_ROTATION_DELAY_SECS
+//! (default 86400) and ALIPAY_PINNED_ROOT_CERT (path to a DER root certificate).
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::api::AppState;
+use crate::db::models::{DbKeyRotation, DbTrade, KeyRotationStatus};
+use crate::key_set::parse_key_hash;
+
+/// Default distinct trades (and buyers and orders) needed before a key can be submitted
+pub const DEFAULT_MIN_RECEIPTS: u32 = 3;
+
+/// Default wait between eligibility and submission without an approval (24 hours)
+pub const DEFAULT_DELAY_SECS: u64 = 86_400;
+
+/// How often the worker promotes and submits rotations
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Actor recorded in the audit trail for the relay's own steps
+pub const RELAY_ACTOR: &str = "relay";
+
+/// Rotation thresholds (and the optional pinned root)
+#[derive(Debug, Clone)]
+pub struct KeyRotationPolicy {
+    pub min_receipts: u32,
+    pub delay: chrono::Duration,
+    /// DER root certificate signing certificates must chain to (None = not checked)
+    pub pinned_root: Option<Vec<u8>>,
+}
+
+/// What the worker should do with a rotation next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationStep {
+    Wait,
+    /// Sighting threshold reached - quarantined -> eligible
+    Promote,
+    /// Delay passed or approved - update the verifier
+    Submit,
+}
+
+/// Outcome of a receipt signed by an untrusted key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeySighting {
+    /// Counted (or already counted) towards the key's rotation
+    Pending { sightings: u32, required: u32 },
+    /// The key was rejected by an admin
+    Rejected,
+    /// The signing certificate doesn't chain to the pinned root
+    ChainInvalid(String),
+}
+
+impl Default for KeyRotationPolicy {
+    fn default() -> Self {
+        Self {
+            min_receipts: DEFAULT_MIN_RECEIPTS,
+            delay: chrono::Duration::seconds(DEFAULT_DELAY_SECS as i64),
+            pinned_root: None,
+        }
+    }
+}
+
+impl KeyRotationPolicy {
+    /// Load from KEY_ROTATION_MIN_RECEIPTS, KEY_ROTATION_DELAY_SECS and ALIPAY_PINNED_ROOT_CERT
+    pub fn from_env() -> Result<Self, String> {
+        let min_receipts = match std::env::var("KEY_ROTATION_MIN_RECEIPTS") {
+            Ok(value) => value.trim().parse::<u32>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| format!("Invalid KEY_ROTATION_MIN_RECEIPTS: {}", value))?,
+            Err(_) => DEFAULT_MIN_RECEIPTS,
+        };
+        let delay_secs = match std::env::var("KEY_ROTATION_DELAY_SECS") {
+            Ok(value) => value.trim().parse::<u32>()
+                .map_err(|_| format!("Invalid KEY_ROTATION_DELAY_SECS: {}", value))?
+                .into(),
+            Err(_) => DEFAULT_DELAY_SECS,
+        };
+        let pinned_root = match std::env::var("ALIPAY_PINNED_ROOT_CERT") {
+            Ok(path) => Some(
+                std::fs::read(&path)
+                    .map_err(|e| format!("Failed to read ALIPAY_PINNED_ROOT_CERT {}: {}", path, e))?,
+            ),
+            Err(_) => None,
+        };
+
+        Ok(Self {
+            min_receipts,
+            delay: chrono::Duration::seconds(delay_secs as i64),
+            pinned_root,
+        })
+    }
+
+    /// Check the receipt's certificate chain (None when no root is pinned)
+    pub fn check_chain(&self, pdf_bytes: &[u8], pk_hash: &[u8; 32]) -> Option<Result<(), String>> {
+        let root = self.pinned_root.as_ref()?;
+        Some(signature_validator::chain::validate_certificate_chain(pdf_bytes, pk_hash, root).map(|_| ()))
+    }
+
+    /// Enough distinct trades, buyers and orders (and a validated chain when a root is pinned)
+    pub fn meets_threshold(&self, rotation: &DbKeyRotation) -> bool {
+        let min = self.min_receipts as i32;
+        rotation.sightings >= min
+            && rotation.buyers >= min
+            && rotation.orders >= min
+            && (self.pinned_root.is_none() || rotation.chain_validated)
+    }
+
+    /// Next step for a rotation at `now`
+    pub fn next_step(&self, rotation: &DbKeyRotation, now: DateTime<Utc>) -> RotationStep {
+        match KeyRotationStatus::parse(&rotation.status) {
+            Some(KeyRotationStatus::Quarantined) if self.meets_threshold(rotation) => RotationStep::Promote,
+            // The delay alone only submits a key whose chain was checked against a pinned root
+            Some(KeyRotationStatus::Eligible) if self.pinned_root.is_some() && self.meets_threshold(rotation) => {
+                match rotation.eligible_at {
+                    Some(eligible_at) if eligible_at + self.delay <= now => RotationStep::Submit,
+                    _ => RotationStep::Wait,
+                }
+            }
+            Some(KeyRotationStatus::Approved) if self.meets_threshold(rotation) => RotationStep::Submit,
+            _ => RotationStep::Wait,
+        }
+    }
+}
+
+/// Count the trade's valid receipt signed by `pk_hash`, which the verifier doesn't trust yet
+pub async fn record_sighting(
+    state: &AppState,
+    pdf_bytes: &[u8],
+    pk_hash: [u8; 32],
+    transaction_id: &str,
+    trade: &DbTrade,
+) -> Result<KeySighting, String> {
+    let trade_id = trade.trade_id.as_str();
+    let pk_hash_hex = format!("0x{}", hex::encode(pk_hash));
+    let policy = &state.key_rotation;
+
+    let existing = state.db.get_key_rotation(&pk_hash_hex).await.map_err(|e| e.to_string())?;
+    if existing.as_ref().is_some_and(|rotation| rotation.status == KeyRotationStatus::Rejected.to_string()) {
+        return Ok(KeySighting::Rejected);
+    }
+
+    let chain_validated = match policy.check_chain(pdf_bytes, &pk_hash) {
+        Some(Err(e)) => {
+            tracing::warn!("🔑 Key {} does not chain to the pinned root: {}", pk_hash_hex, e);
+            let detail = format!("trade {}: {}", trade_id, e);
+            if let Err(e) = state.db.audit_key_rotation(&pk_hash_hex, "chain_failed", RELAY_ACTOR, Some(&detail)).await {
+                tracing::error!("Failed to audit key rotation {}: {}", pk_hash_hex, e);
+            }
+            return Ok(KeySighting::ChainInvalid(e));
+        }
+        Some(Ok(())) => true,
+        None => false,
+    };
+
+    let rotation = state.db.record_key_sighting(&pk_hash_hex, transaction_id, trade, chain_validated).await
+        .map_err(|e| e.to_string())?;
+    tracing::info!(
+        "🔑 Key {} sighted on trade {} ({}/{} trades, {} buyers, {} orders, status {})",
+        pk_hash_hex, trade_id, rotation.sightings, policy.min_receipts, rotation.buyers, rotation.orders, rotation.status
+    );
+
+    // First sighting: alert the admin so the key gets reviewed during the delay
+    if existing.is_none() {
+        if let Some(email_service) = crate::email::EmailService::from_env() {
+            let trade_id = trade_id.to_string();
+            tokio::spawn(async move {
+                if let Err(e) = email_service.send_key_quarantine_alert(&pk_hash_hex, &trade_id).await {
+                    tracing::error!("Failed to send key quarantine alert: {}", e);
+                }
+            });
+        }
+    }
+
+    Ok(KeySighting::Pending {
+        sightings: rotation.sightings.min(rotation.buyers).min(rotation.orders).max(0) as u32,
+        required: policy.min_receipts,
+    })
+}
+
+/// Background worker: promote rotations past the threshold, submit those approved
+/// or (with a pinned root) past the delay (retried every CHECK_INTERVAL until the transaction succeeds)
+pub async fn run_key_rotations(state: AppState) {
+    tracing::info!(
+        "🔑 Key rotation worker started ({} receipts, {}s delay, pinned root: {})",
+        state.key_rotation.min_receipts,
+        state.key_rotation.delay.num_seconds(),
+        if state.key_rotation.pinned_root.is_some() { "yes" } else { "no" }
+    );
+
+    loop {
+        if let Err(e) = process_rotations(&state).await {
+            tracing::error!("❌ Key rotation check failed: {}", e);
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+async fn process_rotations(state: &AppState) -> Result<(), String> {
+    let open = [KeyRotationStatus::Quarantined, KeyRotationStatus::Eligible, KeyRotationStatus::Approved];
+    let rotations = state.db.get_key_rotations(&open).await.map_err(|e| e.to_string())?;
+
+    for rotation in rotations {
+        match state.key_rotation.next_step(&rotation, Utc::now()) {
+            RotationStep::Wait => {}
+            RotationStep::Promote => {
+                let detail = format!("{} sightings", rotation.sightings);
+                let promoted = state.db.transition_key_rotation(
+                    &rotation.pk_hash, &[KeyRotationStatus::Quarantined], KeyRotationStatus::Eligible,
+                    RELAY_ACTOR, Some(&detail), None,
+                ).await.map_err(|e| e.to_string())?;
+                if promoted {
+                    tracing::info!("🔑 Key {} eligible for rotation ({})", rotation.pk_hash, detail);
+                }
+            }
+            RotationStep::Submit => {
+                if let Err(e) = submit_rotation(state, &rotation).await {
+                    tracing::error!("❌ Key rotation {} failed: {}", rotation.pk_hash, e);
+                    if let Err(e) = state.db.audit_key_rotation(&rotation.pk_hash, "submit_failed", RELAY_ACTOR, Some(&e)).await {
+                        tracing::error!("Failed to audit key rotation {}: {}", rotation.pk_hash, e);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Update the verifier to trust the key, then trust it locally
+async fn submit_rotation(state: &AppState, rotation: &DbKeyRotation) -> Result<(), String> {
+    let key = parse_key_hash(&rotation.pk_hash)
+        .ok_or_else(|| format!("Invalid key hash {}", rotation.pk_hash))?;
+    let blockchain_client = state.blockchain_client.as_ref()
+        .ok_or_else(|| "Blockchain not enabled".to_string())?;
+
+    // Single key: the key's hash. Key set: the root with the key added
+    let commitment = match &state.key_set {
+        Some(trusted) => trusted.read().await.with_key(key).root()
+            .ok_or_else(|| "Empty key set".to_string())?,
+        None => key,
+    };
+
+    let contract_pk_hash = blockchain_client.get_alipay_public_key_hash().await
+        .map_err(|e| format!("Failed to get contract pk hash: {}", e))?;
+    let tx_hash = if contract_pk_hash == commitment {
+        None
+    } else {
+        tracing::info!("🔄 Submitting key rotation {} (commitment 0x{})", rotation.pk_hash, hex::encode(commitment));
+        let tx_hash = blockchain_client.update_public_key_hash(commitment).await
+            .map_err(|e| format!("updatePublicKeyHash failed: {}", e))?;
+        Some(format!("{:#x}", tx_hash))
+    };
+
+    let detail = format!(
+        "commitment 0x{} (was 0x{})",
+        hex::encode(commitment), hex::encode(contract_pk_hash)
+    );
+    state.db.transition_key_rotation(
+        &rotation.pk_hash,
+        &[KeyRotationStatus::Eligible, KeyRotationStatus::Approved],
+        KeyRotationStatus::Submitted,
+        RELAY_ACTOR,
+        Some(&detail),
+        tx_hash.as_deref(),
+    ).await.map_err(|e| e.to_string())?;
+    tracing::info!("✅ Key rotation {} submitted ({})", rotation.pk_hash, tx_hash.as_deref().unwrap_or("already on-chain"));
+
+    // Only now does the key join the trusted set
+    if let Some(trusted) = &state.key_set {
+        trusted.write().await.insert(key);
+    }
+    let first_trade_id = rotation.first_trade_id.clone().unwrap_or_default();
+    if let Err(e) = state.db.add_alipay_key(&rotation.pk_hash, &first_trade_id).await {
+        tracing::error!("Failed to persist trusted key {}: {}", rotation.pk_hash, e);
+    }
+
+    if let Some(email_service) = crate::email::EmailService::from_env() {
+        let old_hash = format!("0x{}", hex::encode(contract_pk_hash));
+        let new_hash = format!("0x{}", hex::encode(commitment));
+        tokio::spawn(async move {
+            if let Err(e) = email_service.send_key_rotation_alert(&old_hash, &new_hash, &first_trade_id).await {
+                tracing::error!("Failed to send key rotation alert: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rotation(status: KeyRotationStatus, sightings: i32, chain_validated: bool) -> DbKeyRotation {
+        let now = Utc::now();
+        DbKeyRotation {
+            pk_hash: format!("0x{}", "ab".repeat(32)),
+            status: status.to_string(),
+            sightings,
+            buyers: sightings,
+            orders: sightings,
+            chain_validated,
+            first_trade_id: None,
+            eligible_at: None,
+            approved_by: None,
+            approved_at: None,
+            tx_hash: None,
+            submitted_at: None,
+            first_seen_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_promotion_needs_sightings_and_chain() {
+        let now = Utc::now();
+        let policy = KeyRotationPolicy::default();
+
+        assert_eq!(policy.next_step(&rotation(KeyRotationStatus::Quarantined, 2, false), now), RotationStep::Wait);
+        assert_eq!(policy.next_step(&rotation(KeyRotationStatus::Quarantined, 3, false), now), RotationStep::Promote);
+
+        // With a pinned root, at least one sighting must have a validated chain
+        let pinned = KeyRotationPolicy { pinned_root: Some(vec![0x30]), ..KeyRotationPolicy::default() };
+        assert_eq!(pinned.next_step(&rotation(KeyRotationStatus::Quarantined, 5, false), now), RotationStep::Wait);
+        assert_eq!(pinned.next_step(&rotation(KeyRotationStatus::Quarantined, 3, true), now), RotationStep::Promote);
+    }
+
+    #[test]
+    fn test_promotion_needs_distinct_buyers_and_orders() {
+        let now = Utc::now();
+        let policy = KeyRotationPolicy::default();
+
+        // Three trades by one buyer, or on one order, are not independent sightings
+        let mut one_buyer = rotation(KeyRotationStatus::Quarantined, 3, false);
+        one_buyer.buyers = 1;
+        assert_eq!(policy.next_step(&one_buyer, now), RotationStep::Wait);
+        let mut one_order = rotation(KeyRotationStatus::Quarantined, 3, false);
+        one_order.orders = 1;
+        assert_eq!(policy.next_step(&one_order, now), RotationStep::Wait);
+    }
+
+    #[test]
+    fn test_submission_after_delay_or_approval() {
+        let now = Utc::now();
+        let policy = KeyRotationPolicy::default();
+        let pinned = KeyRotationPolicy { pinned_root: Some(vec![0x30]), ..KeyRotationPolicy::default() };
+
+        let mut eligible = rotation(KeyRotationStatus::Eligible, 3, true);
+        eligible.eligible_at = Some(now - chrono::Duration::hours(23));
+        assert_eq!(pinned.next_step(&eligible, now), RotationStep::Wait);
+        eligible.eligible_at = Some(now - chrono::Duration::hours(24));
+        assert_eq!(pinned.next_step(&eligible, now), RotationStep::Submit);
+
+        // Without a pinned root the delay never submits - only an approval does
+        eligible.eligible_at = Some(now - chrono::Duration::days(30));
+        assert_eq!(policy.next_step(&eligible, now), RotationStep::Wait);
+
+        // Approval skips the delay, never the threshold
+        assert_eq!(policy.next_step(&rotation(KeyRotationStatus::Approved, 3, false), now), RotationStep::Submit);
+        assert_eq!(policy.next_step(&rotation(KeyRotationStatus::Approved, 1, false), now), RotationStep::Wait);
+
+        for status in [KeyRotationStatus::Rejected, KeyRotationStatus::Submitted] {
+            assert_eq!(policy.next_step(&rotation(status, 10, true), now), RotationStep::Wait);
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Trusted Alipay key set - Merkle root over signing key hashes
+//!
+//! Alipay rotates its PDF signing certificate, and every rotation used to need an
+//! on-chain `updatePublicKeyHash` to the new key's hash. With a key set, the contract
+//! stores the root over all trusted key hashes instead, and the guest proves the
+//! receipt's key is a member (see guest main.rs, key mode 1):
+//! - leaves: distinct key hashes, sorted
+//! - parent = SHA256(min(a, b) || max(a, b)), an odd last node is carried up unchanged
+//! - witness: the siblings on the leaf's path (sorted pairs, so no leaf index)
+//!
+//! A rotation back to a key already in the set needs no on-chain update. A new key
+//! joins the set (persisted in `alipay_keys`) once its governed rotation is
+//! submitted, which updates the root on-chain (see key_rotation).
+//!
+//! Enabled by ALIPAY_TRUSTED_KEYS (comma-separated key hashes seeding the set, may be empty).
+
+use sha2::{Digest, Sha256};
+
+/// Set of trusted key hashes (sorted, distinct)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeySet {
+    leaves: Vec<[u8; 32]>,
+}
+
+/// Membership proof for one key hash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyWitness {
+    /// Sibling hashes from the leaf up (levels where the node was carried up are skipped)
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl KeySet {
+    pub fn new(keys: impl IntoIterator<Item = [u8; 32]>) -> Self {
+        let mut leaves: Vec<[u8; 32]> = keys.into_iter().collect();
+        leaves.sort_unstable();
+        leaves.dedup();
+        Self { leaves }
+    }
+
+    /// Seed keys from ALIPAY_TRUSTED_KEYS (None = key set disabled, single-key mode)
+    pub fn from_env() -> Option<Self> {
+        let value = std::env::var("ALIPAY_TRUSTED_KEYS").ok()?;
+
+        let keys = value
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| match parse_key_hash(s) {
+                Some(key) => Some(key),
+                None => {
+                    tracing::warn!("⚠️ Ignoring invalid ALIPAY_TRUSTED_KEYS entry: {}", s);
+                    None
+                }
+            });
+        Some(Self::new(keys))
+    }
+
+    pub fn keys(&self) -> &[[u8; 32]] {
+        &self.leaves
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    pub fn contains(&self, key: &[u8; 32]) -> bool {
+        self.leaves.binary_search(key).is_ok()
+    }
+
+    /// Add a key - returns false if it was already trusted (root unchanged)
+    pub fn insert(&mut self, key: [u8; 32]) -> bool {
+        match self.leaves.binary_search(&key) {
+            Ok(_) => false,
+            Err(position) => {
+                self.leaves.insert(position, key);
+                true
+            }
+        }
+    }
+
+    /// This set plus one key (the candidate set while a new key's receipt validates)
+    pub fn with_key(&self, key: [u8; 32]) -> Self {
+        let mut set = self.clone();
+        set.insert(key);
+        set
+    }
+
+    /// Root committed by the guest and stored on-chain (None for an empty set)
+    pub fn root(&self) -> Option<[u8; 32]> {
+        self.levels().last().and_then(|level| level.first().copied())
+    }
+
+    /// Membership proof for a key (None if the key is not in the set)
+    pub fn witness(&self, key: &[u8; 32]) -> Option<KeyWitness> {
+        let mut index = self.leaves.binary_search(key).ok()?;
+
+        let mut siblings = Vec::new();
+        for level in self.levels() {
+            if let Some(sibling) = level.get(index ^ 1) {
+                siblings.push(*sibling);
+            }
+            index /= 2;
+        }
+        Some(KeyWitness { siblings })
+    }
+
+    /// Tree levels from the leaves up to the root
+    fn levels(&self) -> Vec<Vec<[u8; 32]>> {
+        if self.leaves.is_empty() {
+            return Vec::new();
+        }
+
+        let mut levels = vec![self.leaves.clone()];
+        while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+            let parent = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_sorted_pair(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(parent);
+        }
+        levels
+    }
+}
+
+impl KeyWitness {
+    /// Root reached by folding a leaf up this proof (what the guest commits)
+    pub fn root(&self, leaf: &[u8; 32]) -> [u8; 32] {
+        self.siblings
+            .iter()
+            .fold(*leaf, |node, sibling| hash_sorted_pair(&node, sibling))
+    }
+}
+
+/// SHA256(min(a, b) || max(a, b)) - mirrors the guest's `key_set_root`
+fn hash_sorted_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (low, high) = if a <= b { (a, b) } else { (b, a) };
+    let mut hasher = Sha256::new();
+    hasher.update(low);
+    hasher.update(high);
+    hasher.finalize().into()
+}
+
+/// Parse a key hash (hex, optional 0x prefix)
+pub fn parse_key_hash(value: &str) -> Option<[u8; 32]> {
+    hex::decode(value.trim_start_matches("0x")).ok()?.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> [u8; 32] {
+        [byte; 32]
+    }
+
+    #[test]
+    fn test_witness_folds_to_root() {
+        for size in 1..=9u8 {
+            let set = KeySet::new((1..=size).rev().map(key));
+            let root = set.root().unwrap();
+
+            for k in set.keys() {
+                let witness = set.witness(k).unwrap();
+                assert_eq!(witness.root(k), root, "set of {} keys", size);
+            }
+            assert_eq!(set.witness(&key(0xff)), None);
+        }
+
+        // One key: the root is the key hash itself, with an empty witness
+        let single = KeySet::new([key(7)]);
+        assert_eq!(single.root(), Some(key(7)));
+        assert!(single.witness(&key(7)).unwrap().siblings.is_empty());
+        assert_eq!(KeySet::default().root(), None);
+    }
+
+    #[test]
+    fn test_insert_changes_root_only_for_new_keys() {
+        let mut set = KeySet::new([key(3), key(1), key(3)]);
+        assert_eq!(set.len(), 2);
+        let root = set.root();
+
+        assert!(!set.insert(key(1)));
+        assert_eq!(set.root(), root);
+
+        let candidate = set.with_key(key(2));
+        assert_eq!(set.len(), 2);
+        assert!(set.insert(key(2)));
+        assert_eq!(set, candidate);
+        assert_ne!(set.root(), root);
+        assert_eq!(set.keys(), &[key(1), key(2), key(3)]);
+    }
+
+    #[test]
+    fn test_parse_key_hash() {
+        let hex_key = format!("0x{}", "ab".repeat(32));
+        assert_eq!(parse_key_hash(&hex_key), Some([0xab; 32]));
+        assert_eq!(parse_key_hash("abcd"), None);
+        assert_eq!(parse_key_hash("zz"), None);
+    }
+}
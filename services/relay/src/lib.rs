@@ -1,11 +1,15 @@
 //! LyncZ Relay Service
 //!
 //! Backend service for LyncZ P2P fiat-crypto escrow:
-//! - Event listener syncs blockchain → database
+//! - Event listener syncs blockchain → database (one transaction per block range, failed logs retried, reorgs rolled back and replayed)
+//! - lyncz-reindex rebuilds the database mirror from chain history (resumable, dry-run diff)
 //! - Read-only APIs for orders and trades
-//! - PDF upload and Axiom ZK proof generation
-//! - Relayer submits proofs to blockchain
+//! - PDF upload and Axiom ZK proof generation (optionally against a trusted Alipay key set)
+//! - Governed Alipay key rotation (quarantine, sightings, delay or admin approval)
+//! - Relayer submits proofs to blockchain (optionally batched, one proof for several trades)
+//! - Relayer gas ledger, low-balance alerts and an hourly spend cap on sponsored transactions
 //! - Email notifications to accounts (wallet addresses)
+//! - Live trade updates over SSE / WebSocket
 
 pub mod config;
 pub mod crypto;
@@ -14,9 +18,18 @@ pub mod api;
 pub mod blockchain;
 pub mod axiom_prover;
 pub mod email;
+pub mod gas_budget;
+pub mod key_rotation;
+pub mod key_set;
+pub mod payment_time;
+pub mod reindex;
+pub mod settlement_batch;
+pub mod trade_events;
 
 pub use config::Config;
 pub use db::{Database, DbError, DbResult};
 pub use api::{AppState, create_router};
 pub use email::{EmailService, EmailEvent, EmailInfo};
+pub use payment_time::{PaymentTime, PaymentTimeError};
+pub use trade_events::{TradeEvent, TradeEventBus};
 // Build trigger: Sun Dec 28 13:40:12 PST 2025
@@ -0,0 +1,231 @@
+//! Alipay receipt payment times (PDF line 27)
+//!
+//! Alipay prints payment times in China Standard Time (Asia/Shanghai, UTC+8, no DST
+//! since 1991) and, depending on the receipt type, in a few different layouts.
+//! The escrow contract only accepts "YYYY-MM-DD HH:MM:SS" (19 bytes), see
+//! `LyncZEscrow._parsePaymentTime` / `InvalidPaymentTimeFormat`, and the verifier
+//! hashes the same string as line 27 - so a receipt can only settle on-chain when
+//! its raw line is already in the contract format.
+
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone, Utc};
+use thiserror::Error;
+
+/// Asia/Shanghai UTC offset in seconds (fixed - China has not observed DST since 1991)
+pub const ASIA_SHANGHAI_OFFSET_SECS: i32 = 8 * 3600;
+
+/// Exact layout required by the contract's `InvalidPaymentTimeFormat` check
+pub const CONTRACT_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Length of a contract-formatted payment time in bytes
+pub const CONTRACT_FORMAT_LEN: usize = 19;
+
+/// Tolerated clock skew between Alipay and the relay when rejecting future payment times
+pub const MAX_CLOCK_SKEW_SECS: i64 = 5 * 60;
+
+/// Layouts seen across Alipay receipt types (after whitespace normalization)
+const ALIPAY_FORMATS: &[&str] = &[
+    CONTRACT_FORMAT,            // 2025-12-27 08:36:12 (transfer receipts)
+    "%Y/%m/%d %H:%M:%S",        // 2025/12/27 08:36:12
+    "%Y.%m.%d %H:%M:%S",        // 2025.12.27 08:36:12
+    "%Y年%m月%d日 %H:%M:%S",     // 2025年12月27日 08:36:12
+    "%Y年%m月%d日%H:%M:%S",      // 2025年12月27日08:36:12
+    "%Y-%m-%d %H:%M",           // 2025-12-27 08:36 (no seconds)
+    "%Y/%m/%d %H:%M",           // 2025/12/27 08:36
+    "%Y年%m月%d日 %H:%M",        // 2025年12月27日 08:36
+];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PaymentTimeError {
+    #[error("Unrecognized payment time format: '{0}'")]
+    InvalidFormat(String),
+
+    #[error("Payment time '{0}' is not in the contract format YYYY-MM-DD HH:MM:SS")]
+    NotContractFormat(String),
+
+    #[error("Payment time {payment} is before trade creation {created_at}")]
+    BeforeTradeCreated { payment: i64, created_at: i64 },
+
+    #[error("Payment time {payment} is after trade expiry {expires_at}")]
+    AfterExpiry { payment: i64, expires_at: i64 },
+
+    #[error("Payment time {payment} is in the future (now {now})")]
+    InFuture { payment: i64, now: i64 },
+}
+
+impl PaymentTimeError {
+    /// Stable validation code for the frontend (see ValidateResponse)
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidFormat(_) | Self::NotContractFormat(_) => "INVALID_PAYMENT_TIME_FORMAT",
+            Self::BeforeTradeCreated { .. } => "PAYMENT_TOO_OLD",
+            Self::AfterExpiry { .. } => "PAYMENT_AFTER_EXPIRY",
+            Self::InFuture { .. } => "PAYMENT_TIME_IN_FUTURE",
+        }
+    }
+}
+
+/// Asia/Shanghai timezone
+pub fn asia_shanghai() -> FixedOffset {
+    FixedOffset::east_opt(ASIA_SHANGHAI_OFFSET_SECS).expect("UTC+8 is a valid offset")
+}
+
+/// A receipt payment time, parsed in Asia/Shanghai
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentTime {
+    raw: String,
+    local: DateTime<FixedOffset>,
+}
+
+impl PaymentTime {
+    /// Parse a receipt payment time in any known Alipay layout
+    pub fn parse(raw: &str) -> Result<Self, PaymentTimeError> {
+        let normalized = normalize(raw);
+
+        let naive = ALIPAY_FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(&normalized, format).ok())
+            .ok_or_else(|| PaymentTimeError::InvalidFormat(raw.to_string()))?;
+
+        // Contract timestamps are unsigned - reject anything before the epoch
+        let local = asia_shanghai()
+            .from_local_datetime(&naive)
+            .single()
+            .filter(|dt| dt.timestamp() >= 0)
+            .ok_or_else(|| PaymentTimeError::InvalidFormat(raw.to_string()))?;
+
+        Ok(Self { raw: raw.to_string(), local })
+    }
+
+    /// The string exactly as printed on the receipt (what the guest program hashes)
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// Unix timestamp (UTC seconds), matching `LyncZEscrow._parsePaymentTime`
+    pub fn timestamp(&self) -> i64 {
+        self.local.timestamp()
+    }
+
+    /// Payment time in UTC
+    pub fn utc(&self) -> DateTime<Utc> {
+        self.local.with_timezone(&Utc)
+    }
+
+    /// "YYYY-MM-DD HH:MM:SS" in Asia/Shanghai - the string the contract accepts
+    pub fn contract_format(&self) -> String {
+        self.local.format(CONTRACT_FORMAT).to_string()
+    }
+
+    /// Whether the raw receipt line can be submitted on-chain as-is
+    pub fn is_contract_format(&self) -> bool {
+        self.raw.len() == CONTRACT_FORMAT_LEN && self.raw == self.contract_format()
+    }
+
+    /// Require the raw line to already be in the contract format
+    ///
+    /// The verifier hashes paymentTime as receipt line 27, so it cannot be reformatted
+    /// before submission - other layouts can be parsed but never settle on-chain.
+    pub fn require_contract_format(&self) -> Result<&str, PaymentTimeError> {
+        if self.is_contract_format() {
+            Ok(&self.raw)
+        } else {
+            Err(PaymentTimeError::NotContractFormat(self.raw.clone()))
+        }
+    }
+
+    /// Check the payment falls inside the trade window and isn't in the future
+    pub fn check_window(&self, created_at: i64, expires_at: i64, now: i64) -> Result<(), PaymentTimeError> {
+        let payment = self.timestamp();
+
+        if payment > now + MAX_CLOCK_SKEW_SECS {
+            return Err(PaymentTimeError::InFuture { payment, now });
+        }
+        if payment < created_at {
+            return Err(PaymentTimeError::BeforeTradeCreated { payment, created_at });
+        }
+        if payment > expires_at {
+            return Err(PaymentTimeError::AfterExpiry { payment, expires_at });
+        }
+
+        Ok(())
+    }
+}
+
+/// Collapse whitespace (incl. full-width spaces) and map full-width digits/punctuation to ASCII
+fn normalize(raw: &str) -> String {
+    let mapped: String = raw
+        .chars()
+        .map(|c| match c {
+            '０'..='９' => char::from_u32(c as u32 - '０' as u32 + '0' as u32).unwrap_or(c),
+            '：' => ':',
+            '－' => '-',
+            '／' => '/',
+            '\u{3000}' => ' ',
+            _ => c,
+        })
+        .collect();
+
+    mapped.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2025-12-27 08:36:12 in Asia/Shanghai = 2025-12-27 00:36:12 UTC
+    const TS: i64 = 1_766_795_772;
+
+    #[test]
+    fn test_contract_format_roundtrip() {
+        let time = PaymentTime::parse("2025-12-27 08:36:12").unwrap();
+        assert_eq!(time.timestamp(), TS);
+        assert_eq!(time.contract_format(), "2025-12-27 08:36:12");
+        assert_eq!(time.contract_format().len(), CONTRACT_FORMAT_LEN);
+        assert!(time.is_contract_format());
+        assert_eq!(time.require_contract_format().unwrap(), "2025-12-27 08:36:12");
+    }
+
+    #[test]
+    fn test_other_alipay_formats() {
+        for raw in [
+            "2025/12/27 08:36:12",
+            "2025.12.27 08:36:12",
+            "2025年12月27日 08:36:12",
+            "2025年12月27日08:36:12",
+            "２０２５-１２-２７ ０８：３６：１２",
+            "  2025-12-27   08:36:12 ",
+        ] {
+            let time = PaymentTime::parse(raw).unwrap();
+            assert_eq!(time.timestamp(), TS, "{}", raw);
+            assert_eq!(time.contract_format(), "2025-12-27 08:36:12");
+            assert!(!time.is_contract_format(), "{}", raw);
+            assert_eq!(time.require_contract_format().unwrap_err().code(), "INVALID_PAYMENT_TIME_FORMAT");
+        }
+
+        let no_seconds = PaymentTime::parse("2025-12-27 08:36").unwrap();
+        assert_eq!(no_seconds.timestamp(), TS - 12);
+    }
+
+    #[test]
+    fn test_invalid_formats() {
+        for raw in ["", "yesterday", "2025-13-01 00:00:00", "2025-12-27", "1970-01-01 07:59:59"] {
+            assert!(matches!(PaymentTime::parse(raw), Err(PaymentTimeError::InvalidFormat(_))), "{}", raw);
+        }
+    }
+
+    #[test]
+    fn test_check_window() {
+        let time = PaymentTime::parse("2025-12-27 08:36:12").unwrap();
+        let now = TS + 60;
+
+        assert!(time.check_window(TS - 100, TS + 100, now).is_ok());
+        assert!(time.check_window(TS, TS, now).is_ok());
+        assert_eq!(time.check_window(TS + 1, TS + 100, now).unwrap_err().code(), "PAYMENT_TOO_OLD");
+        assert_eq!(time.check_window(TS - 100, TS - 1, now).unwrap_err().code(), "PAYMENT_AFTER_EXPIRY");
+        assert_eq!(
+            time.check_window(TS - 100, TS + 10_000, TS - MAX_CLOCK_SKEW_SECS - 1).unwrap_err().code(),
+            "PAYMENT_TIME_IN_FUTURE"
+        );
+        assert!(time.check_window(TS - 100, TS + 10_000, TS - MAX_CLOCK_SKEW_SECS).is_ok());
+    }
+}
@@ -0,0 +1,167 @@
+//! Chain history replay (lyncz-reindex)
+//!
+//! Rebuilds the database mirror by replaying the escrow's logs over a block range
+//! through the event listener's handlers (`EventListener::replay_range`):
+//! - `apply`: chunks of blocks, one transaction each, without notifications. Logs the
+//!   database already applied (processed_logs) are skipped, so replays are idempotent
+//! - `baseline`: only records logs as applied - for databases synced before log
+//!   dedupe, whose history would otherwise be applied twice
+//! - `dry-run`: chunks of blocks in a single transaction that is rolled back, reporting
+//!   how every order and trade the range touches would change. Later chunks build on
+//!   the earlier chunks' replay; each entity is compared with the database before the run.
+//!   Already applied logs are skipped as in `apply` (handlers apply deltas, so they
+//!   can't be re-run over existing rows): a dry run previews unapplied logs only and
+//!   doesn't detect rows that drifted from applied history
+//!
+//! Apply and baseline runs are recorded in reindex_runs and resume where they stopped.
+
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+/// How a replay treats the database
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayMode {
+    Apply,
+    Baseline,
+    DryRun,
+}
+
+impl ReplayMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReplayMode::Apply => "apply",
+            ReplayMode::Baseline => "baseline",
+            ReplayMode::DryRun => "dry-run",
+        }
+    }
+
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "apply" => Some(ReplayMode::Apply),
+            "baseline" => Some(ReplayMode::Baseline),
+            "dry-run" => Some(ReplayMode::DryRun),
+            _ => None,
+        }
+    }
+}
+
+/// Log counts of a replayed range
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    pub logs: u64,
+    /// Applied (baseline: recorded as applied)
+    pub applied: u64,
+    /// Already applied
+    pub skipped: u64,
+    /// Handler failed - dead-lettered for the event listener to retry
+    pub failed: u64,
+}
+
+impl ReplayReport {
+    pub fn add(&mut self, other: &ReplayReport) {
+        self.logs += other.logs;
+        self.applied += other.applied;
+        self.skipped += other.skipped;
+        self.failed += other.failed;
+    }
+}
+
+/// Chain-mirrored fields of an order or trade: kind and JSON (`EventBatch::snapshot`)
+pub type EntitySnapshot = (&'static str, String);
+
+/// How a dry run would change an order or trade
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntityChange {
+    Created { kind: &'static str, id: String },
+    Changed { kind: &'static str, id: String, fields: Vec<FieldChange> },
+}
+
+/// One changed field (values as JSON)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+impl EntityChange {
+    /// Compare an entity before and after the replay (None if unchanged or untouched)
+    pub fn between(id: &str, before: Option<&EntitySnapshot>, after: Option<&EntitySnapshot>) -> Option<Self> {
+        let (kind, after) = after?;
+        let Some((_, before)) = before else {
+            return Some(EntityChange::Created { kind, id: id.to_string() });
+        };
+
+        let before = fields(before);
+        let after = fields(after);
+        let mut changed: Vec<FieldChange> = after.iter()
+            .filter(|(field, value)| before.get(*field) != Some(*value))
+            .map(|(field, value)| FieldChange {
+                field: field.clone(),
+                before: before.get(field).unwrap_or(&Value::Null).to_string(),
+                after: value.to_string(),
+            })
+            .collect();
+        changed.sort_by(|a, b| a.field.cmp(&b.field));
+
+        (!changed.is_empty()).then(|| EntityChange::Changed { kind, id: id.to_string(), fields: changed })
+    }
+}
+
+impl fmt::Display for EntityChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntityChange::Created { kind, id } => write!(f, "+ {} {} (missing, created)", kind, id),
+            EntityChange::Changed { kind, id, fields } => {
+                write!(f, "~ {} {}:", kind, id)?;
+                for (i, change) in fields.iter().enumerate() {
+                    let separator = if i == 0 { " " } else { ", " };
+                    write!(f, "{}{} {} -> {}", separator, change.field, change.before, change.after)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn fields(snapshot: &str) -> Map<String, Value> {
+    serde_json::from_str(snapshot).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_round_trip() {
+        for mode in [ReplayMode::Apply, ReplayMode::Baseline, ReplayMode::DryRun] {
+            assert_eq!(ReplayMode::parse(mode.as_str()), Some(mode));
+        }
+        assert_eq!(ReplayMode::parse("rebuild"), None);
+    }
+
+    #[test]
+    fn test_entity_change() {
+        let before = ("order", r#"{"remainingAmount": "100", "rail": 0, "withdrawals": 0}"#.to_string());
+        let after = ("order", r#"{"remainingAmount": "80", "rail": 0, "withdrawals": 1}"#.to_string());
+
+        let change = EntityChange::between("0xab", Some(&before), Some(&after)).unwrap();
+        assert_eq!(change.to_string(), r#"~ order 0xab: remainingAmount "100" -> "80", withdrawals 0 -> 1"#);
+
+        assert_eq!(EntityChange::between("0xab", Some(&after), Some(&after)), None);
+        assert_eq!(EntityChange::between("0xab", None, None), None);
+        assert_eq!(
+            EntityChange::between("0xcd", None, Some(&("trade", "{}".to_string()))),
+            Some(EntityChange::Created { kind: "trade", id: "0xcd".to_string() })
+        );
+    }
+
+    #[test]
+    fn test_report_totals() {
+        let mut total = ReplayReport::default();
+        total.add(&ReplayReport { logs: 3, applied: 2, skipped: 1, failed: 0 });
+        total.add(&ReplayReport { logs: 2, applied: 1, skipped: 0, failed: 1 });
+        assert_eq!(total, ReplayReport { logs: 5, applied: 3, skipped: 1, failed: 1 });
+    }
+}
@@ -0,0 +1,222 @@
+//! Settlement batching - one EVM proof for several validated trades
+//!
+//! Each trade costs one Axiom EVM proof and one submitProof transaction, so fixed
+//! cost dominates small trades. With batching enabled, validated trades are queued
+//! for a short window and proved together:
+//! - guest input: `InputStreams::assemble` over each trade's cached receipt streams
+//! - proof output: batch root over the trades' expected hashes (`crypto::compute_batch_root`)
+//! - settlement: one `submitBatchProof` call, leaves in the same order as the trades
+//!
+//! Configured by SETTLEMENT_BATCH_WINDOW_SECS (0 / unset = disabled) and
+//! SETTLEMENT_BATCH_MAX_SIZE (default 8, capped at the contract's MAX_BATCH_SIZE).
+
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Default number of trades per batch proof
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 8;
+
+/// `LyncZEscrow.MAX_BATCH_SIZE` - larger batches revert with BatchTooLarge
+pub const CONTRACT_MAX_BATCH_SIZE: usize = 16;
+
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    /// How long the first queued trade waits for others to join its batch
+    pub window: Duration,
+    /// Batch is proved as soon as it reaches this many trades
+    pub max_size: usize,
+}
+
+impl BatchConfig {
+    /// Read batching config from the environment (None = batching disabled)
+    pub fn from_env() -> Option<Self> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Same as `from_env`, reading each variable through `lookup`
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let window_secs: u64 = lookup("SETTLEMENT_BATCH_WINDOW_SECS")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        if window_secs == 0 {
+            return None;
+        }
+
+        let max_size = lookup("SETTLEMENT_BATCH_MAX_SIZE")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_BATCH_SIZE)
+            .clamp(1, CONTRACT_MAX_BATCH_SIZE);
+
+        Some(Self {
+            window: Duration::from_secs(window_secs),
+            max_size,
+        })
+    }
+}
+
+/// A validated trade waiting to be proved
+#[derive(Debug, Clone)]
+pub struct BatchItem {
+    pub trade_id: String,
+    pub transaction_id: String,
+    pub payment_time: String,
+}
+
+/// Queue side of the batching window (held in AppState)
+#[derive(Clone)]
+pub struct SettlementBatcher {
+    sender: mpsc::UnboundedSender<BatchItem>,
+}
+
+impl SettlementBatcher {
+    /// Create a batcher and the receiver that groups queued trades into batches
+    pub fn new(config: BatchConfig) -> (Self, BatchReceiver) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { sender }, BatchReceiver { receiver, config })
+    }
+
+    /// Queue a validated trade - returns it back if the batch worker has stopped
+    pub fn enqueue(&self, item: BatchItem) -> Result<(), BatchItem> {
+        self.sender.send(item).map_err(|e| e.0)
+    }
+}
+
+/// Worker side of the batching window
+pub struct BatchReceiver {
+    receiver: mpsc::UnboundedReceiver<BatchItem>,
+    config: BatchConfig,
+}
+
+impl BatchReceiver {
+    pub fn config(&self) -> &BatchConfig {
+        &self.config
+    }
+
+    /// Wait for the next batch
+    ///
+    /// Blocks until a trade is queued, then collects more until the window closes or
+    /// the batch is full. Returns None once every batcher handle is dropped.
+    pub async fn next_batch(&mut self) -> Option<Vec<BatchItem>> {
+        let first = self.receiver.recv().await?;
+        let mut batch = vec![first];
+
+        let deadline = tokio::time::Instant::now() + self.config.window;
+        while batch.len() < self.config.max_size {
+            match tokio::time::timeout_at(deadline, self.receiver.recv()).await {
+                Ok(Some(item)) => {
+                    // Re-validation of a queued trade replaces its earlier entry
+                    batch.retain(|queued| queued.trade_id != item.trade_id);
+                    batch.push(item);
+                }
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        Some(batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(trade_id: &str, transaction_id: &str) -> BatchItem {
+        BatchItem {
+            trade_id: trade_id.to_string(),
+            transaction_id: transaction_id.to_string(),
+            payment_time: "2025-12-27 08:36:12".to_string(),
+        }
+    }
+
+    fn batcher(window_secs: u64, max_size: usize) -> (SettlementBatcher, BatchReceiver) {
+        SettlementBatcher::new(BatchConfig {
+            window: Duration::from_secs(window_secs),
+            max_size,
+        })
+    }
+
+    fn trade_ids(batch: &[BatchItem]) -> Vec<&str> {
+        batch.iter().map(|item| item.trade_id.as_str()).collect()
+    }
+
+    fn config(window: Option<&str>, max_size: Option<&str>) -> Option<BatchConfig> {
+        BatchConfig::from_lookup(|name| match name {
+            "SETTLEMENT_BATCH_WINDOW_SECS" => window.map(str::to_string),
+            "SETTLEMENT_BATCH_MAX_SIZE" => max_size.map(str::to_string),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_config_from_lookup() {
+        assert!(config(None, None).is_none());
+
+        let enabled = config(Some("30"), None).unwrap();
+        assert_eq!(enabled.window, Duration::from_secs(30));
+        assert_eq!(enabled.max_size, DEFAULT_MAX_BATCH_SIZE);
+
+        // Clamped to what the contract accepts
+        assert_eq!(config(Some("30"), Some("100")).unwrap().max_size, CONTRACT_MAX_BATCH_SIZE);
+        assert_eq!(config(Some("30"), Some("0")).unwrap().max_size, 1);
+
+        assert!(config(Some("0"), Some("4")).is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_batch_closes_when_window_ends() {
+        let (batcher, mut receiver) = batcher(10, 8);
+        batcher.enqueue(item("0xa", "tx-a")).unwrap();
+        batcher.enqueue(item("0xb", "tx-b")).unwrap();
+
+        let started = tokio::time::Instant::now();
+        let batch = receiver.next_batch().await.unwrap();
+        assert_eq!(trade_ids(&batch), ["0xa", "0xb"]);
+        assert_eq!(started.elapsed(), Duration::from_secs(10));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_full_batch_does_not_wait_for_window() {
+        let (batcher, mut receiver) = batcher(10, 2);
+        for id in ["0xa", "0xb", "0xc"] {
+            batcher.enqueue(item(id, id)).unwrap();
+        }
+
+        let started = tokio::time::Instant::now();
+        assert_eq!(trade_ids(&receiver.next_batch().await.unwrap()), ["0xa", "0xb"]);
+        assert_eq!(started.elapsed(), Duration::ZERO);
+
+        // The overflow trade opens the next window
+        assert_eq!(trade_ids(&receiver.next_batch().await.unwrap()), ["0xc"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_revalidated_trade_replaces_queued_entry() {
+        let (batcher, mut receiver) = batcher(10, 8);
+        batcher.enqueue(item("0xa", "tx-old")).unwrap();
+        batcher.enqueue(item("0xb", "tx-b")).unwrap();
+        batcher.enqueue(item("0xa", "tx-new")).unwrap();
+
+        let batch = receiver.next_batch().await.unwrap();
+        assert_eq!(trade_ids(&batch), ["0xb", "0xa"]);
+        assert_eq!(batch[1].transaction_id, "tx-new");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_receiver_stops_after_batchers_drop() {
+        let (batcher, mut receiver) = batcher(10, 8);
+        batcher.enqueue(item("0xa", "tx-a")).unwrap();
+        drop(batcher);
+
+        // Queued trades are still proved, then the worker sees the end
+        assert_eq!(trade_ids(&receiver.next_batch().await.unwrap()), ["0xa"]);
+        assert!(receiver.next_batch().await.is_none());
+    }
+
+    #[test]
+    fn test_enqueue_returns_item_after_worker_stops() {
+        let (batcher, receiver) = batcher(10, 8);
+        drop(receiver);
+        let returned = batcher.enqueue(item("0xa", "tx-a")).unwrap_err();
+        assert_eq!(returned.trade_id, "0xa");
+    }
+}
@@ -0,0 +1,205 @@
+//! Trade event bus - in-process broadcast of live trade updates
+//!
+//! Producers:
+//! - Settlement flow: settlement state transitions
+//! - AxiomProver: proof status transitions (poll_proof_status)
+//! - EthereumClient: submitProof tx sent / confirmed
+//! - EventListener: on-chain trade status changes
+//!
+//! Consumers: SSE and WebSocket streams at /api/trades/:id/events and /api/trades/:id/ws
+
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Live update for a single trade
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TradeEvent {
+    /// Current state, sent first when a client subscribes
+    Snapshot {
+        trade_id: String,
+        status: i32,
+        settlement_state: Option<String>,
+    },
+    /// Settlement sub-status transition (see SettlementState)
+    SettlementState {
+        trade_id: String,
+        state: String,
+        detail: Option<String>,
+    },
+    /// Axiom proof state transition (Queued, Executing, AppProving, ..., Succeeded)
+    ProofStatus {
+        trade_id: String,
+        proof_id: String,
+        status: String,
+    },
+    /// submitProof transaction broadcast
+    TxSent {
+        trade_id: String,
+        tx_hash: String,
+    },
+    /// submitProof transaction mined successfully
+    TxConfirmed {
+        trade_id: String,
+        tx_hash: String,
+    },
+    /// On-chain trade status from the event listener: 0=PENDING, 1=SETTLED, 2=EXPIRED
+    TradeStatus {
+        trade_id: String,
+        status: i32,
+    },
+    /// The subscriber fell behind and missed events - refetch GET /api/trades/:id
+    Lagged {
+        trade_id: String,
+        skipped: u64,
+    },
+}
+
+impl TradeEvent {
+    pub fn trade_id(&self) -> &str {
+        match self {
+            Self::Snapshot { trade_id, .. }
+            | Self::SettlementState { trade_id, .. }
+            | Self::ProofStatus { trade_id, .. }
+            | Self::TxSent { trade_id, .. }
+            | Self::TxConfirmed { trade_id, .. }
+            | Self::TradeStatus { trade_id, .. }
+            | Self::Lagged { trade_id, .. } => trade_id,
+        }
+    }
+
+    /// SSE event name / WebSocket message type
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Snapshot { .. } => "snapshot",
+            Self::SettlementState { .. } => "settlement_state",
+            Self::ProofStatus { .. } => "proof_status",
+            Self::TxSent { .. } => "tx_sent",
+            Self::TxConfirmed { .. } => "tx_confirmed",
+            Self::TradeStatus { .. } => "trade_status",
+            Self::Lagged { .. } => "lagged",
+        }
+    }
+
+    /// True if this event belongs to the given trade (trade IDs compare case-insensitively)
+    pub fn is_for(&self, trade_id: &str) -> bool {
+        self.trade_id().eq_ignore_ascii_case(trade_id)
+    }
+}
+
+/// Broadcast bus shared by all producers and stream subscribers
+#[derive(Clone)]
+pub struct TradeEventBus {
+    sender: broadcast::Sender<TradeEvent>,
+}
+
+impl TradeEventBus {
+    /// Events buffered per subscriber before it starts lagging
+    const CAPACITY: usize = 256;
+
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(Self::CAPACITY);
+        Self { sender }
+    }
+
+    /// Publish an event - silently dropped if nobody is subscribed
+    pub fn publish(&self, event: TradeEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Subscribe to one trade's events
+    pub fn subscribe(&self, trade_id: &str) -> TradeSubscription {
+        TradeSubscription {
+            receiver: self.sender.subscribe(),
+            trade_id: trade_id.to_string(),
+        }
+    }
+}
+
+/// One stream subscriber's view of the bus, filtered to a trade
+pub struct TradeSubscription {
+    receiver: broadcast::Receiver<TradeEvent>,
+    trade_id: String,
+}
+
+impl TradeSubscription {
+    /// Next event for the trade - `Lagged` if the subscriber missed some, None once the bus is gone
+    pub async fn next(&mut self) -> Option<TradeEvent> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) if event.is_for(&self.trade_id) => return Some(event),
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    return Some(TradeEvent::Lagged { trade_id: self.trade_id.clone(), skipped });
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+impl Default for TradeEventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(trade_id: &str, status: i32) -> TradeEvent {
+        TradeEvent::TradeStatus { trade_id: trade_id.to_string(), status }
+    }
+
+    #[tokio::test]
+    async fn test_subscription_filters_by_trade() {
+        let bus = TradeEventBus::new();
+        let mut subscription = bus.subscribe("0xAB");
+
+        bus.publish(status("0xcd", 1));
+        bus.publish(status("0xab", 1));
+
+        let event = subscription.next().await.unwrap();
+        assert_eq!(event.trade_id(), "0xab");
+        assert_eq!(event.name(), "trade_status");
+    }
+
+    #[tokio::test]
+    async fn test_lagged_subscriber_is_told() {
+        let bus = TradeEventBus::new();
+        let mut subscription = bus.subscribe("0xab");
+
+        for _ in 0..TradeEventBus::CAPACITY + 3 {
+            bus.publish(status("0xab", 0));
+        }
+
+        match subscription.next().await.unwrap() {
+            TradeEvent::Lagged { trade_id, skipped } => {
+                assert_eq!(trade_id, "0xab");
+                assert_eq!(skipped, 3);
+            }
+            other => panic!("expected lagged, got {:?}", other),
+        }
+        // Then the events still buffered
+        assert_eq!(subscription.next().await.unwrap().name(), "trade_status");
+    }
+
+    #[tokio::test]
+    async fn test_closed_bus_ends_subscription() {
+        let bus = TradeEventBus::new();
+        let mut subscription = bus.subscribe("0xab");
+        drop(bus);
+        assert!(subscription.next().await.is_none());
+    }
+
+    #[test]
+    fn test_json_tagged_by_type() {
+        let lagged = TradeEvent::Lagged { trade_id: "0xab".to_string(), skipped: 4 };
+        assert_eq!(
+            serde_json::to_value(&lagged).unwrap(),
+            serde_json::json!({ "type": "lagged", "trade_id": "0xab", "skipped": 4 })
+        );
+        assert_eq!(lagged.name(), "lagged");
+    }
+}
@@ -8,20 +8,148 @@
 //   - time_amount_hash: SHA256(27 || line27 || 29 || line29) - time and amount (contract recomputes)
 //   - output: SHA256(is_valid || pk_hash || account_lines_hash || tx_id_hash || time_amount_hash)
 //
+// Optional trade memo (6th line number, 0 or absent = no memo):
+//   - memo_hash: SHA256(memo_line) - e.g. "备注：1A2B3C4D5E6F7081", the trade reference code
+//     the buyer put in the transfer memo (line number varies by receipt, so not hashed)
+//   - output: SHA256(is_valid || pk_hash || account_lines_hash || tx_id_hash || time_amount_hash || memo_hash)
+//
+// Amount mode (read after the line numbers):
+//   - 0 = exact: line29 is hashed verbatim (must equal the trade amount)
+//   - 1 = at least: followed by expected_cents (u64). The guest parses line 29 and, if
+//     paid_cents >= expected_cents, hashes the canonical "小写：<expected>" line instead,
+//     so the expected amount is committed and the contract's recomputation still matches.
+//     Underpayments keep the raw line, and the hash mismatches.
+//
+// Trusted key set (read after the amount mode):
+//   - 0 = single key: pk_hash is committed as is (contract stores the key hash)
+//   - 1 = key set: followed by sibling_count (u32) and that many 32-byte siblings, a
+//     membership proof for pk_hash. The guest folds pk_hash up the proof,
+//     parent = SHA256(min(a, b) || max(a, b)), and commits the resulting key-set root
+//     in place of pk_hash, so the contract stores the root and a rotation back to any
+//     key already in the set needs no on-chain update.
+//
+// Prover hints (read after the key mode):
+//   - 0 = none: the whole PDF is parsed (verify_and_extract)
+//   - 1 = hints: followed by the encoded ReceiptHints (read_vec) - the ByteRange offset
+//     and page 1's object offsets, computed by the host. The guest parses only those
+//     objects and checks each one is the latest definition and lies inside the signed
+//     ByteRange (pdf_core::hints). A wrong hint fails like an invalid PDF, so hints cut
+//     cycles without changing what is committed.
+//
+// Input layout (built and decoded by the input-streams crate, shared with the relay):
+//   - format_version first (must equal INPUT_FORMAT_VERSION), then receipt_count
+//   - per receipt: pdf_bytes (read_vec, zero-padded to 4 bytes) and pdf_len, which
+//     trims the padding, then the line numbers and the modes below
+//
+// Batches (receipt_count read after the version, then each receipt's inputs in turn):
+//   - each receipt yields its commitment (the single-receipt output above)
+//   - revealed: Merkle root over leaf = SHA256(0x00 || commitment), parent =
+//     SHA256(0x01 || left || right), an odd last node is carried up unchanged. With
+//     one receipt the root IS the commitment, so single-trade proofs are unchanged.
+//
+// Diagnostic build (`--features diagnostic`, openvm.diagnostic.toml), execute mode only:
+//   - bytes [0, 32): the root above, unchanged
+//   - bytes [32, 64): failure bitmask (u32 LE, see FAIL_*), rest zero
+//   - bytes [64, 224): key commitment (pk_hash or key-set root), account_lines_hash,
+//     tx_id_hash, time_amount_hash, memo_hash (zero if no memo) of the first receipt
+//   Lets the relay tell which component mismatched without revealing any plaintext.
+//   Its larger public output can never verify against the on-chain verifier.
+//
 // This design ensures:
 //   - Seller's account info is private (only hash stored on-chain)
 //   - Transaction ID is private (only hash passed to contract)
 //   - Payment time is public (for createdAt validation)
-//   - Amount is verified against trade storage
+//   - Amount is verified against trade storage (exactly, or paid >= trade amount)
 //   - System remains trustless (all hashes verified against ZK proof)
+//   - Memo-bound trades can't be claimed with a receipt for an unrelated payment
 
 use openvm::io::{read, read_vec, reveal_bytes32};
+#[cfg(feature = "diagnostic")]
+use openvm::io::reveal_u32;
 use openvm_sha2::sha256;
+use pdf_core::amount::{format_amount_line, parse_amount_cents};
+use pdf_core::hints::{verify_and_extract_first_page, ReceiptHints};
 use pdf_core::verify_and_extract;
 
+// Mirrors input_streams::FORMAT_VERSION
+const INPUT_FORMAT_VERSION: u32 = 1;
+
+const AMOUNT_MODE_EXACT: u32 = 0;
+const AMOUNT_MODE_AT_LEAST: u32 = 1;
+
+const KEY_MODE_SINGLE: u32 = 0;
+const KEY_MODE_SET: u32 = 1;
+
+const HINT_MODE_NONE: u32 = 0;
+const HINT_MODE_HINTS: u32 = 1;
+
+// Failure bitmask (diagnostic build)
+const FAIL_PDF: u32 = 1 << 0;                 // PDF parse / signature extraction error (or bad hint)
+const FAIL_SIGNATURE: u32 = 1 << 1;           // Signature present but invalid
+const FAIL_LINE_MISSING: u32 = 1 << 2;        // A requested line is past the end of page 1
+const FAIL_AMOUNT_UNPARSEABLE: u32 = 1 << 3;  // At-least mode: line 29 is not an amount
+const FAIL_AMOUNT_TOO_LOW: u32 = 1 << 4;      // At-least mode: paid < expected
+
+/// One receipt's commitment and the sub-hashes it was built from
+#[cfg_attr(not(feature = "diagnostic"), allow(dead_code))]
+struct ReceiptCommitment {
+    output: [u8; 32],
+    failures: u32,
+    key_commitment: [u8; 32],
+    account_lines_hash: [u8; 32],
+    tx_id_hash: [u8; 32],
+    time_amount_hash: [u8; 32],
+    memo_hash: [u8; 32],
+}
+
 fn main() {
+    let format_version: u32 = read();
+    assert_eq!(format_version, INPUT_FORMAT_VERSION, "unsupported input format version");
+    
+    let receipt_count: u32 = read();
+    assert!(receipt_count > 0, "empty batch");
+    
+    let receipts: Vec<ReceiptCommitment> = (0..receipt_count)
+        .map(|_| verify_receipt())
+        .collect();
+    let commitments: Vec<[u8; 32]> = receipts.iter().map(|r| r.output).collect();
+    
+    reveal_bytes32(batch_root(&commitments));
+    
+    #[cfg(feature = "diagnostic")]
+    reveal_diagnostics(&receipts[0]);
+}
+
+/// Reveal the failure bitmask and sub-hashes after the root (32-byte slots 1..7)
+#[cfg(feature = "diagnostic")]
+fn reveal_diagnostics(receipt: &ReceiptCommitment) {
+    reveal_u32(receipt.failures, 8);
+    
+    let hashes = [
+        receipt.key_commitment,
+        receipt.account_lines_hash,
+        receipt.tx_id_hash,
+        receipt.time_amount_hash,
+        receipt.memo_hash,
+    ];
+    for (slot, hash) in hashes.iter().enumerate() {
+        for (word, chunk) in hash.chunks(4).enumerate() {
+            let value = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            reveal_u32(value, 16 + slot * 8 + word);
+        }
+    }
+}
+
+/// Read one receipt's inputs, verify it and return its commitment
+fn verify_receipt() -> ReceiptCommitment {
+    let mut failures = 0u32;
+    
     // Read inputs
-    let pdf_bytes: Vec<u8> = read_vec();
+    let mut pdf_bytes: Vec<u8> = read_vec();
+    let pdf_len: u32 = read();
+    assert!(pdf_len as usize <= pdf_bytes.len(), "pdf length exceeds its stream");
+    pdf_bytes.truncate(pdf_len as usize);
+    
     let line_count: u32 = read();
     
     let mut line_numbers: Vec<u32> = Vec::with_capacity(line_count as usize);
@@ -29,8 +157,38 @@ fn main() {
         line_numbers.push(read());
     }
     
-    // Verify signature and extract text
-    let (is_valid, pk_hash, extracted_lines) = match verify_and_extract(pdf_bytes) {
+    let amount_mode: u32 = read();
+    let expected_cents: Option<u64> = match amount_mode {
+        AMOUNT_MODE_EXACT => None,
+        AMOUNT_MODE_AT_LEAST => Some(read()),
+        _ => panic!("unknown amount mode"),
+    };
+    
+    let key_mode: u32 = read();
+    let key_siblings: Option<Vec<Vec<u8>>> = match key_mode {
+        KEY_MODE_SINGLE => None,
+        KEY_MODE_SET => {
+            let sibling_count: u32 = read();
+            Some((0..sibling_count).map(|_| read_vec()).collect())
+        }
+        _ => panic!("unknown key mode"),
+    };
+    
+    let hint_mode: u32 = read();
+    let hints: Option<Vec<u8>> = match hint_mode {
+        HINT_MODE_NONE => None,
+        HINT_MODE_HINTS => Some(read_vec()),
+        _ => panic!("unknown hint mode"),
+    };
+    
+    // Verify signature and extract text (page 1 only when hinted)
+    let verified = match hints {
+        Some(hints) => ReceiptHints::from_bytes(&hints)
+            .and_then(|hints| verify_and_extract_first_page(&pdf_bytes, &hints))
+            .map(|(page, sig)| (vec![page], sig)),
+        None => verify_and_extract(pdf_bytes),
+    };
+    let (is_valid, pk_hash, extracted_lines) = match verified {
         Ok((pages, sig)) => {
             let page_text = pages.first().map(|s| s.as_str()).unwrap_or("");
             let lines: Vec<&str> = page_text.lines().collect();
@@ -39,15 +197,25 @@ fn main() {
             let extracted: Vec<String> = line_numbers.iter()
                 .map(|&n| {
                     if n == 0 { return String::new(); }
-                    lines.get(n as usize - 1)
-                        .map(|s| s.to_string())
-                        .unwrap_or_default()
+                    match lines.get(n as usize - 1) {
+                        Some(line) => line.to_string(),
+                        None => {
+                            failures |= FAIL_LINE_MISSING;
+                            String::new()
+                        }
+                    }
                 })
                 .collect();
             
+            if !sig.is_valid {
+                failures |= FAIL_SIGNATURE;
+            }
             (sig.is_valid, sig.public_key_der_hash, extracted)
         }
-        Err(_) => (false, vec![0u8; 32], vec![]),
+        Err(_) => {
+            failures |= FAIL_PDF;
+            (false, vec![0u8; 32], vec![])
+        }
     };
     
     // Compute account_lines_hash = SHA256(20 || line20 || 21 || line21)
@@ -79,21 +247,119 @@ fn main() {
     // The contract recomputes this from plaintext paymentTime + formatted fiatAmount
     let mut time_amount_data = Vec::new();
     if extracted_lines.len() >= 5 {
+        // At-least mode: commit the expected amount line if the receipt paid enough
+        let amount_line = match expected_cents {
+            Some(expected) => match parse_amount_cents(&extracted_lines[4]) {
+                Some(paid) if paid >= expected => format_amount_line(expected),
+                Some(_) => {
+                    failures |= FAIL_AMOUNT_TOO_LOW;
+                    extracted_lines[4].clone()
+                }
+                None => {
+                    failures |= FAIL_AMOUNT_UNPARSEABLE;
+                    extracted_lines[4].clone()
+                }
+            },
+            None => extracted_lines[4].clone(),
+        };
+        
         time_amount_data.extend_from_slice(&line_numbers[3].to_le_bytes());  // 27
         time_amount_data.extend_from_slice(extracted_lines[3].as_bytes());
         time_amount_data.extend_from_slice(&line_numbers[4].to_le_bytes());  // 29
-        time_amount_data.extend_from_slice(extracted_lines[4].as_bytes());
+        time_amount_data.extend_from_slice(amount_line.as_bytes());
     }
     let time_amount_hash = sha256(&time_amount_data);
     
-    // Compute output = SHA256(is_valid || pk_hash || account_lines_hash || tx_id_hash || time_amount_hash)
-    let mut output_data = Vec::with_capacity(1 + 32 + 32 + 32 + 32);
+    // Compute memo_hash = SHA256(memo_line) when a memo line was requested
+    // Line N: Transfer memo (e.g., "备注：1A2B3C4D5E6F7081") - binds the payment to one trade
+    let memo_requested = line_numbers.len() >= 6 && line_numbers[5] != 0;
+    let memo_hash = if memo_requested {
+        let memo_line = extracted_lines.get(5).map(|s| s.as_str()).unwrap_or("");
+        Some(sha256(memo_line.as_bytes()))
+    } else {
+        None
+    };
+    
+    let mut pk_hash_bytes = [0u8; 32];
+    let pk_len = pk_hash.len().min(32);
+    pk_hash_bytes[..pk_len].copy_from_slice(&pk_hash[..pk_len]);
+    
+    // Key commitment: pk_hash itself, or the key-set root it is a member of
+    let key_commitment = match &key_siblings {
+        Some(siblings) => key_set_root(pk_hash_bytes, siblings),
+        None => pk_hash_bytes,
+    };
+    
+    // Compute output = SHA256(is_valid || key_commitment || account_lines_hash || tx_id_hash || time_amount_hash [|| memo_hash])
+    let mut output_data = Vec::with_capacity(1 + 32 + 32 + 32 + 32 + 32);
     output_data.push(is_valid as u8);
-    output_data.extend_from_slice(&pk_hash);
+    output_data.extend_from_slice(&key_commitment);
     output_data.extend_from_slice(&account_lines_hash);
     output_data.extend_from_slice(&tx_id_hash);
     output_data.extend_from_slice(&time_amount_hash);
-    let output = sha256(&output_data);
+    if let Some(memo_hash) = memo_hash {
+        output_data.extend_from_slice(&memo_hash);
+    }
     
-    reveal_bytes32(output);
+    ReceiptCommitment {
+        output: sha256(&output_data),
+        failures,
+        key_commitment,
+        account_lines_hash,
+        tx_id_hash,
+        time_amount_hash,
+        memo_hash: memo_hash.unwrap_or([0u8; 32]),
+    }
+}
+
+/// Key-set root from a membership proof (sorted-pair hashing, no leaf index needed)
+fn key_set_root(leaf: [u8; 32], siblings: &[Vec<u8>]) -> [u8; 32] {
+    siblings.iter().fold(leaf, |node, sibling| {
+        assert_eq!(sibling.len(), 32, "invalid key-set sibling");
+        let (low, high) = if node.as_slice() <= sibling.as_slice() {
+            (node.as_slice(), sibling.as_slice())
+        } else {
+            (sibling.as_slice(), node.as_slice())
+        };
+        let mut pair = [0u8; 64];
+        pair[..32].copy_from_slice(low);
+        pair[32..].copy_from_slice(high);
+        sha256(&pair)
+    })
+}
+
+/// Merkle root over receipt commitments
+///
+/// leaf = SHA256(0x00 || output), node = SHA256(0x01 || left || right), an odd last
+/// node is carried up unchanged. A lone receipt's root is its bare output.
+fn batch_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if let [single] = leaves {
+        return *single;
+    }
+    let mut level: Vec<[u8; 32]> = leaves
+        .iter()
+        .map(|leaf| {
+            let mut data = [0u8; 33];
+            data[0] = 0x00;
+            data[1..].copy_from_slice(leaf);
+            sha256(&data)
+        })
+        .collect();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut node = [0u8; 65];
+                    node[0] = 0x01;
+                    node[1..33].copy_from_slice(left);
+                    node[33..].copy_from_slice(right);
+                    sha256(&node)
+                }
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    level[0]
 }
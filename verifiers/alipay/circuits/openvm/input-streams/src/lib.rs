@@ -0,0 +1,326 @@
+//! Guest input streams - the encoding shared by the relay and gen_input
+//!
+//! The guest reads its inputs as a sequence of OpenVM hint streams, each a hex string
+//! `0x01<bytes>`. This crate is the single place that builds and decodes them:
+//!
+//! ```text
+//! format_version (u32), receipt_count (u32), then per receipt:
+//!   pdf (raw bytes, zero-padded to 4 bytes), pdf_len (u32),
+//!   line_count (u32), line numbers (u32 each),
+//!   amount_mode (u32: 0 exact, 1 at least + expected_cents u64),
+//!   key_mode (u32: 0 single, 1 key set + sibling_count u32 + 32-byte siblings),
+//!   hint_mode (u32: 0 none, 1 hints + encoded ReceiptHints bytes)
+//! ```
+//!
+//! u32 = one little-endian word, u64 = two words low first (as `openvm::serde`).
+//! The guest checks `format_version` first, so an input built for another layout fails
+//! loudly instead of being misread. Bump FORMAT_VERSION with any layout change.
+
+/// Input layout version - first stream, mirrored by the guest's INPUT_FORMAT_VERSION
+pub const FORMAT_VERSION: u32 = 1;
+
+const AMOUNT_MODE_EXACT: u32 = 0;
+const AMOUNT_MODE_AT_LEAST: u32 = 1;
+
+const KEY_MODE_SINGLE: u32 = 0;
+const KEY_MODE_SET: u32 = 1;
+
+const HINT_MODE_NONE: u32 = 0;
+const HINT_MODE_HINTS: u32 = 1;
+
+/// One receipt's guest inputs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceiptInput {
+    pub pdf: Vec<u8>,
+    pub line_numbers: Vec<u32>,
+    /// Expected cents in at-least amount mode (None = exact line 29)
+    pub min_amount_cents: Option<u64>,
+    /// Key-set membership witness siblings (None = single-key mode)
+    pub key_siblings: Option<Vec<[u8; 32]>>,
+    /// Encoded prover hints (None = the guest parses the whole PDF)
+    pub hints: Option<Vec<u8>>,
+}
+
+/// Guest input for one proof: a batch of receipts (one receipt for single trades)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputStreams {
+    pub receipts: Vec<ReceiptInput>,
+}
+
+impl ReceiptInput {
+    pub fn new(pdf: Vec<u8>, line_numbers: Vec<u32>) -> Self {
+        Self {
+            pdf,
+            line_numbers,
+            min_amount_cents: None,
+            key_siblings: None,
+            hints: None,
+        }
+    }
+
+    /// At-least amount mode: accept receipts paying at least `cents`
+    pub fn with_min_amount(mut self, cents: u64) -> Self {
+        self.min_amount_cents = Some(cents);
+        self
+    }
+
+    /// Key-set mode: commit the key-set root reached through these siblings
+    pub fn with_key_siblings(mut self, siblings: Vec<[u8; 32]>) -> Self {
+        self.key_siblings = Some(siblings);
+        self
+    }
+
+    /// Prover hints (`pdf_core::hints::ReceiptHints::to_bytes`, a multiple of 4 bytes)
+    pub fn with_hints(mut self, hints: Vec<u8>) -> Self {
+        self.hints = Some(hints);
+        self
+    }
+
+    /// This receipt's streams, without the batch header
+    pub fn to_streams(&self) -> Vec<String> {
+        let mut streams = vec![bytes_stream(&self.pdf), u32_stream(self.pdf.len() as u32)];
+        streams.push(u32_stream(self.line_numbers.len() as u32));
+        streams.extend(self.line_numbers.iter().map(|&n| u32_stream(n)));
+
+        match self.min_amount_cents {
+            Some(cents) => {
+                streams.push(u32_stream(AMOUNT_MODE_AT_LEAST));
+                streams.push(u64_stream(cents));
+            }
+            None => streams.push(u32_stream(AMOUNT_MODE_EXACT)),
+        }
+
+        match &self.key_siblings {
+            Some(siblings) => {
+                streams.push(u32_stream(KEY_MODE_SET));
+                streams.push(u32_stream(siblings.len() as u32));
+                streams.extend(siblings.iter().map(|sibling| bytes_stream(sibling)));
+            }
+            None => streams.push(u32_stream(KEY_MODE_SINGLE)),
+        }
+
+        match &self.hints {
+            Some(hints) => {
+                streams.push(u32_stream(HINT_MODE_HINTS));
+                streams.push(bytes_stream(hints));
+            }
+            None => streams.push(u32_stream(HINT_MODE_NONE)),
+        }
+
+        streams
+    }
+
+    /// Decode one receipt's streams (inverse of `to_streams`)
+    pub fn from_streams(streams: &[String]) -> Result<Self, String> {
+        let mut reader = StreamReader { streams, position: 0 };
+        let receipt = Self::read(&mut reader)?;
+        reader.finish()?;
+        Ok(receipt)
+    }
+
+    fn read(reader: &mut StreamReader) -> Result<Self, String> {
+        let mut pdf = reader.next()?;
+        let pdf_len = reader.read_u32()? as usize;
+        if pdf_len > pdf.len() {
+            return Err(format!("PDF length {} exceeds its {}-byte stream", pdf_len, pdf.len()));
+        }
+        pdf.truncate(pdf_len);
+
+        let line_count = reader.read_u32()?;
+        let line_numbers = (0..line_count)
+            .map(|_| reader.read_u32())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let min_amount_cents = match reader.read_u32()? {
+            AMOUNT_MODE_EXACT => None,
+            AMOUNT_MODE_AT_LEAST => Some(reader.read_u64()?),
+            mode => return Err(format!("Unknown amount mode {}", mode)),
+        };
+
+        let key_siblings = match reader.read_u32()? {
+            KEY_MODE_SINGLE => None,
+            KEY_MODE_SET => {
+                let sibling_count = reader.read_u32()?;
+                Some((0..sibling_count).map(|_| reader.read_hash()).collect::<Result<Vec<_>, _>>()?)
+            }
+            mode => return Err(format!("Unknown key mode {}", mode)),
+        };
+
+        let hints = match reader.read_u32()? {
+            HINT_MODE_NONE => None,
+            HINT_MODE_HINTS => Some(reader.next()?),
+            mode => return Err(format!("Unknown hint mode {}", mode)),
+        };
+
+        Ok(Self {
+            pdf,
+            line_numbers,
+            min_amount_cents,
+            key_siblings,
+            hints,
+        })
+    }
+}
+
+impl InputStreams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a receipt (leaf order = order added)
+    pub fn with_receipt(mut self, receipt: ReceiptInput) -> Self {
+        self.receipts.push(receipt);
+        self
+    }
+
+    /// Full guest input: version, receipt count, then each receipt's streams
+    pub fn to_streams(&self) -> Result<Vec<String>, String> {
+        let receipts: Vec<Vec<String>> = self.receipts.iter().map(ReceiptInput::to_streams).collect();
+        Self::assemble(&receipts)
+    }
+
+    /// Full guest input from receipts already encoded with `ReceiptInput::to_streams`
+    /// (e.g. streams cached between validation and proving)
+    pub fn assemble(receipts: &[Vec<String>]) -> Result<Vec<String>, String> {
+        if receipts.is_empty() {
+            return Err("Empty batch".to_string());
+        }
+
+        let mut streams = vec![u32_stream(FORMAT_VERSION), u32_stream(receipts.len() as u32)];
+        for receipt in receipts {
+            streams.extend(receipt.iter().cloned());
+        }
+        Ok(streams)
+    }
+
+    /// Decode a full guest input (inverse of `to_streams`)
+    pub fn parse(streams: &[String]) -> Result<Self, String> {
+        let mut reader = StreamReader { streams, position: 0 };
+
+        let version = reader.read_u32()?;
+        if version != FORMAT_VERSION {
+            return Err(format!("Unsupported input format version {} (expected {})", version, FORMAT_VERSION));
+        }
+
+        let receipt_count = reader.read_u32()?;
+        if receipt_count == 0 {
+            return Err("Empty batch".to_string());
+        }
+        let receipts = (0..receipt_count)
+            .map(|_| ReceiptInput::read(&mut reader))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        reader.finish()?;
+        Ok(Self { receipts })
+    }
+}
+
+fn u32_stream(value: u32) -> String {
+    bytes_stream(&value.to_le_bytes())
+}
+
+fn u64_stream(value: u64) -> String {
+    bytes_stream(&value.to_le_bytes())
+}
+
+/// Raw bytes, zero-padded to 4-byte alignment
+fn bytes_stream(bytes: &[u8]) -> String {
+    let padding = (4 - (bytes.len() % 4)) % 4;
+    format!("0x01{}{}", hex::encode(bytes), "00".repeat(padding))
+}
+
+/// Sequential reader over 0x01-prefixed hex streams
+struct StreamReader<'a> {
+    streams: &'a [String],
+    position: usize,
+}
+
+impl StreamReader<'_> {
+    fn next(&mut self) -> Result<Vec<u8>, String> {
+        let stream = self.streams.get(self.position)
+            .ok_or_else(|| format!("Missing stream {}", self.position))?;
+        let bytes = stream.strip_prefix("0x01")
+            .ok_or_else(|| format!("Stream {} is not 0x01-prefixed", self.position))
+            .and_then(|hex_str| hex::decode(hex_str).map_err(|e| format!("Stream {}: {}", self.position, e)))?;
+        self.position += 1;
+        Ok(bytes)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, String> {
+        let bytes = self.next()?;
+        let word: [u8; 4] = bytes.as_slice().try_into()
+            .map_err(|_| format!("Stream {} is not a u32", self.position - 1))?;
+        Ok(u32::from_le_bytes(word))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, String> {
+        let bytes = self.next()?;
+        let words: [u8; 8] = bytes.as_slice().try_into()
+            .map_err(|_| format!("Stream {} is not a u64", self.position - 1))?;
+        Ok(u64::from_le_bytes(words))
+    }
+
+    fn read_hash(&mut self) -> Result<[u8; 32], String> {
+        let bytes = self.next()?;
+        bytes.try_into()
+            .map_err(|_| format!("Stream {} is not a 32-byte hash", self.position - 1))
+    }
+
+    fn finish(&self) -> Result<(), String> {
+        if self.position != self.streams.len() {
+            return Err(format!("{} trailing streams", self.streams.len() - self.position));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let first = ReceiptInput::new(b"%PDF-1.7\n%%EOF\n".to_vec(), vec![20, 21, 25, 27, 29]);
+        let second = ReceiptInput::new(b"%PDF".to_vec(), vec![20, 21, 25, 27, 29, 31])
+            .with_min_amount(10_050)
+            .with_key_siblings(vec![[7; 32], [9; 32]])
+            .with_hints(vec![1, 0, 0, 0]);
+        let input = InputStreams::new().with_receipt(first.clone()).with_receipt(second.clone());
+
+        let streams = input.to_streams().unwrap();
+        assert_eq!(streams[0], "0x0101000000");
+        assert_eq!(streams[1], "0x0102000000");
+        // PDF padded to 4 bytes, its length restores it exactly
+        assert_eq!(streams[2].len(), 4 + 16 * 2);
+
+        assert_eq!(InputStreams::parse(&streams).unwrap(), input);
+        assert_eq!(ReceiptInput::from_streams(&second.to_streams()).unwrap(), second);
+        assert_eq!(
+            InputStreams::assemble(&[first.to_streams(), second.to_streams()]).unwrap(),
+            streams
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_input() {
+        let receipt = ReceiptInput::new(b"%PDF".to_vec(), vec![20, 21, 25, 27, 29]).to_streams();
+
+        // Count says 2 receipts, only 1 present
+        let mut truncated = vec![u32_stream(FORMAT_VERSION), u32_stream(2)];
+        truncated.extend(receipt.iter().cloned());
+        assert!(InputStreams::parse(&truncated).is_err());
+
+        // Trailing streams after the last receipt
+        let mut trailing = InputStreams::assemble(std::slice::from_ref(&receipt)).unwrap();
+        trailing.push(u32_stream(0));
+        assert!(InputStreams::parse(&trailing).is_err());
+
+        // Unknown format version
+        let mut other_version = InputStreams::assemble(std::slice::from_ref(&receipt)).unwrap();
+        other_version[0] = u32_stream(FORMAT_VERSION + 1);
+        assert!(InputStreams::parse(&other_version).is_err());
+
+        assert!(InputStreams::parse(&[u32_stream(FORMAT_VERSION), u32_stream(0)]).is_err());
+        assert!(InputStreams::new().to_streams().is_err());
+    }
+}
@@ -1,54 +1,147 @@
+use input_streams::{InputStreams, ReceiptInput};
+use pdf_core::hints::ReceiptHints;
 use std::{env, fs};
 
 /// OpenVM CLI input generator for zkPDF
-/// Usage: cargo run --bin gen_input -- --pdf <path> --lines <n1,n2,...>
+/// Usage: cargo run --bin gen_input -- --pdf <path> --lines <n1,n2,...> [--key-witness <h1,h2,...>]
+///        [--pdf <path> --lines <...> [--key-witness <...>]]... [--min-amount <cents>] [--hints]
+///
+/// Repeat --pdf/--lines to build a batch (one proof, Merkle root over the receipts).
+/// --min-amount switches the guest to amount >= expected mode (default: exact line 29)
+/// --key-witness commits a key-set root instead of pk_hash: the receipt key's membership
+/// proof as hex siblings ("-" for a one-key set). Give it once per --pdf, or not at all.
+/// --hints passes page 1's object offsets so the guest skips parsing the whole PDF
+/// (fewer cycles, same output)
+///
+/// Decode:  cargo run --bin gen_input -- decode <cli_input.json> [--out <dir>]
+/// Writes each receipt's PDF to <dir>/receipt_<n>.pdf and prints its parameters.
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     
-    let pdf_path = get_arg(&args, "--pdf");
-    let lines_str = get_arg(&args, "--lines");
+    if args.get(1).map(String::as_str) == Some("decode") {
+        decode(&args);
+        return;
+    }
     
-    let line_numbers: Vec<u32> = lines_str
-        .split(',')
-        .map(|s| s.trim().parse().expect("Invalid line number"))
-        .collect();
+    let pdf_paths = get_all_args(&args, "--pdf");
+    let lines_strs = get_all_args(&args, "--lines");
+    assert!(!pdf_paths.is_empty(), "Missing --pdf");
+    assert_eq!(pdf_paths.len(), lines_strs.len(), "Each --pdf needs a matching --lines");
     
-    let pdf_bytes = fs::read(&pdf_path).expect("Failed to read PDF");
+    let key_witnesses = get_all_args(&args, "--key-witness");
+    assert!(
+        key_witnesses.is_empty() || key_witnesses.len() == pdf_paths.len(),
+        "Give --key-witness once per --pdf, or not at all"
+    );
     
-    // Build streams: [pdf_bytes, line_count, line_num1, line_num2, ...]
-    let mut streams = vec![to_hex_stream_raw(&pdf_bytes)];
-    streams.push(to_hex_stream(&(line_numbers.len() as u32)));
-    for num in &line_numbers {
-        streams.push(to_hex_stream(num));
+    let min_amount: Option<u64> = get_optional_arg(&args, "--min-amount")
+        .map(|s| s.trim().parse().expect("Invalid --min-amount (cents)"));
+    let use_hints = args.iter().any(|s| s == "--hints");
+    
+    // Streams: format version, receipt count, then each receipt (see the input-streams crate)
+    let mut input = InputStreams::new();
+    for (i, (pdf_path, lines_str)) in pdf_paths.iter().zip(&lines_strs).enumerate() {
+        let line_numbers: Vec<u32> = lines_str
+            .split(',')
+            .map(|s| s.trim().parse().expect("Invalid line number"))
+            .collect();
+        
+        let pdf_bytes = fs::read(pdf_path).expect("Failed to read PDF");
+        println!("📄 {} | PDF: {} bytes | Lines: {:?}", pdf_path, pdf_bytes.len(), line_numbers);
+        
+        let mut receipt = ReceiptInput::new(pdf_bytes, line_numbers);
+        if let Some(cents) = min_amount {
+            receipt = receipt.with_min_amount(cents);
+        }
+        
+        if let Some(witness) = key_witnesses.get(i) {
+            let siblings: Vec<[u8; 32]> = witness
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty() && *s != "-")
+                .map(|s| {
+                    let sibling = hex::decode(s.trim_start_matches("0x")).expect("Invalid --key-witness hex");
+                    sibling.try_into().expect("Key-set siblings are 32 bytes")
+                })
+                .collect();
+            receipt = receipt.with_key_siblings(siblings);
+        }
+        
+        if use_hints {
+            let hints = ReceiptHints::compute(&receipt.pdf).expect("Failed to compute hints");
+            println!("💡 {} object hints", hints.objects.len());
+            receipt = receipt.with_hints(hints.to_bytes());
+        }
+        
+        input = input.with_receipt(receipt);
     }
+    let streams = input.to_streams().expect("Failed to build input streams");
     
     let output = serde_json::json!({"input": streams});
     fs::write("../guest/cli_input.json", serde_json::to_string_pretty(&output).unwrap())
         .expect("Failed to write");
     
-    println!("✅ {} streams | PDF: {} bytes | Lines: {:?}", 
-             streams.len(), pdf_bytes.len(), line_numbers);
+    println!("✅ {} streams | Receipts: {}", streams.len(), pdf_paths.len());
 }
 
-fn get_arg(args: &[String], flag: &str) -> String {
-    args.iter()
-        .position(|s| s == flag)
-        .and_then(|i| args.get(i + 1))
-        .cloned()
-        .unwrap_or_else(|| panic!("Missing {}", flag))
+/// Decode a cli_input.json back into the PDFs and parameters
+fn decode(args: &[String]) {
+    let path = args.get(2).expect("Usage: gen_input decode <cli_input.json> [--out <dir>]");
+    let out_dir = get_optional_arg(args, "--out").unwrap_or_else(|| ".".to_string());
+    
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(path).expect("Failed to read input"))
+        .expect("Invalid JSON");
+    let streams: Vec<String> = json["input"]
+        .as_array()
+        .expect("Missing \"input\" array")
+        .iter()
+        .map(|stream| stream.as_str().expect("Streams are hex strings").to_string())
+        .collect();
+    
+    let input = InputStreams::parse(&streams).expect("Failed to decode input streams");
+    println!("🔍 Format v{} | Receipts: {}", input_streams::FORMAT_VERSION, input.receipts.len());
+    
+    fs::create_dir_all(&out_dir).expect("Failed to create output directory");
+    for (i, receipt) in input.receipts.iter().enumerate() {
+        let pdf_path = format!("{}/receipt_{}.pdf", out_dir, i);
+        fs::write(&pdf_path, &receipt.pdf).expect("Failed to write PDF");
+        
+        println!("📄 Receipt {} -> {} ({} bytes)", i, pdf_path, receipt.pdf.len());
+        println!("   Lines: {:?}", receipt.line_numbers);
+        match receipt.min_amount_cents {
+            Some(cents) => println!("   Amount: at least {} cents", cents),
+            None => println!("   Amount: exact"),
+        }
+        match &receipt.key_siblings {
+            Some(siblings) => {
+                let siblings: Vec<String> = siblings.iter().map(hex::encode).collect();
+                println!("   Key: key set, witness [{}]", siblings.join(","));
+            }
+            None => println!("   Key: single"),
+        }
+        match receipt.hints.as_deref().map(ReceiptHints::from_bytes) {
+            Some(Ok(hints)) => println!(
+                "   Hints: ByteRange at {}, {} objects",
+                hints.byte_range_offset,
+                hints.objects.len()
+            ),
+            Some(Err(e)) => println!("   Hints: invalid ({})", e),
+            None => println!("   Hints: none"),
+        }
+    }
 }
 
-fn to_hex_stream<T: serde::Serialize>(value: &T) -> String {
-    let words = openvm::serde::to_vec(value).unwrap();
-    let bytes: Vec<u8> = words.into_iter().flat_map(|w| w.to_le_bytes()).collect();
-    format!("0x01{}", hex::encode(bytes))
+fn get_all_args(args: &[String], flag: &str) -> Vec<String> {
+    args.windows(2)
+        .filter(|pair| pair[0] == flag)
+        .map(|pair| pair[1].clone())
+        .collect()
 }
 
-fn to_hex_stream_raw(data: &[u8]) -> String {
-    // Pad to 4-byte alignment
-    let padding = (4 - (data.len() % 4)) % 4;
-    let mut padded = data.to_vec();
-    padded.extend(vec![0u8; padding]);
-    format!("0x01{}", hex::encode(padded))
+fn get_optional_arg(args: &[String], flag: &str) -> Option<String> {
+    args.iter()
+        .position(|s| s == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
 }
@@ -0,0 +1,125 @@
+//! Alipay receipt amount line (line 29) parsing.
+//!
+//! Shared by the guest program (amount ≥ expected mode) and the relay pre-checks,
+//! so both sides read the paid amount identically.
+
+/// Prefix of the amount line, e.g. "小写：100.50"
+pub const AMOUNT_PREFIX: &str = "小写：";
+
+/// Format an amount line exactly as Alipay prints it (and as the verifier recomputes it).
+///
+/// `format_amount_line(10050)` -> `"小写：100.50"`
+pub fn format_amount_line(cents: u64) -> String {
+    format!("{}{}.{:02}", AMOUNT_PREFIX, cents / 100, cents % 100)
+}
+
+/// Parse the paid amount in cents (fen) from an amount line.
+///
+/// Accepts `"小写：100.50"`, `"小写：1,234.5"`, `"小写：¥100"` (surrounding whitespace ignored).
+/// Returns None for anything else, including more than two decimal places, misplaced
+/// separators, signs, and amounts that overflow u64 cents.
+pub fn parse_amount_cents(line: &str) -> Option<u64> {
+    let amount = line.trim().strip_prefix(AMOUNT_PREFIX)?.trim();
+    let amount = amount
+        .strip_prefix('¥')
+        .or_else(|| amount.strip_prefix('￥'))
+        .unwrap_or(amount);
+
+    let (yuan, fen) = match amount.split_once('.') {
+        Some((yuan, fen)) => (yuan, fen),
+        None => (amount, ""),
+    };
+
+    // Thousands separators only in the yuan part, and only between groups of three
+    let mut groups = yuan.split(',');
+    let lead = groups.next()?;
+    if lead.is_empty() || (yuan.contains(',') && lead.len() > 3) {
+        return None;
+    }
+    if !groups.all(|group| group.len() == 3) {
+        return None;
+    }
+    let yuan: String = yuan.chars().filter(|&c| c != ',').collect();
+    if !yuan.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    if fen.len() > 2 || !fen.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let yuan: u64 = yuan.parse().ok()?;
+    let fen: u64 = match fen.len() {
+        0 => 0,
+        1 => fen.parse::<u64>().ok()? * 10,
+        _ => fen.parse().ok()?,
+    };
+
+    yuan.checked_mul(100)?.checked_add(fen)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_amount_line() {
+        assert_eq!(format_amount_line(10050), "小写：100.50");
+        assert_eq!(format_amount_line(100), "小写：1.00");
+        assert_eq!(format_amount_line(35), "小写：0.35");
+        assert_eq!(format_amount_line(5), "小写：0.05");
+    }
+
+    #[test]
+    fn test_parse_round_trips_format() {
+        for cents in [0, 5, 35, 100, 10050, 123456789] {
+            assert_eq!(parse_amount_cents(&format_amount_line(cents)), Some(cents));
+        }
+    }
+
+    #[test]
+    fn test_parse_decimals() {
+        assert_eq!(parse_amount_cents("小写：100"), Some(10000));
+        assert_eq!(parse_amount_cents("小写：100.5"), Some(10050));
+        assert_eq!(parse_amount_cents("小写：100.05"), Some(10005));
+        assert_eq!(parse_amount_cents("小写：100."), Some(10000));
+        assert_eq!(parse_amount_cents("小写：100.505"), None);
+        assert_eq!(parse_amount_cents("小写：.50"), None);
+    }
+
+    #[test]
+    fn test_parse_separators_and_currency() {
+        assert_eq!(parse_amount_cents("小写：1,234.50"), Some(123450));
+        assert_eq!(parse_amount_cents("小写：1,234,567"), Some(123456700));
+        assert_eq!(parse_amount_cents("小写：¥100.50"), Some(10050));
+        assert_eq!(parse_amount_cents("小写：￥100.50"), Some(10050));
+        assert_eq!(parse_amount_cents("  小写： 100.50 "), Some(10050));
+
+        assert_eq!(parse_amount_cents("小写：1,23.50"), None);
+        assert_eq!(parse_amount_cents("小写：1234,567"), None);
+        assert_eq!(parse_amount_cents("小写：1,,234"), None);
+        assert_eq!(parse_amount_cents("小写：,234"), None);
+        assert_eq!(parse_amount_cents("小写：1,234,"), None);
+        assert_eq!(parse_amount_cents("小写：100.5,0"), None);
+    }
+
+    #[test]
+    fn test_parse_overflow() {
+        let max_yuan = u64::MAX / 100;
+        assert_eq!(parse_amount_cents(&format!("小写：{}", max_yuan)), Some(max_yuan * 100));
+        assert_eq!(parse_amount_cents(&format!("小写：{}", max_yuan + 1)), None);
+        assert_eq!(parse_amount_cents("小写：99999999999999999999999"), None);
+    }
+
+    #[test]
+    fn test_parse_rejects_garbage() {
+        assert_eq!(parse_amount_cents("小写：-100.50"), None);
+        assert_eq!(parse_amount_cents("小写：+100.50"), None);
+        assert_eq!(parse_amount_cents("小写：1e5"), None);
+        assert_eq!(parse_amount_cents("小写：100.5a"), None);
+        assert_eq!(parse_amount_cents("小写："), None);
+        assert_eq!(parse_amount_cents("小写：¥"), None);
+        assert_eq!(parse_amount_cents("大写：壹佰元整"), None);
+        assert_eq!(parse_amount_cents("100.50"), None);
+        assert_eq!(parse_amount_cents(""), None);
+    }
+}
@@ -0,0 +1,118 @@
+//! Prover hints for a receipt - page 1 without parsing the whole PDF
+//!
+//! The host computes where the signature's ByteRange and page 1's objects sit in
+//! the file; the guest checks each hint (see `extractor::hints`) instead of scanning
+//! and parsing everything. Every hinted object and the trailer must lie inside the
+//! signed ByteRange, so hinted bytes are covered by the signature.
+//!
+//! Wire format (little-endian u32 words):
+//! `[version, byte_range_offset, count, (id, generation, offset) * count]`
+
+use signature_validator::{find_byte_range, verify_pdf_signature_at, PdfSignatureResult};
+
+pub use extractor::hints::ObjectHint;
+
+/// Hint encoding version (first word of `to_bytes`)
+pub const HINTS_VERSION: u32 = 1;
+
+/// Hints for one receipt PDF
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceiptHints {
+    /// Byte offset of the signature's "/ByteRange" entry
+    pub byte_range_offset: usize,
+    /// Page 1's objects, in parse order
+    pub objects: Vec<ObjectHint>,
+}
+
+impl ReceiptHints {
+    /// Host side: compute hints, checking the hinted page 1 matches the full extraction
+    pub fn compute(pdf_bytes: &[u8]) -> Result<Self, String> {
+        let byte_range_offset =
+            find_byte_range(pdf_bytes).ok_or_else(|| "ByteRange not found".to_string())?;
+        let objects = extractor::hints::first_page_object_hints(pdf_bytes)
+            .map_err(|e| format!("object hints error: {:?}", e))?;
+        let hints = Self {
+            byte_range_offset,
+            objects,
+        };
+
+        let (hinted_page, _) = verify_and_extract_first_page(pdf_bytes, &hints)?;
+        let pages = extractor::extract_text(pdf_bytes.to_vec())
+            .map_err(|e| format!("text extraction error: {:?}", e))?;
+        if pages.first() != Some(&hinted_page) {
+            return Err("hinted page 1 differs from the full extraction".to_string());
+        }
+
+        Ok(hints)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut words = vec![
+            HINTS_VERSION,
+            self.byte_range_offset as u32,
+            self.objects.len() as u32,
+        ];
+        for hint in &self.objects {
+            words.extend_from_slice(&[hint.id, hint.generation as u32, hint.offset as u32]);
+        }
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() % 4 != 0 {
+            return Err("hints length is not a multiple of 4".to_string());
+        }
+        let words: Vec<u32> = bytes
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+
+        match words.as_slice() {
+            [HINTS_VERSION, byte_range_offset, count, rest @ ..] => {
+                if rest.len() != *count as usize * 3 {
+                    return Err("hints object count does not match length".to_string());
+                }
+                let objects = rest
+                    .chunks_exact(3)
+                    .map(|hint| {
+                        Ok(ObjectHint {
+                            id: hint[0],
+                            generation: u16::try_from(hint[1])
+                                .map_err(|_| "hint generation out of range".to_string())?,
+                            offset: hint[2] as usize,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                Ok(Self {
+                    byte_range_offset: *byte_range_offset as usize,
+                    objects,
+                })
+            }
+            [version, ..] => Err(format!("unsupported hints version {}", version)),
+            [] => Err("empty hints".to_string()),
+        }
+    }
+}
+
+/// Verify the PDF signature and extract page 1 text using hints.
+///
+/// Same result as `verify_and_extract` for page 1, at a fraction of the cycles.
+/// A wrong hint fails here rather than changing the extracted text.
+pub fn verify_and_extract_first_page(
+    pdf_bytes: &[u8],
+    hints: &ReceiptHints,
+) -> Result<(String, PdfSignatureResult), String> {
+    let (signature, ranges) = verify_pdf_signature_at(pdf_bytes, hints.byte_range_offset)
+        .map_err(|e| format!("signature error: {}", e))?;
+
+    let in_range = |start: usize, end: usize| {
+        ranges
+            .iter()
+            .any(|&(offset, length)| start >= offset && end <= offset + length)
+    };
+    let page =
+        extractor::hints::extract_first_page_with_hints(pdf_bytes, &hints.objects, &in_range)
+            .map_err(|e| format!("text extraction error: {:?}", e))?;
+
+    Ok((page, signature))
+}
@@ -1,3 +1,6 @@
+pub mod amount;
+pub mod hints;
+
 pub use extractor::extract_text;
 pub use signature_validator::{verify_pdf_signature, PdfSignatureResult};
 
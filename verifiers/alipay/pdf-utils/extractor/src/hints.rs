@@ -0,0 +1,579 @@
+//! Hinted first-page extraction - parse only the objects page 1 needs
+//!
+//! `extract_text` parses every object of the file (all pages, fonts, images, the
+//! signature dictionary) to read a few lines from page 1. The host instead runs
+//! the full parse once and hints the byte offsets of the objects on page 1's path:
+//! catalog, page tree nodes, the page, its content streams and resources (fonts,
+//! ToUnicode maps, XObjects). The guest parses only those and checks each hint:
+//! - the offset holds the hinted object header ("<id> <gen> obj") up to "endobj"
+//! - the object lies in a caller-approved byte range (the signed ByteRange)
+//! - no later definition of the object exists (incremental updates override)
+//! - every object page 1 references (page tree, Contents, Resources, transitively)
+//!   is hinted - an omitted font or content stream would otherwise read as absent
+//!   and silently change the text
+//!
+//! The trailer is located through the last `startxref`, never hinted. A wrong or
+//! missing hint makes extraction fail. The host refuses files whose page 1 holds a
+//! dangling reference, since the guest could not tell it from an omitted hint.
+
+use crate::parser::Parser;
+use crate::parser_utils::is_delimiter;
+use crate::types::{PageContent, PdfError, PdfObj};
+use crate::{
+    extract_text_from_page, parse_indirect_object, parse_pdf_with_offsets, process_page_dict,
+    process_page_stream,
+};
+use miniz_oxide::inflate::decompress_to_vec_zlib;
+use std::collections::{HashMap, HashSet};
+
+// Page tree depth limit (the walk follows Kids[0] only)
+const MAX_PAGE_TREE_DEPTH: usize = 32;
+
+// Keys never followed when collecting page 1's objects: back-references up the
+// page tree, and font programs (text extraction only reads the font dictionary)
+const SKIPPED_KEYS: [&str; 2] = ["Parent", "FontDescriptor"];
+
+/// Byte offset of one top-level object ("<id> <gen> obj")
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectHint {
+    pub id: u32,
+    pub generation: u16,
+    pub offset: usize,
+}
+
+/// Host side: hints for every object page 1's text depends on
+///
+/// Fails if one of them lives in an object stream (no byte offset of its own) -
+/// use the full `extract_text` for such files.
+pub fn first_page_object_hints(data: &[u8]) -> Result<Vec<ObjectHint>, PdfError> {
+    let (_, objects, offsets) = parse_pdf_with_offsets(data)?;
+    let (trailer, _) = latest_trailer(data, &objects)?;
+
+    let mut needed = Vec::new();
+    let (page, inherited_path) = find_first_page(&trailer, &objects, &mut needed)?;
+    first_page_references(page, &inherited_path, &objects, &mut needed)?;
+
+    let mut hints = Vec::with_capacity(needed.len());
+    for id in needed {
+        if !objects.contains_key(&id) {
+            // The guest can't tell a dangling reference from an omitted hint
+            return Err(PdfError::ParseError(
+                "Page 1 references a missing object and cannot be hinted",
+            ));
+        }
+        let offset = offsets.get(&id).ok_or(PdfError::ParseError(
+            "Object in an object stream cannot be hinted",
+        ))?;
+        hints.push(ObjectHint {
+            id: id.0,
+            generation: id.1,
+            offset: *offset,
+        });
+    }
+
+    // Plain numbers first (indirect stream /Length values), then in file order
+    hints.sort_by_key(|hint| {
+        let is_number = matches!(
+            objects.get(&(hint.id, hint.generation)),
+            Some(PdfObj::Number(_))
+        );
+        (!is_number, hint.offset)
+    });
+
+    if redefined_after(data, &hints) {
+        return Err(PdfError::ParseError("Hinted object is redefined later"));
+    }
+    Ok(hints)
+}
+
+/// Guest side: page 1 text from the hinted objects only
+///
+/// `in_range(start, end)` approves the byte span of every object used (e.g. both
+/// parts of the signed ByteRange); the trailer's span is checked the same way.
+pub fn extract_first_page_with_hints(
+    data: &[u8],
+    hints: &[ObjectHint],
+    in_range: &dyn Fn(usize, usize) -> bool,
+) -> Result<String, PdfError> {
+    let mut objects: HashMap<(u32, u16), PdfObj> = HashMap::new();
+    for hint in hints {
+        if hint.offset >= data.len() {
+            return Err(PdfError::ParseError("Object hint out of bounds"));
+        }
+        let mut parser = Parser::new(data);
+        parser.pos = hint.offset;
+        let (id, obj) = parse_indirect_object(&mut parser, &objects)?;
+        if id != (hint.id, hint.generation) {
+            return Err(PdfError::ParseError(
+                "Object hint does not match object header",
+            ));
+        }
+        if !in_range(hint.offset, parser.pos) {
+            return Err(PdfError::ParseError(
+                "Hinted object outside the approved byte range",
+            ));
+        }
+        objects.insert(id, obj);
+    }
+
+    if redefined_after(data, hints) {
+        return Err(PdfError::ParseError("Hinted object is redefined later"));
+    }
+
+    let (trailer, (start, end)) = latest_trailer(data, &objects)?;
+    if !in_range(start, end) {
+        return Err(PdfError::ParseError(
+            "Trailer outside the approved byte range",
+        ));
+    }
+
+    let mut needed = Vec::new();
+    let (page, inherited_path) = find_first_page(&trailer, &objects, &mut needed)?;
+    first_page_references(page, &inherited_path, &objects, &mut needed)?;
+    if needed.iter().any(|id| !objects.contains_key(id)) {
+        return Err(PdfError::ParseError(
+            "Page 1 references an object that was not hinted",
+        ));
+    }
+
+    let page = first_page_content(page, &inherited_path, &objects)?;
+    Ok(extract_text_from_page(&page, &objects))
+}
+
+/// Trailer dictionary of the latest revision, found through the last `startxref`,
+/// and the byte span from `startxref`'s target to the end of the keyword's number
+fn latest_trailer(
+    data: &[u8],
+    objects: &HashMap<(u32, u16), PdfObj>,
+) -> Result<(HashMap<String, PdfObj>, (usize, usize)), PdfError> {
+    let startxref = data
+        .windows(b"startxref".len())
+        .rposition(|w| w == b"startxref")
+        .ok_or(PdfError::ParseError("startxref not found"))?;
+
+    let mut parser = Parser::new(data);
+    parser.pos = startxref + b"startxref".len();
+    parser.skip_whitespace_and_comments();
+    let xref_offset = match parser.parse_number()? {
+        PdfObj::Number(n) if n >= 0.0 && (n as usize) < startxref => n as usize,
+        _ => return Err(PdfError::ParseError("Invalid startxref offset")),
+    };
+    let span = (xref_offset, parser.pos);
+
+    parser.pos = xref_offset;
+    if parser.remaining_starts_with(b"xref") {
+        // Classic cross-reference table, followed by its trailer
+        let trailer = data[xref_offset..startxref]
+            .windows(b"trailer".len())
+            .position(|w| w == b"trailer")
+            .ok_or(PdfError::ParseError("Trailer dictionary not found"))?;
+        parser.pos = xref_offset + trailer + b"trailer".len();
+        parser.skip_whitespace_and_comments();
+        if !parser.remaining_starts_with(b"<<") {
+            return Err(PdfError::ParseError("Trailer dictionary not found"));
+        }
+        parser.pos += 2;
+        match parser.parse_dictionary()? {
+            PdfObj::Dictionary(dict) => Ok((dict, span)),
+            _ => Err(PdfError::ParseError("Trailer is not a dictionary")),
+        }
+    } else {
+        // Cross-reference stream - its dictionary is the trailer
+        match parse_indirect_object(&mut parser, objects)? {
+            (_, PdfObj::Stream(stream)) if matches!(stream.dict.get("Type"), Some(PdfObj::Name(t)) if t == "XRef") => {
+                Ok((stream.dict, span))
+            }
+            _ => Err(PdfError::ParseError(
+                "startxref does not point to a cross-reference section",
+            )),
+        }
+    }
+}
+
+/// Walk from the catalog down Kids[0] to page 1
+///
+/// Returns the page node and the ids of the `Pages` nodes above it (root first).
+/// Every object id resolved on the way is appended to `path`.
+fn find_first_page<'a>(
+    trailer: &HashMap<String, PdfObj>,
+    objects: &'a HashMap<(u32, u16), PdfObj>,
+    path: &mut Vec<(u32, u16)>,
+) -> Result<(&'a PdfObj, Vec<(u32, u16)>), PdfError> {
+    let mut resolve = |id: (u32, u16), error: &'static str| -> Result<&'a PdfObj, PdfError> {
+        if !path.contains(&id) {
+            path.push(id);
+        }
+        objects.get(&id).ok_or(PdfError::ParseError(error))
+    };
+
+    let catalog = match trailer.get("Root") {
+        Some(PdfObj::Reference(id)) => resolve(*id, "Root object not found")?,
+        _ => return Err(PdfError::ParseError("Root object not found")),
+    };
+    let mut node_id = match catalog {
+        PdfObj::Dictionary(dict) => match dict.get("Pages") {
+            Some(PdfObj::Reference(id)) => *id,
+            _ => return Err(PdfError::ParseError("Pages reference not found in Catalog")),
+        },
+        _ => return Err(PdfError::ParseError("Catalog object is not a dictionary")),
+    };
+
+    let mut pages_nodes = Vec::new();
+    for _ in 0..MAX_PAGE_TREE_DEPTH {
+        let node = resolve(node_id, "Missing object in page tree")?;
+        let dict = match node {
+            PdfObj::Dictionary(dict) => dict,
+            PdfObj::Stream(stream) => &stream.dict,
+            _ => return Err(PdfError::ParseError("Invalid object in page tree")),
+        };
+        match dict.get("Type") {
+            Some(PdfObj::Name(t)) if t == "Page" => return Ok((node, pages_nodes)),
+            Some(PdfObj::Name(t)) if t == "Pages" => {
+                if matches!(node, PdfObj::Stream(_)) {
+                    return Err(PdfError::ParseError(
+                        "Pages object in stream form is not supported",
+                    ));
+                }
+                pages_nodes.push(node_id);
+                let kids = match dict.get("Kids") {
+                    Some(PdfObj::Array(kids)) => kids,
+                    Some(PdfObj::Reference(kids_id)) => {
+                        match resolve(*kids_id, "Missing Kids array")? {
+                            PdfObj::Array(kids) => kids,
+                            _ => {
+                                return Err(PdfError::ParseError("Kids reference is not an array"))
+                            }
+                        }
+                    }
+                    _ => return Err(PdfError::ParseError("Pages node missing Kids")),
+                };
+                node_id = match kids.first() {
+                    Some(PdfObj::Reference(id)) => *id,
+                    _ => return Err(PdfError::ParseError("First kid is not a page reference")),
+                };
+            }
+            _ => return Err(PdfError::ParseError("Unknown object in page tree")),
+        }
+    }
+    Err(PdfError::ParseError("Page tree too deep"))
+}
+
+/// Page content of page 1, with resources inherited down the page tree
+fn first_page_content(
+    page: &PdfObj,
+    pages_nodes: &[(u32, u16)],
+    objects: &HashMap<(u32, u16), PdfObj>,
+) -> Result<PageContent, PdfError> {
+    // Nearest ancestor's Resources win, as in traverse_pages
+    let mut inherited: Option<&HashMap<String, PdfObj>> = None;
+    for node_id in pages_nodes {
+        if let Some(PdfObj::Dictionary(dict)) = objects.get(node_id) {
+            match dict.get("Resources") {
+                Some(PdfObj::Dictionary(res)) => inherited = Some(res),
+                Some(PdfObj::Reference(res_ref)) => {
+                    if let Some(PdfObj::Dictionary(res)) = objects.get(res_ref) {
+                        inherited = Some(res);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let decompress =
+        |bytes: &[u8]| decompress_to_vec_zlib(bytes).map_err(|_| PdfError::DecompressionError);
+    let mut result = Vec::new();
+    match page {
+        PdfObj::Dictionary(dict) => {
+            process_page_dict(dict, inherited, objects, &mut result, &decompress)?
+        }
+        PdfObj::Stream(stream) => {
+            process_page_stream(stream, inherited, objects, &mut result, &decompress)?
+        }
+        _ => return Err(PdfError::ParseError("Invalid object in page tree")),
+    }
+    result.pop().ok_or(PdfError::ParseError("Page 1 not found"))
+}
+
+/// Append the ids of everything page 1's text depends on to `needed`: the
+/// Resources of the page tree nodes and the page, plus the page's content streams
+///
+/// Missing objects are appended too (not descended into), so callers can reject them.
+fn first_page_references(
+    page: &PdfObj,
+    pages_nodes: &[(u32, u16)],
+    objects: &HashMap<(u32, u16), PdfObj>,
+    needed: &mut Vec<(u32, u16)>,
+) -> Result<(), PdfError> {
+    let mut seen: HashSet<(u32, u16)> = needed.iter().copied().collect();
+    for node_id in pages_nodes {
+        if let Some(PdfObj::Dictionary(dict)) = objects.get(node_id) {
+            if let Some(resources) = dict.get("Resources") {
+                collect_references(resources, objects, &mut seen, needed);
+            }
+        }
+    }
+    let page_dict = match page {
+        PdfObj::Dictionary(dict) => dict,
+        PdfObj::Stream(stream) => &stream.dict,
+        _ => return Err(PdfError::ParseError("Invalid object in page tree")),
+    };
+    for key in ["Resources", "Contents", "Length"] {
+        if let Some(value) = page_dict.get(key) {
+            collect_references(value, objects, &mut seen, needed);
+        }
+    }
+    Ok(())
+}
+
+/// Collect the ids of all objects referenced (transitively) from a value
+fn collect_references(
+    value: &PdfObj,
+    objects: &HashMap<(u32, u16), PdfObj>,
+    seen: &mut HashSet<(u32, u16)>,
+    needed: &mut Vec<(u32, u16)>,
+) {
+    match value {
+        PdfObj::Reference(id) => {
+            if seen.insert(*id) {
+                needed.push(*id);
+                if let Some(obj) = objects.get(id) {
+                    collect_references(obj, objects, seen, needed);
+                }
+            }
+        }
+        PdfObj::Array(items) => {
+            for item in items {
+                collect_references(item, objects, seen, needed);
+            }
+        }
+        PdfObj::Dictionary(dict) => {
+            for (key, item) in dict {
+                if !SKIPPED_KEYS.contains(&key.as_str()) {
+                    collect_references(item, objects, seen, needed);
+                }
+            }
+        }
+        PdfObj::Stream(stream) => {
+            for (key, item) in &stream.dict {
+                if !SKIPPED_KEYS.contains(&key.as_str()) {
+                    collect_references(item, objects, seen, needed);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// True if a hinted object has another "<id> <gen> obj" header after its hinted offset
+///
+/// One pass over the file from the first hinted offset.
+fn redefined_after(data: &[u8], hints: &[ObjectHint]) -> bool {
+    let first = match hints.iter().map(|hint| hint.offset).min() {
+        Some(first) => first,
+        None => return false,
+    };
+    let is_space = |b: u8| matches!(b, 0x00 | 0x09 | 0x0A | 0x0C | 0x0D | 0x20);
+
+    let mut i = first + 1;
+    while i + 3 <= data.len() {
+        let keyword = &data[i..i + 3] == b"obj"
+            && data
+                .get(i + 3)
+                .map_or(true, |&b| is_space(b) || is_delimiter(b));
+        if keyword {
+            if let Some((header, id, generation)) = header_before(data, i, &is_space) {
+                if hints.iter().any(|hint| {
+                    hint.id == id && hint.generation == generation && header > hint.offset
+                }) {
+                    return true;
+                }
+            }
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Parse "<id> <gen> " backwards from an "obj" keyword at `keyword`
+fn header_before(
+    data: &[u8],
+    keyword: usize,
+    is_space: &dyn Fn(u8) -> bool,
+) -> Option<(usize, u32, u16)> {
+    let mut j = keyword;
+    let skip_space = |j: &mut usize| -> bool {
+        let start = *j;
+        while *j > 0 && is_space(data[*j - 1]) {
+            *j -= 1;
+        }
+        *j < start
+    };
+    let take_digits = |j: &mut usize| -> Option<u32> {
+        let end = *j;
+        while *j > 0 && data[*j - 1].is_ascii_digit() {
+            *j -= 1;
+        }
+        std::str::from_utf8(&data[*j..end]).ok()?.parse().ok()
+    };
+
+    if !skip_space(&mut j) {
+        return None;
+    }
+    let generation = take_digits(&mut j)?;
+    if !skip_space(&mut j) {
+        return None;
+    }
+    let id = take_digits(&mut j)?;
+    if j > 0 && !is_space(data[j - 1]) && !is_delimiter(data[j - 1]) {
+        return None;
+    }
+    Some((j, id, u16::try_from(generation).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal one-page PDF; `extra` objects go after the regular ones, before the xref
+    fn pdf(font_ref: &str, extra: &[&str]) -> Vec<u8> {
+        let content = "BT /F1 12 Tf (Hello) Tj ET";
+        let mut objects = vec![
+            "1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n".to_string(),
+            "2 0 obj\n<< /Type /Pages /Kids [3 0 R] /Count 1 >>\nendobj\n".to_string(),
+            format!(
+                "3 0 obj\n<< /Type /Page /Parent 2 0 R /Resources << /Font << /F1 {} >> >> /Contents 4 0 R >>\nendobj\n",
+                font_ref
+            ),
+            format!(
+                "4 0 obj\n<< /Length {} >>\nstream\n{}\nendstream\nendobj\n",
+                content.len(),
+                content
+            ),
+            "5 0 obj\n<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>\nendobj\n".to_string(),
+        ];
+        objects.extend(extra.iter().map(|obj| obj.to_string()));
+
+        let mut data = b"%PDF-1.4\n".to_vec();
+        for obj in objects {
+            data.extend_from_slice(obj.as_bytes());
+        }
+        let xref = data.len();
+        data.extend_from_slice(
+            format!("xref\n0 6\ntrailer\n<< /Size 6 /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", xref)
+                .as_bytes(),
+        );
+        data
+    }
+
+    fn offset_of(data: &[u8], header: &str) -> usize {
+        data.windows(header.len())
+            .position(|w| w == header.as_bytes())
+            .unwrap()
+    }
+
+    fn approve_all(_: usize, _: usize) -> bool {
+        true
+    }
+
+    #[test]
+    fn test_valid_hints_match_full_extraction() {
+        let data = pdf("5 0 R", &[]);
+        let hints = first_page_object_hints(&data).unwrap();
+        let ids: HashSet<u32> = hints.iter().map(|hint| hint.id).collect();
+        assert_eq!(ids, HashSet::from([1, 2, 3, 4, 5]));
+
+        let page = extract_first_page_with_hints(&data, &hints, &approve_all).unwrap();
+        assert_eq!(page, "Hello");
+        assert_eq!(crate::extract_text(data).unwrap()[0], page);
+    }
+
+    #[test]
+    fn test_omitted_object_fails() {
+        let data = pdf("5 0 R", &[]);
+        let hints = first_page_object_hints(&data).unwrap();
+
+        // Dropping the content stream or the font must not just change the text
+        for omitted in [4, 5] {
+            let partial: Vec<ObjectHint> =
+                hints.iter().copied().filter(|hint| hint.id != omitted).collect();
+            assert!(matches!(
+                extract_first_page_with_hints(&data, &partial, &approve_all),
+                Err(PdfError::ParseError("Page 1 references an object that was not hinted"))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_wrong_offset_fails() {
+        let data = pdf("5 0 R", &[]);
+        let mut hints = first_page_object_hints(&data).unwrap();
+        let content = hints.iter().position(|hint| hint.id == 4).unwrap();
+
+        // Pointing at another object's header
+        hints[content].offset = offset_of(&data, "5 0 obj");
+        assert!(extract_first_page_with_hints(&data, &hints, &approve_all).is_err());
+
+        // Past the end of the file
+        hints[content].offset = data.len();
+        assert!(extract_first_page_with_hints(&data, &hints, &approve_all).is_err());
+    }
+
+    #[test]
+    fn test_object_redefined_later_fails() {
+        let content = "BT /F1 12 Tf (Bye) Tj ET";
+        let update = format!(
+            "4 0 obj\n<< /Length {} >>\nstream\n{}\nendstream\nendobj\n",
+            content.len(),
+            content
+        );
+        let data = pdf("5 0 R", &[update.as_str()]);
+
+        // The host hints the latest definition
+        let mut hints = first_page_object_hints(&data).unwrap();
+        assert_eq!(
+            extract_first_page_with_hints(&data, &hints, &approve_all).unwrap(),
+            "Bye"
+        );
+
+        // Hinting the overridden one is caught
+        let first = offset_of(&data, "4 0 obj");
+        hints.iter_mut().find(|hint| hint.id == 4).unwrap().offset = first;
+        assert!(matches!(
+            extract_first_page_with_hints(&data, &hints, &approve_all),
+            Err(PdfError::ParseError("Hinted object is redefined later"))
+        ));
+    }
+
+    #[test]
+    fn test_object_outside_approved_range_fails() {
+        let data = pdf("5 0 R", &[]);
+        let hints = first_page_object_hints(&data).unwrap();
+        let font = offset_of(&data, "5 0 obj");
+
+        let before_font = |_: usize, end: usize| end <= font;
+        assert!(matches!(
+            extract_first_page_with_hints(&data, &hints, &before_font),
+            Err(PdfError::ParseError("Hinted object outside the approved byte range"))
+        ));
+
+        // The trailer is checked the same way
+        let xref = offset_of(&data, "xref");
+        let objects_only = |_: usize, end: usize| end <= xref;
+        assert!(matches!(
+            extract_first_page_with_hints(&data, &hints, &objects_only),
+            Err(PdfError::ParseError("Trailer outside the approved byte range"))
+        ));
+    }
+
+    #[test]
+    fn test_dangling_reference_is_not_hinted() {
+        let data = pdf("9 0 R", &[]);
+        assert!(matches!(
+            first_page_object_hints(&data),
+            Err(PdfError::ParseError(
+                "Page 1 references a missing object and cannot be hinted"
+            ))
+        ));
+    }
+}
@@ -1,3 +1,4 @@
+pub mod hints;
 pub mod parser_utils;
 pub mod types;
 
@@ -329,8 +330,18 @@ pub fn handle_stream_filters(
 
 // Parse an entire PDF byte slice and produce page content data
 pub fn parse_pdf(data: &[u8]) -> Result<(Vec<PageContent>, HashMap<(u32, u16), PdfObj>), PdfError> {
+    let (pages, objects, _offsets) = parse_pdf_with_offsets(data)?;
+    Ok((pages, objects))
+}
+
+/// Same as `parse_pdf`, also returning the byte offset of each top-level object
+/// (the last definition, as for the objects map)
+pub fn parse_pdf_with_offsets(
+    data: &[u8],
+) -> Result<(Vec<PageContent>, HashMap<(u32, u16), PdfObj>, HashMap<(u32, u16), usize>), PdfError> {
     let mut parser = Parser::new(data);
     let mut objects: HashMap<(u32, u16), PdfObj> = HashMap::new();
+    let mut offsets: HashMap<(u32, u16), usize> = HashMap::new();
 
     // Skip PDF header (e.g. %PDF-1.7)
     // The header line ends with LF or CRLF. Skip until we hit a line break after "%PDF"
@@ -374,173 +385,33 @@ pub fn parse_pdf(data: &[u8]) -> Result<(Vec<PageContent>, HashMap<(u32, u16), P
             }
             continue;
         }
-        //  "<obj_id> <gen_id> obj"
-        let obj_id = match parser.parse_number()? {
-            PdfObj::Number(num) => num as u32,
-            _ => return Err(PdfError::ParseError("Invalid object id")),
-        };
-        parser.skip_whitespace_and_comments();
-        let gen1 = match parser.parse_number()? {
-            PdfObj::Number(num) => num as u16,
-            _ => return Err(PdfError::ParseError("Invalid generation number")),
-        };
-        parser.skip_whitespace_and_comments();
-        if !parser.remaining_starts_with(b"obj") {
-            return Err(PdfError::ParseError("Missing 'obj' keyword"));
-        }
-        parser.pos += 3;
-        parser.skip_whitespace_and_comments();
-        let obj_value = if parser.pos < parser.len
-            && parser.data[parser.pos] == b'<'
-            && parser.pos + 1 < parser.len
-            && parser.data[parser.pos + 1] == b'<'
-        {
-            parser.pos += 2;
-            let dict_obj = parser.parse_dictionary()?;
-
-            parser.skip_whitespace_and_comments();
-            if parser.remaining_starts_with(b"stream") {
-                parser.pos += 6;
-                if parser.pos < parser.len && parser.data[parser.pos] == b'\r' {
-                    parser.pos += 1;
-                    if parser.pos < parser.len && parser.data[parser.pos] == b'\n' {
-                        parser.pos += 1;
-                    }
-                } else if parser.pos < parser.len && parser.data[parser.pos] == b'\n' {
-                    parser.pos += 1;
-                }
-
-                let stream_start = parser.pos;
-
-                let mut length_opt: Option<usize> = None;
-                if let PdfObj::Dictionary(ref d) = dict_obj {
-                    if let Some(len_obj) = d.get("Length") {
-                        match len_obj {
-                            PdfObj::Number(n) => length_opt = Some(*n as usize),
-                            PdfObj::Reference((obj, generation)) => {
-                                if let Some(PdfObj::Number(n)) = objects.get(&(*obj, *generation)) {
-                                    length_opt = Some(*n as usize);
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-
-                let search_term = b"endstream";
-                let search_len = search_term.len();
-
-                let stream_data = if let Some(len) = length_opt {
-                    if stream_start + len > parser.len {
-                        return Err(PdfError::ParseError("Unexpected EOF in stream"));
-                    }
-                    let data_end = stream_start + len;
-                    parser.pos = data_end;
-                    if parser.pos < parser.len && parser.data[parser.pos] == b'\r' {
-                        parser.pos += 1;
-                        if parser.pos < parser.len && parser.data[parser.pos] == b'\n' {
-                            parser.pos += 1;
-                        }
-                    } else if parser.pos < parser.len && parser.data[parser.pos] == b'\n' {
-                        parser.pos += 1;
-                    }
-                    parser.skip_whitespace_and_comments();
-                    if !parser.remaining_starts_with(search_term) {
-                        return Err(PdfError::ParseError("Missing 'endstream'"));
-                    }
-                    parser.data[stream_start..data_end].to_vec()
-                } else {
-                    let mut endstream_index = None;
-                    let mut i = stream_start;
-                    while i + search_len <= parser.len {
-                        if &parser.data[i..i + search_len] == search_term {
-                            let prev_ok = if i == 0 {
-                                true
-                            } else {
-                                let prev = parser.data[i - 1];
-                                prev == b'\n' || prev == b'\r' || prev.is_ascii_whitespace()
-                            };
-                            let next_ok = if i + search_len >= parser.len {
-                                true
-                            } else if parser.data[i + search_len..].starts_with(b"endobj") {
-                                true
-                            } else {
-                                let next = parser.data[i + search_len];
-                                next.is_ascii_whitespace()
-                            };
-                            if prev_ok && next_ok {
-                                endstream_index = Some(i);
-                                break;
-                            }
-                        }
-                        i += 1;
-                    }
-                    let end_idx =
-                        endstream_index.ok_or(PdfError::ParseError("Missing 'endstream'"))?;
-                    parser.pos = end_idx;
-                    let mut data_end = end_idx;
-                    while data_end > stream_start && parser.data[data_end - 1].is_ascii_whitespace()
+        let offset = parser.pos;
+        let (id, obj_value) = parse_indirect_object(&mut parser, &objects)?;
+
+        // Objects inside object streams have no byte offset of their own
+        if let PdfObj::Stream(ref stream_obj) = obj_value {
+            if let Some(PdfObj::Name(ref t)) = stream_obj.dict.get("Type") {
+                if t == "ObjStm" {
+                    if let (Some(PdfObj::Number(first)), Some(PdfObj::Number(n))) =
+                        (stream_obj.dict.get("First"), stream_obj.dict.get("N"))
                     {
-                        data_end -= 1;
-                    }
-                    parser.data[stream_start..data_end].to_vec()
-                };
-
-                parser.pos += search_len;
-                parser.skip_whitespace_and_comments();
-                if !parser.remaining_starts_with(b"endobj") {
-                    return Err(PdfError::ParseError("Missing 'endobj' after stream"));
-                }
-                parser.pos += 6;
-                let dict = if let PdfObj::Dictionary(d) = dict_obj {
-                    d
-                } else {
-                    HashMap::new()
-                };
-                let stream_obj = PdfStream {
-                    dict,
-                    data: stream_data,
-                };
-
-                if let Some(PdfObj::Name(ref t)) = stream_obj.dict.get("Type") {
-                    if t == "ObjStm" {
-                        if let (Some(PdfObj::Number(first)), Some(PdfObj::Number(n))) =
-                            (stream_obj.dict.get("First"), stream_obj.dict.get("N"))
-                        {
-                            if let Ok(decompressed) = decompress_to_vec_zlib(&stream_obj.data) {
-                                parse_obj_stream(
-                                    &decompressed,
-                                    *first as usize,
-                                    *n as usize,
-                                    &mut objects,
-                                )?;
+                        if let Ok(decompressed) = decompress_to_vec_zlib(&stream_obj.data) {
+                            let contained = parse_obj_stream(
+                                &decompressed,
+                                *first as usize,
+                                *n as usize,
+                                &mut objects,
+                            )?;
+                            for contained_id in contained {
+                                offsets.remove(&contained_id);
                             }
                         }
                     }
                 }
-
-                PdfObj::Stream(stream_obj)
-            } else {
-                // "endobj"
-                parser.skip_whitespace_and_comments();
-                if !parser.remaining_starts_with(b"endobj") {
-                    return Err(PdfError::ParseError(
-                        "Missing 'endobj' for dictionary object",
-                    ));
-                }
-                parser.pos += 6;
-                dict_obj
-            }
-        } else {
-            let value_obj = parser.parse_value()?;
-            parser.skip_whitespace_and_comments();
-            if !parser.remaining_starts_with(b"endobj") {
-                return Err(PdfError::ParseError("Missing 'endobj' for object"));
             }
-            parser.pos += 6;
-            value_obj
-        };
-        objects.insert((obj_id, gen1), obj_value);
+        }
+        offsets.insert(id, offset);
+        objects.insert(id, obj_value);
     }
 
     let mut trailer_index = None;
@@ -617,15 +488,175 @@ pub fn parse_pdf(data: &[u8]) -> Result<(Vec<PageContent>, HashMap<(u32, u16), P
         ));
     }
 
-    Ok((result, objects))
+    Ok((result, objects, offsets))
 }
 
+/// Parse one indirect object ("<obj_id> <gen_id> obj ... endobj") at the parser position
+///
+/// `objects` resolves indirect stream /Length values (objects parsed so far).
+fn parse_indirect_object(
+    parser: &mut Parser,
+    objects: &HashMap<(u32, u16), PdfObj>,
+) -> Result<((u32, u16), PdfObj), PdfError> {
+    //  "<obj_id> <gen_id> obj"
+    let obj_id = match parser.parse_number()? {
+        PdfObj::Number(num) => num as u32,
+        _ => return Err(PdfError::ParseError("Invalid object id")),
+    };
+    parser.skip_whitespace_and_comments();
+    let gen1 = match parser.parse_number()? {
+        PdfObj::Number(num) => num as u16,
+        _ => return Err(PdfError::ParseError("Invalid generation number")),
+    };
+    parser.skip_whitespace_and_comments();
+    if !parser.remaining_starts_with(b"obj") {
+        return Err(PdfError::ParseError("Missing 'obj' keyword"));
+    }
+    parser.pos += 3;
+    parser.skip_whitespace_and_comments();
+    let obj_value = if parser.pos < parser.len
+        && parser.data[parser.pos] == b'<'
+        && parser.pos + 1 < parser.len
+        && parser.data[parser.pos + 1] == b'<'
+    {
+        parser.pos += 2;
+        let dict_obj = parser.parse_dictionary()?;
+
+        parser.skip_whitespace_and_comments();
+        if parser.remaining_starts_with(b"stream") {
+            parser.pos += 6;
+            if parser.pos < parser.len && parser.data[parser.pos] == b'\r' {
+                parser.pos += 1;
+                if parser.pos < parser.len && parser.data[parser.pos] == b'\n' {
+                    parser.pos += 1;
+                }
+            } else if parser.pos < parser.len && parser.data[parser.pos] == b'\n' {
+                parser.pos += 1;
+            }
+
+            let stream_start = parser.pos;
+
+            let mut length_opt: Option<usize> = None;
+            if let PdfObj::Dictionary(ref d) = dict_obj {
+                if let Some(len_obj) = d.get("Length") {
+                    match len_obj {
+                        PdfObj::Number(n) => length_opt = Some(*n as usize),
+                        PdfObj::Reference((obj, generation)) => {
+                            if let Some(PdfObj::Number(n)) = objects.get(&(*obj, *generation)) {
+                                length_opt = Some(*n as usize);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            let search_term = b"endstream";
+            let search_len = search_term.len();
+
+            let stream_data = if let Some(len) = length_opt {
+                if stream_start + len > parser.len {
+                    return Err(PdfError::ParseError("Unexpected EOF in stream"));
+                }
+                let data_end = stream_start + len;
+                parser.pos = data_end;
+                if parser.pos < parser.len && parser.data[parser.pos] == b'\r' {
+                    parser.pos += 1;
+                    if parser.pos < parser.len && parser.data[parser.pos] == b'\n' {
+                        parser.pos += 1;
+                    }
+                } else if parser.pos < parser.len && parser.data[parser.pos] == b'\n' {
+                    parser.pos += 1;
+                }
+                parser.skip_whitespace_and_comments();
+                if !parser.remaining_starts_with(search_term) {
+                    return Err(PdfError::ParseError("Missing 'endstream'"));
+                }
+                parser.data[stream_start..data_end].to_vec()
+            } else {
+                let mut endstream_index = None;
+                let mut i = stream_start;
+                while i + search_len <= parser.len {
+                    if &parser.data[i..i + search_len] == search_term {
+                        let prev_ok = if i == 0 {
+                            true
+                        } else {
+                            let prev = parser.data[i - 1];
+                            prev == b'\n' || prev == b'\r' || prev.is_ascii_whitespace()
+                        };
+                        let next_ok = if i + search_len >= parser.len {
+                            true
+                        } else if parser.data[i + search_len..].starts_with(b"endobj") {
+                            true
+                        } else {
+                            let next = parser.data[i + search_len];
+                            next.is_ascii_whitespace()
+                        };
+                        if prev_ok && next_ok {
+                            endstream_index = Some(i);
+                            break;
+                        }
+                    }
+                    i += 1;
+                }
+                let end_idx =
+                    endstream_index.ok_or(PdfError::ParseError("Missing 'endstream'"))?;
+                parser.pos = end_idx;
+                let mut data_end = end_idx;
+                while data_end > stream_start && parser.data[data_end - 1].is_ascii_whitespace()
+                {
+                    data_end -= 1;
+                }
+                parser.data[stream_start..data_end].to_vec()
+            };
+
+            parser.pos += search_len;
+            parser.skip_whitespace_and_comments();
+            if !parser.remaining_starts_with(b"endobj") {
+                return Err(PdfError::ParseError("Missing 'endobj' after stream"));
+            }
+            parser.pos += 6;
+            let dict = if let PdfObj::Dictionary(d) = dict_obj {
+                d
+            } else {
+                HashMap::new()
+            };
+            let stream_obj = PdfStream {
+                dict,
+                data: stream_data,
+            };
+
+            PdfObj::Stream(stream_obj)
+        } else {
+            // "endobj"
+            parser.skip_whitespace_and_comments();
+            if !parser.remaining_starts_with(b"endobj") {
+                return Err(PdfError::ParseError(
+                    "Missing 'endobj' for dictionary object",
+                ));
+            }
+            parser.pos += 6;
+            dict_obj
+        }
+    } else {
+        let value_obj = parser.parse_value()?;
+        parser.skip_whitespace_and_comments();
+        if !parser.remaining_starts_with(b"endobj") {
+            return Err(PdfError::ParseError("Missing 'endobj' for object"));
+        }
+        parser.pos += 6;
+        value_obj
+    };
+    Ok(((obj_id, gen1), obj_value))
+}
+
+/// Parse the objects of an object stream into `objects`, returning their ids
 fn parse_obj_stream(
     data: &[u8],
     first: usize,
     count: usize,
     objects: &mut HashMap<(u32, u16), PdfObj>,
-) -> Result<(), PdfError> {
+) -> Result<Vec<(u32, u16)>, PdfError> {
     let mut parser = Parser::new(data);
     let mut headers = Vec::new();
     for _ in 0..count {
@@ -651,7 +682,7 @@ fn parse_obj_stream(
         let value = sub.parse_value()?;
         objects.insert((headers[i].0, 0), value);
     }
-    Ok(())
+    Ok(headers.iter().map(|(obj_num, _)| (*obj_num, 0)).collect())
 }
 
 fn parse_content_tokens(data: &[u8]) -> Vec<Token> {
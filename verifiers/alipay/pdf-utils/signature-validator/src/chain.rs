@@ -0,0 +1,234 @@
+//! Certificate chain validation against a pinned root (host only)
+//!
+//! A PDF signature only proves the PDF was signed by the key in its embedded
+//! certificate - anyone can sign a PDF with their own key. Before trusting a new
+//! signing key, the relay checks the PKCS#7 certificates chain from the signer's
+//! certificate up to a pinned root certificate.
+//!
+//! Walks raw DER (signatures cover the exact TBSCertificate bytes, so certificates
+//! are never re-encoded). Only sha256WithRSAEncryption is accepted; validity dates
+//! are not checked (receipts outlive their signing certificates).
+
+use rsa::{Pkcs1v15Sign, RsaPublicKey};
+use sha2::{Digest, Sha256};
+
+use crate::signed_bytes_extractor::get_signature_der;
+
+/// sha256WithRSAEncryption (1.2.840.113549.1.1.11)
+const SHA256_WITH_RSA_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+
+/// Longest chain walked from the signer certificate to the root
+const MAX_CHAIN_DEPTH: usize = 8;
+
+/// One DER element: tag, content, and the whole encoding
+#[derive(Debug, Clone, Copy)]
+struct Der<'a> {
+    tag: u8,
+    content: &'a [u8],
+    raw: &'a [u8],
+}
+
+/// The parts of an X.509 certificate needed to walk a chain
+#[derive(Debug, Clone)]
+struct Certificate<'a> {
+    raw: &'a [u8],
+    tbs: &'a [u8],
+    issuer: &'a [u8],
+    subject: &'a [u8],
+    spki: &'a [u8],
+    signature_algorithm: &'a [u8],
+    signature: &'a [u8],
+}
+
+/// Check the PDF's signing certificate chains to `pinned_root_der`.
+///
+/// `signer_key_hash` is the SHA-256 of the signer's SubjectPublicKeyInfo
+/// (`PdfSignatureResult::public_key_der_hash`). Returns the chain length
+/// (certificates from the signer up to, not including, the root).
+pub fn validate_certificate_chain(
+    pdf_bytes: &[u8],
+    signer_key_hash: &[u8],
+    pinned_root_der: &[u8],
+) -> Result<usize, String> {
+    let (signature_der, _) =
+        get_signature_der(pdf_bytes).map_err(|e| format!("Failed to extract signature: {}", e))?;
+    validate_signature_chain(&signature_der, signer_key_hash, pinned_root_der)
+}
+
+/// Same as `validate_certificate_chain`, for the PKCS#7 SignedData DER itself
+pub fn validate_signature_chain(
+    signature_der: &[u8],
+    signer_key_hash: &[u8],
+    pinned_root_der: &[u8],
+) -> Result<usize, String> {
+    let root = parse_certificate(pinned_root_der).map_err(|e| format!("pinned root: {}", e))?;
+    let certificates = embedded_certificates(signature_der)?
+        .into_iter()
+        .map(parse_certificate)
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut current = certificates
+        .iter()
+        .find(|cert| Sha256::digest(cert.spki).as_slice() == signer_key_hash)
+        .ok_or_else(|| "signer certificate not found in the signature".to_string())?;
+
+    for depth in 1..=MAX_CHAIN_DEPTH {
+        if current.raw == root.raw {
+            return Ok(depth - 1);
+        }
+        if current.issuer == root.subject {
+            verify_issued_by(current, &root)?;
+            return Ok(depth);
+        }
+
+        let issuer = certificates
+            .iter()
+            .find(|cert| cert.subject == current.issuer && cert.raw != current.raw)
+            .ok_or_else(|| "chain does not reach the pinned root".to_string())?;
+        verify_issued_by(current, issuer)?;
+        current = issuer;
+    }
+    Err(format!(
+        "chain longer than {} certificates",
+        MAX_CHAIN_DEPTH
+    ))
+}
+
+/// Verify `cert`'s signature with `issuer`'s RSA key
+fn verify_issued_by(cert: &Certificate, issuer: &Certificate) -> Result<(), String> {
+    if cert.signature_algorithm != SHA256_WITH_RSA_OID {
+        return Err("unsupported certificate signature algorithm".to_string());
+    }
+
+    let spki = children(read_der(issuer.spki)?.content)?;
+    let key_bits = match spki.as_slice() {
+        [_, bits] if bits.tag == 0x03 => bit_string_bytes(bits)?,
+        _ => return Err("invalid issuer public key".to_string()),
+    };
+    let (modulus, exponent) = match children(read_der(key_bits)?.content)?.as_slice() {
+        [n, e] if n.tag == 0x02 && e.tag == 0x02 => (n.content, e.content),
+        _ => return Err("issuer key is not RSA".to_string()),
+    };
+    let key = RsaPublicKey::new(
+        rsa::BigUint::from_bytes_be(modulus),
+        rsa::BigUint::from_bytes_be(exponent),
+    )
+    .map_err(|e| format!("invalid issuer RSA key: {}", e))?;
+
+    key.verify(
+        Pkcs1v15Sign::new::<Sha256>(),
+        &Sha256::digest(cert.tbs),
+        cert.signature,
+    )
+    .map_err(|_| "certificate signature does not verify".to_string())
+}
+
+/// Certificates from SignedData's `certificates [0] IMPLICIT` field
+fn embedded_certificates(signature_der: &[u8]) -> Result<Vec<&[u8]>, String> {
+    // ContentInfo ::= SEQUENCE { contentType, [0] EXPLICIT SignedData }
+    let content_info = children(read_der(signature_der)?.content)?;
+    let signed_data = match content_info.as_slice() {
+        [_, explicit] if explicit.tag == 0xa0 => read_der(explicit.content)?,
+        _ => return Err("Not a SignedData ContentInfo".to_string()),
+    };
+
+    // SignedData ::= SEQUENCE { version, digestAlgorithms, encapContentInfo, [0] certificates, ... }
+    let certificates = children(signed_data.content)?
+        .into_iter()
+        .find(|field| field.tag == 0xa0)
+        .ok_or_else(|| "signature has no certificates".to_string())?;
+    Ok(children(certificates.content)?
+        .into_iter()
+        .filter(|cert| cert.tag == 0x30)
+        .map(|cert| cert.raw)
+        .collect())
+}
+
+fn parse_certificate(der: &[u8]) -> Result<Certificate<'_>, String> {
+    let cert = read_der(der)?;
+    let (tbs, algorithm, signature) = match children(cert.content)?.as_slice() {
+        [tbs, algorithm, signature] if signature.tag == 0x03 => (*tbs, *algorithm, *signature),
+        _ => return Err("invalid certificate".to_string()),
+    };
+
+    // TBSCertificate ::= SEQUENCE { [0] version OPTIONAL, serial, signature, issuer,
+    //                               validity, subject, subjectPublicKeyInfo, ... }
+    let fields = children(tbs.content)?;
+    let skip = usize::from(fields.first().is_some_and(|field| field.tag == 0xa0));
+    let field = |index: usize| {
+        fields
+            .get(skip + index)
+            .copied()
+            .ok_or_else(|| "truncated TBSCertificate".to_string())
+    };
+
+    let signature_algorithm = match children(algorithm.content)?.first() {
+        Some(oid) if oid.tag == 0x06 => oid.content,
+        _ => return Err("invalid signature algorithm".to_string()),
+    };
+
+    Ok(Certificate {
+        raw: cert.raw,
+        tbs: tbs.raw,
+        issuer: field(2)?.raw,
+        subject: field(4)?.raw,
+        spki: field(5)?.raw,
+        signature_algorithm,
+        signature: bit_string_bytes(&signature)?,
+    })
+}
+
+/// BIT STRING content without the unused-bits byte (must be 0)
+fn bit_string_bytes<'a>(bits: &Der<'a>) -> Result<&'a [u8], String> {
+    match bits.content.split_first() {
+        Some((0, bytes)) => Ok(bytes),
+        _ => Err("invalid BIT STRING".to_string()),
+    }
+}
+
+/// All DER elements in `bytes`
+fn children(mut bytes: &[u8]) -> Result<Vec<Der<'_>>, String> {
+    let mut elements = Vec::new();
+    while !bytes.is_empty() {
+        let element = read_der(bytes)?;
+        bytes = &bytes[element.raw.len()..];
+        elements.push(element);
+    }
+    Ok(elements)
+}
+
+/// The first DER element in `bytes` (single-byte tags, definite lengths)
+fn read_der(bytes: &[u8]) -> Result<Der<'_>, String> {
+    let (&tag, rest) = bytes
+        .split_first()
+        .ok_or_else(|| "unexpected end of DER".to_string())?;
+    if tag & 0x1f == 0x1f {
+        return Err("multi-byte DER tags are not supported".to_string());
+    }
+
+    let (&first, rest) = rest
+        .split_first()
+        .ok_or_else(|| "unexpected end of DER".to_string())?;
+    let (length, header) = if first < 0x80 {
+        (first as usize, 2)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return Err("invalid DER length".to_string());
+        }
+        let length = rest[..count]
+            .iter()
+            .fold(0usize, |length, &byte| (length << 8) | byte as usize);
+        (length, 2 + count)
+    };
+
+    let end = header
+        .checked_add(length)
+        .filter(|&end| end <= bytes.len())
+        .ok_or_else(|| "DER length exceeds input".to_string())?;
+    Ok(Der {
+        tag,
+        content: &bytes[header..end],
+        raw: &bytes[..end],
+    })
+}
@@ -1,3 +1,4 @@
+pub mod chain;
 pub mod pkcs7_parser;
 pub mod signed_bytes_extractor;
 
@@ -12,9 +13,11 @@ use openvm_sha2 as openvm_sha256_crate;
 #[cfg(not(feature = "openvm_accel"))]
 use sha2::Digest;
 
-use signed_bytes_extractor::get_signature_der;
+use signed_bytes_extractor::{get_signature_der, get_signature_der_at};
 use simple_asn1::{oid, to_der, ASN1Block};
 
+pub use signed_bytes_extractor::{find_byte_range, ByteRanges};
+
 /// Minimal signature result - only essential fields for Alipay verification
 #[derive(Debug, Clone)]
 pub struct PdfSignatureResult {
@@ -47,12 +50,29 @@ pub fn verify_pdf_signature(pdf_bytes: &[u8]) -> Result<PdfSignatureResult, Stri
     let (signature_der, signed_data) = get_signature_der(pdf_bytes)
         .map_err(|e| format!("Failed to extract signature: {}", e))?;
 
+    verify_signature_der(&signature_der, &signed_data)
+}
+
+/// Verify the signature whose `/ByteRange` key is at a known (hinted) position
+/// Also returns the signed byte ranges, so callers can check what the signature covers
+pub fn verify_pdf_signature_at(
+    pdf_bytes: &[u8],
+    byte_range_pos: usize,
+) -> Result<(PdfSignatureResult, ByteRanges), String> {
+    let (signature_der, signed_data, ranges) = get_signature_der_at(pdf_bytes, byte_range_pos)
+        .map_err(|e| format!("Failed to extract signature: {}", e))?;
+
+    Ok((verify_signature_der(&signature_der, &signed_data)?, ranges))
+}
+
+/// Verify PKCS#7 signature DER over the signed data
+fn verify_signature_der(signature_der: &[u8], signed_data: &[u8]) -> Result<PdfSignatureResult, String> {
     // Parse PKCS#7 signed data
-    let params = parse_signed_data(&signature_der)
+    let params = parse_signed_data(signature_der)
         .map_err(|e| format!("Failed to parse signature: {}", e))?;
 
     // Calculate hash of signed data (SHA-256 for Alipay)
-    let calculated_hash = sha256_hash(&signed_data);
+    let calculated_hash = sha256_hash(signed_data);
 
     // Verify message digest matches
     if params.signed_data_message_digest != calculated_hash {
@@ -1,10 +1,24 @@
 use std::str;
 
-fn parse_byte_range(pdf_bytes: &[u8]) -> Result<(Vec<u8>, usize), &'static str> {
-    let br_pos = pdf_bytes
+/// Signed byte ranges of a signature: [(offset1, len1), (offset2, len2)]
+pub type ByteRanges = [(usize, usize); 2];
+
+/// Position of the first `/ByteRange` key (the signature dictionary)
+pub fn find_byte_range(pdf_bytes: &[u8]) -> Option<usize> {
+    pdf_bytes
         .windows(b"/ByteRange".len())
         .position(|w| w == b"/ByteRange")
-        .ok_or("ByteRange not found")?;
+}
+
+fn parse_byte_range(pdf_bytes: &[u8]) -> Result<(Vec<u8>, usize, ByteRanges), &'static str> {
+    let br_pos = find_byte_range(pdf_bytes).ok_or("ByteRange not found")?;
+    parse_byte_range_at(pdf_bytes, br_pos)
+}
+
+fn parse_byte_range_at(
+    pdf_bytes: &[u8],
+    br_pos: usize,
+) -> Result<(Vec<u8>, usize, ByteRanges), &'static str> {
     let br_start = pdf_bytes[br_pos..]
         .iter()
         .position(|&b| b == b'[')
@@ -48,7 +62,7 @@ fn parse_byte_range(pdf_bytes: &[u8]) -> Result<(Vec<u8>, usize), &'static str>
     signed_bytes.extend_from_slice(&pdf_bytes[offset1..offset1 + len1]);
     signed_bytes.extend_from_slice(&pdf_bytes[offset2..offset2 + len2]);
 
-    Ok((signed_bytes, contents_pos))
+    Ok((signed_bytes, contents_pos, [(offset1, len1), (offset2, len2)]))
 }
 
 fn extract_signature_hex(pdf_bytes: &[u8], contents_pos: usize) -> Result<String, &'static str> {
@@ -171,9 +185,52 @@ fn decode_signature_hex(hex_str: &str) -> Result<Vec<u8>, &'static str> {
 }
 
 pub fn get_signature_der(pdf_bytes: &[u8]) -> Result<(Vec<u8>, Vec<u8>), &'static str> {
-    let (signed_bytes, contents_pos) = parse_byte_range(pdf_bytes)?;
+    let (signed_bytes, contents_pos, _) = parse_byte_range(pdf_bytes)?;
     let hex_str = extract_signature_hex(pdf_bytes, contents_pos)?;
     let signature_der = decode_signature_hex(&hex_str)?;
 
     Ok((signature_der, signed_bytes))
 }
+
+/// Same as `get_signature_der` for the signature whose `/ByteRange` key is at a
+/// known (hinted) position - skips the scan and also returns the signed ranges
+pub fn get_signature_der_at(
+    pdf_bytes: &[u8],
+    byte_range_pos: usize,
+) -> Result<(Vec<u8>, Vec<u8>, ByteRanges), &'static str> {
+    if !pdf_bytes
+        .get(byte_range_pos..)
+        .is_some_and(|rest| rest.starts_with(b"/ByteRange"))
+    {
+        return Err("ByteRange hint does not point to /ByteRange");
+    }
+    let (signed_bytes, contents_pos, ranges) = parse_byte_range_at(pdf_bytes, byte_range_pos)?;
+    let hex_str = extract_signature_hex(pdf_bytes, contents_pos)?;
+    let signature_der = decode_signature_hex(&hex_str)?;
+
+    Ok((signature_der, signed_bytes, ranges))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PDF: &[u8] = b"%PDF-1.4\n<< /Type /Sig /ByteRange [0 10 30 4] /Contents <3000> >>\n%%EOF";
+
+    #[test]
+    fn test_byte_range_hint_must_point_at_key() {
+        let pos = find_byte_range(PDF).unwrap();
+        assert!(get_signature_der_at(PDF, pos + 1).is_err_and(|e| e.contains("does not point")));
+        assert!(get_signature_der_at(PDF, pos - 1).is_err_and(|e| e.contains("does not point")));
+        assert!(get_signature_der_at(PDF, PDF.len()).is_err_and(|e| e.contains("does not point")));
+        assert!(get_signature_der_at(PDF, usize::MAX).is_err_and(|e| e.contains("does not point")));
+    }
+
+    #[test]
+    fn test_byte_range_at_key_is_parsed() {
+        let pos = find_byte_range(PDF).unwrap();
+        let (_, signed, ranges) = get_signature_der_at(PDF, pos).unwrap();
+        assert_eq!(ranges, [(0, 10), (30, 4)]);
+        assert_eq!(signed, [&PDF[..10], &PDF[30..34]].concat());
+    }
+}